@@ -0,0 +1,83 @@
+use std::path::Path;
+use std::time::Instant;
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::storage::file_lock::FileLock;
+
+const BULK_ROWS: usize = 1_000_000;
+const ROW_ROWS: usize = 100_000;
+const BATCH_SIZE: usize = 65_536;
+
+/// Compares row-at-a-time appends with the columnar bulk append API.
+///
+/// Run with `cargo run --release --example bulk_append`.
+fn main() -> std::io::Result<()> {
+    let path = std::env::temp_dir().join("fluxdb_bulk_append.flxdb");
+
+    let rows_per_sec = append_rows(&path)?;
+    println!("append_row     : {ROW_ROWS:>9} rows, {rows_per_sec:>12.0} rows/s");
+
+    let bulk_per_sec = append_bulk(&path)?;
+    println!("append_columns : {BULK_ROWS:>9} rows, {bulk_per_sec:>12.0} rows/s");
+
+    println!("speedup        : {:.1}x", bulk_per_sec / rows_per_sec);
+
+    std::fs::remove_file(&path)?;
+    std::fs::remove_file(FileLock::lock_path(&path))?;
+    Ok(())
+}
+
+fn open_events(path: &Path) -> std::io::Result<Database> {
+    let mut db = Database::open(path, true)?;
+    db.create_table("events")?;
+    db.add_column("events", "id", ColumnType::Integer64)?;
+    db.add_column("events", "user", ColumnType::Utf8)?;
+    db.add_column("events", "amount", ColumnType::Float64)?;
+    db.add_column("events", "is_refund", ColumnType::Boolean)?;
+    Ok(db)
+}
+
+fn append_rows(path: &Path) -> std::io::Result<f64> {
+    let mut db = open_events(path)?;
+    let users: Vec<String> = (0..1000).map(|i| format!("user_{i}")).collect();
+
+    let start = Instant::now();
+    for i in 0..ROW_ROWS {
+        db.append_row("events", vec![
+            ("id", Value::Int64(i as i64)),
+            ("user", Value::String(users[i % users.len()].clone())),
+            ("amount", Value::Float64(i as f64 * 0.25)),
+            ("is_refund", Value::Bool(i % 10 == 0)),
+        ])?;
+    }
+    db.flush()?;
+
+    Ok(ROW_ROWS as f64 / start.elapsed().as_secs_f64())
+}
+
+fn append_bulk(path: &Path) -> std::io::Result<f64> {
+    let mut db = open_events(path)?;
+    let users: Vec<String> = (0..1000).map(|i| format!("user_{i}")).collect();
+
+    let ids: Vec<i64> = (0..BULK_ROWS as i64).collect();
+    let names: Vec<&str> = (0..BULK_ROWS).map(|i| users[i % users.len()].as_str()).collect();
+    let amounts: Vec<f64> = (0..BULK_ROWS).map(|i| i as f64 * 0.25).collect();
+    let refunds: Vec<bool> = (0..BULK_ROWS).map(|i| i % 10 == 0).collect();
+
+    let start = Instant::now();
+    for offset in (0..BULK_ROWS).step_by(BATCH_SIZE) {
+        let end = (offset + BATCH_SIZE).min(BULK_ROWS);
+
+        db.append_columns("events", &[
+            ColumnInput::new("id", ColumnSlice::Int64(&ids[offset..end])),
+            ColumnInput::new("user", ColumnSlice::Utf8(&names[offset..end])),
+            ColumnInput::new("amount", ColumnSlice::Float64(&amounts[offset..end])),
+            ColumnInput::new("is_refund", ColumnSlice::Bool(&refunds[offset..end])),
+        ])?;
+    }
+    db.flush()?;
+
+    Ok(BULK_ROWS as f64 / start.elapsed().as_secs_f64())
+}
@@ -1,8 +1,131 @@
 use std::collections::HashMap;
+use crate::metadata::schema::catalog_change::CatalogChange;
+use crate::metadata::schema::column_type::ColumnType;
 use crate::metadata::schema::table_column::TableColumn;
 use crate::metadata::schema::table_meta::TableMeta;
+#[derive(Clone)]
 pub struct Catalog {
     pub tables_by_id: HashMap<u32, TableMeta>,
     pub tables_by_name: HashMap<String, u32>,
     pub columns_by_table: HashMap<u32, Vec<TableColumn>>,
-}
\ No newline at end of file
+    /// Per table, one past the highest ordinal ever given to a column,
+    /// dropped columns included.
+    pub next_ordinals: HashMap<u32, u16>,
+}
+
+impl Catalog {
+    /// Number of columns in the table, nested children included.
+    pub fn column_count(&self, table_id: u32) -> usize {
+        self.columns_by_table
+            .get(&table_id)
+            .map(|cols| cols.iter().map(|c| c.flatten().len()).sum())
+            .unwrap_or(0)
+    }
+
+    pub fn find_column_by_ordinal(&self, table_id: u32, ordinal: u16) -> Option<&TableColumn> {
+        self.columns_by_table
+            .get(&table_id)?
+            .iter()
+            .flat_map(|c| c.flatten())
+            .find(|c| c.ordinal == ordinal)
+    }
+
+    pub fn find_column_by_id_mut(&mut self, table_id: u32, column_id: u32) -> Option<&mut TableColumn> {
+        fn find(cols: &mut [TableColumn], column_id: u32) -> Option<&mut TableColumn> {
+            for col in cols {
+                if col.column_id == column_id {
+                    return Some(col);
+                }
+                if let Some(found) = find(&mut col.children, column_id) {
+                    return Some(found);
+                }
+            }
+            None
+        }
+
+        find(self.columns_by_table.get_mut(&table_id)?, column_id)
+    }
+
+    /// Storage slot for a new column of the table: one past the highest it
+    /// ever used, so a dropped column's ordinal is never handed out again.
+    pub fn next_ordinal(&self, table_id: u32) -> u16 {
+        self.next_ordinals.get(&table_id).copied().unwrap_or(0)
+    }
+
+    /// Marks the ordinals of the table below `next_ordinal` as used.
+    pub fn reserve_ordinals(&mut self, table_id: u32, next_ordinal: u16) {
+        let next = self.next_ordinals.entry(table_id).or_default();
+        *next = (*next).max(next_ordinal);
+    }
+
+    /// Applies a schema change to the in-memory catalog.
+    pub fn apply(&mut self, change: &CatalogChange) {
+        match change {
+            CatalogChange::DropTable { table_id } => {
+                if let Some(table) = self.tables_by_id.remove(table_id) {
+                    if self.tables_by_name.get(&table.name) == Some(table_id) {
+                        self.tables_by_name.remove(&table.name);
+                    }
+                }
+                self.columns_by_table.remove(table_id);
+                self.next_ordinals.remove(table_id);
+            }
+            CatalogChange::DropColumn { table_id, column_id } => {
+                if let Some(columns) = self.columns_by_table.get_mut(table_id) {
+                    columns.retain(|c| c.column_id != *column_id);
+                }
+            }
+            CatalogChange::RenameTable { table_id, name } => {
+                if let Some(table) = self.tables_by_id.get_mut(table_id) {
+                    if self.tables_by_name.get(&table.name) == Some(table_id) {
+                        self.tables_by_name.remove(&table.name);
+                    }
+                    table.name = name.clone();
+                    self.tables_by_name.insert(name.clone(), *table_id);
+                }
+            }
+            CatalogChange::RenameColumn { table_id, column_id, name } => {
+                if let Some(column) = self.find_column_by_id_mut(*table_id, *column_id) {
+                    column.name = name.clone();
+                }
+            }
+            CatalogChange::ReserveOrdinals { table_id, next_ordinal } => {
+                self.reserve_ordinals(*table_id, *next_ordinal);
+            }
+        }
+    }
+
+    /// Resolves a dotted column path such as `payload.user.id`.
+    ///
+    /// Returns every column along the path, outermost first. List element
+    /// columns may be named or stepped through implicitly, so `events.price`
+    /// reaches the `price` field of a `List` of structs named `events`.
+    pub fn resolve_column_path(&self, table_id: u32, path: &str) -> Option<Vec<&TableColumn>> {
+        let mut segments = path.split('.');
+        let first = segments.next()?;
+
+        let mut current = self.columns_by_table
+            .get(&table_id)?
+            .iter()
+            .find(|c| c.name == first)?;
+        let mut chain = vec![current];
+
+        for segment in segments {
+            loop {
+                if let Some(child) = current.find_child(segment) {
+                    current = child;
+                    chain.push(current);
+                    break;
+                }
+
+                if current.column_type != ColumnType::List {
+                    return None;
+                }
+                current = current.children.first()?;
+                chain.push(current);
+            }
+        }
+
+        Some(chain)
+    }
+}
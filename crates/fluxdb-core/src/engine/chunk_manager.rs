@@ -1,20 +1,117 @@
-use std::collections::HashMap;
-use std::io::Error;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Error, ErrorKind};
+use std::ops::Range;
 use crate::engine::catalog::Catalog;
+use crate::engine::column_slice::{ColumnInput, ColumnSlice};
+use crate::engine::nested;
 use crate::metadata::chunks::active_chunk::ActiveChunk;
+use crate::metadata::chunks::active_chunk_meta::ActiveChunkMeta;
+use crate::metadata::chunks::chunk_meta::ChunkMeta;
+use crate::metadata::chunks::chunk_rewrite::ChunkRewrite;
+use crate::metadata::chunks::commit_marker::CommitMarker;
+use crate::metadata::chunks::delete_vector::DeleteVector;
+use crate::metadata::chunks::table_truncate::TableTruncate;
 use crate::metadata::schema::column_type::ColumnType;
 use crate::metadata::schema::table_column::TableColumn;
 use crate::metadata::schema::table_meta::TableMeta;
-use crate::storage::pager::Pager;
+use crate::metadata::value::{EncodedValue, Value};
+use crate::query::vector::Vector;
+use crate::storage::page::Page;
+use crate::storage::pager::{ChunkCatalog, PageInit, Pager};
+
+/// Number of table rows a chunk covers before all of the table's active
+/// chunks are sealed together. Keeping the boundaries aligned across columns
+/// lets scans read one row range from every column at once.
+pub const ROWS_PER_CHUNK: u64 = 16_384;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TableRows {
+    /// Row id the next appended row gets.
+    pub next_row_id: u64,
+    /// First row of the table's active (unsealed) chunks.
+    pub active_row_start: u64,
+    /// Rows below this one are committed and visible to scans.
+    pub committed_row_end: u64,
+}
+
+impl TableRows {
+    pub fn active_rows(&self) -> u64 {
+        self.next_row_id - self.active_row_start
+    }
+}
+
+thread_local! {
+    /// Reads by the current thread, so concurrent scans each see their own.
+    static READ_STATS: Cell<ReadStats> = const { Cell::new(ReadStats { pages_read: 0, bytes_decoded: 0 }) };
+}
+
+/// Running totals of the column data read back by one thread, for
+/// profiling scans.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReadStats {
+    /// Data pages read from the file.
+    pub pages_read: u64,
+    /// Encoded value bytes decoded, from the file or active chunks' tail pages.
+    pub bytes_decoded: u64,
+}
+
+impl ReadStats {
+    /// What was read between `earlier` and these totals.
+    pub fn since(&self, earlier: ReadStats) -> ReadStats {
+        ReadStats {
+            pages_read: self.pages_read - earlier.pages_read,
+            bytes_decoded: self.bytes_decoded - earlier.bytes_decoded,
+        }
+    }
+}
 
 pub struct ChunkManager {
     pub pager: Pager,
     pub active_chunks: HashMap<(u32, u16), ActiveChunk>,
+    /// Sealed chunks per `(table_id, column_id)`, sorted by `row_start`.
+    pub chunk_index: HashMap<(u32, u32), Vec<ChunkMeta>>,
+    pub table_rows: HashMap<u32, TableRows>,
+    /// The last committed version. Scans see rows committed up to it.
+    pub version: u64,
+    /// The last version a `CommitMarker` was written for.
+    durable_version: u64,
+    /// Whether records for the next version were written since the last
+    /// commit (chunks sealed while holding uncommitted rows, delete
+    /// vectors), so the next commit has to write a marker.
+    records_pending: bool,
+    /// Delete vectors per table, by the first row they cover.
+    pub deletes: HashMap<u32, BTreeMap<u64, Vec<DeleteVector>>>,
 }
 
 impl ChunkManager {
     pub fn new(pager: Pager) -> Self {
-        Self { pager, active_chunks: HashMap::new() }
+        Self {
+            pager,
+            active_chunks: HashMap::new(),
+            chunk_index: HashMap::new(),
+            table_rows: HashMap::new(),
+            version: 0,
+            durable_version: 0,
+            records_pending: false,
+            deletes: HashMap::new(),
+        }
+    }
+
+    /// A copy of the chunk state as it is now, reading pages through a
+    /// [`Pager::snapshot`]. Data pages are never rewritten once full and the
+    /// copy keeps its own active tail pages, so later appends do not show.
+    pub(crate) fn snapshot(&self) -> ChunkManager {
+        ChunkManager {
+            pager: self.pager.snapshot(),
+            active_chunks: self.active_chunks.clone(),
+            chunk_index: self.chunk_index.clone(),
+            table_rows: self.table_rows.clone(),
+            version: self.version,
+            durable_version: self.durable_version,
+            records_pending: self.records_pending,
+            deletes: self.deletes.clone(),
+        }
     }
 
     pub fn load_catalog(&mut self) -> Result<Catalog, Error> {
@@ -25,11 +122,972 @@ impl ChunkManager {
         self.pager.init_catalog_root()
     }
 
+    /// Loads sealed chunk metadata and derives every table's row counter from it.
+    /// Chunks sealed for a version that was never committed keep only the
+    /// rows committed before them, if any, and such deletes are left out, as
+    /// are the chunks and deletes of truncated tables. Active chunks are
+    /// replayed as of the last commit that recorded them.
+    pub fn load_chunk_index(&mut self) -> Result<(), Error> {
+        let ChunkCatalog { mut chunks, committed, deletes, truncates, rewrites, active } = self.pager.load_chunk_metadata()?;
+
+        // Versions of transactions that never committed are skipped, so a
+        // later commit's marker cannot count their records
+        let mut version = committed.iter().copied().max().unwrap_or(0);
+        version = version.max(deletes.iter().map(|d| d.commit_version).max().unwrap_or(0));
+        version = version.max(truncates.iter().map(|t| t.commit_version).max().unwrap_or(0));
+        version = version.max(rewrites.iter().map(|r| r.commit_version).max().unwrap_or(0));
+        version = version.max(active.iter().map(|a| a.commit_version).max().unwrap_or(0));
+
+        // Per table, the version up to which its records were truncated away
+        let mut truncated: HashMap<u32, u64> = HashMap::new();
+        for truncate in truncates.iter().filter(|t| committed.contains(&t.commit_version)) {
+            let up_to = truncated.entry(truncate.table_id).or_default();
+            *up_to = (*up_to).max(truncate.commit_version);
+        }
+        // Compaction replaced the chunks and deletes of some row ranges
+        let rewrites: Vec<ChunkRewrite> = rewrites.into_iter().filter(|r| committed.contains(&r.commit_version)).collect();
+        let kept = |table_id: u32, row_start: u64, row_end: u64, commit_version: u64| {
+            truncated.get(&table_id).is_none_or(|&up_to| commit_version > up_to)
+                && !rewrites.iter().any(|r| r.replaces(table_id, row_start, row_end, commit_version))
+        };
+
+        for column_chunks in chunks.values_mut() {
+            for chunk in column_chunks.iter_mut() {
+                version = version.max(chunk.commit_version);
+                if chunk.commit_version != 0 && !committed.contains(&chunk.commit_version) {
+                    chunk.row_end = chunk.committed_row_end.max(chunk.row_start);
+                }
+            }
+            column_chunks.retain(|chunk| {
+                chunk.row_end > chunk.row_start && kept(chunk.table_id, chunk.row_start, chunk.row_end, chunk.commit_version)
+            });
+        }
+        chunks.retain(|_, column_chunks| !column_chunks.is_empty());
+        self.chunk_index = chunks;
+        self.version = version;
+        self.durable_version = version;
+
+        for chunks in self.chunk_index.values() {
+            for chunk in chunks {
+                let rows = self.table_rows.entry(chunk.table_id).or_default();
+                rows.next_row_id = rows.next_row_id.max(chunk.row_end);
+                rows.active_row_start = rows.next_row_id;
+                rows.committed_row_end = rows.next_row_id;
+            }
+        }
+
+        // Each commit records the active chunks of the tables it appended
+        // to, so a table's last such records hold all of them, unless the
+        // rows were sealed since
+        let active: Vec<ActiveChunkMeta> = active
+            .into_iter()
+            .filter(|a| committed.contains(&a.commit_version) && kept(a.table_id, a.row_start, a.row_end, a.commit_version))
+            .collect();
+        let mut last_commit: HashMap<u32, u64> = HashMap::new();
+        for meta in &active {
+            let last = last_commit.entry(meta.table_id).or_default();
+            *last = (*last).max(meta.commit_version);
+        }
+        let sealed_rows: HashMap<u32, u64> = self.table_rows.iter().map(|(&table_id, rows)| (table_id, rows.next_row_id)).collect();
+        for meta in active {
+            let sealed_end = sealed_rows.get(&meta.table_id).copied().unwrap_or(0);
+            if last_commit[&meta.table_id] != meta.commit_version || meta.row_start < sealed_end || meta.row_end <= meta.row_start {
+                continue;
+            }
+
+            let chunk = self.replay_active_chunk(&meta)?;
+            self.active_chunks.insert((meta.table_id, chunk.column_ordinal), chunk);
+            let rows = self.table_rows.entry(meta.table_id).or_default();
+            rows.next_row_id = rows.next_row_id.max(meta.row_end);
+            rows.active_row_start = meta.row_start;
+            rows.committed_row_end = rows.next_row_id;
+        }
+
+        // Deletes of rows that were never committed went with them
+        for deletes in deletes {
+            let sealed = deletes.row_start < self.table_rows(deletes.table_id).next_row_id;
+            let replaced = !kept(deletes.table_id, deletes.row_start, deletes.row_end(), deletes.commit_version);
+            if committed.contains(&deletes.commit_version) && sealed && !replaced {
+                self.insert_deletes(deletes);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rebuilds an active chunk from the pages a commit recorded for it,
+    /// leaving out the values appended after that commit.
+    fn replay_active_chunk(&self, meta: &ActiveChunkMeta) -> Result<ActiveChunk, Error> {
+        let mut pages = Vec::with_capacity(meta.page_count as usize);
+        let mut page_id = meta.first_page_id;
+        let mut tail = self.pager.read_page(page_id as u64)?;
+        pages.push(page_id);
+        for _ in 1..meta.page_count {
+            page_id = tail.chunk_header().next_page_id;
+            tail = self.pager.read_page(page_id as u64)?;
+            pages.push(page_id);
+        }
+        tail.truncate_values(meta.tail_value_count, meta.tail_free_start);
+
+        let mut active = ActiveChunk::new(meta.column_id, meta.column_type, meta.row_start, tail);
+        active.first_page_id = meta.first_page_id;
+        active.pages = pages;
+
+        let physical_type = meta.column_type.physical_type();
+        for value in self.read_active_chunk(&active)? {
+            active.observe(&value);
+            if let (Ok(EncodedValue::Bytes(bytes)), false) = (value.encode(physical_type), meta.column_type.is_nested()) {
+                active.distinct.insert(&bytes);
+            }
+        }
+        Ok(active)
+    }
+
+    /// Commits every row appended and deleted so far as the next version,
+    /// making the changes visible to scans started after. Returns the
+    /// committed version, which stays the same when nothing changed since
+    /// the last commit.
+    ///
+    /// Committed rows stay in their active chunks: the chunks' tail pages
+    /// are written and recorded for the version, and the file is synced
+    /// once its commit marker is, so the commit outlasts a crash.
+    pub fn commit(&mut self) -> Result<u64, Error> {
+        let mut appended: Vec<u32> = self.table_rows
+            .iter()
+            .filter(|(_, rows)| rows.committed_row_end < rows.next_row_id)
+            .map(|(&table_id, _)| table_id)
+            .collect();
+        if appended.is_empty() && !self.records_pending {
+            return Ok(self.version);
+        }
+        appended.sort();
+
+        let version = self.version + 1;
+        for table_id in appended {
+            let row_end = self.table_rows(table_id).next_row_id;
+            let mut keys: Vec<(u32, u16)> = self.active_chunks
+                .keys()
+                .filter(|(t, _)| *t == table_id)
+                .copied()
+                .collect();
+            keys.sort();
+
+            for key in keys {
+                let active = &self.active_chunks[&key];
+                self.pager.write_page(active.tail.header.page_id as u64, &active.tail)?;
+                self.pager.insert_active_chunk_meta(&ActiveChunkMeta::new(active, row_end, version))?;
+            }
+        }
+
+        // Records written for this version only count once the marker is on disk
+        self.pager.insert_commit_marker(&CommitMarker { version })?;
+        self.pager.sync()?;
+        self.durable_version = version;
+        self.records_pending = false;
+
+        for rows in self.table_rows.values_mut() {
+            rows.committed_row_end = rows.next_row_id;
+        }
+        self.version = version;
+        Ok(version)
+    }
+
+    /// Discards every row appended and deleted since the last commit,
+    /// reloading the chunk state as the file has it committed and freeing
+    /// the pages only the discarded rows took. The version they were
+    /// written for is skipped, so no later commit counts their records.
+    pub fn rollback(&mut self) -> Result<(), Error> {
+        let appended = self.table_rows.values().any(|rows| rows.committed_row_end < rows.next_row_id);
+        if !appended && !self.records_pending {
+            return Ok(());
+        }
+
+        let mut pages: Vec<u32> = self.active_chunks.values().flat_map(|a| a.pages.iter().copied()).collect();
+        self.active_chunks.clear();
+        self.chunk_index.clear();
+        self.table_rows.clear();
+        self.deletes.clear();
+        self.records_pending = false;
+        self.load_chunk_index()?;
+
+        let kept: HashSet<u32> = self.active_chunks.values().flat_map(|a| a.pages.iter().copied()).collect();
+        pages.retain(|page_id| !kept.contains(page_id));
+        self.pager.free_pages(pages)
+    }
+
+    /// Deletes `rows`, ascending row ids of the table, as of the next
+    /// version: writes one delete vector per chunk they fall in. Returns
+    /// the number of rows deleted.
+    pub fn delete_rows(&mut self, table_id: u32, rows: &[u64]) -> Result<u64, Error> {
+        let mut vectors: Vec<DeleteVector> = Vec::new();
+        for &row in rows {
+            if vectors.last().is_none_or(|v| row >= v.row_end()) {
+                let (chunk_start, chunk_end) = self.chunk_range(table_id, row);
+                let max_rows = u64::from(DeleteVector::MAX_ROWS);
+                let row_start = chunk_start + (row - chunk_start) / max_rows * max_rows;
+                let row_count = (chunk_end - row_start).min(max_rows) as u32;
+                vectors.push(DeleteVector::new(table_id, row_start, row_count, self.version + 1));
+            }
+            vectors.last_mut().unwrap().delete(row);
+        }
+
+        let mut deleted = 0;
+        for deletes in vectors {
+            self.pager.insert_delete_vector(&deletes)?;
+            self.records_pending = true;
+            deleted += deletes.deleted_count();
+            self.insert_deletes(deletes);
+        }
+        Ok(deleted)
+    }
+
+    fn insert_deletes(&mut self, deletes: DeleteVector) {
+        self.deletes
+            .entry(deletes.table_id)
+            .or_default()
+            .entry(deletes.row_start)
+            .or_default()
+            .push(deletes);
+    }
+
+    /// The row range of the sealed or active chunk holding `row`.
+    fn chunk_range(&self, table_id: u32, row: u64) -> (u64, u64) {
+        let sealed = self.chunk_index
+            .iter()
+            .filter(|((table, _), _)| *table == table_id)
+            .find_map(|(_, chunks)| {
+                let chunk = chunks.get(chunks.partition_point(|c| c.row_end <= row))?;
+                (chunk.row_start <= row).then_some((chunk.row_start, chunk.row_end))
+            });
+
+        sealed.unwrap_or_else(|| {
+            let rows = self.table_rows(table_id);
+            (rows.active_row_start, rows.next_row_id)
+        })
+    }
+
+    /// Which of the `row_count` rows from `row_start` are deleted as of the
+    /// committed version, or `None` when none is.
+    pub fn deleted_rows(&self, table_id: u32, row_start: u64, row_count: usize) -> Option<Vec<bool>> {
+        self.deleted_rows_as_of(table_id, row_start, row_count, self.version)
+    }
+
+    /// Which of the rows are deleted as of `version`, as
+    /// [`ChunkManager::deleted_rows`]; the next version counts the deletes
+    /// still pending commit too.
+    pub fn deleted_rows_as_of(&self, table_id: u32, row_start: u64, row_count: usize, version: u64) -> Option<Vec<bool>> {
+        let row_end = row_start + row_count as u64;
+        let mut deleted: Option<Vec<bool>> = None;
+
+        // Vectors cover at most MAX_ROWS rows, so earlier ones end before the range
+        let vectors = self.deletes.get(&table_id)?.range(..row_end).rev();
+        for (_, vectors) in vectors.take_while(|(&start, _)| start + u64::from(DeleteVector::MAX_ROWS) > row_start) {
+            for vector in vectors.iter().filter(|v| v.commit_version <= version) {
+                let from = vector.row_start.max(row_start);
+                for row in from..vector.row_end().min(row_end) {
+                    if vector.is_deleted(row) {
+                        deleted.get_or_insert_with(|| vec![false; row_count])[(row - row_start) as usize] = true;
+                    }
+                }
+            }
+        }
+        deleted
+    }
+
     pub fn create_table(&mut self, p0: &str) -> Result<TableMeta, Error> {
         self.pager.create_table(p0)
     }
 
-    pub fn add_column(&mut self, table_id: u32, col_name: &str, col_type: ColumnType, ordinal: u16) -> Result<TableColumn, Error> {
-        self.pager.add_column(table_id, col_name, col_type, ordinal)
+    pub fn add_column(
+        &mut self,
+        table_id: u32,
+        col_name: &str,
+        col_type: ColumnType,
+        ordinal: u16,
+        parent_column_id: u32,
+        not_null: bool,
+    ) -> Result<TableColumn, Error> {
+        // New columns start with a fresh chunk so every chunk of the table
+        // still covers the same row range
+        self.seal_table(table_id)?;
+        self.pager.add_column(table_id, col_name, col_type, ordinal, parent_column_id, not_null)
+    }
+
+    /// Forgets a dropped table's chunks and row counter. Their pages stay
+    /// allocated in the file.
+    pub fn drop_table(&mut self, table_id: u32) {
+        self.active_chunks.retain(|(table, _), _| *table != table_id);
+        self.chunk_index.retain(|(table, _), _| *table != table_id);
+        self.table_rows.remove(&table_id);
+        self.deletes.remove(&table_id);
+    }
+
+    /// Empties a table, committing right away: its chunks, active chunks and
+    /// deletes are dropped and their pages freed, and its rows count from 0
+    /// again. The truncation takes effect with its commit marker, so a
+    /// crash leaves the table either whole or empty; pages freed after that
+    /// point may leak, but are never reused while still in use.
+    pub fn truncate_table(&mut self, table_id: u32) -> Result<u64, Error> {
+        let mut pages = Vec::new();
+        for (_, chunks) in self.chunk_index.iter().filter(|((table, _), _)| *table == table_id) {
+            for chunk in chunks {
+                pages.extend(self.chunk_pages(chunk)?);
+            }
+        }
+        for (_, active) in self.active_chunks.iter().filter(|((table, _), _)| *table == table_id) {
+            pages.extend(&active.pages);
+        }
+
+        self.pager.insert_table_truncate(&TableTruncate { table_id, commit_version: self.version + 1 })?;
+        self.records_pending = true;
+        let version = self.commit()?;
+
+        self.active_chunks.retain(|(table, _), _| *table != table_id);
+        self.chunk_index.retain(|(table, _), _| *table != table_id);
+        self.deletes.remove(&table_id);
+        self.table_rows.remove(&table_id);
+
+        pages.sort_unstable();
+        pages.dedup();
+        self.pager.free_pages(pages)?;
+        Ok(version)
+    }
+
+    /// Writes `rows`, one value per top-level column each, as new chunks
+    /// numbered from `row_start` for the next version, to replace the
+    /// chunks of a range starting there once
+    /// [`ChunkManager::commit_rewrites`] commits it. The table's own active
+    /// chunks and row counter are left as they were. Nothing else may be
+    /// pending commit.
+    pub fn rewrite_rows(&mut self, columns: &[TableColumn], row_start: u64, rows: &[Vec<Value>]) -> Result<(), Error> {
+        let Some(table_id) = columns.first().map(|c| c.table_id) else {
+            return Ok(());
+        };
+        let table_rows = self.table_rows(table_id);
+        let active: Vec<_> = self.active_chunks.extract_if(|(table, _), _| *table == table_id).collect();
+
+        // Counted as uncommitted, the rows seal for the next version
+        self.table_rows.insert(table_id, TableRows {
+            next_row_id: row_start,
+            active_row_start: row_start,
+            committed_row_end: row_start,
+        });
+        let written = rows
+            .iter()
+            .try_for_each(|row| self.append_row(columns, row))
+            .and_then(|()| self.seal_table(table_id));
+
+        if written.is_err() {
+            // Leave no rewritten chunk next to the ones it was to replace, and
+            // skip their version so no later marker commits the records
+            let pending = self.version;
+            self.active_chunks.retain(|(table, _), _| *table != table_id);
+            for ((table, _), chunks) in self.chunk_index.iter_mut() {
+                if *table == table_id {
+                    chunks.retain(|chunk| chunk.commit_version <= pending);
+                }
+            }
+            self.version += 1;
+            self.records_pending = false;
+        }
+        self.table_rows.insert(table_id, table_rows);
+        self.active_chunks.extend(active);
+        written
+    }
+
+    /// Commits the chunks written by [`ChunkManager::rewrite_rows`] in place
+    /// of the earlier chunks and deletes of the table's row `ranges`, then
+    /// frees the pages of the replaced chunks. The swap takes effect with the
+    /// commit marker, so a crash leaves either the old or the new chunks.
+    /// Returns the number of pages freed.
+    pub fn commit_rewrites(&mut self, table_id: u32, ranges: &[(u64, u64)]) -> Result<usize, Error> {
+        let rewrites: Vec<ChunkRewrite> = ranges
+            .iter()
+            .map(|&(row_start, row_end)| ChunkRewrite { table_id, row_start, row_end, commit_version: self.version + 1 })
+            .collect();
+        let replaced = |chunk: &ChunkMeta| {
+            rewrites.iter().any(|r| r.replaces(chunk.table_id, chunk.row_start, chunk.row_end, chunk.commit_version))
+        };
+
+        let mut pages = Vec::new();
+        for (_, chunks) in self.chunk_index.iter().filter(|((table, _), _)| *table == table_id) {
+            for chunk in chunks.iter().filter(|chunk| replaced(chunk)) {
+                pages.extend(self.chunk_pages(chunk)?);
+            }
+        }
+
+        for rewrite in &rewrites {
+            self.pager.insert_chunk_rewrite(rewrite)?;
+        }
+        self.records_pending = true;
+        self.commit()?;
+
+        for ((table, _), chunks) in self.chunk_index.iter_mut() {
+            if *table == table_id {
+                chunks.retain(|chunk| !replaced(chunk));
+                chunks.sort_by_key(|chunk| chunk.row_start);
+            }
+        }
+        self.chunk_index.retain(|_, chunks| !chunks.is_empty());
+        if let Some(deletes) = self.deletes.get_mut(&table_id) {
+            deletes.retain(|&row_start, _| !ranges.iter().any(|&(start, end)| (start..end).contains(&row_start)));
+        }
+
+        let freed = pages.len();
+        self.pager.free_pages(pages)?;
+        Ok(freed)
+    }
+
+    /// Copies the sealed chunks and committed deletes into `target`, the
+    /// pager of a fresh file whose catalog is written: each chunk's pages
+    /// are laid out one after another, then the chunk catalog heap holds
+    /// one `ChunkMeta` per chunk, one delete vector per chunk range and a
+    /// commit marker, all as of the committed version. Active rows are not
+    /// copied, so everything should be committed and sealed first.
+    pub fn copy_to(&self, target: &mut Pager) -> Result<(), Error> {
+        let mut keys: Vec<&(u32, u32)> = self.chunk_index.keys().collect();
+        keys.sort();
+
+        let mut copied = Vec::new();
+        for key in keys {
+            for (chunk_id, chunk) in self.chunk_index[key].iter().enumerate() {
+                let pages = self.chunk_pages(chunk)?;
+                let first_page_id = target.header.page_count;
+                for (i, &page_id) in pages.iter().enumerate() {
+                    let mut page = self.read_data_page(page_id as u64)?;
+                    let next = if i + 1 < pages.len() { first_page_id as u32 + i as u32 + 1 } else { 0 };
+                    page.set_next_data_page(next);
+                    target.append_page(page)?;
+                }
+
+                copied.push(ChunkMeta {
+                    chunk_id: chunk_id as u32,
+                    first_page_id,
+                    commit_version: self.version,
+                    committed_row_end: chunk.row_end,
+                    ..chunk.clone()
+                });
+            }
+        }
+        for chunk in &copied {
+            target.insert_chunk_meta(chunk)?;
+        }
+
+        // Vectors written for the same rows are merged into one
+        let mut tables: Vec<(&u32, &BTreeMap<u64, Vec<DeleteVector>>)> = self.deletes.iter().collect();
+        tables.sort_by_key(|(table_id, _)| **table_id);
+        for (&table_id, vectors) in tables {
+            for vectors in vectors.values() {
+                let vectors: Vec<&DeleteVector> = vectors.iter().filter(|v| v.commit_version <= self.version).collect();
+                let Some(row_count) = vectors.iter().map(|v| v.row_count).max() else { continue };
+                let row_start = vectors[0].row_start;
+                let mut merged = DeleteVector::new(table_id, row_start, row_count, self.version);
+                for row in row_start..merged.row_end() {
+                    if vectors.iter().any(|v| v.is_deleted(row)) {
+                        merged.delete(row);
+                    }
+                }
+                target.insert_delete_vector(&merged)?;
+            }
+        }
+
+        if self.version > 0 {
+            target.insert_commit_marker(&CommitMarker { version: self.version })?;
+        }
+        Ok(())
+    }
+
+    /// The pages of a sealed chunk, following its page chain.
+    fn chunk_pages(&self, chunk: &ChunkMeta) -> Result<Vec<u32>, Error> {
+        let mut pages = Vec::with_capacity(chunk.page_count as usize);
+        let mut page_id = chunk.first_page_id;
+        for _ in 0..chunk.page_count {
+            pages.push(page_id as u32);
+            page_id = self.read_data_page(page_id)?.chunk_header().next_page_id as u64;
+        }
+        Ok(pages)
+    }
+
+    /// Forgets the chunks of a dropped column and of its nested children.
+    pub fn drop_column(&mut self, column: &TableColumn) {
+        for column in column.flatten() {
+            self.active_chunks.remove(&(column.table_id, column.ordinal));
+            self.chunk_index.remove(&(column.table_id, column.column_id));
+        }
+    }
+
+    /// Forgets the sealed and active chunks of tables and columns the
+    /// catalog no longer has, once both are loaded. Row counters still count
+    /// their rows.
+    pub fn retain_chunks(&mut self, catalog: &Catalog) {
+        let in_catalog = |table_id: &u32, column_id: u32| {
+            catalog.columns_by_table
+                .get(table_id)
+                .is_some_and(|columns| columns.iter().flat_map(|c| c.flatten()).any(|c| c.column_id == column_id))
+        };
+        self.deletes.retain(|table_id, _| catalog.tables_by_id.contains_key(table_id));
+        self.chunk_index.retain(|(table_id, column_id), _| in_catalog(table_id, *column_id));
+        self.active_chunks.retain(|(table_id, _), active| in_catalog(table_id, active.column_id));
+    }
+
+    pub fn table_rows(&self, table_id: u32) -> TableRows {
+        self.table_rows.get(&table_id).copied().unwrap_or_default()
+    }
+
+    /// Appends one row. `values` holds one value per top-level column, in
+    /// column order. The whole row is validated, down to each value fitting
+    /// a data page, before anything is written.
+    pub fn append_row(&mut self, columns: &[TableColumn], values: &[Value]) -> Result<(), Error> {
+        let Some(table_id) = columns.first().map(|c| c.table_id) else {
+            return Err(Error::new(ErrorKind::InvalidInput, "table has no columns"));
+        };
+
+        let mut streams = Vec::new();
+        for (column, value) in columns.iter().zip(values) {
+            if column.not_null && matches!(value, Value::Null) {
+                return Err(not_null_error(column));
+            }
+            nested::shred(column, value, &mut streams)
+                .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        }
+
+        let by_ordinal: HashMap<u16, &TableColumn> = columns
+            .iter()
+            .flat_map(|c| c.flatten())
+            .map(|c| (c.ordinal, c))
+            .collect();
+
+        let mut encoded = Vec::with_capacity(streams.len());
+        for (ordinal, value) in &streams {
+            encoded.push(self.encode_value(by_ordinal[ordinal], value)?);
+        }
+
+        for ((ordinal, value), encoded) in streams.iter().zip(encoded) {
+            self.append_encoded(by_ordinal[ordinal], value, encoded)?;
+        }
+
+        let rows = self.table_rows.entry(table_id).or_default();
+        rows.next_row_id += 1;
+
+        if rows.active_rows() >= ROWS_PER_CHUNK {
+            self.seal_table(table_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that `value` could be appended to the top-level column: that
+    /// it is allowed, has the column's type and fits a data page.
+    pub fn check_value(&self, column: &TableColumn, value: &Value) -> Result<(), Error> {
+        if column.not_null && matches!(value, Value::Null) {
+            return Err(not_null_error(column));
+        }
+        let mut streams = Vec::new();
+        nested::shred(column, value, &mut streams).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+
+        let by_ordinal: HashMap<u16, &TableColumn> = column.flatten().into_iter().map(|c| (c.ordinal, c)).collect();
+        for (ordinal, value) in &streams {
+            self.encode_value(by_ordinal[ordinal], value)?;
+        }
+        Ok(())
+    }
+
+    /// Appends a single value to the column's active chunk, opening the
+    /// chunk or chaining a new data page as needed.
+    pub fn append_value(&mut self, column: &TableColumn, value: &Value) -> Result<(), Error> {
+        let encoded = self.encode_value(column, value)?;
+        self.append_encoded(column, value, encoded)
+    }
+
+    /// Encodes a value of the column, failing when it has the wrong type or
+    /// is longer than a data page holds.
+    fn encode_value(&self, column: &TableColumn, value: &Value) -> Result<EncodedValue, Error> {
+        let encoded = value
+            .encode(column.column_type.physical_type())
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+
+        if let EncodedValue::Bytes(bytes) = &encoded {
+            self.check_value_len(column, bytes.len())?;
+        }
+        Ok(encoded)
+    }
+
+    fn check_value_len(&self, column: &TableColumn, len: usize) -> Result<(), Error> {
+        let max = Page::max_value_len(self.pager.header.page_size as usize);
+        if len > max {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("value of column '{}' is {len} bytes, more than a data page holds ({max})", column.name),
+            ));
+        }
+        Ok(())
+    }
+
+    fn append_encoded(&mut self, column: &TableColumn, value: &Value, encoded: EncodedValue) -> Result<(), Error> {
+        self.active_chunk(column)?;
+        let active = self.active_chunks.get_mut(&(column.table_id, column.ordinal)).unwrap();
+
+        if active.tail.append_value(&encoded).is_err() {
+            Self::chain_page(&mut self.pager, active)?;
+            active.tail.append_value(&encoded).map_err(|_| {
+                Error::new(ErrorKind::InvalidInput, "value is larger than a data page")
+            })?;
+        }
+
+        active.observe(value);
+        if let (EncodedValue::Bytes(bytes), false) = (&encoded, column.column_type.is_nested()) {
+            active.distinct.insert(bytes);
+        }
+        Ok(())
+    }
+
+    /// Appends whole columns at once. `inputs` holds one entry per top-level
+    /// column, in column order; `None` columns are filled with nulls.
+    ///
+    /// Typed slices are encoded straight into the active chunks' tail pages,
+    /// and the batch is split at chunk boundaries so chunks are sealed as they
+    /// fill. Everything is validated, down to each value fitting a data
+    /// page, before the first value is written.
+    pub fn append_columns(&mut self, columns: &[TableColumn], inputs: &[Option<ColumnInput>]) -> Result<usize, Error> {
+        let Some(table_id) = columns.first().map(|c| c.table_id) else {
+            return Err(Error::new(ErrorKind::InvalidInput, "table has no columns"));
+        };
+        let invalid = |msg: String| Error::new(ErrorKind::InvalidInput, msg);
+
+        let row_count = inputs.iter().flatten().map(|i| i.data.len()).next().unwrap_or(0);
+        let mut scratch = Vec::new();
+
+        for (column, input) in columns.iter().zip(inputs) {
+            let Some(input) = input else {
+                if column.not_null && row_count > 0 {
+                    return Err(not_null_error(column));
+                }
+                continue;
+            };
+
+            if input.data.len() != row_count {
+                return Err(invalid(format!(
+                    "column '{}' has {} values, expected {row_count}",
+                    column.name,
+                    input.data.len()
+                )));
+            }
+            if input.validity.is_some_and(|v| v.len() != row_count) {
+                return Err(invalid(format!("validity of column '{}' has the wrong length", column.name)));
+            }
+            if column.not_null && input.validity.is_some_and(|v| v.contains(&false)) {
+                return Err(not_null_error(column));
+            }
+
+            match input.data {
+                ColumnSlice::Values(values) => {
+                    if column.not_null && values.iter().any(|v| matches!(v, Value::Null)) {
+                        return Err(not_null_error(column));
+                    }
+                    let by_ordinal: HashMap<u16, &TableColumn> = column.flatten().into_iter().map(|c| (c.ordinal, c)).collect();
+                    for value in values {
+                        nested::shred(column, value, &mut scratch).map_err(invalid)?;
+                        for (ordinal, value) in scratch.drain(..) {
+                            self.encode_value(by_ordinal[&ordinal], &value)?;
+                        }
+                    }
+                }
+                data => {
+                    if column.column_type.is_nested() {
+                        return Err(invalid(format!("nested column '{}' needs Value input", column.name)));
+                    }
+                    data.check_column_type(column.column_type)
+                        .map_err(|e| invalid(format!("column '{}': {e}", column.name)))?;
+                    for i in (0..row_count).filter(|&i| input.is_valid(i)) {
+                        self.check_value_len(column, data.encoded_len(i, column.column_type))?;
+                    }
+                }
+            }
+        }
+
+        let mut offset = 0;
+        while offset < row_count {
+            let take = ((ROWS_PER_CHUNK - self.table_rows(table_id).active_rows()) as usize)
+                .min(row_count - offset);
+            let range = offset..offset + take;
+
+            for (column, input) in columns.iter().zip(inputs) {
+                match input {
+                    Some(input) if !matches!(input.data, ColumnSlice::Values(_)) => {
+                        self.append_slice(column, input, range.clone())?;
+                    }
+                    _ => {
+                        for i in range.clone() {
+                            let value = match input {
+                                Some(input) if input.is_valid(i) => input.data.value(i),
+                                _ => Value::Null,
+                            };
+
+                            nested::shred(column, &value, &mut scratch).map_err(invalid)?;
+                            for (ordinal, value) in scratch.drain(..) {
+                                let target = column.flatten().into_iter().find(|c| c.ordinal == ordinal).unwrap();
+                                self.append_value(target, &value)?;
+                            }
+                        }
+                    }
+                }
+            }
+
+            let rows = self.table_rows.entry(table_id).or_default();
+            rows.next_row_id += take as u64;
+            offset += take;
+
+            if rows.active_rows() >= ROWS_PER_CHUNK {
+                self.seal_table(table_id)?;
+            }
+        }
+
+        Ok(row_count)
+    }
+
+    fn append_slice(&mut self, column: &TableColumn, input: &ColumnInput, range: Range<usize>) -> Result<(), Error> {
+        let column_type = column.column_type;
+
+        self.active_chunk(column)?;
+        let active = self.active_chunks.get_mut(&(column.table_id, column.ordinal)).unwrap();
+
+        let mut null_count = 0;
+        for i in range.clone() {
+            let valid = input.is_valid(i);
+            let len = if valid { input.data.encoded_len(i, column_type) } else { 0 };
+
+            if !valid {
+                null_count += 1;
+            }
+
+            if let Some(slot) = active.tail.reserve_value(len, valid) {
+                if valid {
+                    input.data.encode_into(i, column_type, slot);
+                    active.distinct.insert(slot);
+                }
+                continue;
+            }
+
+            Self::chain_page(&mut self.pager, active)?;
+            let slot = active.tail.reserve_value(len, valid).ok_or_else(|| {
+                Error::new(ErrorKind::InvalidInput, "value is larger than a data page")
+            })?;
+            if valid {
+                input.data.encode_into(i, column_type, slot);
+                active.distinct.insert(slot);
+            }
+        }
+
+        let (min, max) = input.data.min_max(range.clone(), |i| input.is_valid(i));
+        active.observe_bulk(range.len() as u32, null_count, min, max);
+        Ok(())
+    }
+
+    /// Persists the full tail page of an active chunk and replaces it with a
+    /// freshly allocated page linked after it.
+    fn chain_page(pager: &mut Pager, active: &mut ActiveChunk) -> Result<(), Error> {
+        let next = pager.allocate_page(PageInit::ChunkData {
+            table_id: active.table_id,
+            column_ordinal: active.column_ordinal,
+        })?;
+
+        active.tail.set_next_data_page(next.header.page_id);
+        pager.write_page(active.tail.header.page_id as u64, &active.tail)?;
+
+        active.pages.push(next.header.page_id);
+        active.tail = next;
+        Ok(())
+    }
+
+    fn active_chunk(&mut self, column: &TableColumn) -> Result<&mut ActiveChunk, Error> {
+        let key = (column.table_id, column.ordinal);
+
+        if !self.active_chunks.contains_key(&key) {
+            let page = self.pager.allocate_page(PageInit::ChunkData {
+                table_id: column.table_id,
+                column_ordinal: column.ordinal,
+            })?;
+            let row_start = self.table_rows(column.table_id).active_row_start;
+
+            self.active_chunks.insert(
+                key,
+                ActiveChunk::new(column.column_id, column.column_type, row_start, page),
+            );
+        }
+
+        Ok(self.active_chunks.get_mut(&key).unwrap())
+    }
+
+    /// Seals every active chunk of the table: flushes the tail pages and
+    /// persists a `ChunkMeta` (with min/max statistics) per column.
+    pub fn seal_table(&mut self, table_id: u32) -> Result<(), Error> {
+        let rows = self.table_rows(table_id);
+        if rows.active_rows() == 0 {
+            return Ok(());
+        }
+
+        let mut keys: Vec<(u32, u16)> = self.active_chunks
+            .keys()
+            .filter(|(t, _)| *t == table_id)
+            .copied()
+            .collect();
+        keys.sort();
+
+        // Chunks holding uncommitted rows belong to the next version
+        let commit_version = if rows.committed_row_end < rows.next_row_id {
+            self.records_pending = true;
+            self.version + 1
+        } else {
+            self.version
+        };
+
+        for key in keys {
+            let active = self.active_chunks.remove(&key).unwrap();
+            self.pager.write_page(active.tail.header.page_id as u64, &active.tail)?;
+
+            let chunks = self.chunk_index.entry((table_id, active.column_id)).or_default();
+            let chunk = ChunkMeta {
+                table_id,
+                column_id: active.column_id,
+                chunk_id: chunks.len() as u32,
+                row_start: active.row_start,
+                row_end: rows.next_row_id,
+                column_type: active.column_type,
+                first_page_id: active.first_page_id as u64,
+                page_count: active.pages.len() as u64,
+                value_count: active.value_count,
+                null_count: active.null_count,
+                min: active.min,
+                max: active.max,
+                distinct: active.distinct,
+                commit_version,
+                committed_row_end: rows.committed_row_end.max(active.row_start),
+            };
+
+            self.pager.insert_chunk_meta(&chunk)?;
+            chunks.push(chunk);
+        }
+
+        if commit_version == self.version && self.version > self.durable_version {
+            self.pager.insert_commit_marker(&CommitMarker { version: commit_version })?;
+            self.durable_version = commit_version;
+        }
+
+        self.table_rows.entry(table_id).or_default().active_row_start = rows.next_row_id;
+        Ok(())
+    }
+
+    /// Seals the active chunks of every table.
+    pub fn seal_all(&mut self) -> Result<(), Error> {
+        let mut tables: Vec<u32> = self.table_rows.keys().copied().collect();
+        tables.sort();
+
+        for table_id in tables {
+            self.seal_table(table_id)?;
+        }
+        Ok(())
+    }
+
+    pub fn chunks_for(&self, table_id: u32, column_id: u32) -> &[ChunkMeta] {
+        self.chunk_index
+            .get(&(table_id, column_id))
+            .map(|c| c.as_slice())
+            .unwrap_or(&[])
     }
-}
\ No newline at end of file
+
+    /// Decodes every value of a sealed chunk, following its page chain.
+    pub fn read_chunk(&self, chunk: &ChunkMeta) -> Result<Vec<Value>, Error> {
+        let mut values = Vec::with_capacity(chunk.value_count as usize);
+        let mut page_id = chunk.first_page_id;
+
+        for _ in 0..chunk.page_count {
+            let page = self.read_data_page(page_id)?;
+            values.extend(page.read_values(chunk.column_type)?);
+            page_id = page.chunk_header().next_page_id as u64;
+        }
+
+        Ok(values)
+    }
+
+    /// Decodes every value appended so far to an active chunk. The tail page
+    /// is read from memory, it is only written to disk once full or sealed.
+    pub fn read_active_chunk(&self, active: &ActiveChunk) -> Result<Vec<Value>, Error> {
+        let mut values = Vec::with_capacity(active.value_count as usize);
+
+        for page_id in &active.pages[..active.pages.len() - 1] {
+            let page = self.read_data_page(*page_id as u64)?;
+            values.extend(page.read_values(active.column_type)?);
+        }
+        values.extend(active.tail.read_values(active.column_type)?);
+        self.count_read(0, active.tail.value_bytes());
+
+        Ok(values)
+    }
+
+    /// Totals of the reads made by the calling thread so far.
+    pub fn read_stats(&self) -> ReadStats {
+        READ_STATS.get()
+    }
+
+    fn read_data_page(&self, page_id: u64) -> Result<Page, Error> {
+        let page = self.pager.read_page(page_id)?;
+        self.count_read(1, page.value_bytes());
+        Ok(page)
+    }
+
+    fn count_read(&self, pages: u64, bytes: usize) {
+        let mut stats = READ_STATS.get();
+        stats.pages_read += pages;
+        stats.bytes_decoded += bytes as u64;
+        READ_STATS.set(stats);
+    }
+
+    /// Values of `column` for the rows starting at `row_start`, from the sealed
+    /// or active chunk covering them. A column with no chunk for that range
+    /// (added after the rows were written) yields no values.
+    pub fn read_column_range(&self, column: &TableColumn, row_start: u64) -> Result<Vec<Value>, Error> {
+        let chunks = self.chunks_for(column.table_id, column.column_id);
+        if let Ok(i) = chunks.binary_search_by_key(&row_start, |c| c.row_start) {
+            return self.read_chunk(&chunks[i]);
+        }
+
+        match self.active_chunks.get(&(column.table_id, column.ordinal)) {
+            Some(active) if active.row_start == row_start => self.read_active_chunk(active),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Values of a flat `column` for the rows starting at `row_start`, as
+    /// [`ChunkManager::read_column_range`] reads them, decoded into a
+    /// [`Vector`] page by page.
+    pub fn read_vector_range(&self, column: &TableColumn, row_start: u64) -> Result<Vector, Error> {
+        let mut vector = Vector::new(column.column_type);
+        let chunks = self.chunks_for(column.table_id, column.column_id);
+        if let Ok(i) = chunks.binary_search_by_key(&row_start, |c| c.row_start) {
+            let chunk = &chunks[i];
+            let mut page_id = chunk.first_page_id;
+            for _ in 0..chunk.page_count {
+                let page = self.read_data_page(page_id)?;
+                vector.extend_from_page(&page, chunk.column_type)?;
+                page_id = page.chunk_header().next_page_id as u64;
+            }
+            return Ok(vector);
+        }
+
+        if let Some(active) = self.active_chunks.get(&(column.table_id, column.ordinal)) {
+            if active.row_start == row_start {
+                for page_id in &active.pages[..active.pages.len() - 1] {
+                    let page = self.read_data_page(*page_id as u64)?;
+                    vector.extend_from_page(&page, active.column_type)?;
+                }
+                vector.extend_from_page(&active.tail, active.column_type)?;
+                self.count_read(0, active.tail.value_bytes());
+            }
+        }
+        Ok(vector)
+    }
+}
+
+pub(crate) fn not_null_error(column: &TableColumn) -> Error {
+    Error::new(ErrorKind::InvalidInput, format!("column '{}' is NOT NULL", column.name))
+}
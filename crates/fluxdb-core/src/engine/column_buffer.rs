@@ -0,0 +1,155 @@
+use crate::engine::column_slice::{ColumnInput, ColumnSlice};
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+
+#[derive(Debug, Clone)]
+pub enum ColumnData {
+    Int32(Vec<i32>),
+    Int64(Vec<i64>),
+    Float32(Vec<f32>),
+    Float64(Vec<f64>),
+    Bool(Vec<bool>),
+    Utf8(Vec<String>),
+    Timestamp(Vec<i64>),
+    Binary(Vec<Vec<u8>>),
+    Uuid(Vec<[u8; 16]>),
+    Values(Vec<Value>),
+}
+
+/// Owned, growable column of values used to stage rows for
+/// [`Database::append_columns`](crate::engine::database::Database::append_columns).
+#[derive(Debug, Clone)]
+pub struct ColumnBuffer {
+    pub name: String,
+    pub column_type: ColumnType,
+    pub data: ColumnData,
+    pub validity: Vec<bool>,
+    null_count: usize,
+}
+
+impl ColumnBuffer {
+    pub fn new(name: &str, column_type: ColumnType) -> Self {
+        let data = match column_type {
+            ColumnType::Integer32 => ColumnData::Int32(Vec::new()),
+            ColumnType::Integer64 => ColumnData::Int64(Vec::new()),
+            ColumnType::Float32 => ColumnData::Float32(Vec::new()),
+            ColumnType::Float64 => ColumnData::Float64(Vec::new()),
+            ColumnType::Boolean => ColumnData::Bool(Vec::new()),
+            ColumnType::Utf8 => ColumnData::Utf8(Vec::new()),
+            ColumnType::Timestamp => ColumnData::Timestamp(Vec::new()),
+            ColumnType::Binary | ColumnType::FixedSizeBinary(_) => ColumnData::Binary(Vec::new()),
+            ColumnType::Uuid => ColumnData::Uuid(Vec::new()),
+            ColumnType::List | ColumnType::Struct => ColumnData::Values(Vec::new()),
+        };
+
+        Self {
+            name: name.to_string(),
+            column_type,
+            data,
+            validity: Vec::new(),
+            null_count: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.validity.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.validity.is_empty()
+    }
+
+    /// Appends a value, which must match the buffer's column type.
+    pub fn push(&mut self, value: Value) -> Result<(), String> {
+        if value.is_null() {
+            self.push_null();
+            return Ok(());
+        }
+        if !value.matches_column_type(self.column_type) {
+            return Err(format!("value {value} does not match column type {:?}", self.column_type));
+        }
+
+        match (&mut self.data, value) {
+            (ColumnData::Int32(v), Value::Int32(x)) => v.push(x),
+            (ColumnData::Int64(v), Value::Int64(x)) => v.push(x),
+            (ColumnData::Float32(v), Value::Float32(x)) => v.push(x),
+            (ColumnData::Float64(v), Value::Float64(x)) => v.push(x),
+            (ColumnData::Bool(v), Value::Bool(x)) => v.push(x),
+            (ColumnData::Utf8(v), Value::String(x)) => v.push(x),
+            (ColumnData::Timestamp(v), Value::Timestamp(x)) => v.push(x),
+            (ColumnData::Binary(v), Value::Binary(x)) => v.push(x),
+            (ColumnData::Uuid(v), Value::Uuid(x)) => v.push(x),
+            (ColumnData::Values(v), x) => v.push(x),
+            _ => unreachable!("checked by matches_column_type"),
+        }
+
+        self.validity.push(true);
+        Ok(())
+    }
+
+    /// Appends a null. Typed data gets a placeholder that is never read.
+    pub fn push_null(&mut self) {
+        match &mut self.data {
+            ColumnData::Int32(v) => v.push(0),
+            ColumnData::Int64(v) => v.push(0),
+            ColumnData::Float32(v) => v.push(0.0),
+            ColumnData::Float64(v) => v.push(0.0),
+            ColumnData::Bool(v) => v.push(false),
+            ColumnData::Utf8(v) => v.push(String::new()),
+            ColumnData::Timestamp(v) => v.push(0),
+            ColumnData::Binary(v) => match self.column_type {
+                ColumnType::FixedSizeBinary(width) => v.push(vec![0; width as usize]),
+                _ => v.push(Vec::new()),
+            },
+            ColumnData::Uuid(v) => v.push([0; 16]),
+            ColumnData::Values(v) => v.push(Value::Null),
+        }
+
+        self.validity.push(false);
+        self.null_count += 1;
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new(&self.name, self.column_type);
+    }
+
+    /// Borrowed views of the string and binary values, which
+    /// [`ColumnBuffer::input`] needs to build a [`ColumnSlice`].
+    pub fn views(&self) -> BufferViews<'_> {
+        match &self.data {
+            ColumnData::Utf8(v) => BufferViews::Utf8(v.iter().map(|s| s.as_str()).collect()),
+            ColumnData::Binary(v) => BufferViews::Binary(v.iter().map(|b| b.as_slice()).collect()),
+            _ => BufferViews::None,
+        }
+    }
+
+    /// Borrows the buffer as a bulk append input.
+    pub fn input<'a>(&'a self, views: &'a BufferViews<'a>) -> ColumnInput<'a> {
+        let data = match (&self.data, views) {
+            (ColumnData::Int32(v), _) => ColumnSlice::Int32(v),
+            (ColumnData::Int64(v), _) => ColumnSlice::Int64(v),
+            (ColumnData::Float32(v), _) => ColumnSlice::Float32(v),
+            (ColumnData::Float64(v), _) => ColumnSlice::Float64(v),
+            (ColumnData::Bool(v), _) => ColumnSlice::Bool(v),
+            (ColumnData::Timestamp(v), _) => ColumnSlice::Timestamp(v),
+            (ColumnData::Uuid(v), _) => ColumnSlice::Uuid(v),
+            (ColumnData::Values(v), _) => ColumnSlice::Values(v),
+            (ColumnData::Utf8(_), BufferViews::Utf8(v)) => ColumnSlice::Utf8(v),
+            (ColumnData::Binary(_), BufferViews::Binary(v)) => ColumnSlice::Binary(v),
+            _ => panic!("views do not belong to column buffer '{}'", self.name),
+        };
+
+        let input = ColumnInput::new(&self.name, data);
+        if self.null_count > 0 {
+            input.with_validity(&self.validity)
+        } else {
+            input
+        }
+    }
+}
+
+pub enum BufferViews<'a> {
+    None,
+    Utf8(Vec<&'a str>),
+    Binary(Vec<&'a [u8]>),
+}
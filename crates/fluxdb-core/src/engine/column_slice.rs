@@ -0,0 +1,192 @@
+use std::cmp::Ordering;
+use std::ops::Range;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+
+/// A borrowed column of values for bulk appends.
+#[derive(Debug, Clone, Copy)]
+pub enum ColumnSlice<'a> {
+    Int32(&'a [i32]),
+    Int64(&'a [i64]),
+    Float32(&'a [f32]),
+    Float64(&'a [f64]),
+    Bool(&'a [bool]),
+    Utf8(&'a [&'a str]),
+    Timestamp(&'a [i64]),
+    /// Values for `Binary` or `FixedSizeBinary` columns.
+    Binary(&'a [&'a [u8]]),
+    Uuid(&'a [[u8; 16]]),
+    /// Values of any column type, nested ones included. Written through the
+    /// row-at-a-time path, so prefer the typed variants where possible.
+    Values(&'a [Value]),
+}
+
+/// One column of a bulk append.
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnInput<'a> {
+    pub name: &'a str,
+    pub data: ColumnSlice<'a>,
+    /// `false` marks a null row. `None` means the column has no nulls.
+    pub validity: Option<&'a [bool]>,
+}
+
+impl<'a> ColumnInput<'a> {
+    pub fn new(name: &'a str, data: ColumnSlice<'a>) -> Self {
+        Self { name, data, validity: None }
+    }
+
+    pub fn with_validity(mut self, validity: &'a [bool]) -> Self {
+        self.validity = Some(validity);
+        self
+    }
+
+    pub fn is_valid(&self, index: usize) -> bool {
+        self.validity.is_none_or(|v| v[index])
+    }
+}
+
+impl ColumnSlice<'_> {
+    pub fn len(&self) -> usize {
+        match self {
+            ColumnSlice::Int32(v) => v.len(),
+            ColumnSlice::Int64(v) => v.len(),
+            ColumnSlice::Float32(v) => v.len(),
+            ColumnSlice::Float64(v) => v.len(),
+            ColumnSlice::Bool(v) => v.len(),
+            ColumnSlice::Utf8(v) => v.len(),
+            ColumnSlice::Timestamp(v) => v.len(),
+            ColumnSlice::Binary(v) => v.len(),
+            ColumnSlice::Uuid(v) => v.len(),
+            ColumnSlice::Values(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Checks the slice can be written to a column of `column_type`.
+    pub fn check_column_type(&self, column_type: ColumnType) -> Result<(), String> {
+        let matches = match (self, column_type) {
+            (ColumnSlice::Int32(_), ColumnType::Integer32) => true,
+            (ColumnSlice::Int64(_), ColumnType::Integer64) => true,
+            (ColumnSlice::Float32(_), ColumnType::Float32) => true,
+            (ColumnSlice::Float64(_), ColumnType::Float64) => true,
+            (ColumnSlice::Bool(_), ColumnType::Boolean) => true,
+            (ColumnSlice::Utf8(_), ColumnType::Utf8) => true,
+            (ColumnSlice::Timestamp(_), ColumnType::Timestamp) => true,
+            (ColumnSlice::Binary(_), ColumnType::Binary) => true,
+            (ColumnSlice::Binary(v), ColumnType::FixedSizeBinary(width)) => {
+                if let Some(b) = v.iter().find(|b| b.len() != width as usize) {
+                    return Err(format!("binary value of {} bytes in FixedSizeBinary({width}) column", b.len()));
+                }
+                true
+            }
+            (ColumnSlice::Uuid(_), ColumnType::Uuid) => true,
+            (ColumnSlice::Values(_), _) => true,
+            _ => false,
+        };
+
+        if matches {
+            Ok(())
+        } else {
+            Err(format!("column data does not match column type {column_type:?}"))
+        }
+    }
+
+    /// Encoded size of value `index`, see [`Value::encode`] for the layout.
+    pub fn encoded_len(&self, index: usize, column_type: ColumnType) -> usize {
+        match self {
+            ColumnSlice::Utf8(v) => 4 + v[index].len(),
+            ColumnSlice::Binary(v) if column_type == ColumnType::Binary => 4 + v[index].len(),
+            ColumnSlice::Binary(v) => v[index].len(),
+            _ => column_type.fixed_width().unwrap(),
+        }
+    }
+
+    /// Encodes value `index` into `out`, which is exactly
+    /// [`ColumnSlice::encoded_len`] bytes long.
+    pub fn encode_into(&self, index: usize, column_type: ColumnType, out: &mut [u8]) {
+        let length_prefixed = |out: &mut [u8], bytes: &[u8]| {
+            out[..4].copy_from_slice(&(bytes.len() as u32).to_le_bytes());
+            out[4..].copy_from_slice(bytes);
+        };
+
+        match self {
+            ColumnSlice::Int32(v) => out.copy_from_slice(&v[index].to_le_bytes()),
+            ColumnSlice::Int64(v) => out.copy_from_slice(&v[index].to_le_bytes()),
+            ColumnSlice::Float32(v) => out.copy_from_slice(&v[index].to_le_bytes()),
+            ColumnSlice::Float64(v) => out.copy_from_slice(&v[index].to_le_bytes()),
+            ColumnSlice::Bool(v) => out[0] = v[index] as u8,
+            ColumnSlice::Timestamp(v) => out.copy_from_slice(&v[index].to_le_bytes()),
+            ColumnSlice::Uuid(v) => out.copy_from_slice(&v[index]),
+            ColumnSlice::Utf8(v) => length_prefixed(out, v[index].as_bytes()),
+            ColumnSlice::Binary(v) if column_type == ColumnType::Binary => length_prefixed(out, v[index]),
+            ColumnSlice::Binary(v) => out.copy_from_slice(v[index]),
+            ColumnSlice::Values(_) => unreachable!("Values are appended through the row path"),
+        }
+    }
+
+    pub fn value(&self, index: usize) -> Value {
+        match self {
+            ColumnSlice::Int32(v) => Value::Int32(v[index]),
+            ColumnSlice::Int64(v) => Value::Int64(v[index]),
+            ColumnSlice::Float32(v) => Value::Float32(v[index]),
+            ColumnSlice::Float64(v) => Value::Float64(v[index]),
+            ColumnSlice::Bool(v) => Value::Bool(v[index]),
+            ColumnSlice::Utf8(v) => Value::String(v[index].to_string()),
+            ColumnSlice::Timestamp(v) => Value::Timestamp(v[index]),
+            ColumnSlice::Binary(v) => Value::Binary(v[index].to_vec()),
+            ColumnSlice::Uuid(v) => Value::Uuid(v[index]),
+            ColumnSlice::Values(v) => v[index].clone(),
+        }
+    }
+
+    /// Min and max of the non-null values in `range`, computed on the typed
+    /// data so only the two results are converted to `Value`s.
+    pub fn min_max(&self, range: Range<usize>, is_valid: impl Fn(usize) -> bool) -> (Option<Value>, Option<Value>) {
+        fn extremes<T>(
+            items: &[T],
+            range: Range<usize>,
+            is_valid: impl Fn(usize) -> bool,
+            cmp: impl Fn(&T, &T) -> Option<Ordering>,
+        ) -> Option<(usize, usize)> {
+            let mut result: Option<(usize, usize)> = None;
+
+            for i in range {
+                // Skip nulls and unordered values (NaN)
+                if !is_valid(i) || cmp(&items[i], &items[i]).is_none() {
+                    continue;
+                }
+
+                result = Some(match result {
+                    None => (i, i),
+                    Some((min, max)) => (
+                        if cmp(&items[i], &items[min]) == Some(Ordering::Less) { i } else { min },
+                        if cmp(&items[i], &items[max]) == Some(Ordering::Greater) { i } else { max },
+                    ),
+                });
+            }
+
+            result
+        }
+
+        let found = match self {
+            ColumnSlice::Int32(v) => extremes(v, range, is_valid, |a, b| a.partial_cmp(b)),
+            ColumnSlice::Int64(v) => extremes(v, range, is_valid, |a, b| a.partial_cmp(b)),
+            ColumnSlice::Float32(v) => extremes(v, range, is_valid, |a, b| a.partial_cmp(b)),
+            ColumnSlice::Float64(v) => extremes(v, range, is_valid, |a, b| a.partial_cmp(b)),
+            ColumnSlice::Bool(v) => extremes(v, range, is_valid, |a, b| a.partial_cmp(b)),
+            ColumnSlice::Utf8(v) => extremes(v, range, is_valid, |a, b| a.partial_cmp(b)),
+            ColumnSlice::Timestamp(v) => extremes(v, range, is_valid, |a, b| a.partial_cmp(b)),
+            ColumnSlice::Binary(v) => extremes(v, range, is_valid, |a, b| a.partial_cmp(b)),
+            ColumnSlice::Uuid(v) => extremes(v, range, is_valid, |a, b| a.partial_cmp(b)),
+            ColumnSlice::Values(v) => extremes(v, range, is_valid, |a, b| a.compare(b)),
+        };
+
+        match found {
+            Some((min, max)) => (Some(self.value(min)), Some(self.value(max))),
+            None => (None, None),
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::io::{Error, ErrorKind, Result};
+use std::collections::BTreeSet;
+use crate::engine::chunk_manager::{ChunkManager, ROWS_PER_CHUNK};
+use crate::engine::database::Database;
+use crate::metadata::schema::table_column::TableColumn;
+use crate::metadata::value::Value;
+
+/// Which chunks compaction rewrites, and into how large chunks.
+#[derive(Debug, Clone, Copy)]
+pub struct CompactionOptions {
+    /// Chunks with fewer live rows than this are small, and merged with
+    /// their neighbours.
+    pub small_chunk_rows: u64,
+    /// Chunks with at least this fraction of their rows deleted are
+    /// rewritten without them.
+    pub deleted_fraction: f64,
+    /// Most live rows merged into one chunk.
+    pub target_chunk_rows: u64,
+}
+
+impl Default for CompactionOptions {
+    fn default() -> Self {
+        CompactionOptions {
+            small_chunk_rows: ROWS_PER_CHUNK / 4,
+            deleted_fraction: 0.2,
+            target_chunk_rows: ROWS_PER_CHUNK,
+        }
+    }
+}
+
+/// What a compaction rewrote.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompactionStats {
+    /// Row ranges replaced, each a chunk per column.
+    pub chunks_replaced: usize,
+    /// Row ranges written in their place.
+    pub chunks_written: usize,
+    /// Live rows copied into the new chunks.
+    pub rows_written: u64,
+    /// Deleted rows left out of them.
+    pub rows_purged: u64,
+    /// Pages of the replaced chunks put on the free list.
+    pub pages_freed: usize,
+}
+
+impl CompactionStats {
+    /// Adds the counters of another compaction, such as of another table.
+    pub fn merge(&mut self, other: &CompactionStats) {
+        self.chunks_replaced += other.chunks_replaced;
+        self.chunks_written += other.chunks_written;
+        self.rows_written += other.rows_written;
+        self.rows_purged += other.rows_purged;
+        self.pages_freed += other.pages_freed;
+    }
+}
+
+/// Adjacent sealed row ranges merged into one.
+struct Group {
+    row_start: u64,
+    row_end: u64,
+    ranges: usize,
+    live_rows: u64,
+    deleted_rows: u64,
+}
+
+/// Rewrites the small and heavily deleted sealed chunks of a table, for
+/// all its columns: runs of them are merged into chunks of up to
+/// [`CompactionOptions::target_chunk_rows`] live rows, re-encoded with fresh
+/// statistics and without the deleted rows.
+///
+/// A merged range keeps its first row id and its rows their order; the
+/// rows it no longer holds are left unused. Each range's new chunks replace
+/// the old ones, and their delete vectors, in a commit of its own, so the
+/// next range is read from committed chunks only; the open transaction, if
+/// there is one, is committed first. The old pages are freed once no
+/// snapshot can still read them. The active rows are left alone.
+pub fn compact_table(db: &mut Database, table_name: &str, options: &CompactionOptions) -> Result<CompactionStats> {
+    let table_id = db.catalog.tables_by_name
+        .get(table_name)
+        .copied()
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, "table not found"))?;
+    let columns = db.catalog.columns_by_table.get(&table_id).cloned().unwrap_or_default();
+    db.commit()?;
+
+    let groups = plan(&db.chunk_manager, table_id, &columns, options);
+    let mut stats = CompactionStats::default();
+    if groups.is_empty() {
+        return Ok(stats);
+    }
+
+    for group in &groups {
+        let rows = live_rows(db, table_name, group)?;
+        db.chunk_manager.rewrite_rows(&columns, group.row_start, &rows)?;
+        stats.pages_freed += db.chunk_manager.commit_rewrites(table_id, &[(group.row_start, group.row_end)])?;
+
+        stats.chunks_replaced += group.ranges;
+        stats.chunks_written += (rows.len() as u64).div_ceil(ROWS_PER_CHUNK) as usize;
+        stats.rows_written += rows.len() as u64;
+        stats.rows_purged += group.deleted_rows;
+    }
+    Ok(stats)
+}
+
+/// Compacts every table, see [`compact_table`].
+pub fn compact(db: &mut Database, options: &CompactionOptions) -> Result<CompactionStats> {
+    let mut tables: Vec<(u32, String)> = db.catalog.tables_by_name
+        .iter()
+        .map(|(name, &table_id)| (table_id, name.clone()))
+        .collect();
+    tables.sort();
+
+    let mut stats = CompactionStats::default();
+    for (_, table_name) in tables {
+        stats.merge(&compact_table(db, &table_name, options)?);
+    }
+    Ok(stats)
+}
+
+/// Groups the table's sealed row ranges worth rewriting: runs of adjacent
+/// small or heavily deleted ranges, as long as their live rows fit a
+/// chunk. A group is only rewritten when it merges ranges or drops rows.
+fn plan(chunk_manager: &ChunkManager, table_id: u32, columns: &[TableColumn], options: &CompactionOptions) -> Vec<Group> {
+    let committed = chunk_manager.table_rows(table_id).committed_row_end;
+    let ranges: BTreeSet<(u64, u64)> = columns
+        .iter()
+        .flat_map(|column| chunk_manager.chunks_for(table_id, column.column_id))
+        .filter(|chunk| chunk.row_end <= committed)
+        .map(|chunk| (chunk.row_start, chunk.row_end))
+        .collect();
+
+    let mut groups: Vec<Group> = Vec::new();
+    let mut run: Option<Group> = None;
+    for (row_start, row_end) in ranges {
+        let rows = row_end - row_start;
+        let deleted_rows = chunk_manager
+            .deleted_rows(table_id, row_start, rows as usize)
+            .map_or(0, |deleted| deleted.iter().filter(|&&d| d).count() as u64);
+        let live_rows = rows - deleted_rows;
+
+        let candidate = live_rows < options.small_chunk_rows
+            || (deleted_rows > 0 && deleted_rows as f64 >= rows as f64 * options.deleted_fraction);
+        let fits = run.as_ref().is_some_and(|r| r.live_rows + live_rows <= options.target_chunk_rows);
+        if !candidate || !fits {
+            groups.extend(run.take());
+        }
+        if candidate {
+            let group = run.get_or_insert(Group { row_start, row_end, ranges: 0, live_rows: 0, deleted_rows: 0 });
+            group.row_end = row_end;
+            group.ranges += 1;
+            group.live_rows += live_rows;
+            group.deleted_rows += deleted_rows;
+        }
+    }
+    groups.extend(run);
+
+    groups.retain(|group| group.ranges > 1 || group.deleted_rows > 0);
+    groups
+}
+
+/// The rows of a group its deletes left, one value per top-level column.
+fn live_rows(db: &Database, table_name: &str, group: &Group) -> Result<Vec<Vec<Value>>> {
+    let mut rows = Vec::with_capacity(group.live_rows as usize);
+    for batch in db.scan(table_name, &[])?.within_rows(group.row_start, group.row_end) {
+        let batch = batch?;
+        let selected = batch.selection.as_ref().map_or(batch.row_count, |selection| selection.len());
+        for i in 0..selected {
+            rows.push(batch.columns.iter().map(|column| column[i].clone()).collect());
+        }
+    }
+    Ok(rows)
+}
@@ -1,25 +1,51 @@
-use std::collections::HashMap;
 use std::fs::OpenOptions;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::io::{Error, ErrorKind, Result};
-use std::ptr::null;
+use std::sync::Arc;
 use crate::engine::catalog::Catalog;
 use crate::engine::chunk_manager::ChunkManager;
+use crate::engine::column_slice::ColumnInput;
+use crate::engine::compaction::{self, CompactionOptions, CompactionStats};
 use crate::engine::initializer::Initializer;
+use crate::engine::table_scan::TableScan;
+use crate::engine::vacuum::{self, VacuumStats};
+use crate::metadata::schema::catalog_change::CatalogChange;
 use crate::metadata::schema::column_type::ColumnType;
-use crate::storage::chunk_data_header::ChunkDataHeader;
-use crate::storage::page_header::PageHeader;
-use crate::storage::page_type::PageType;
-use crate::storage::pager::{PageInit, Pager};
+use crate::metadata::schema::table_column::TableColumn;
+use crate::metadata::value::Value;
+use crate::query::execute::QueryOptions;
+use crate::query::expr::Expr;
+use crate::query::stream::BatchStream;
+use crate::storage::file_lock::{FileLock, LockMode};
+use crate::storage::pager::Pager;
 
 pub struct Database {
     pub catalog: Catalog,
     pub chunk_manager: ChunkManager,
+    /// Whether appends wait for [`Database::commit`] instead of committing
+    /// one by one.
+    in_transaction: bool,
+    /// Keeps other processes from writing the file while this database,
+    /// or any snapshot of it, is open.
+    lock: Arc<FileLock>,
+    path: PathBuf,
 }
 impl Database {
     /// Opens an existing database or creates a new one if it does not exist.
     /// Loads the catalog ONCE and caches it in memory.
+    ///
+    /// The file is locked exclusively first: opening fails with
+    /// [`ErrorKind::ResourceBusy`] while another process has it open.
+    /// Without `initialize`, a missing file fails with
+    /// [`ErrorKind::NotFound`] before any lock file is created.
     pub fn open(path: &Path, initialize: bool) -> Result<Self> {
+        if !initialize && !path.exists() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("database '{}' does not exist", path.display()),
+            ));
+        }
+        let lock = Arc::new(FileLock::acquire(path, LockMode::Exclusive)?);
         let initializer = Initializer::new(path);
         if initialize {
             initializer.init_db_file(); // safe to call multiple times
@@ -37,26 +63,67 @@ impl Database {
 
         let catalog = match chunk_manager.load_catalog() {
             Ok(catalog) => catalog,
-            Err(e) => {
+            Err(_) => {
                 chunk_manager.init_catalog_root()?;
                 chunk_manager.load_catalog()?
             }
         };
 
-        let mut db =Self {
+        chunk_manager.load_chunk_index()?;
+        chunk_manager.retain_chunks(&catalog);
+
+        let mut db = Self {
             catalog,
-            chunk_manager
+            chunk_manager,
+            in_transaction: false,
+            lock,
+            path: path.to_path_buf(),
         };
 
-        if(initialize){
+        if initialize {
             db.seed_schema().unwrap();
         }
 
         Ok(db)
     }
 
+    /// Opens an existing database for reading only. Any number of processes
+    /// may do so at once, but not while one has it open for writing, see
+    /// [`Database::open`]. Changing the database fails with
+    /// [`ErrorKind::PermissionDenied`].
+    pub fn open_read_only(path: &Path) -> Result<Self> {
+        // Opened before locking, so a missing database leaves no lock file
+        let file = OpenOptions::new().read(true).open(path)?;
+        let lock = Arc::new(FileLock::acquire(path, LockMode::Shared)?);
+        let header = Initializer::new(path).read_header();
+
+        let mut chunk_manager = ChunkManager::new(Pager::new_read_only(file, header));
+        let catalog = chunk_manager.load_catalog()?;
+        chunk_manager.load_chunk_index()?;
+        chunk_manager.retain_chunks(&catalog);
+
+        Ok(Self { catalog, chunk_manager, in_transaction: false, lock, path: path.to_path_buf() })
+    }
+
+    /// A read-only copy of the database as it is now, for
+    /// [`SharedDatabase`](crate::engine::shared_database::SharedDatabase)
+    /// readers: it shares the file, but its catalog and chunks stay as they
+    /// are while this database goes on changing.
+    pub(crate) fn snapshot(&self) -> Database {
+        Database {
+            catalog: self.catalog.clone(),
+            chunk_manager: self.chunk_manager.snapshot(),
+            in_transaction: false,
+            lock: Arc::clone(&self.lock),
+            path: self.path.clone(),
+        }
+    }
+
     /// Creates a table (disk + memory)
     pub fn create_table(&mut self, name: &str) -> Result<()> {
+        if self.catalog.tables_by_name.contains_key(name) {
+            return Err(Error::new(ErrorKind::AlreadyExists, format!("table '{name}' already exists")));
+        }
         let table = self.chunk_manager.create_table(name)?;
 
         self.catalog
@@ -77,9 +144,35 @@ impl Database {
         column_name: &str,
         column_type: ColumnType,
     ) -> Result<()> {
-        let table_id = *self.catalog.tables_by_name
-            .get(table_name)
-            .ok_or_else(|| Error::new(std::io::ErrorKind::NotFound, "table not found"))?;
+        self.add_top_level_column(table_name, column_name, column_type, false)
+    }
+
+    /// Adds a column whose appends reject nulls. Rows already in the table
+    /// would read as null, so the table must be empty.
+    pub fn add_not_null_column(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+        column_type: ColumnType,
+    ) -> Result<()> {
+        let table_id = self.table_id(table_name)?;
+        if self.chunk_manager.table_rows(table_id).next_row_id > 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("cannot add NOT NULL column '{column_name}' to table '{table_name}', it has rows"),
+            ));
+        }
+        self.add_top_level_column(table_name, column_name, column_type, true)
+    }
+
+    fn add_top_level_column(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+        column_type: ColumnType,
+        not_null: bool,
+    ) -> Result<()> {
+        let table_id = self.table_id(table_name)?;
 
         // prevent duplicates
         if self.catalog
@@ -91,15 +184,16 @@ impl Database {
             return Ok(());
         }
 
-        let cols = self.catalog.columns_by_table.get(&table_id).unwrap();
-
         let col = self.chunk_manager.add_column(
             table_id,
             column_name,
             column_type,
-            cols.len() as u16
+            self.catalog.next_ordinal(table_id),
+            0,
+            not_null,
         )?;
 
+        self.catalog.reserve_ordinals(table_id, col.ordinal + 1);
         self.catalog
             .columns_by_table
             .entry(table_id)
@@ -109,6 +203,383 @@ impl Database {
         Ok(())
     }
 
+    /// Adds a field to a `Struct` column, or the element column of a `List`
+    /// column. `parent_path` is a dotted path to the parent (e.g. `payload.items`).
+    pub fn add_child_column(
+        &mut self,
+        table_name: &str,
+        parent_path: &str,
+        column_name: &str,
+        column_type: ColumnType,
+    ) -> Result<()> {
+        let table_id = self.table_id(table_name)?;
+
+        let parent = self.catalog
+            .resolve_column_path(table_id, parent_path)
+            .and_then(|chain| chain.last().copied())
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("column '{parent_path}' not found")))?;
+
+        match parent.column_type {
+            ColumnType::Struct => {
+                // prevent duplicates
+                if parent.find_child(column_name).is_some() {
+                    return Ok(());
+                }
+            }
+            ColumnType::List => {
+                if !parent.children.is_empty() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("list column '{parent_path}' already has an element column"),
+                    ));
+                }
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("column '{parent_path}' is not a List or Struct"),
+                ));
+            }
+        }
+
+        let parent_column_id = parent.column_id;
+        let col = self.chunk_manager.add_column(
+            table_id,
+            column_name,
+            column_type,
+            self.catalog.next_ordinal(table_id),
+            parent_column_id,
+            false,
+        )?;
+
+        self.catalog.reserve_ordinals(table_id, col.ordinal + 1);
+        self.catalog
+            .find_column_by_id_mut(table_id, parent_column_id)
+            .unwrap()
+            .children
+            .push(col);
+
+        Ok(())
+    }
+
+    /// Drops a table. Its data stays in the file but can no longer be read.
+    pub fn drop_table(&mut self, table_name: &str) -> Result<()> {
+        let table_id = self.table_id(table_name)?;
+        self.change_catalog(CatalogChange::DropTable { table_id })?;
+        self.chunk_manager.drop_table(table_id);
+        Ok(())
+    }
+
+    /// Removes every row of a table, keeping its schema, and frees the
+    /// pages they took. The truncation commits on its own, after committing
+    /// the open transaction if there is one.
+    pub fn truncate_table(&mut self, table_name: &str) -> Result<()> {
+        let table_id = self.table_id(table_name)?;
+        self.commit()?;
+        self.chunk_manager.truncate_table(table_id)?;
+        Ok(())
+    }
+
+    /// Merges the table's small chunks and rewrites its heavily deleted
+    /// ones without the deleted rows, committing on its own (see
+    /// [`compaction::compact_table`]).
+    pub fn compact_table(&mut self, table_name: &str, options: &CompactionOptions) -> Result<CompactionStats> {
+        compaction::compact_table(self, table_name, options)
+    }
+
+    /// Compacts every table, see [`Database::compact_table`].
+    pub fn compact(&mut self, options: &CompactionOptions) -> Result<CompactionStats> {
+        compaction::compact(self, options)
+    }
+
+    /// Rewrites the whole file compactly and swaps it in, reclaiming the
+    /// space of dropped, truncated, compacted and freed data (see
+    /// [`vacuum::vacuum`]). Commits the open transaction first.
+    pub fn vacuum(&mut self) -> Result<VacuumStats> {
+        if self.lock.mode == LockMode::Shared {
+            return Err(Error::new(ErrorKind::PermissionDenied, "database is open read-only"));
+        }
+        let path = self.path.clone();
+        vacuum::vacuum(self, &path)
+    }
+
+    /// Drops a top-level column, nested children included.
+    pub fn drop_column(&mut self, table_name: &str, column_name: &str) -> Result<()> {
+        let table_id = self.table_id(table_name)?;
+        let column = self.top_level_column(table_id, column_name)?.clone();
+        self.change_catalog(CatalogChange::DropColumn { table_id, column_id: column.column_id })?;
+        self.chunk_manager.drop_column(&column);
+        Ok(())
+    }
+
+    pub fn rename_table(&mut self, table_name: &str, new_name: &str) -> Result<()> {
+        let table_id = self.table_id(table_name)?;
+        if self.catalog.tables_by_name.contains_key(new_name) {
+            return Err(Error::new(ErrorKind::AlreadyExists, format!("table '{new_name}' already exists")));
+        }
+        self.change_catalog(CatalogChange::RenameTable { table_id, name: new_name.to_string() })
+    }
+
+    /// Renames a top-level column.
+    pub fn rename_column(&mut self, table_name: &str, column_name: &str, new_name: &str) -> Result<()> {
+        let table_id = self.table_id(table_name)?;
+        let column_id = self.top_level_column(table_id, column_name)?.column_id;
+        if self.top_level_column(table_id, new_name).is_ok() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("column '{new_name}' already exists in table '{table_name}'"),
+            ));
+        }
+        self.change_catalog(CatalogChange::RenameColumn { table_id, column_id, name: new_name.to_string() })
+    }
+
+    fn top_level_column(&self, table_id: u32, column_name: &str) -> Result<&TableColumn> {
+        self.catalog.columns_by_table
+            .get(&table_id)
+            .and_then(|columns| columns.iter().find(|c| c.name == column_name))
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("column '{column_name}' not found")))
+    }
+
+    /// Persists a schema change, then applies it to the cached catalog.
+    fn change_catalog(&mut self, change: CatalogChange) -> Result<()> {
+        self.chunk_manager.pager.append_catalog_change(&change)?;
+        self.catalog.apply(&change);
+        Ok(())
+    }
+
+    /// Appends one row. Columns missing from `row` are stored as nulls,
+    /// nested columns take `Value::List` / `Value::Struct` values.
+    pub fn append_row(&mut self, table_name: &str, row: Vec<(&str, Value)>) -> Result<()> {
+        let table_id = self.table_id(table_name)?;
+        let columns = self.catalog.columns_by_table
+            .get(&table_id)
+            .map(|c| c.as_slice())
+            .unwrap_or(&[]);
+
+        let mut values = vec![Value::Null; columns.len()];
+        for (name, value) in row {
+            let index = columns
+                .iter()
+                .position(|c| c.name == name)
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("column '{name}' not found")))?;
+            values[index] = value;
+        }
+
+        self.chunk_manager.append_row(columns, &values)?;
+        self.autocommit()
+    }
+
+    /// Appends whole columns in one call, see [`ChunkManager::append_columns`].
+    /// All inputs must have the same length; columns not listed are stored
+    /// as nulls. Returns the number of rows appended.
+    pub fn append_columns(&mut self, table_name: &str, inputs: &[ColumnInput]) -> Result<usize> {
+        let table_id = self.table_id(table_name)?;
+        let columns = self.catalog.columns_by_table
+            .get(&table_id)
+            .map(|c| c.as_slice())
+            .unwrap_or(&[]);
+
+        let mut by_column: Vec<Option<ColumnInput>> = vec![None; columns.len()];
+        for input in inputs {
+            let index = columns
+                .iter()
+                .position(|c| c.name == input.name)
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("column '{}' not found", input.name)))?;
+
+            if by_column[index].replace(*input).is_some() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("column '{}' given twice", input.name),
+                ));
+            }
+        }
+
+        let rows = self.chunk_manager.append_columns(columns, &by_column)?;
+        self.autocommit()?;
+        Ok(rows)
+    }
+
+    /// Deletes the rows of the table `predicate` selects, returning how many.
+    /// The deleted row ids are recorded in per-chunk delete vectors that
+    /// every scan skips; data pages are left as they are. Like appends,
+    /// deletes commit right away unless a transaction is open, whose own
+    /// appends and deletes they see.
+    pub fn delete_where(&mut self, table_name: &str, predicate: Expr) -> Result<u64> {
+        let table_id = self.table_id(table_name)?;
+        let columns = predicate.columns().into_iter().map(str::to_string).collect::<Vec<_>>();
+        let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+
+        let mut rows = Vec::new();
+        for batch in self.scan_pending(table_id, &columns, predicate)? {
+            let batch = batch?;
+            match &batch.selection {
+                Some(selection) => rows.extend(selection.iter().map(|&row| batch.row_start + u64::from(row))),
+                None => rows.extend(batch.row_start..batch.row_start + batch.row_count as u64),
+            }
+        }
+
+        let deleted = self.chunk_manager.delete_rows(table_id, &rows)?;
+        self.autocommit()?;
+        Ok(deleted)
+    }
+
+    /// Sets the columns in `assignments` on the rows of the table
+    /// `predicate` selects, returning how many rows were updated. Each row
+    /// is appended again with the new values under a new row id, and the
+    /// old one deleted (see [`Database::delete_where`]); both take effect in
+    /// the same commit, so scans see every row exactly once, either before
+    /// or after the update. Within a transaction, the rows it appended and
+    /// updated so far are updated too.
+    ///
+    /// The assignments are checked against their columns, size included,
+    /// before anything is written. Should appending fail all the same, the
+    /// rows appended so far are deleted again and the old rows kept, so the
+    /// table is left as it was.
+    pub fn update_where(&mut self, table_name: &str, predicate: Expr, assignments: &[(&str, Value)]) -> Result<u64> {
+        let table_id = self.table_id(table_name)?;
+        let columns = self.catalog.columns_by_table
+            .get(&table_id)
+            .map(|c| c.as_slice())
+            .unwrap_or(&[]);
+
+        // Checked up front, as the other values come from rows already stored
+        let mut updates = Vec::with_capacity(assignments.len());
+        for (name, value) in assignments {
+            let index = columns
+                .iter()
+                .position(|c| c.name == *name)
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("column '{name}' not found")))?;
+            self.chunk_manager.check_value(&columns[index], value)?;
+            updates.push((index, value));
+        }
+
+        let mut row_ids = Vec::new();
+        let mut rows = Vec::new();
+        for batch in self.scan_pending(table_id, &[], predicate)? {
+            let batch = batch?;
+            let offsets: Vec<u64> = match &batch.selection {
+                Some(selection) => selection.iter().map(|&row| u64::from(row)).collect(),
+                None => (0..batch.row_count as u64).collect(),
+            };
+            for (i, offset) in offsets.into_iter().enumerate() {
+                row_ids.push(batch.row_start + offset);
+                rows.push(batch.columns.iter().map(|column| column[i].clone()).collect::<Vec<Value>>());
+            }
+        }
+
+        let first_appended = self.chunk_manager.table_rows(table_id).next_row_id;
+        let appended = rows.into_iter().try_for_each(|mut row| {
+            for (index, value) in &updates {
+                row[*index] = (*value).clone();
+            }
+            self.chunk_manager.append_row(columns, &row)
+        });
+        if let Err(e) = appended {
+            let next_row_id = self.chunk_manager.table_rows(table_id).next_row_id;
+            let partial: Vec<u64> = (first_appended..next_row_id).collect();
+            self.chunk_manager.delete_rows(table_id, &partial)?;
+            self.autocommit()?;
+            return Err(e);
+        }
+
+        let updated = self.chunk_manager.delete_rows(table_id, &row_ids)?;
+        self.autocommit()?;
+        Ok(updated)
+    }
+
+    /// Starts a transaction: rows appended or deleted from now on stay as
+    /// they were to scans until [`Database::commit`], instead of being
+    /// committed by every append and delete.
+    pub fn begin(&mut self) {
+        self.in_transaction = true;
+    }
+
+    /// Commits every row appended and deleted so far and ends the
+    /// transaction, if one was started. Returns the new committed version.
+    ///
+    /// The committed rows stay in the active chunks, recorded as far as the
+    /// commit reaches (see [`ChunkManager::commit`]), and the file is synced
+    /// before this returns, so they are read back after a crash.
+    pub fn commit(&mut self) -> Result<u64> {
+        self.in_transaction = false;
+        self.chunk_manager.commit()
+    }
+
+    /// Discards every row appended and deleted since the last commit and
+    /// ends the transaction, if one was started (see
+    /// [`ChunkManager::rollback`]). Schema changes are not undone.
+    pub fn rollback(&mut self) -> Result<()> {
+        self.in_transaction = false;
+        self.chunk_manager.rollback()?;
+        self.chunk_manager.retain_chunks(&self.catalog);
+        Ok(())
+    }
+
+    /// The last committed version, which scans started now read at.
+    pub fn version(&self) -> u64 {
+        self.chunk_manager.version
+    }
+
+    fn autocommit(&mut self) -> Result<()> {
+        if !self.in_transaction {
+            self.chunk_manager.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Seals all active chunks, ending them early so the next appends start
+    /// new ones. Rows of an open transaction are sealed too, but only count
+    /// once committed.
+    pub fn flush(&mut self) -> Result<()> {
+        self.chunk_manager.seal_all()
+    }
+
+    /// Seals the table's active chunks, ending the current chunk early so the
+    /// next append starts a new one.
+    pub fn seal_table(&mut self, table_name: &str) -> Result<()> {
+        let table_id = self.table_id(table_name)?;
+        self.chunk_manager.seal_table(table_id)
+    }
+
+    /// Scans `columns` of a table chunk by chunk. Columns may be dotted paths
+    /// into nested columns; an empty list scans every top-level column.
+    pub fn scan(&self, table_name: &str, columns: &[&str]) -> Result<TableScan<'_>> {
+        let table_id = self.table_id(table_name)?;
+        TableScan::new(&self.chunk_manager, &self.catalog, table_id, columns)
+    }
+
+    /// Scans `columns` of the rows where `filter` is true, skipping the
+    /// chunks whose zone maps rule it out.
+    pub fn scan_where(&self, table_name: &str, columns: &[&str], filter: Expr) -> Result<TableScan<'_>> {
+        self.scan(table_name, columns)?.with_filter(filter)
+    }
+
+    /// Scans the rows `predicate` selects as the open transaction sees
+    /// them, with the rows appended and deleted since the last commit.
+    fn scan_pending(&self, table_id: u32, columns: &[&str], predicate: Expr) -> Result<TableScan<'_>> {
+        TableScan::with_pending(&self.chunk_manager, &self.catalog, table_id, columns)?.with_filter(predicate)
+    }
+
+    /// Runs a SQL `SELECT` (see [`sql::query::query`](crate::sql::query::query))
+    /// with the default memory budget.
+    pub fn query(&self, sql: &str) -> Result<BatchStream<'_>> {
+        crate::sql::query::query(self, sql, &QueryOptions::default())
+    }
+
+    /// Runs a script of SQL schema statements (see
+    /// [`sql::ddl::execute_script`](crate::sql::ddl::execute_script)),
+    /// returning how many ran.
+    pub fn execute(&mut self, sql: &str) -> Result<usize> {
+        crate::sql::ddl::execute_script(self, sql)
+    }
+
+    fn table_id(&self, table_name: &str) -> Result<u32> {
+        self.catalog.tables_by_name
+            .get(table_name)
+            .copied()
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "table not found"))
+    }
+
     fn seed_schema(&mut self) -> std::io::Result<()> {
 
         let tables = [
@@ -117,7 +588,7 @@ impl Database {
                 ("first_name", ColumnType::Utf8),
                 ("last_name", ColumnType::Utf8),
                 ("email", ColumnType::Utf8),
-                ("password_hash", ColumnType::Utf8),
+                ("password_hash", ColumnType::Binary),
                 ("is_active", ColumnType::Boolean),
             ])
         ];
@@ -130,7 +601,7 @@ impl Database {
             for (col_name, col_type) in columns {
                 self.add_column(
                     table_name,
-                    col_name.into(),
+                    col_name,
                     col_type
                 )?;
             }
@@ -151,6 +622,17 @@ impl Database {
     }
 }
 
+impl Drop for Database {
+    /// Rolls back a transaction left open, so the pages only its rows took
+    /// go back to the free list. Committed rows need nothing more, they are
+    /// replayed on open.
+    fn drop(&mut self) {
+        if self.in_transaction {
+            let _ = self.rollback();
+        }
+    }
+}
+
 // pub enum EncodedValue {
 //     Bytes(Vec<u8>),
 //     Null,
@@ -24,7 +24,7 @@ impl Initializer {
 
         let flags = HeaderFlags::CHECKSUM_ENABLED | HeaderFlags::COLUMNAR_V1 | HeaderFlags::COMPRESSION;
 
-        let mut header = Header::new(4096, flags);
+        let header = Header::new(4096, flags);
         header.write_to(&mut file).unwrap();
     }
 
@@ -32,10 +32,8 @@ impl Initializer {
     pub fn read_header(&self) -> Header {
         let mut file = OpenOptions::new()
             .read(true)
-            .write(true)
             .open(&self.path).unwrap();
 
-        let header = Header::read_from(&mut file).unwrap();
-        header
+        Header::read_from(&mut file).unwrap()
     }
 }
\ No newline at end of file
@@ -1,4 +1,11 @@
 pub mod catalog;
 pub mod database;
 mod initializer;
-mod chunk_manager;
\ No newline at end of file
+pub mod chunk_manager;
+pub mod column_slice;
+pub mod nested;
+pub mod shared_database;
+pub mod table_scan;
+pub mod column_buffer;
+pub mod compaction;
+pub mod vacuum;
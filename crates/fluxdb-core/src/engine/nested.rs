@@ -0,0 +1,171 @@
+//! Conversion between nested values and the flat per-column streams they are
+//! stored as.
+//!
+//! Every column of the tree (nested or not) owns one stream of values:
+//! - leaf columns store their values,
+//! - `List` columns store the element count of each list (null for a null
+//!   list) and their element column stores all elements back to back,
+//! - `Struct` columns store a presence flag (null for a null struct) and each
+//!   field column stores one value per struct, null when the struct is null.
+
+use std::collections::HashMap;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::schema::table_column::TableColumn;
+use crate::metadata::value::Value;
+
+/// Splits `value` into `(ordinal, value)` pairs for `column` and its children.
+pub fn shred(column: &TableColumn, value: &Value, out: &mut Vec<(u16, Value)>) -> Result<(), String> {
+    match column.column_type {
+        ColumnType::List => {
+            let element = column.children.first()
+                .ok_or_else(|| format!("list column '{}' has no element column", column.name))?;
+
+            match value {
+                Value::Null => out.push((column.ordinal, Value::Null)),
+                Value::List(items) => {
+                    out.push((column.ordinal, Value::Int32(items.len() as i32)));
+                    for item in items {
+                        shred(element, item, out)?;
+                    }
+                }
+                other => return Err(format!("value {other} does not match list column '{}'", column.name)),
+            }
+        }
+
+        ColumnType::Struct => match value {
+            Value::Null => {
+                out.push((column.ordinal, Value::Null));
+                for child in &column.children {
+                    shred(child, &Value::Null, out)?;
+                }
+            }
+            Value::Struct(fields) => {
+                if fields.len() > column.children.len() {
+                    return Err(format!(
+                        "struct column '{}' has {} fields, got {}",
+                        column.name,
+                        column.children.len(),
+                        fields.len()
+                    ));
+                }
+
+                out.push((column.ordinal, Value::Bool(true)));
+                for (i, child) in column.children.iter().enumerate() {
+                    shred(child, fields.get(i).unwrap_or(&Value::Null), out)?;
+                }
+            }
+            other => return Err(format!("value {other} does not match struct column '{}'", column.name)),
+        },
+
+        column_type => {
+            if !value.matches_column_type(column_type) {
+                return Err(format!(
+                    "value {value} does not match column '{}' ({column_type:?})",
+                    column.name
+                ));
+            }
+            out.push((column.ordinal, value.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Rebuilds the next value of `column` from its streams, advancing `cursors`.
+///
+/// Streams missing from `streams` (e.g. a field added after the data was
+/// written) read as nulls.
+pub fn assemble(
+    column: &TableColumn,
+    streams: &HashMap<u16, Vec<Value>>,
+    cursors: &mut HashMap<u16, usize>,
+) -> Value {
+    let cursor = cursors.entry(column.ordinal).or_insert(0);
+    let value = streams
+        .get(&column.ordinal)
+        .and_then(|s| s.get(*cursor))
+        .cloned()
+        .unwrap_or(Value::Null);
+    *cursor += 1;
+
+    match column.column_type {
+        ColumnType::List => match (value, column.children.first()) {
+            (Value::Int32(len), Some(element)) => Value::List(
+                (0..len).map(|_| assemble(element, streams, cursors)).collect(),
+            ),
+            _ => Value::Null,
+        },
+
+        ColumnType::Struct => {
+            let fields: Vec<Value> = column.children
+                .iter()
+                .map(|child| assemble(child, streams, cursors))
+                .collect();
+
+            if value.is_null() { Value::Null } else { Value::Struct(fields) }
+        }
+
+        _ => value,
+    }
+}
+
+/// Copy of the column tree along `path` (as returned by
+/// [`Catalog::resolve_column_path`](crate::engine::catalog::Catalog::resolve_column_path)),
+/// keeping only the children needed to reach the last column.
+pub fn prune_to_path(path: &[&TableColumn]) -> TableColumn {
+    let (last, ancestors) = path.split_last().expect("column path is never empty");
+
+    let mut pruned = (*last).clone();
+    for ancestor in ancestors.iter().rev() {
+        let mut parent = (*ancestor).clone();
+        parent.children = vec![pruned];
+        pruned = parent;
+    }
+    pruned
+}
+
+/// Unwraps an assembled value of a pruned tree down to the column with
+/// `target_ordinal`: struct levels are stepped into, list levels are mapped.
+pub fn extract(pruned: &TableColumn, value: Value, target_ordinal: u16) -> Value {
+    if pruned.ordinal == target_ordinal {
+        return value;
+    }
+
+    let Some(child) = pruned.children.first() else {
+        return value;
+    };
+
+    match value {
+        Value::Struct(mut fields) if !fields.is_empty() => {
+            extract(child, fields.swap_remove(0), target_ordinal)
+        }
+        Value::List(items) => Value::List(
+            items.into_iter()
+                .map(|item| extract(child, item, target_ordinal))
+                .collect(),
+        ),
+        _ => Value::Null,
+    }
+}
+
+/// Shape of the values [`extract`] returns: the target column, wrapped in a
+/// list for every list level stepped through on the way.
+pub fn extracted_schema(pruned: &TableColumn, target_ordinal: u16) -> TableColumn {
+    if pruned.ordinal == target_ordinal {
+        return pruned.clone();
+    }
+
+    let Some(child) = pruned.children.first() else {
+        return pruned.clone();
+    };
+
+    let inner = extracted_schema(child, target_ordinal);
+    match pruned.column_type {
+        ColumnType::List => {
+            let mut list = pruned.clone();
+            list.children = vec![inner];
+            list
+        }
+        _ => inner,
+    }
+}
@@ -0,0 +1,192 @@
+use std::io::Result;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use crate::engine::compaction::{CompactionOptions, CompactionStats};
+use crate::engine::database::Database;
+
+/// A [`Database`] shared between threads. Cloning the handle is cheap and
+/// every clone reaches the same database.
+///
+/// Any number of readers query [`SharedDatabase::snapshot`]s, copies of the
+/// catalog and chunk state as of the last write, which later writes leave
+/// alone. One writer at a time changes the database through
+/// [`SharedDatabase::write`]; its changes reach new snapshots once it is done.
+/// Readers and the writer never wait for each other, only for the moment a
+/// snapshot is swapped in.
+#[derive(Clone)]
+pub struct SharedDatabase {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    writer: Mutex<Database>,
+    /// The database as of the last finished write.
+    snapshot: RwLock<Arc<Database>>,
+}
+
+impl SharedDatabase {
+    /// Opens a database as [`Database::open`] does, behind a shared handle.
+    pub fn open(path: &Path, initialize: bool) -> Result<Self> {
+        Ok(SharedDatabase::new(Database::open(path, initialize)?))
+    }
+
+    pub fn new(db: Database) -> Self {
+        let snapshot = RwLock::new(Arc::new(db.snapshot()));
+        SharedDatabase { shared: Arc::new(Shared { writer: Mutex::new(db), snapshot }) }
+    }
+
+    /// The database as of the last finished write. Queries over it see
+    /// none of the writes made after, however long they run.
+    pub fn snapshot(&self) -> Arc<Database> {
+        let snapshot = self.shared.snapshot.read().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(&snapshot)
+    }
+
+    /// Locks the database for writing, waiting for the current writer if
+    /// there is one. The rows appended through the guard are one
+    /// transaction, committed and shown in new snapshots once the guard is
+    /// dropped or [`WriteGuard::commit`]ted.
+    ///
+    /// A writer that panicked leaves the lock poisoned; the next writer
+    /// takes it over once the panicked transaction is rolled back, and
+    /// fails only if that rollback does.
+    pub fn write(&self) -> Result<WriteGuard<'_>> {
+        let mut db = match self.shared.writer.lock() {
+            Ok(db) => db,
+            Err(poisoned) => {
+                let mut db = poisoned.into_inner();
+                db.rollback()?;
+                self.shared.writer.clear_poison();
+                db
+            }
+        };
+        db.begin();
+        Ok(WriteGuard { db, snapshot: &self.shared.snapshot })
+    }
+
+    /// Compacts every table (see [`Database::compact`]) every `interval` on
+    /// a background thread, taking the writer's turn like any other write,
+    /// until the returned handle is dropped. A compaction that fails is
+    /// tried again the next time.
+    pub fn compact_in_background(&self, interval: Duration, options: CompactionOptions) -> BackgroundCompaction {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let stats = Arc::new(Mutex::new(CompactionStats::default()));
+
+        let db = self.clone();
+        let thread = {
+            let stop = Arc::clone(&stop);
+            let stats = Arc::clone(&stats);
+            thread::spawn(move || {
+                let (stopped, wake) = &*stop;
+                let mut stopped = stopped.lock().unwrap_or_else(PoisonError::into_inner);
+                loop {
+                    stopped = wake
+                        .wait_timeout_while(stopped, interval, |stopped| !*stopped)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0;
+                    if *stopped {
+                        return;
+                    }
+                    if let Ok(round) = db.write().and_then(|mut writer| writer.compact(&options)) {
+                        stats.lock().unwrap_or_else(PoisonError::into_inner).merge(&round);
+                    }
+                }
+            })
+        };
+
+        BackgroundCompaction { stop, stats, thread: Some(thread) }
+    }
+}
+
+/// Compaction running on a background thread, from
+/// [`SharedDatabase::compact_in_background`]. Dropping it stops the thread,
+/// waiting for a compaction under way to finish.
+pub struct BackgroundCompaction {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    stats: Arc<Mutex<CompactionStats>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl BackgroundCompaction {
+    /// What the compactions so far rewrote, all together.
+    pub fn stats(&self) -> CompactionStats {
+        *self.stats.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for BackgroundCompaction {
+    fn drop(&mut self) {
+        let (stopped, wake) = &*self.stop;
+        *stopped.lock().unwrap_or_else(PoisonError::into_inner) = true;
+        wake.notify_all();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Exclusive access to a [`SharedDatabase`] for changing it, from
+/// [`SharedDatabase::write`].
+pub struct WriteGuard<'a> {
+    db: MutexGuard<'a, Database>,
+    snapshot: &'a RwLock<Arc<Database>>,
+}
+
+impl WriteGuard<'_> {
+    /// Commits the rows appended through the guard so far and publishes
+    /// them, returning the committed version. Like [`Database::commit`],
+    /// it syncs the file before returning. Later appends start the next
+    /// transaction. Dropping the guard commits too, but cannot report a
+    /// failed commit.
+    pub fn commit(&mut self) -> Result<u64> {
+        let version = self.db.commit()?;
+        self.db.begin();
+        self.publish();
+        Ok(version)
+    }
+
+    fn publish(&mut self) {
+        let snapshot = Arc::new(self.db.snapshot());
+        *self.snapshot.write().unwrap_or_else(PoisonError::into_inner) = snapshot;
+    }
+}
+
+impl Deref for WriteGuard<'_> {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        &self.db
+    }
+}
+
+impl DerefMut for WriteGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Database {
+        &mut self.db
+    }
+}
+
+impl Drop for WriteGuard<'_> {
+    /// Commits and publishes the writes as the new snapshot, unless the
+    /// writer is panicking part way through them: those are rolled back
+    /// instead, or by the next [`SharedDatabase::write`] should that fail.
+    /// Rows left uncommitted by a failed commit stay invisible until a
+    /// later commit.
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            let _ = self.db.rollback();
+            return;
+        }
+        let _ = self.db.commit();
+        self.publish();
+    }
+}
+
+// Handles are passed between threads, so all they share must allow it
+const _: fn() = || {
+    fn shareable<T: Send + Sync>() {}
+    shareable::<SharedDatabase>();
+    shareable::<Database>();
+};
@@ -0,0 +1,540 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::ops::Bound;
+use std::io::{Error, ErrorKind};
+use crate::engine::catalog::Catalog;
+use crate::engine::chunk_manager::ChunkManager;
+use crate::engine::nested;
+use crate::metadata::chunks::chunk_meta::ChunkMeta;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::schema::table_column::TableColumn;
+use crate::metadata::value::Value;
+use crate::query::batch::Batch;
+use crate::query::expr::Expr;
+use crate::query::predicate::SelectionVector;
+use crate::query::vector::{Vector, VectorBatch, VECTOR_SIZE};
+use crate::query::vector_predicate::select_vectors;
+use crate::query::zone_map::{self, ZoneMap};
+
+/// Up to [`VECTOR_SIZE`] rows of one
+/// chunk range for the projected columns.
+pub struct ScanBatch {
+    pub row_start: u64,
+    pub row_count: usize,
+    /// One vector per projected column, each `row_count` long.
+    pub columns: Vec<Vec<Value>>,
+    /// Offsets from `row_start` of the rows in the batch when a filter
+    /// dropped some of the range, `None` when the batch holds every row.
+    pub selection: Option<SelectionVector>,
+}
+
+/// Counters of a scan so far.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScanStats {
+    /// Row ranges read.
+    pub ranges_scanned: usize,
+    /// Row ranges skipped because their zone maps rule out the filter, or
+    /// left out by a lookup.
+    pub ranges_pruned: usize,
+    pub rows_scanned: u64,
+    /// Rows that passed the filter.
+    pub rows_selected: u64,
+    /// Data pages read from the file.
+    pub pages_read: u64,
+    /// Encoded value bytes decoded.
+    pub bytes_decoded: u64,
+}
+
+impl ScanStats {
+    /// Adds the counters of another scan, such as another part of a split scan.
+    pub fn merge(&mut self, other: &ScanStats) {
+        self.ranges_scanned += other.ranges_scanned;
+        self.ranges_pruned += other.ranges_pruned;
+        self.rows_scanned += other.rows_scanned;
+        self.rows_selected += other.rows_selected;
+        self.pages_read += other.pages_read;
+        self.bytes_decoded += other.bytes_decoded;
+    }
+}
+
+#[derive(Clone)]
+struct ProjectedColumn {
+    /// Column tree pruned down to the projected path.
+    pruned: TableColumn,
+    target_ordinal: u16,
+    /// Type of the projected values.
+    column_type: ColumnType,
+}
+
+#[derive(Clone)]
+struct ScanFilter {
+    expr: Expr,
+    /// Indexes into the scan's columns of the columns `expr` reads.
+    columns: Vec<usize>,
+    /// Top-level columns read by `expr`, by name, whose chunk zone maps
+    /// can rule out a row range.
+    zone_columns: Vec<(String, u32)>,
+}
+
+/// Column scan over a table, walking the chunk row ranges: sealed chunks in
+/// row order, then the rows of the active chunks. Each range is read into
+/// [`Vector`]s and handed out a [`VECTOR_SIZE`] window at a
+/// time, by [`TableScan::next_vectors`] or as [`ScanBatch`]es.
+///
+/// A filter attached with [`TableScan::with_filter`] skips the ranges whose
+/// zone maps rule it out and drops the rows it does not select.
+pub struct TableScan<'a> {
+    chunk_manager: &'a ChunkManager,
+    catalog: &'a Catalog,
+    table_id: u32,
+    /// Projected columns, followed by the columns only the filter reads.
+    projection: Vec<ProjectedColumn>,
+    /// Names of the columns in `projection`.
+    projection_names: Vec<String>,
+    filter: Option<ScanFilter>,
+    /// Whether ranges are checked against the filter's zone maps.
+    pruning: bool,
+    ranges: VecDeque<(u64, u64)>,
+    /// Deletes up to this version are skipped: the committed one, or the
+    /// next for a scan of the pending writes.
+    version: u64,
+    /// Windows of the last range read not handed out yet, by first row.
+    pending: VecDeque<(u64, VectorBatch)>,
+    pub column_names: Vec<String>,
+    /// Schema of each projected column's values, see [`nested::extracted_schema`].
+    pub column_schemas: Vec<TableColumn>,
+    pub stats: ScanStats,
+}
+
+impl<'a> TableScan<'a> {
+    /// Creates a scan projecting `paths`, which may be dotted paths into
+    /// nested columns (see [`Catalog::resolve_column_path`]). An empty
+    /// projection scans every top-level column.
+    pub fn new(
+        chunk_manager: &'a ChunkManager,
+        catalog: &'a Catalog,
+        table_id: u32,
+        paths: &[&str],
+    ) -> Result<Self, Error> {
+        let committed = chunk_manager.table_rows(table_id).committed_row_end;
+        Self::up_to(chunk_manager, catalog, table_id, paths, committed, chunk_manager.version)
+    }
+
+    /// Creates a scan as [`TableScan::new`] does that also reads the rows
+    /// appended since the last commit and skips those deleted since, so a
+    /// transaction sees its own writes.
+    pub fn with_pending(
+        chunk_manager: &'a ChunkManager,
+        catalog: &'a Catalog,
+        table_id: u32,
+        paths: &[&str],
+    ) -> Result<Self, Error> {
+        let appended = chunk_manager.table_rows(table_id).next_row_id;
+        Self::up_to(chunk_manager, catalog, table_id, paths, appended, chunk_manager.version + 1)
+    }
+
+    /// A scan of the rows below `row_end`, skipping the rows deleted as of
+    /// `version`.
+    fn up_to(
+        chunk_manager: &'a ChunkManager,
+        catalog: &'a Catalog,
+        table_id: u32,
+        paths: &[&str],
+        row_end: u64,
+        version: u64,
+    ) -> Result<Self, Error> {
+        let columns = catalog.columns_by_table
+            .get(&table_id)
+            .map(|c| c.as_slice())
+            .unwrap_or(&[]);
+
+        let column_names: Vec<String> = if paths.is_empty() {
+            columns.iter().map(|c| c.name.clone()).collect()
+        } else {
+            paths.iter().map(|p| p.to_string()).collect()
+        };
+
+        let mut projection = Vec::with_capacity(column_names.len());
+        let mut column_schemas = Vec::with_capacity(column_names.len());
+        for path in &column_names {
+            let (projected, schema) = Self::project(catalog, table_id, path)?;
+            column_schemas.push(schema);
+            projection.push(projected);
+        }
+
+        // Chunks of a table are sealed together, so any top-level column's
+        // chunks give the row ranges; columns added later simply lack the early ones.
+        // Only rows below `row_end` are read, which may end part way into a range
+        let rows = chunk_manager.table_rows(table_id);
+        let mut ranges = BTreeSet::new();
+        for column in columns {
+            for chunk in chunk_manager.chunks_for(table_id, column.column_id) {
+                if chunk.row_start < row_end {
+                    ranges.insert((chunk.row_start, chunk.row_end.min(row_end)));
+                }
+            }
+        }
+
+        if rows.active_row_start < row_end {
+            ranges.insert((rows.active_row_start, row_end));
+        }
+
+        Ok(Self {
+            chunk_manager,
+            catalog,
+            table_id,
+            projection,
+            projection_names: column_names.clone(),
+            filter: None,
+            pruning: true,
+            ranges: ranges.into_iter().collect(),
+            version,
+            pending: VecDeque::new(),
+            column_names,
+            column_schemas,
+            stats: ScanStats::default(),
+        })
+    }
+
+    fn project(catalog: &Catalog, table_id: u32, path: &str) -> Result<(ProjectedColumn, TableColumn), Error> {
+        let chain = catalog.resolve_column_path(table_id, path).ok_or_else(|| {
+            Error::new(ErrorKind::NotFound, format!("column '{path}' not found"))
+        })?;
+
+        let target_ordinal = chain.last().unwrap().ordinal;
+        let pruned = nested::prune_to_path(&chain);
+        let schema = nested::extracted_schema(&pruned, target_ordinal);
+        let column_type = schema.column_type;
+        Ok((ProjectedColumn { target_ordinal, pruned, column_type }, schema))
+    }
+
+    /// Keeps only the rows where `filter` is true. The filter may read
+    /// columns outside the projection; it is type checked against the table
+    /// (see [`Expr::bind`]) before the scan starts. Filters attached one
+    /// after another combine with `AND`.
+    pub fn with_filter(mut self, filter: Expr) -> Result<Self, Error> {
+        let filter = match self.filter.take() {
+            Some(existing) => existing.expr.and(filter),
+            None => filter,
+        };
+
+        let catalog = self.catalog;
+        let table_id = self.table_id;
+        let expr = filter.bind(&|path| {
+            catalog.resolve_column_path(table_id, path).map(|chain| chain.last().unwrap().column_type)
+        })?;
+
+        let mut columns = Vec::new();
+        let mut zone_columns = Vec::new();
+        for path in expr.columns() {
+            let index = match self.projection_names.iter().position(|name| name == path) {
+                Some(index) => index,
+                None => {
+                    let (projected, _) = Self::project(catalog, table_id, path)?;
+                    self.projection.push(projected);
+                    self.projection_names.push(path.to_string());
+                    self.projection.len() - 1
+                }
+            };
+            columns.push(index);
+
+            let chain = catalog.resolve_column_path(table_id, path).unwrap();
+            if let [column] = chain.as_slice() {
+                if !column.column_type.is_nested() {
+                    zone_columns.push((path.to_string(), column.column_id));
+                }
+            }
+        }
+
+        self.filter = Some(ScanFilter { expr, columns, zone_columns });
+        Ok(self)
+    }
+
+    /// Reads every range without checking the filter against its zone
+    /// maps, for filters they cannot rule anything out for. The filter still
+    /// drops the rows it does not select.
+    pub fn without_pruning(mut self) -> Self {
+        self.pruning = false;
+        self
+    }
+
+    /// Narrows the scan to the ranges lying within rows `row_start..row_end`.
+    pub fn within_rows(mut self, row_start: u64, row_end: u64) -> Self {
+        self.ranges.retain(|&(start, end)| start >= row_start && end <= row_end);
+        self
+    }
+
+    /// Narrows the scan to the ranges where the top-level `column` may hold
+    /// a value within `low..high`, by binary search over the min/max of the
+    /// column's chunks. Only works when the chunks ascend (see
+    /// [`zone_map::ascending`]); otherwise every range is kept. Ranges
+    /// without a chunk of the column, such as the active rows, are kept too.
+    ///
+    /// Rows are not filtered: attach a filter implying the bounds.
+    pub fn with_lookup(mut self, column: &str, low: Bound<&Value>, high: Bound<&Value>) -> Result<Self, Error> {
+        let column_id = match self.catalog.resolve_column_path(self.table_id, column).as_deref() {
+            Some([column]) if !column.column_type.is_nested() => column.column_id,
+            _ => return Err(Error::new(ErrorKind::InvalidInput, format!("cannot look up by column '{column}'"))),
+        };
+
+        let chunks = self.chunk_manager.chunks_for(self.table_id, column_id);
+        if !zone_map::ascending(chunks) {
+            return Ok(self);
+        }
+
+        // Chunks ascend, so those wholly below `low` come first and those
+        // wholly above `high` last. Bounds of no common order keep everything
+        let below_low = |chunk: &ChunkMeta| {
+            let max = chunk.max.as_ref().unwrap();
+            match low {
+                Bound::Included(low) => max.compare(low).is_some_and(Ordering::is_lt),
+                Bound::Excluded(low) => max.compare(low).is_some_and(Ordering::is_le),
+                Bound::Unbounded => false,
+            }
+        };
+        let not_above_high = |chunk: &ChunkMeta| {
+            let min = chunk.min.as_ref().unwrap();
+            match high {
+                Bound::Included(high) => min.compare(high).is_none_or(Ordering::is_le),
+                Bound::Excluded(high) => min.compare(high).is_none_or(Ordering::is_lt),
+                Bound::Unbounded => true,
+            }
+        };
+        let first = chunks.partition_point(below_low);
+        let end = chunks.partition_point(not_above_high).max(first);
+
+        let ranges = self.ranges.len();
+        self.ranges.retain(|(row_start, _)| match chunks.binary_search_by_key(row_start, |c| c.row_start) {
+            Ok(index) => (first..end).contains(&index),
+            Err(_) => true,
+        });
+        self.stats.ranges_pruned += ranges - self.ranges.len();
+        Ok(self)
+    }
+
+    /// Splits the ranges still to scan over `parts` scans of the same
+    /// columns and filter, dealing them out in turn so each part gets
+    /// ranges from all over the table. The first part keeps the stats so far.
+    pub fn split(mut self, parts: usize) -> Vec<TableScan<'a>> {
+        let parts = parts.max(1);
+        let ranges = std::mem::take(&mut self.ranges);
+        let mut scans: Vec<TableScan<'a>> = (0..parts)
+            .map(|part| TableScan {
+                projection: self.projection.clone(),
+                projection_names: self.projection_names.clone(),
+                filter: self.filter.clone(),
+                ranges: ranges.iter().skip(part).step_by(parts).copied().collect(),
+                pending: VecDeque::new(),
+                column_names: self.column_names.clone(),
+                column_schemas: self.column_schemas.clone(),
+                stats: ScanStats::default(),
+                ..self
+            })
+            .collect();
+
+        scans[0].pending = self.pending;
+        scans[0].stats = self.stats;
+        scans
+    }
+
+    /// The attached filter, bound to the table's column types.
+    pub fn bound_filter(&self) -> Option<&Expr> {
+        self.filter.as_ref().map(|filter| &filter.expr)
+    }
+
+    /// Rows in the ranges still to scan whose zone maps do not rule out
+    /// the filter, before the filter drops any row.
+    pub fn estimated_rows(&self) -> u64 {
+        self.ranges
+            .iter()
+            .filter(|(start, _)| !self.prune(*start))
+            .map(|(start, end)| end - start)
+            .sum()
+    }
+
+    /// Row ranges still to scan, and how many of them the zone maps rule
+    /// out, known before any data is read.
+    pub fn estimated_ranges(&self) -> (usize, usize) {
+        let pruned = self.ranges.iter().filter(|(start, _)| self.prune(*start)).count();
+        (self.ranges.len() - pruned, pruned)
+    }
+
+    /// Whether the zone maps of the range's chunks rule out the filter.
+    /// Ranges without sealed chunks, such as the active rows, are never pruned.
+    fn prune(&self, row_start: u64) -> bool {
+        let Some(filter) = self.filter.as_ref().filter(|_| self.pruning) else {
+            return false;
+        };
+        if filter.zone_columns.is_empty() {
+            return false;
+        }
+
+        let zone_map = |name: &str| {
+            let (_, column_id) = filter.zone_columns.iter().find(|(column, _)| column == name)?;
+            self.chunk_manager
+                .chunks_for(self.table_id, *column_id)
+                .iter()
+                .find(|chunk| chunk.row_start == row_start)
+                .map(ZoneMap::from_chunk)
+        };
+
+        !zone_map::may_match(&filter.expr, &zone_map)
+    }
+
+    fn read_column(&self, index: usize, row_start: u64, row_count: usize) -> Result<Vec<Value>, Error> {
+        let projected = &self.projection[index];
+
+        let mut streams = HashMap::new();
+        for column in projected.pruned.flatten() {
+            streams.insert(column.ordinal, self.chunk_manager.read_column_range(column, row_start)?);
+        }
+
+        let mut cursors = HashMap::new();
+        Ok((0..row_count)
+            .map(|_| {
+                let value = nested::assemble(&projected.pruned, &streams, &mut cursors);
+                nested::extract(&projected.pruned, value, projected.target_ordinal)
+            })
+            .collect())
+    }
+
+    /// Reads a column of a range into a vector of `row_count` rows. Flat
+    /// columns decode straight from their pages, nested values are
+    /// assembled first.
+    fn read_vector(&self, index: usize, row_start: u64, row_count: usize) -> Result<Vector, Error> {
+        let projected = &self.projection[index];
+        let flat = projected.pruned.ordinal == projected.target_ordinal && !projected.pruned.column_type.is_nested();
+        if !flat {
+            let values = self.read_column(index, row_start, row_count)?;
+            return Ok(Vector::from_values(projected.column_type, values));
+        }
+
+        // Past the committed rows a chunk may hold rows of later commits
+        let mut vector = self.chunk_manager.read_vector_range(&projected.pruned, row_start)?;
+        vector.truncate(row_count);
+        vector.pad_nulls(row_count);
+        Ok(vector)
+    }
+
+    /// Reads a range as windows of up to `VECTOR_SIZE` rows, leaving out
+    /// deleted rows and those the filter does not select.
+    fn read_range(&self, row_start: u64, row_end: u64) -> Result<Vec<(u64, VectorBatch)>, Error> {
+        let row_count = (row_end - row_start) as usize;
+        let mut columns: Vec<Option<Vec<Vector>>> = (0..self.projection.len()).map(|_| None).collect();
+        let windows: Vec<usize> = (0..row_count).step_by(VECTOR_SIZE).collect();
+
+        let deleted = self.chunk_manager.deleted_rows_as_of(self.table_id, row_start, row_count, self.version);
+        let is_live = |start: usize, row: u32| deleted.as_ref().is_none_or(|deleted| !deleted[start + row as usize]);
+        let mut selections: Vec<Option<Option<SelectionVector>>> = windows
+            .iter()
+            .map(|&start| {
+                let len = (row_count - start).min(VECTOR_SIZE);
+                match deleted {
+                    Some(_) => narrow((0..len as u32).filter(|&row| is_live(start, row)).collect(), len),
+                    None => Some(None),
+                }
+            })
+            .collect();
+
+        // Read the filter's columns first, the rest only if a row is selected
+        if let Some(filter) = &self.filter {
+            for &index in &filter.columns {
+                columns[index] = Some(self.read_vector(index, row_start, row_count)?.split());
+            }
+
+            for (window, &start) in windows.iter().enumerate() {
+                if selections[window].is_none() {
+                    continue;
+                }
+                let len = (row_count - start).min(VECTOR_SIZE);
+                let inputs: Vec<(&str, &Vector)> = filter.columns
+                    .iter()
+                    .map(|&index| (self.projection_names[index].as_str(), &columns[index].as_ref().unwrap()[window]))
+                    .collect();
+
+                let mut selected = select_vectors(&filter.expr, &inputs, len)?;
+                selected.retain(|&row| is_live(start, row));
+                selections[window] = narrow(selected, len);
+            }
+        }
+        if selections.iter().all(Option::is_none) {
+            return Ok(Vec::new());
+        }
+
+        let mut outputs = Vec::with_capacity(self.column_names.len());
+        for (index, vectors) in columns.into_iter().take(self.column_names.len()).enumerate() {
+            let vectors = match vectors {
+                Some(vectors) => vectors,
+                None => self.read_vector(index, row_start, row_count)?.split(),
+            };
+            outputs.push(vectors.into_iter());
+        }
+
+        let mut batches = Vec::new();
+        for (start, selection) in windows.into_iter().zip(selections) {
+            let columns: Vec<Vector> = outputs.iter_mut().map(|vectors| vectors.next().unwrap()).collect();
+            if let Some(selection) = selection {
+                let row_count = (row_count - start).min(VECTOR_SIZE);
+                batches.push((row_start + start as u64, VectorBatch { row_count, columns, selection }));
+            }
+        }
+        Ok(batches)
+    }
+
+    /// The next window of the scan with at least one selected row.
+    pub fn next_vectors(&mut self) -> Option<Result<VectorBatch, Error>> {
+        self.next_window().map(|window| window.map(|(_, batch)| batch))
+    }
+
+    fn next_window(&mut self) -> Option<Result<(u64, VectorBatch), Error>> {
+        loop {
+            if let Some((row_start, batch)) = self.pending.pop_front() {
+                self.stats.rows_selected += batch.selected() as u64;
+                return Some(Ok((row_start, batch)));
+            }
+
+            let (row_start, row_end) = self.ranges.pop_front()?;
+            if self.prune(row_start) {
+                self.stats.ranges_pruned += 1;
+                continue;
+            }
+
+            self.stats.ranges_scanned += 1;
+            self.stats.rows_scanned += row_end - row_start;
+            let before = self.chunk_manager.read_stats();
+            let windows = self.read_range(row_start, row_end);
+            let read = self.chunk_manager.read_stats().since(before);
+            self.stats.pages_read += read.pages_read;
+            self.stats.bytes_decoded += read.bytes_decoded;
+
+            match windows {
+                Ok(windows) => self.pending.extend(windows),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl Iterator for TableScan<'_> {
+    type Item = Result<ScanBatch, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (row_start, batch) = match self.next_window()? {
+            Ok(window) => window,
+            Err(e) => return Some(Err(e)),
+        };
+        let selection = batch.selection.clone();
+        let Batch { row_count, columns } = batch.into_batch();
+        Some(Ok(ScanBatch { row_start, row_count, columns, selection }))
+    }
+}
+
+/// The selection of a window of `len` rows: `None` when no row is
+/// selected, `Some(None)` when all are.
+fn narrow(selected: SelectionVector, len: usize) -> Option<Option<SelectionVector>> {
+    match selected.len() {
+        0 => None,
+        n if n < len => Some(Some(selected)),
+        _ => Some(None),
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use crate::engine::chunk_manager::ChunkManager;
+use crate::engine::database::Database;
+use crate::engine::initializer::Initializer;
+use crate::general::header::Header;
+use crate::storage::pager::Pager;
+
+/// The size of the file before and after a vacuum.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VacuumStats {
+    pub pages_before: u64,
+    pub pages_after: u64,
+}
+
+/// Rewrites the database at `path` into a fresh file and swaps it in: the
+/// catalog as it is now, then every chunk's data pages one after another,
+/// then the chunk catalog with one record per live chunk and delete vector.
+/// Dropped tables and columns, replaced chunks, catalog changes and free
+/// pages are left behind.
+///
+/// The open transaction is committed and every table sealed first. The
+/// copy is written beside the database and renamed over it, so a crash
+/// leaves either file whole. Snapshots taken before go on reading the old
+/// file until they are dropped; the file lock stays held throughout.
+pub fn vacuum(db: &mut Database, path: &Path) -> Result<VacuumStats> {
+    db.commit()?;
+    db.flush()?;
+    let pages_before = db.chunk_manager.pager.header.page_count;
+
+    let copy_path = vacuum_path(path);
+    if let Err(e) = write_copy(db, &copy_path).and_then(|()| fs::rename(&copy_path, path)) {
+        let _ = fs::remove_file(&copy_path);
+        return Err(e);
+    }
+    // Best effort: persists the rename where directories can be synced
+    if let Some(dir) = path.parent() {
+        let _ = File::open(dir).and_then(|dir| dir.sync_all());
+    }
+
+    let file = OpenOptions::new().read(true).write(true).open(path)?;
+    let header = Initializer::new(path).read_header();
+    let mut chunk_manager = ChunkManager::new(Pager::new(file, header));
+    let catalog = chunk_manager.load_catalog()?;
+    chunk_manager.load_chunk_index()?;
+    chunk_manager.retain_chunks(&catalog);
+
+    db.catalog = catalog;
+    db.chunk_manager = chunk_manager;
+    Ok(VacuumStats { pages_before, pages_after: db.chunk_manager.pager.header.page_count })
+}
+
+/// Writes the copy of `db` to `copy_path`, synced to disk.
+fn write_copy(db: &mut Database, copy_path: &Path) -> Result<()> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(copy_path)?;
+
+    let header = Header {
+        page_count: 0,
+        chunk_catalog_root_page_id: 0,
+        free_page_head: 0,
+        free_page_count: 0,
+        ..db.chunk_manager.pager.header.clone()
+    };
+    let mut pager = Pager::new(file, header);
+    pager.flush_header()?;
+
+    let root = db.chunk_manager.pager.load_catalog_root()?;
+    pager.write_catalog(&db.catalog, &root)?;
+    db.chunk_manager.copy_to(&mut pager)?;
+    pager.sync()
+}
+
+/// Where the copy of the database at `db_path` is written: its path with
+/// `.vacuum` appended.
+fn vacuum_path(db_path: &Path) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(".vacuum");
+    PathBuf::from(path)
+}
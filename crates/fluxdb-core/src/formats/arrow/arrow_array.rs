@@ -0,0 +1,367 @@
+//! Conversion between columns of [`Value`]s and the buffers of an Arrow
+//! record batch body.
+//!
+//! Arrays are laid out depth first: each array adds one field node and its
+//! buffers (validity first), followed by its children.
+
+use std::io::{Error, ErrorKind, Result};
+use crate::formats::arrow::arrow_field::{ArrowField, ArrowType, TimeUnit};
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+
+static NULL: Value = Value::Null;
+
+/// Length and null count of one array, as stored in a `RecordBatch`.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldNode {
+    pub length: i64,
+    pub null_count: i64,
+}
+
+/// Field nodes, buffer locations and body bytes of a record batch.
+#[derive(Debug, Default)]
+pub struct BatchBody {
+    pub nodes: Vec<FieldNode>,
+    /// `(offset, length)` of each buffer within `data`.
+    pub buffers: Vec<(i64, i64)>,
+    pub data: Vec<u8>,
+}
+
+impl BatchBody {
+    fn push_buffer(&mut self, bytes: &[u8]) {
+        self.buffers.push((self.data.len() as i64, bytes.len() as i64));
+        self.data.extend_from_slice(bytes);
+        // Buffers start on 8-byte boundaries
+        self.data.resize(self.data.len().next_multiple_of(8), 0);
+    }
+}
+
+fn bitmap(bits: impl Iterator<Item = bool>, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len.div_ceil(8)];
+    for (i, bit) in bits.enumerate() {
+        if bit {
+            bytes[i / 8] |= 1 << (i % 8);
+        }
+    }
+    bytes
+}
+
+fn push_offsets(body: &mut BatchBody, lengths: impl Iterator<Item = usize>, large: bool) {
+    let mut offsets = Vec::new();
+    let mut end = 0i64;
+    let push = |offset: i64, out: &mut Vec<u8>| {
+        if large {
+            out.extend_from_slice(&offset.to_le_bytes());
+        } else {
+            out.extend_from_slice(&(offset as i32).to_le_bytes());
+        }
+    };
+
+    push(0, &mut offsets);
+    for len in lengths {
+        end += len as i64;
+        push(end, &mut offsets);
+    }
+    body.push_buffer(&offsets);
+}
+
+/// Appends the array for `values` (and its children) to `body`.
+pub fn encode_array(field: &ArrowField, values: &[&Value], body: &mut BatchBody) -> Result<()> {
+    let null_count = values.iter().filter(|v| v.is_null()).count();
+    body.nodes.push(FieldNode { length: values.len() as i64, null_count: null_count as i64 });
+
+    if null_count > 0 {
+        body.push_buffer(&bitmap(values.iter().map(|v| !v.is_null()), values.len()));
+    } else {
+        body.push_buffer(&[]);
+    }
+
+    let mismatch = |value: &Value| Error::new(
+        ErrorKind::InvalidInput,
+        format!("value {value} does not match Arrow field '{}' ({:?})", field.name, field.data_type),
+    );
+
+    match &field.data_type {
+        ArrowType::Int { bit_width, .. } => {
+            let width = (*bit_width / 8) as usize;
+            let mut data = Vec::with_capacity(values.len() * width);
+            for value in values {
+                let v = if value.is_null() { 0 } else { value.as_i64().ok_or_else(|| mismatch(value))? };
+                data.extend_from_slice(&v.to_le_bytes()[..width]);
+            }
+            body.push_buffer(&data);
+        }
+        ArrowType::Float { precision } => {
+            let mut data = Vec::new();
+            for value in values {
+                let v = if value.is_null() { 0.0 } else { value.as_f64().ok_or_else(|| mismatch(value))? };
+                match precision {
+                    1 => data.extend_from_slice(&(v as f32).to_le_bytes()),
+                    _ => data.extend_from_slice(&v.to_le_bytes()),
+                }
+            }
+            body.push_buffer(&data);
+        }
+        ArrowType::Utf8 | ArrowType::LargeUtf8 | ArrowType::Binary | ArrowType::LargeBinary => {
+            let mut data = Vec::new();
+            let mut lengths = Vec::with_capacity(values.len());
+            for value in values {
+                let bytes: &[u8] = match value {
+                    Value::Null => &[],
+                    Value::String(s) => s.as_bytes(),
+                    Value::Binary(b) => b,
+                    other => return Err(mismatch(other)),
+                };
+                data.extend_from_slice(bytes);
+                lengths.push(bytes.len());
+            }
+
+            let large = matches!(field.data_type, ArrowType::LargeUtf8 | ArrowType::LargeBinary);
+            push_offsets(body, lengths.into_iter(), large);
+            body.push_buffer(&data);
+        }
+        ArrowType::FixedSizeBinary(width) => {
+            let width = *width as usize;
+            let mut data = Vec::with_capacity(values.len() * width);
+            for value in values {
+                match value {
+                    Value::Null => data.resize(data.len() + width, 0),
+                    Value::Binary(b) if b.len() == width => data.extend_from_slice(b),
+                    Value::Uuid(u) if width == 16 => data.extend_from_slice(u),
+                    other => return Err(mismatch(other)),
+                }
+            }
+            body.push_buffer(&data);
+        }
+        ArrowType::Bool => {
+            let bits = values.iter().map(|v| matches!(v, Value::Bool(true)));
+            body.push_buffer(&bitmap(bits, values.len()));
+        }
+        ArrowType::Timestamp { unit, .. } => {
+            let mut data = Vec::with_capacity(values.len() * 8);
+            for value in values {
+                let micros = match value {
+                    Value::Null => 0,
+                    Value::Timestamp(v) => *v,
+                    other => return Err(mismatch(other)),
+                };
+                let v = match unit {
+                    TimeUnit::Second => micros.div_euclid(1_000_000),
+                    TimeUnit::Millisecond => micros.div_euclid(1_000),
+                    TimeUnit::Microsecond => micros,
+                    TimeUnit::Nanosecond => micros.saturating_mul(1_000),
+                };
+                data.extend_from_slice(&v.to_le_bytes());
+            }
+            body.push_buffer(&data);
+        }
+        ArrowType::List => {
+            let element = field.children.first()
+                .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("list field '{}' has no element field", field.name)))?;
+
+            let mut items = Vec::new();
+            let mut lengths = Vec::with_capacity(values.len());
+            for value in values {
+                match value {
+                    Value::Null => lengths.push(0),
+                    Value::List(list) => {
+                        lengths.push(list.len());
+                        items.extend(list.iter());
+                    }
+                    other => return Err(mismatch(other)),
+                }
+            }
+
+            push_offsets(body, lengths.into_iter(), false);
+            encode_array(element, &items, body)?;
+        }
+        ArrowType::Struct => {
+            for (i, child) in field.children.iter().enumerate() {
+                let mut child_values = Vec::with_capacity(values.len());
+                for value in values {
+                    match value {
+                        Value::Null => child_values.push(&NULL),
+                        Value::Struct(fields) => child_values.push(fields.get(i).unwrap_or(&NULL)),
+                        other => return Err(mismatch(other)),
+                    }
+                }
+                encode_array(child, &child_values, body)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Walks the field nodes and buffers of a record batch body in order.
+pub struct BatchReader<'a> {
+    pub nodes: Vec<FieldNode>,
+    pub buffers: Vec<(i64, i64)>,
+    pub body: &'a [u8],
+    next_node: usize,
+    next_buffer: usize,
+}
+
+fn truncated(what: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Arrow record batch is truncated: {what}"))
+}
+
+impl<'a> BatchReader<'a> {
+    pub fn new(nodes: Vec<FieldNode>, buffers: Vec<(i64, i64)>, body: &'a [u8]) -> Self {
+        Self { nodes, buffers, body, next_node: 0, next_buffer: 0 }
+    }
+
+    fn node(&mut self) -> Result<(usize, usize)> {
+        let node = self.nodes.get(self.next_node).ok_or_else(|| truncated("missing field node"))?;
+        self.next_node += 1;
+
+        let length = usize::try_from(node.length).map_err(|_| truncated("negative length"))?;
+        let null_count = usize::try_from(node.null_count).map_err(|_| truncated("negative null count"))?;
+        Ok((length, null_count))
+    }
+
+    fn buffer(&mut self) -> Result<&'a [u8]> {
+        let (offset, len) = *self.buffers.get(self.next_buffer).ok_or_else(|| truncated("missing buffer"))?;
+        self.next_buffer += 1;
+
+        usize::try_from(offset)
+            .ok()
+            .zip(usize::try_from(len).ok())
+            .and_then(|(offset, len)| self.body.get(offset..offset.checked_add(len)?))
+            .ok_or_else(|| truncated("buffer out of bounds"))
+    }
+
+    /// `count` fixed-size elements of a buffer.
+    fn elements(&mut self, count: usize, size: usize) -> Result<std::slice::ChunksExact<'a, u8>> {
+        let buffer = self.buffer()?;
+        let len = count.checked_mul(size).ok_or_else(|| truncated("buffer too short"))?;
+        let data = buffer.get(..len).ok_or_else(|| truncated("buffer too short"))?;
+        Ok(data.chunks_exact(size.max(1)))
+    }
+
+    fn offsets(&mut self, length: usize, large: bool) -> Result<Vec<usize>> {
+        let size = if large { 8 } else { 4 };
+        self.elements(length + 1, size)?
+            .map(|b| {
+                let offset = if large {
+                    i64::from_le_bytes(b.try_into().unwrap())
+                } else {
+                    i32::from_le_bytes(b.try_into().unwrap()) as i64
+                };
+                usize::try_from(offset).map_err(|_| truncated("negative offset"))
+            })
+            .collect()
+    }
+}
+
+fn bit(bytes: &[u8], index: usize) -> Result<bool> {
+    let byte = bytes.get(index / 8).ok_or_else(|| truncated("bitmap too short"))?;
+    Ok(byte & (1 << (index % 8)) != 0)
+}
+
+/// Reads the next array (and its children) as `field`'s column type.
+pub fn decode_array(field: &ArrowField, reader: &mut BatchReader) -> Result<Vec<Value>> {
+    let column_type = field.column_type()?;
+    let (length, null_count) = reader.node()?;
+
+    let validity = reader.buffer()?;
+    let mut valid = Vec::with_capacity(length);
+    for i in 0..length {
+        valid.push(null_count == 0 || validity.is_empty() || bit(validity, i)?);
+    }
+
+    let mut values = Vec::with_capacity(length);
+    match &field.data_type {
+        ArrowType::Int { bit_width, signed } => {
+            for bytes in reader.elements(length, (*bit_width / 8) as usize)? {
+                let v = match (bytes.len(), signed) {
+                    (1, true) => bytes[0] as i8 as i64,
+                    (1, false) => bytes[0] as i64,
+                    (2, true) => i16::from_le_bytes(bytes.try_into().unwrap()) as i64,
+                    (2, false) => u16::from_le_bytes(bytes.try_into().unwrap()) as i64,
+                    (4, true) => i32::from_le_bytes(bytes.try_into().unwrap()) as i64,
+                    (4, false) => u32::from_le_bytes(bytes.try_into().unwrap()) as i64,
+                    _ => i64::from_le_bytes(bytes.try_into().unwrap()),
+                };
+                values.push(match column_type {
+                    ColumnType::Integer32 => Value::Int32(v as i32),
+                    _ => Value::Int64(v),
+                });
+            }
+        }
+        ArrowType::Float { precision } => {
+            let size = if *precision == 1 { 4 } else { 8 };
+            for bytes in reader.elements(length, size)? {
+                values.push(match size {
+                    4 => Value::Float32(f32::from_le_bytes(bytes.try_into().unwrap())),
+                    _ => Value::Float64(f64::from_le_bytes(bytes.try_into().unwrap())),
+                });
+            }
+        }
+        ArrowType::Utf8 | ArrowType::LargeUtf8 | ArrowType::Binary | ArrowType::LargeBinary => {
+            let large = matches!(field.data_type, ArrowType::LargeUtf8 | ArrowType::LargeBinary);
+            let offsets = reader.offsets(length, large)?;
+            let data = reader.buffer()?;
+
+            for window in offsets.windows(2) {
+                let bytes = data.get(window[0]..window[1]).ok_or_else(|| truncated("value data too short"))?;
+                values.push(match column_type {
+                    ColumnType::Utf8 => Value::String(
+                        String::from_utf8(bytes.to_vec())
+                            .map_err(|_| Error::new(ErrorKind::InvalidData, "Arrow Utf8 value is not valid UTF-8"))?,
+                    ),
+                    _ => Value::Binary(bytes.to_vec()),
+                });
+            }
+        }
+        ArrowType::FixedSizeBinary(width) => {
+            for bytes in reader.elements(length, *width as usize)? {
+                values.push(match column_type {
+                    ColumnType::Uuid => Value::Uuid(bytes.try_into().unwrap()),
+                    _ => Value::Binary(bytes.to_vec()),
+                });
+            }
+        }
+        ArrowType::Bool => {
+            let data = reader.buffer()?;
+            for i in 0..length {
+                values.push(Value::Bool(bit(data, i)?));
+            }
+        }
+        ArrowType::Timestamp { unit, .. } => {
+            for bytes in reader.elements(length, 8)? {
+                values.push(Value::Timestamp(unit.to_micros(i64::from_le_bytes(bytes.try_into().unwrap()))));
+            }
+        }
+        ArrowType::List => {
+            let offsets = reader.offsets(length, false)?;
+            let items = decode_array(&field.children[0], reader)?;
+
+            for window in offsets.windows(2) {
+                let slice = items.get(window[0]..window[1]).ok_or_else(|| truncated("list offsets past child"))?;
+                values.push(Value::List(slice.to_vec()));
+            }
+        }
+        ArrowType::Struct => {
+            let mut children = Vec::with_capacity(field.children.len());
+            for child in &field.children {
+                let child_values = decode_array(child, reader)?;
+                if child_values.len() < length {
+                    return Err(truncated("struct child shorter than parent"));
+                }
+                children.push(child_values.into_iter());
+            }
+
+            for _ in 0..length {
+                values.push(Value::Struct(children.iter_mut().map(|c| c.next().unwrap()).collect()));
+            }
+        }
+    }
+
+    for (value, valid) in values.iter_mut().zip(valid) {
+        if !valid {
+            *value = Value::Null;
+        }
+    }
+    Ok(values)
+}
@@ -0,0 +1,39 @@
+use std::io::{Result, Write};
+use crate::engine::database::Database;
+use crate::formats::arrow::arrow_field::ArrowField;
+use crate::formats::arrow::arrow_writer::{ArrowFormat, ArrowWriter};
+
+/// Writes `columns` of a table (all top-level columns when empty) as Arrow
+/// IPC, one record batch per scan window of up to
+/// [`VECTOR_SIZE`](crate::query::vector::VECTOR_SIZE) rows. Fields are named
+/// after the projected column paths. Returns the number of rows written.
+pub fn export_arrow<W: Write>(
+    db: &Database,
+    table_name: &str,
+    columns: &[&str],
+    writer: W,
+    format: ArrowFormat,
+) -> Result<usize> {
+    let scan = db.scan(table_name, columns)?;
+
+    let fields = scan.column_names
+        .iter()
+        .zip(&scan.column_schemas)
+        .map(|(name, schema)| {
+            let mut field = ArrowField::from_column(schema);
+            field.name = name.clone();
+            field
+        })
+        .collect();
+
+    let mut writer = ArrowWriter::new(writer, fields, format)?;
+    let mut rows = 0;
+    for batch in scan {
+        let batch = batch?;
+        writer.write_batch(batch.row_count, &batch.columns)?;
+        rows += batch.row_count;
+    }
+
+    writer.finish()?;
+    Ok(rows)
+}
@@ -0,0 +1,265 @@
+use std::io::{Error, ErrorKind, Result};
+use crate::formats::arrow::flatbuffer::{FbField, FbObject, FbTable};
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::schema::table_column::TableColumn;
+
+/// Canonical extension name Arrow uses for UUIDs stored as `FixedSizeBinary(16)`.
+const UUID_EXTENSION: &str = "arrow.uuid";
+const EXTENSION_NAME_KEY: &str = "ARROW:extension:name";
+
+/// Arrow time units, as numbered in `Schema.fbs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Second = 0,
+    Millisecond = 1,
+    Microsecond = 2,
+    Nanosecond = 3,
+}
+
+impl TimeUnit {
+    /// Converts a value in this unit to microseconds.
+    pub fn to_micros(self, value: i64) -> i64 {
+        match self {
+            TimeUnit::Second => value.saturating_mul(1_000_000),
+            TimeUnit::Millisecond => value.saturating_mul(1_000),
+            TimeUnit::Microsecond => value,
+            TimeUnit::Nanosecond => value.div_euclid(1_000),
+        }
+    }
+}
+
+/// The Arrow types FluxDB reads and writes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArrowType {
+    Int { bit_width: i32, signed: bool },
+    /// `FloatingPoint`, with `SINGLE` = 1 and `DOUBLE` = 2.
+    Float { precision: i16 },
+    Utf8,
+    LargeUtf8,
+    Binary,
+    LargeBinary,
+    FixedSizeBinary(i32),
+    Bool,
+    Timestamp { unit: TimeUnit, timezone: Option<String> },
+    List,
+    Struct,
+}
+
+impl ArrowType {
+    /// Tag of the `Type` union in `Schema.fbs`.
+    fn union_tag(&self) -> u8 {
+        match self {
+            ArrowType::Int { .. } => 2,
+            ArrowType::Float { .. } => 3,
+            ArrowType::Binary => 4,
+            ArrowType::Utf8 => 5,
+            ArrowType::Bool => 6,
+            ArrowType::Timestamp { .. } => 10,
+            ArrowType::List => 12,
+            ArrowType::Struct => 13,
+            ArrowType::FixedSizeBinary(_) => 15,
+            ArrowType::LargeBinary => 19,
+            ArrowType::LargeUtf8 => 20,
+        }
+    }
+
+    fn to_fb(&self) -> FbObject {
+        let fields = match self {
+            ArrowType::Int { bit_width, signed } => vec![(0, FbField::I32(*bit_width)), (1, FbField::Bool(*signed))],
+            ArrowType::Float { precision } => vec![(0, FbField::I16(*precision))],
+            ArrowType::FixedSizeBinary(width) => vec![(0, FbField::I32(*width))],
+            ArrowType::Timestamp { unit, timezone } => {
+                let mut fields = vec![(0, FbField::I16(*unit as i16))];
+                if let Some(tz) = timezone {
+                    fields.push((1, FbField::Object(FbObject::String(tz.clone()))));
+                }
+                fields
+            }
+            _ => Vec::new(),
+        };
+        FbObject::Table(fields)
+    }
+
+    fn from_fb(tag: u8, table: Option<FbTable>) -> Result<Self> {
+        let unsupported = || Error::new(ErrorKind::Unsupported, format!("unsupported Arrow type (union tag {tag})"));
+        let table = table.ok_or_else(|| Error::new(ErrorKind::InvalidData, "Arrow field has no type"))?;
+
+        Ok(match tag {
+            2 => ArrowType::Int { bit_width: table.i32(0, 0)?, signed: table.bool(1, false)? },
+            3 => ArrowType::Float { precision: table.i16(0, 0)? },
+            4 => ArrowType::Binary,
+            5 => ArrowType::Utf8,
+            6 => ArrowType::Bool,
+            10 => {
+                let unit = match table.i16(0, 0)? {
+                    0 => TimeUnit::Second,
+                    1 => TimeUnit::Millisecond,
+                    2 => TimeUnit::Microsecond,
+                    3 => TimeUnit::Nanosecond,
+                    _ => return Err(unsupported()),
+                };
+                ArrowType::Timestamp { unit, timezone: table.string(1)?.map(str::to_string) }
+            }
+            12 => ArrowType::List,
+            13 => ArrowType::Struct,
+            15 => ArrowType::FixedSizeBinary(table.i32(0, 0)?),
+            19 => ArrowType::LargeBinary,
+            20 => ArrowType::LargeUtf8,
+            _ => return Err(unsupported()),
+        })
+    }
+}
+
+/// One field of an Arrow schema.
+#[derive(Debug, Clone)]
+pub struct ArrowField {
+    pub name: String,
+    pub nullable: bool,
+    pub data_type: ArrowType,
+    pub children: Vec<ArrowField>,
+    /// Value of the `ARROW:extension:name` metadata key.
+    pub extension: Option<String>,
+}
+
+impl ArrowField {
+    /// Maps a column (and its nested children) to an Arrow field.
+    ///
+    /// `Timestamp` becomes `Timestamp(MICROSECOND, "UTC")` and `Uuid` becomes
+    /// `FixedSizeBinary(16)` tagged with the `arrow.uuid` extension.
+    pub fn from_column(column: &TableColumn) -> Self {
+        let mut extension = None;
+        let data_type = match column.column_type {
+            ColumnType::Integer32 => ArrowType::Int { bit_width: 32, signed: true },
+            ColumnType::Integer64 => ArrowType::Int { bit_width: 64, signed: true },
+            ColumnType::Float32 => ArrowType::Float { precision: 1 },
+            ColumnType::Float64 => ArrowType::Float { precision: 2 },
+            ColumnType::Utf8 => ArrowType::Utf8,
+            ColumnType::Timestamp => ArrowType::Timestamp {
+                unit: TimeUnit::Microsecond,
+                timezone: Some("UTC".to_string()),
+            },
+            ColumnType::Boolean => ArrowType::Bool,
+            ColumnType::Binary => ArrowType::Binary,
+            ColumnType::FixedSizeBinary(width) => ArrowType::FixedSizeBinary(width as i32),
+            ColumnType::Uuid => {
+                extension = Some(UUID_EXTENSION.to_string());
+                ArrowType::FixedSizeBinary(16)
+            }
+            ColumnType::List => ArrowType::List,
+            ColumnType::Struct => ArrowType::Struct,
+        };
+
+        Self {
+            name: column.name.clone(),
+            nullable: true,
+            data_type,
+            children: column.children.iter().map(ArrowField::from_column).collect(),
+            extension,
+        }
+    }
+
+    /// The column type this field is imported as. Narrower integers widen to
+    /// `Integer32` (`Integer64` for `uint32`); other timestamp units are
+    /// converted to microseconds.
+    pub fn column_type(&self) -> Result<ColumnType> {
+        let unsupported = || Error::new(
+            ErrorKind::Unsupported,
+            format!("Arrow field '{}' of type {:?} cannot be imported", self.name, self.data_type),
+        );
+
+        Ok(match &self.data_type {
+            ArrowType::Int { bit_width: 8 | 16, .. } => ColumnType::Integer32,
+            ArrowType::Int { bit_width: 32, signed: true } => ColumnType::Integer32,
+            ArrowType::Int { bit_width: 32, signed: false } => ColumnType::Integer64,
+            ArrowType::Int { bit_width: 64, signed: true } => ColumnType::Integer64,
+            ArrowType::Int { .. } => return Err(unsupported()),
+            ArrowType::Float { precision: 1 } => ColumnType::Float32,
+            ArrowType::Float { precision: 2 } => ColumnType::Float64,
+            ArrowType::Float { .. } => return Err(unsupported()),
+            ArrowType::Utf8 | ArrowType::LargeUtf8 => ColumnType::Utf8,
+            ArrowType::Binary | ArrowType::LargeBinary => ColumnType::Binary,
+            ArrowType::FixedSizeBinary(16) if self.extension.as_deref() == Some(UUID_EXTENSION) => ColumnType::Uuid,
+            ArrowType::FixedSizeBinary(width) => {
+                ColumnType::FixedSizeBinary(u16::try_from(*width).map_err(|_| unsupported())?)
+            }
+            ArrowType::Bool => ColumnType::Boolean,
+            ArrowType::Timestamp { .. } => ColumnType::Timestamp,
+            ArrowType::List if self.children.len() == 1 => ColumnType::List,
+            ArrowType::Struct => ColumnType::Struct,
+            ArrowType::List => return Err(unsupported()),
+        })
+    }
+
+    pub fn to_fb(&self) -> FbObject {
+        let mut fields = vec![
+            (0, FbField::Object(FbObject::String(self.name.clone()))),
+            (1, FbField::Bool(self.nullable)),
+            (2, FbField::U8(self.data_type.union_tag())),
+            (3, FbField::Object(self.data_type.to_fb())),
+            (5, FbField::Object(FbObject::Tables(self.children.iter().map(|c| c.to_fb()).collect()))),
+        ];
+
+        if let Some(extension) = &self.extension {
+            let key_value = FbObject::Table(vec![
+                (0, FbField::Object(FbObject::String(EXTENSION_NAME_KEY.to_string()))),
+                (1, FbField::Object(FbObject::String(extension.clone()))),
+            ]);
+            fields.push((6, FbField::Object(FbObject::Tables(vec![key_value]))));
+        }
+
+        FbObject::Table(fields)
+    }
+
+    pub fn from_fb(table: FbTable) -> Result<Self> {
+        if table.table(4)?.is_some() {
+            return Err(Error::new(ErrorKind::Unsupported, "dictionary-encoded Arrow fields are not supported"));
+        }
+
+        let mut children = Vec::new();
+        if let Some(vector) = table.vector(5)? {
+            for i in 0..vector.len {
+                children.push(ArrowField::from_fb(vector.table(i)?)?);
+            }
+        }
+
+        let mut extension = None;
+        if let Some(vector) = table.vector(6)? {
+            for i in 0..vector.len {
+                let key_value = vector.table(i)?;
+                if key_value.string(0)? == Some(EXTENSION_NAME_KEY) {
+                    extension = key_value.string(1)?.map(str::to_string);
+                }
+            }
+        }
+
+        Ok(Self {
+            name: table.string(0)?.unwrap_or_default().to_string(),
+            nullable: table.bool(1, false)?,
+            data_type: ArrowType::from_fb(table.u8(2, 0)?, table.table(3)?)?,
+            children,
+            extension,
+        })
+    }
+}
+
+/// Encodes a `Schema` table: little-endian, with `fields`.
+pub fn schema_to_fb(fields: &[ArrowField]) -> FbObject {
+    FbObject::Table(vec![
+        (0, FbField::I16(0)),
+        (1, FbField::Object(FbObject::Tables(fields.iter().map(|f| f.to_fb()).collect()))),
+    ])
+}
+
+pub fn schema_from_fb(table: FbTable) -> Result<Vec<ArrowField>> {
+    if table.i16(0, 0)? != 0 {
+        return Err(Error::new(ErrorKind::Unsupported, "big-endian Arrow data is not supported"));
+    }
+
+    let mut fields = Vec::new();
+    if let Some(vector) = table.vector(1)? {
+        for i in 0..vector.len {
+            fields.push(ArrowField::from_fb(vector.table(i)?)?);
+        }
+    }
+    Ok(fields)
+}
@@ -0,0 +1,88 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use crate::engine::column_buffer::{BufferViews, ColumnBuffer};
+use crate::engine::column_slice::ColumnInput;
+use crate::engine::database::Database;
+use crate::formats::arrow::arrow_field::ArrowField;
+use crate::formats::arrow::arrow_reader::ArrowReader;
+
+/// Imports an Arrow IPC file or stream into `table_name`, creating the table
+/// (nested columns included) from the Arrow schema when it does not exist.
+/// Each record batch is bulk-appended. Returns the number of rows imported.
+///
+/// Dots in field names (as in exported nested paths) become underscores,
+/// since dots separate the parts of a column path.
+pub fn import_arrow(db: &mut Database, table_name: &str, path: &Path) -> Result<usize> {
+    let reader = ArrowReader::open(path)?;
+
+    if !db.catalog.tables_by_name.contains_key(table_name) {
+        db.create_table(table_name)?;
+        for field in &reader.fields {
+            let name = column_name(field);
+            db.add_column(table_name, &name, field.column_type()?)?;
+            add_children(db, table_name, &name, field)?;
+        }
+    }
+
+    let columns = db.catalog.tables_by_name
+        .get(table_name)
+        .and_then(|id| db.catalog.columns_by_table.get(id))
+        .map(|c| c.as_slice())
+        .unwrap_or(&[]);
+
+    // Arrow fields map to top-level columns by name
+    for field in &reader.fields {
+        let name = column_name(field);
+        let column = columns.iter().find(|c| c.name == name).ok_or_else(|| {
+            Error::new(ErrorKind::NotFound, format!("column '{name}' not found in table '{table_name}'"))
+        })?;
+
+        let column_type = field.column_type()?;
+        if column.column_type != column_type {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("column '{name}' is {:?}, Arrow field is {column_type:?}", column.column_type),
+            ));
+        }
+    }
+
+    let fields = reader.fields.clone();
+    let mut rows = 0;
+    for batch in reader {
+        let batch = batch?;
+
+        let mut buffers = Vec::with_capacity(fields.len());
+        for (field, values) in fields.iter().zip(batch.columns) {
+            let mut buffer = ColumnBuffer::new(&column_name(field), field.column_type()?);
+            for value in values {
+                buffer.push(value).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            }
+            buffers.push(buffer);
+        }
+
+        let views: Vec<BufferViews> = buffers.iter().map(|b| b.views()).collect();
+        let inputs: Vec<ColumnInput> = buffers
+            .iter()
+            .zip(&views)
+            .map(|(buffer, views)| buffer.input(views))
+            .collect();
+
+        rows += db.append_columns(table_name, &inputs)?;
+    }
+
+    db.flush()?;
+    Ok(rows)
+}
+
+fn add_children(db: &mut Database, table_name: &str, path: &str, field: &ArrowField) -> Result<()> {
+    for child in &field.children {
+        let name = column_name(child);
+        db.add_child_column(table_name, path, &name, child.column_type()?)?;
+        add_children(db, table_name, &format!("{path}.{name}"), child)?;
+    }
+    Ok(())
+}
+
+fn column_name(field: &ArrowField) -> String {
+    field.name.replace('.', "_")
+}
@@ -0,0 +1,248 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, Error, ErrorKind, Read, Result, Seek, SeekFrom};
+use std::path::Path;
+use crate::engine::table_scan::ScanBatch;
+use crate::formats::arrow::arrow_array::{decode_array, BatchReader, FieldNode};
+use crate::formats::arrow::arrow_field::{schema_from_fb, ArrowField};
+use crate::formats::arrow::arrow_writer::{
+    ARROW_MAGIC, CONTINUATION, HEADER_DICTIONARY_BATCH, HEADER_RECORD_BATCH, HEADER_SCHEMA,
+};
+use crate::formats::arrow::flatbuffer::FbTable;
+
+/// Reads record batches from the Arrow IPC stream or file format. Batches are
+/// yielded as [`ScanBatch`]es, numbering rows from zero.
+pub struct ArrowReader<R: Read> {
+    reader: R,
+    pub fields: Vec<ArrowField>,
+    next_row: u64,
+    /// Bytes consumed from `reader`.
+    position: u64,
+    /// Offsets of the record batches still to read from an IPC file, taken
+    /// from its footer. `None` for streams.
+    blocks: Option<VecDeque<u64>>,
+}
+
+struct Message {
+    header_type: u8,
+    metadata: Vec<u8>,
+    body: Vec<u8>,
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+impl ArrowReader<BufReader<File>> {
+    /// Opens an IPC file or stream, telling them apart by the file magic.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+
+        let mut magic = [0u8; 6];
+        let is_file = len >= 18 && file.read_exact(&mut magic).is_ok() && &magic == ARROW_MAGIC;
+        if !is_file {
+            file.seek(SeekFrom::Start(0))?;
+            return Self::new(BufReader::new(file));
+        }
+
+        // The footer holds the schema and the location of every record batch
+        let mut trailer = [0u8; 10];
+        file.seek(SeekFrom::Start(len - 10))?;
+        file.read_exact(&mut trailer)?;
+        if &trailer[4..] != ARROW_MAGIC {
+            return Err(invalid("Arrow file is missing its trailing magic"));
+        }
+
+        let footer_len = u64::from(u32::from_le_bytes(trailer[..4].try_into().unwrap()));
+        let footer_start = (len - 10)
+            .checked_sub(footer_len)
+            .filter(|start| *start >= 8)
+            .ok_or_else(|| invalid("Arrow file footer is out of bounds"))?;
+
+        let mut footer = vec![0u8; footer_len as usize];
+        file.seek(SeekFrom::Start(footer_start))?;
+        file.read_exact(&mut footer)?;
+
+        let footer = FbTable::root(&footer)?;
+        let schema = footer.table(1)?.ok_or_else(|| invalid("Arrow file footer has no schema"))?;
+        let fields = schema_from_fb(schema)?;
+        for field in &fields {
+            check_field(field)?;
+        }
+
+        if footer.vector(2)?.is_some_and(|v| v.len > 0) {
+            return Err(Error::new(ErrorKind::Unsupported, "dictionary-encoded Arrow data is not supported"));
+        }
+
+        let mut blocks = Vec::new();
+        if let Some(vector) = footer.vector(3)? {
+            for i in 0..vector.len {
+                let offset = i64::from_le_bytes(vector.struct_bytes(i, 24)?[..8].try_into().unwrap());
+                blocks.push(u64::try_from(offset).map_err(|_| invalid("negative Arrow block offset"))?);
+            }
+        }
+        blocks.sort_unstable();
+
+        file.seek(SeekFrom::Start(0))?;
+        Ok(Self {
+            reader: BufReader::new(file),
+            fields,
+            next_row: 0,
+            position: 0,
+            blocks: Some(blocks.into()),
+        })
+    }
+}
+
+impl<R: Read> ArrowReader<R> {
+    /// Reads an IPC stream, starting with its schema message.
+    pub fn new(reader: R) -> Result<Self> {
+        let mut arrow = Self {
+            reader,
+            fields: Vec::new(),
+            next_row: 0,
+            position: 0,
+            blocks: None,
+        };
+
+        let message = arrow.read_message()?.ok_or_else(|| invalid("Arrow stream is empty"))?;
+        if message.header_type != HEADER_SCHEMA {
+            return Err(invalid("Arrow stream does not start with a schema"));
+        }
+
+        let header = FbTable::root(&message.metadata)?
+            .table(2)?
+            .ok_or_else(|| invalid("Arrow schema message has no header"))?;
+        arrow.fields = schema_from_fb(header)?;
+
+        // Check every field maps to a column type before reading data
+        for field in &arrow.fields {
+            check_field(field)?;
+        }
+
+        Ok(arrow)
+    }
+
+    fn read_exact(&mut self, len: usize) -> Result<Vec<u8>> {
+        let mut buf = vec![0u8; len];
+        self.reader.read_exact(&mut buf)?;
+        self.position += len as u64;
+        Ok(buf)
+    }
+
+    /// Reads the next encapsulated message, `None` at the end of the stream.
+    fn read_message(&mut self) -> Result<Option<Message>> {
+        if let Some(blocks) = &mut self.blocks {
+            let Some(offset) = blocks.pop_front() else {
+                return Ok(None);
+            };
+
+            // Skip forward to the block; files may pad between messages
+            let skip = offset.checked_sub(self.position).ok_or_else(|| invalid("overlapping Arrow file blocks"))?;
+            std::io::copy(&mut (&mut self.reader).take(skip), &mut std::io::sink())?;
+            self.position = offset;
+        }
+
+        let mut prefix = [0u8; 4];
+        match self.reader.read_exact(&mut prefix) {
+            Ok(()) => self.position += 4,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof && self.blocks.is_none() => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        // Streams written before the continuation marker start with the length
+        let mut len = u32::from_le_bytes(prefix);
+        if len == CONTINUATION {
+            len = u32::from_le_bytes(self.read_exact(4)?.try_into().unwrap());
+        }
+        if len == 0 {
+            return Ok(None);
+        }
+
+        let metadata = self.read_exact(len as usize)?;
+        let message = FbTable::root(&metadata)?;
+        let header_type = message.u8(1, 0)?;
+        let body_len = usize::try_from(message.i64(3, 0)?).map_err(|_| invalid("negative Arrow body length"))?;
+        let body = self.read_exact(body_len)?;
+
+        Ok(Some(Message { header_type, metadata, body }))
+    }
+
+    fn read_batch(&mut self, message: Message) -> Result<ScanBatch> {
+        let batch = FbTable::root(&message.metadata)?
+            .table(2)?
+            .ok_or_else(|| invalid("Arrow record batch message has no header"))?;
+
+        if batch.table(3)?.is_some() {
+            return Err(Error::new(ErrorKind::Unsupported, "compressed Arrow record batches are not supported"));
+        }
+
+        let row_count = usize::try_from(batch.i64(0, 0)?).map_err(|_| invalid("negative Arrow batch length"))?;
+
+        let mut nodes = Vec::new();
+        if let Some(vector) = batch.vector(1)? {
+            for i in 0..vector.len {
+                let bytes = vector.struct_bytes(i, 16)?;
+                nodes.push(FieldNode {
+                    length: i64::from_le_bytes(bytes[..8].try_into().unwrap()),
+                    null_count: i64::from_le_bytes(bytes[8..].try_into().unwrap()),
+                });
+            }
+        }
+
+        let mut buffers = Vec::new();
+        if let Some(vector) = batch.vector(2)? {
+            for i in 0..vector.len {
+                let bytes = vector.struct_bytes(i, 16)?;
+                buffers.push((
+                    i64::from_le_bytes(bytes[..8].try_into().unwrap()),
+                    i64::from_le_bytes(bytes[8..].try_into().unwrap()),
+                ));
+            }
+        }
+
+        let mut reader = BatchReader::new(nodes, buffers, &message.body);
+        let mut columns = Vec::with_capacity(self.fields.len());
+        for field in &self.fields {
+            let values = decode_array(field, &mut reader)?;
+            if values.len() != row_count {
+                return Err(invalid("Arrow column length does not match the batch length"));
+            }
+            columns.push(values);
+        }
+
+        let row_start = self.next_row;
+        self.next_row += row_count as u64;
+        Ok(ScanBatch { row_start, row_count, columns, selection: None })
+    }
+
+    /// Reads the next record batch, `None` at the end of the stream.
+    pub fn read_next(&mut self) -> Result<Option<ScanBatch>> {
+        let Some(message) = self.read_message()? else {
+            return Ok(None);
+        };
+
+        match message.header_type {
+            HEADER_RECORD_BATCH => self.read_batch(message).map(Some),
+            HEADER_DICTIONARY_BATCH => Err(Error::new(
+                ErrorKind::Unsupported,
+                "dictionary-encoded Arrow data is not supported",
+            )),
+            tag => Err(invalid(&format!("unexpected Arrow message (header type {tag})"))),
+        }
+    }
+}
+
+fn check_field(field: &ArrowField) -> Result<()> {
+    field.column_type()?;
+    field.children.iter().try_for_each(check_field)
+}
+
+impl<R: Read> Iterator for ArrowReader<R> {
+    type Item = Result<ScanBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+}
@@ -0,0 +1,159 @@
+use std::io::{Result, Write};
+use crate::formats::arrow::arrow_array::{encode_array, BatchBody};
+use crate::formats::arrow::arrow_field::{schema_to_fb, ArrowField};
+use crate::formats::arrow::flatbuffer::{build, FbField, FbObject};
+use crate::metadata::value::Value;
+
+/// Leading and trailing magic of the IPC file format.
+pub const ARROW_MAGIC: &[u8; 6] = b"ARROW1";
+/// Marks the start of an encapsulated message.
+pub const CONTINUATION: u32 = 0xFFFF_FFFF;
+/// `MetadataVersion.V5`.
+pub const METADATA_VERSION: i16 = 4;
+
+/// `MessageHeader` union tags.
+pub const HEADER_SCHEMA: u8 = 1;
+pub const HEADER_DICTIONARY_BATCH: u8 = 2;
+pub const HEADER_RECORD_BATCH: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrowFormat {
+    /// IPC streaming format: schema, record batches, end-of-stream marker.
+    Stream,
+    /// IPC file format: the stream framed by magic bytes, with a footer
+    /// indexing the record batches for random access.
+    File,
+}
+
+/// Location of a message in an IPC file.
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    offset: i64,
+    metadata_len: i32,
+    body_len: i64,
+}
+
+/// Writes record batches in the Arrow IPC stream or file format.
+pub struct ArrowWriter<W: Write> {
+    writer: W,
+    format: ArrowFormat,
+    fields: Vec<ArrowField>,
+    position: u64,
+    batches: Vec<Block>,
+}
+
+impl<W: Write> ArrowWriter<W> {
+    /// Starts the output and writes the schema message.
+    pub fn new(writer: W, fields: Vec<ArrowField>, format: ArrowFormat) -> Result<Self> {
+        let mut arrow = Self {
+            writer,
+            format,
+            fields,
+            position: 0,
+            batches: Vec::new(),
+        };
+
+        if format == ArrowFormat::File {
+            arrow.write_all(ARROW_MAGIC)?;
+            arrow.write_all(&[0, 0])?;
+        }
+
+        let schema = schema_to_fb(&arrow.fields);
+        arrow.write_message(HEADER_SCHEMA, schema, 0, &[])?;
+        Ok(arrow)
+    }
+
+    pub fn fields(&self) -> &[ArrowField] {
+        &self.fields
+    }
+
+    /// Writes one record batch; `columns` are in schema order, each
+    /// `row_count` long.
+    pub fn write_batch(&mut self, row_count: usize, columns: &[Vec<Value>]) -> Result<()> {
+        let mut body = BatchBody::default();
+        for (field, values) in self.fields.iter().zip(columns) {
+            let values: Vec<&Value> = values.iter().collect();
+            encode_array(field, &values, &mut body)?;
+        }
+
+        let mut nodes = Vec::with_capacity(body.nodes.len() * 16);
+        for node in &body.nodes {
+            nodes.extend_from_slice(&node.length.to_le_bytes());
+            nodes.extend_from_slice(&node.null_count.to_le_bytes());
+        }
+
+        let mut buffers = Vec::with_capacity(body.buffers.len() * 16);
+        for (offset, len) in &body.buffers {
+            buffers.extend_from_slice(&offset.to_le_bytes());
+            buffers.extend_from_slice(&len.to_le_bytes());
+        }
+
+        let batch = FbObject::Table(vec![
+            (0, FbField::I64(row_count as i64)),
+            (1, FbField::Object(FbObject::Structs { size: 16, align: 8, data: nodes })),
+            (2, FbField::Object(FbObject::Structs { size: 16, align: 8, data: buffers })),
+        ]);
+
+        let block = self.write_message(HEADER_RECORD_BATCH, batch, body.data.len() as i64, &body.data)?;
+        self.batches.push(block);
+        Ok(())
+    }
+
+    /// Writes the end-of-stream marker (and the footer of a file) and
+    /// returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.write_all(&CONTINUATION.to_le_bytes())?;
+        self.write_all(&0u32.to_le_bytes())?;
+
+        if self.format == ArrowFormat::File {
+            let mut blocks = Vec::with_capacity(self.batches.len() * 24);
+            for block in &self.batches {
+                blocks.extend_from_slice(&block.offset.to_le_bytes());
+                blocks.extend_from_slice(&block.metadata_len.to_le_bytes());
+                blocks.extend_from_slice(&[0; 4]);
+                blocks.extend_from_slice(&block.body_len.to_le_bytes());
+            }
+
+            let footer = build(&FbObject::Table(vec![
+                (0, FbField::I16(METADATA_VERSION)),
+                (1, FbField::Object(schema_to_fb(&self.fields))),
+                (2, FbField::Object(FbObject::Structs { size: 24, align: 8, data: Vec::new() })),
+                (3, FbField::Object(FbObject::Structs { size: 24, align: 8, data: blocks })),
+            ]));
+
+            self.write_all(&footer)?;
+            self.write_all(&(footer.len() as i32).to_le_bytes())?;
+            self.write_all(ARROW_MAGIC)?;
+        }
+
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_message(&mut self, header_type: u8, header: FbObject, body_len: i64, body: &[u8]) -> Result<Block> {
+        let message = build(&FbObject::Table(vec![
+            (0, FbField::I16(METADATA_VERSION)),
+            (1, FbField::U8(header_type)),
+            (2, FbField::Object(header)),
+            (3, FbField::I64(body_len)),
+        ]));
+
+        // Metadata is padded so the body starts on an 8-byte boundary
+        let padded = message.len().next_multiple_of(8);
+        let offset = self.position as i64;
+
+        self.write_all(&CONTINUATION.to_le_bytes())?;
+        self.write_all(&(padded as i32).to_le_bytes())?;
+        self.write_all(&message)?;
+        self.write_all(&vec![0; padded - message.len()])?;
+        self.write_all(body)?;
+
+        Ok(Block { offset, metadata_len: 8 + padded as i32, body_len })
+    }
+
+    fn write_all(&mut self, bytes: &[u8]) -> Result<()> {
+        self.writer.write_all(bytes)?;
+        self.position += bytes.len() as u64;
+        Ok(())
+    }
+}
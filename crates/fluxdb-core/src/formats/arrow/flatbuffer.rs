@@ -0,0 +1,261 @@
+//! Just enough FlatBuffers to read and write Arrow IPC metadata.
+//!
+//! The builder lays objects out front to back, parents before children, so
+//! every `uoffset` points forward as the format requires. The reader checks
+//! every access against the buffer bounds.
+
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind, Result};
+
+/// An object to serialize.
+pub enum FbObject {
+    /// Fields by vtable slot.
+    Table(Vec<(u16, FbField)>),
+    String(String),
+    Tables(Vec<FbObject>),
+    /// Vector of fixed-size structs, already encoded back to back.
+    Structs { size: usize, align: usize, data: Vec<u8> },
+}
+
+pub enum FbField {
+    Bool(bool),
+    U8(u8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    Object(FbObject),
+}
+
+impl FbField {
+    fn size(&self) -> usize {
+        match self {
+            FbField::Bool(_) | FbField::U8(_) => 1,
+            FbField::I16(_) => 2,
+            FbField::I32(_) | FbField::Object(_) => 4,
+            FbField::I64(_) => 8,
+        }
+    }
+}
+
+/// Serializes `root` as a finished flatbuffer.
+pub fn build(root: &FbObject) -> Vec<u8> {
+    let mut buf = vec![0u8; 4];
+    let mut pending: VecDeque<(usize, &FbObject)> = VecDeque::from([(0, root)]);
+
+    while let Some((slot, object)) = pending.pop_front() {
+        let pos = write_object(&mut buf, object, &mut pending);
+        buf[slot..slot + 4].copy_from_slice(&((pos - slot) as u32).to_le_bytes());
+    }
+
+    buf
+}
+
+fn pad_to(buf: &mut Vec<u8>, align: usize) {
+    while !buf.len().is_multiple_of(align) {
+        buf.push(0);
+    }
+}
+
+fn write_object<'a>(buf: &mut Vec<u8>, object: &'a FbObject, pending: &mut VecDeque<(usize, &'a FbObject)>) -> usize {
+    match object {
+        FbObject::Table(fields) => {
+            // Inline layout: soffset, then fields largest first so each is aligned
+            let mut order: Vec<&(u16, FbField)> = fields.iter().collect();
+            order.sort_by_key(|(_, field)| std::cmp::Reverse(field.size()));
+
+            let mut offsets = Vec::with_capacity(order.len());
+            let mut size: usize = 4;
+            for (_, field) in &order {
+                size = size.next_multiple_of(field.size());
+                offsets.push(size);
+                size += field.size();
+            }
+            let align = order.first().map_or(4, |(_, f)| f.size().max(4));
+
+            let slots = fields.iter().map(|(slot, _)| *slot as usize + 1).max().unwrap_or(0);
+            let mut vtable = vec![0u16; 2 + slots];
+            vtable[0] = (4 + 2 * slots) as u16;
+            vtable[1] = size as u16;
+            for ((slot, _), offset) in order.iter().zip(&offsets) {
+                vtable[2 + *slot as usize] = *offset as u16;
+            }
+
+            pad_to(buf, 2);
+            let vtable_pos = buf.len();
+            for entry in vtable {
+                buf.extend_from_slice(&entry.to_le_bytes());
+            }
+
+            pad_to(buf, align);
+            let table_pos = buf.len();
+            buf.resize(table_pos + size, 0);
+            buf[table_pos..table_pos + 4].copy_from_slice(&((table_pos - vtable_pos) as i32).to_le_bytes());
+
+            for ((_, field), offset) in order.iter().zip(&offsets) {
+                let at = table_pos + offset;
+                match field {
+                    FbField::Bool(v) => buf[at] = *v as u8,
+                    FbField::U8(v) => buf[at] = *v,
+                    FbField::I16(v) => buf[at..at + 2].copy_from_slice(&v.to_le_bytes()),
+                    FbField::I32(v) => buf[at..at + 4].copy_from_slice(&v.to_le_bytes()),
+                    FbField::I64(v) => buf[at..at + 8].copy_from_slice(&v.to_le_bytes()),
+                    FbField::Object(child) => pending.push_back((at, child)),
+                }
+            }
+
+            table_pos
+        }
+        FbObject::String(s) => {
+            pad_to(buf, 4);
+            let pos = buf.len();
+            buf.extend_from_slice(&(s.len() as u32).to_le_bytes());
+            buf.extend_from_slice(s.as_bytes());
+            buf.push(0);
+            pos
+        }
+        FbObject::Tables(items) => {
+            pad_to(buf, 4);
+            let pos = buf.len();
+            buf.extend_from_slice(&(items.len() as u32).to_le_bytes());
+            for item in items {
+                pending.push_back((buf.len(), item));
+                buf.extend_from_slice(&[0; 4]);
+            }
+            pos
+        }
+        FbObject::Structs { size, align, data } => {
+            // The elements, not the length prefix, carry the struct alignment
+            while !(buf.len() + 4).is_multiple_of(*align) {
+                buf.push(0);
+            }
+            let pos = buf.len();
+            buf.extend_from_slice(&((data.len() / size) as u32).to_le_bytes());
+            buf.extend_from_slice(data);
+            pos
+        }
+    }
+}
+
+fn invalid(what: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("malformed flatbuffer: {what}"))
+}
+
+fn read_bytes(buf: &[u8], pos: usize, len: usize) -> Result<&[u8]> {
+    pos.checked_add(len)
+        .and_then(|end| buf.get(pos..end))
+        .ok_or_else(|| invalid("offset out of bounds"))
+}
+
+fn read_u32(buf: &[u8], pos: usize) -> Result<u32> {
+    Ok(u32::from_le_bytes(read_bytes(buf, pos, 4)?.try_into().unwrap()))
+}
+
+/// A table inside a flatbuffer.
+#[derive(Clone, Copy)]
+pub struct FbTable<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    vtable: usize,
+    vtable_len: usize,
+}
+
+/// A vector inside a flatbuffer: `len` elements starting at `pos`.
+#[derive(Clone, Copy)]
+pub struct FbVector<'a> {
+    buf: &'a [u8],
+    pub pos: usize,
+    pub len: usize,
+}
+
+impl<'a> FbTable<'a> {
+    pub fn root(buf: &'a [u8]) -> Result<Self> {
+        Self::at(buf, read_u32(buf, 0)? as usize)
+    }
+
+    fn at(buf: &'a [u8], pos: usize) -> Result<Self> {
+        let soffset = i32::from_le_bytes(read_bytes(buf, pos, 4)?.try_into().unwrap());
+        let vtable = usize::try_from(pos as i64 - soffset as i64).map_err(|_| invalid("vtable out of bounds"))?;
+        let vtable_len = u16::from_le_bytes(read_bytes(buf, vtable, 2)?.try_into().unwrap()) as usize;
+        read_bytes(buf, vtable, vtable_len)?;
+
+        Ok(Self { buf, pos, vtable, vtable_len })
+    }
+
+    /// Absolute position of the field in `slot`, `None` when absent.
+    fn field(&self, slot: usize) -> Option<usize> {
+        let entry = 4 + 2 * slot;
+        if entry + 2 > self.vtable_len {
+            return None;
+        }
+        let offset = u16::from_le_bytes(self.buf[self.vtable + entry..self.vtable + entry + 2].try_into().unwrap());
+        (offset != 0).then_some(self.pos + offset as usize)
+    }
+
+    fn scalar<const N: usize>(&self, slot: usize) -> Result<Option<[u8; N]>> {
+        match self.field(slot) {
+            Some(pos) => Ok(Some(read_bytes(self.buf, pos, N)?.try_into().unwrap())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn u8(&self, slot: usize, default: u8) -> Result<u8> {
+        Ok(self.scalar::<1>(slot)?.map_or(default, |b| b[0]))
+    }
+
+    pub fn bool(&self, slot: usize, default: bool) -> Result<bool> {
+        Ok(self.scalar::<1>(slot)?.map_or(default, |b| b[0] != 0))
+    }
+
+    pub fn i16(&self, slot: usize, default: i16) -> Result<i16> {
+        Ok(self.scalar(slot)?.map_or(default, i16::from_le_bytes))
+    }
+
+    pub fn i32(&self, slot: usize, default: i32) -> Result<i32> {
+        Ok(self.scalar(slot)?.map_or(default, i32::from_le_bytes))
+    }
+
+    pub fn i64(&self, slot: usize, default: i64) -> Result<i64> {
+        Ok(self.scalar(slot)?.map_or(default, i64::from_le_bytes))
+    }
+
+    fn target(&self, slot: usize) -> Result<Option<usize>> {
+        match self.field(slot) {
+            Some(pos) => Ok(Some(pos + read_u32(self.buf, pos)? as usize)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn table(&self, slot: usize) -> Result<Option<FbTable<'a>>> {
+        self.target(slot)?.map(|pos| FbTable::at(self.buf, pos)).transpose()
+    }
+
+    pub fn string(&self, slot: usize) -> Result<Option<&'a str>> {
+        let Some(pos) = self.target(slot)? else {
+            return Ok(None);
+        };
+        let len = read_u32(self.buf, pos)? as usize;
+        let bytes = read_bytes(self.buf, pos + 4, len)?;
+        std::str::from_utf8(bytes).map(Some).map_err(|_| invalid("string is not UTF-8"))
+    }
+
+    pub fn vector(&self, slot: usize) -> Result<Option<FbVector<'a>>> {
+        let Some(pos) = self.target(slot)? else {
+            return Ok(None);
+        };
+        let len = read_u32(self.buf, pos)? as usize;
+        Ok(Some(FbVector { buf: self.buf, pos: pos + 4, len }))
+    }
+}
+
+impl<'a> FbVector<'a> {
+    /// Table element `index` of a vector of tables.
+    pub fn table(&self, index: usize) -> Result<FbTable<'a>> {
+        let slot = self.pos + 4 * index;
+        FbTable::at(self.buf, slot + read_u32(self.buf, slot)? as usize)
+    }
+
+    /// Raw bytes of struct element `index` of a vector of `size`-byte structs.
+    pub fn struct_bytes(&self, index: usize, size: usize) -> Result<&'a [u8]> {
+        read_bytes(self.buf, self.pos + size * index, size)
+    }
+}
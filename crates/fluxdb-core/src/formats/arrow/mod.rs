@@ -0,0 +1,7 @@
+mod flatbuffer;
+pub mod arrow_field;
+pub mod arrow_array;
+pub mod arrow_writer;
+pub mod arrow_reader;
+pub mod arrow_import;
+pub mod arrow_export;
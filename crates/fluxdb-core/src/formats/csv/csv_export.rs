@@ -0,0 +1,93 @@
+use std::fmt::Write as _;
+use std::io::{BufWriter, Result, Write};
+use crate::engine::database::Database;
+use crate::formats::scan_rows::{for_each_row, RowFilter};
+use crate::metadata::value::Value;
+
+#[derive(Debug, Clone)]
+pub struct CsvExportOptions {
+    pub delimiter: char,
+    pub write_header: bool,
+    /// Written for null values. Strings equal to it are quoted, so the
+    /// output imports back with the same nulls.
+    pub null_value: String,
+}
+
+impl Default for CsvExportOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            write_header: true,
+            null_value: String::new(),
+        }
+    }
+}
+
+/// Writes `columns` of a table (all top-level columns when empty) as CSV,
+/// streaming one chunk at a time. Rows rejected by `filter` are skipped.
+/// Returns the number of rows written.
+pub fn export_csv<W: Write>(
+    db: &Database,
+    table_name: &str,
+    columns: &[&str],
+    filter: Option<RowFilter>,
+    writer: W,
+    options: &CsvExportOptions,
+) -> Result<usize> {
+    let scan = db.scan(table_name, columns)?;
+    let mut writer = BufWriter::new(writer);
+    let mut line = String::new();
+
+    if options.write_header {
+        for (i, name) in scan.column_names.iter().enumerate() {
+            if i > 0 {
+                line.push(options.delimiter);
+            }
+            write_field(&mut line, name, options);
+        }
+        line.push('\n');
+        writer.write_all(line.as_bytes())?;
+    }
+
+    let rows = for_each_row(scan, filter, |row| {
+        line.clear();
+        for (i, value) in row.iter().enumerate() {
+            if i > 0 {
+                line.push(options.delimiter);
+            }
+
+            match value {
+                Value::Null => line.push_str(&options.null_value),
+                Value::String(s) => write_field(&mut line, s, options),
+                // Nested values contain ", " separators; quote as needed
+                Value::List(_) | Value::Struct(_) => write_field(&mut line, &value.to_string(), options),
+                other => write!(line, "{other}").unwrap(),
+            }
+        }
+        line.push('\n');
+        writer.write_all(line.as_bytes())
+    })?;
+
+    writer.flush()?;
+    Ok(rows)
+}
+
+/// Appends a field, quoting it when it would not read back as the same text.
+fn write_field(line: &mut String, text: &str, options: &CsvExportOptions) {
+    let needs_quotes = text == options.null_value
+        || text.contains([options.delimiter, '"', '\n', '\r']);
+
+    if !needs_quotes {
+        line.push_str(text);
+        return;
+    }
+
+    line.push('"');
+    for c in text.chars() {
+        if c == '"' {
+            line.push('"');
+        }
+        line.push(c);
+    }
+    line.push('"');
+}
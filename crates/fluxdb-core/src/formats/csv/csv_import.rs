@@ -0,0 +1,359 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+use crate::engine::column_buffer::{BufferViews, ColumnBuffer};
+use crate::engine::column_slice::ColumnInput;
+use crate::engine::database::Database;
+use crate::formats::csv::csv_reader::{CsvReader, CsvRecord};
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::schema::table_column::TableColumn;
+use crate::metadata::value::Value;
+
+/// Rejected rows beyond this are counted but not described in the report.
+const MAX_REPORTED_ERRORS: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct CsvImportOptions {
+    pub delimiter: char,
+    /// The first record holds column names. Without a header, fields map to
+    /// the table's columns by position.
+    pub has_header: bool,
+    /// Infer column types when the table has to be created. Otherwise every
+    /// column is created as `Utf8`.
+    pub infer_schema: bool,
+    /// Number of records sampled for schema inference.
+    pub infer_rows: usize,
+    /// Rows buffered per bulk append.
+    pub batch_size: usize,
+    /// Unquoted fields equal to one of these are imported as null.
+    pub null_values: Vec<String>,
+    /// Rejected records are copied here (after the header, if any) so they can
+    /// be fixed and re-imported.
+    pub reject_file: Option<PathBuf>,
+}
+
+impl Default for CsvImportOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            has_header: true,
+            infer_schema: true,
+            infer_rows: 1000,
+            batch_size: 65_536,
+            null_values: vec![String::new(), "NULL".to_string()],
+            reject_file: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RowError {
+    /// 1-based line the rejected record starts on.
+    pub line: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CsvImportReport {
+    pub rows_imported: usize,
+    pub rows_rejected: usize,
+    /// The first rejected rows, see `MAX_REPORTED_ERRORS`.
+    pub errors: Vec<RowError>,
+}
+
+/// Imports a CSV file into `table_name`, creating the table when it does not
+/// exist. Rows that do not parse are rejected one by one and reported; the
+/// rest are bulk-appended and flushed.
+pub fn import_csv(
+    db: &mut Database,
+    table_name: &str,
+    path: &Path,
+    options: &CsvImportOptions,
+) -> Result<CsvImportReport> {
+    if options.batch_size == 0 {
+        return Err(Error::new(ErrorKind::InvalidInput, "batch_size must be greater than zero"));
+    }
+
+    let mut reader = CsvReader::new(BufReader::new(File::open(path)?), options.delimiter);
+    let header = if options.has_header { reader.read_record()? } else { None };
+
+    // Sample records up front when the schema has to be inferred; they are
+    // replayed before the rest of the file.
+    let mut sample = Vec::new();
+    if !db.catalog.tables_by_name.contains_key(table_name) {
+        let limit = if options.infer_schema { options.infer_rows.max(1) } else { 1 };
+        while sample.len() < limit {
+            match reader.read_record()? {
+                Some(record) => sample.push(record),
+                None => break,
+            }
+        }
+
+        create_table(db, table_name, header.as_ref(), &sample, options)?;
+    }
+
+    let mut importer = Importer::new(db, table_name, header, options)?;
+    for record in sample {
+        importer.import_record(db, record)?;
+    }
+    for record in reader {
+        importer.import_record(db, record?)?;
+    }
+    importer.flush(db)?;
+    db.flush()?;
+
+    Ok(importer.report)
+}
+
+fn create_table(
+    db: &mut Database,
+    table_name: &str,
+    header: Option<&CsvRecord>,
+    sample: &[CsvRecord],
+    options: &CsvImportOptions,
+) -> Result<()> {
+    let names: Vec<String> = match header {
+        Some(header) => header.fields.iter().map(|name| name.trim().to_string()).collect(),
+        None => {
+            let width = sample.first().map_or(0, |r| r.fields.len());
+            (1..=width).map(|i| format!("column_{i}")).collect()
+        }
+    };
+
+    if names.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidData, "cannot create a table from an empty CSV file"));
+    }
+    if let Some(i) = names.iter().position(|n| n.is_empty()) {
+        return Err(Error::new(ErrorKind::InvalidData, format!("header column {} has no name", i + 1)));
+    }
+    for (i, name) in names.iter().enumerate() {
+        if names[..i].contains(name) {
+            return Err(Error::new(ErrorKind::InvalidData, format!("duplicate header column '{name}'")));
+        }
+    }
+
+    db.create_table(table_name)?;
+    for (i, name) in names.iter().enumerate() {
+        let column_type = if options.infer_schema {
+            infer_column_type(sample, i, options)
+        } else {
+            ColumnType::Utf8
+        };
+        db.add_column(table_name, name, column_type)?;
+    }
+
+    Ok(())
+}
+
+/// Picks the narrowest of Boolean, Integer64, Float64, Timestamp and Utf8
+/// that parses every non-null sample value of field `index`.
+fn infer_column_type(sample: &[CsvRecord], index: usize, options: &CsvImportOptions) -> ColumnType {
+    let mut candidates = vec![ColumnType::Boolean, ColumnType::Integer64, ColumnType::Float64, ColumnType::Timestamp];
+    let mut seen_value = false;
+
+    for record in sample {
+        let Some(field) = field_text(record, index, options) else {
+            continue;
+        };
+        seen_value = true;
+
+        candidates.retain(|&column_type| match column_type {
+            // `1`/`0` parse as booleans, but are far more likely integers
+            ColumnType::Boolean => matches!(
+                field.trim().to_ascii_lowercase().as_str(),
+                "true" | "false" | "t" | "f" | "yes" | "no"
+            ),
+            _ => Value::parse(field, column_type).is_ok(),
+        });
+    }
+
+    match candidates.first() {
+        Some(&column_type) if seen_value => column_type,
+        _ => ColumnType::Utf8,
+    }
+}
+
+/// Field `index` of a record, or `None` when it is missing or null.
+fn field_text<'a>(record: &'a CsvRecord, index: usize, options: &CsvImportOptions) -> Option<&'a str> {
+    let field = record.fields.get(index)?;
+    let is_null = !record.quoted[index] && options.null_values.iter().any(|n| n == field);
+    (!is_null).then_some(field.as_str())
+}
+
+struct Importer<'o> {
+    table_name: String,
+    options: &'o CsvImportOptions,
+    header: Option<CsvRecord>,
+    width: usize,
+    /// CSV field index for each buffer.
+    fields: Vec<usize>,
+    /// Table column for each buffer.
+    columns: Vec<TableColumn>,
+    buffers: Vec<ColumnBuffer>,
+    reject_writer: Option<BufWriter<File>>,
+    report: CsvImportReport,
+}
+
+impl<'o> Importer<'o> {
+    fn new(db: &Database, table_name: &str, header: Option<CsvRecord>, options: &'o CsvImportOptions) -> Result<Self> {
+        let columns = db.catalog.tables_by_name
+            .get(table_name)
+            .and_then(|id| db.catalog.columns_by_table.get(id))
+            .map(|c| c.as_slice())
+            .unwrap_or(&[]);
+
+        // Map CSV fields to top-level columns, by name or by position
+        let mapped: Vec<(usize, usize)> = match &header {
+            Some(header) => header.fields
+                .iter()
+                .enumerate()
+                .map(|(field, name)| {
+                    columns
+                        .iter()
+                        .position(|c| c.name == name.trim())
+                        .map(|column| (field, column))
+                        .ok_or_else(|| Error::new(
+                            ErrorKind::NotFound,
+                            format!("column '{}' not found in table '{table_name}'", name.trim()),
+                        ))
+                })
+                .collect::<Result<_>>()?,
+            None => (0..columns.len()).map(|i| (i, i)).collect(),
+        };
+
+        // Columns left out get nulls, which a NOT NULL one would reject in
+        // every batch
+        if let Some(column) = columns.iter().enumerate().find(|(i, c)| c.not_null && !mapped.iter().any(|(_, m)| m == i)) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("column '{}' is NOT NULL but has no field in the CSV file", column.1.name),
+            ));
+        }
+
+        let mut fields = Vec::with_capacity(mapped.len());
+        let mut mapped_columns = Vec::with_capacity(mapped.len());
+        let mut buffers = Vec::with_capacity(mapped.len());
+        for (field, column) in mapped {
+            let column = &columns[column];
+            if column.column_type.is_nested() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("column '{}' is {:?} and cannot be imported from CSV", column.name, column.column_type),
+                ));
+            }
+            if buffers.iter().any(|b: &ColumnBuffer| b.name == column.name) {
+                return Err(Error::new(ErrorKind::InvalidData, format!("duplicate header column '{}'", column.name)));
+            }
+
+            fields.push(field);
+            mapped_columns.push(column.clone());
+            buffers.push(ColumnBuffer::new(&column.name, column.column_type));
+        }
+
+        let width = match &header {
+            Some(header) => header.fields.len(),
+            None => columns.len(),
+        };
+
+        Ok(Self {
+            table_name: table_name.to_string(),
+            options,
+            header,
+            width,
+            fields,
+            columns: mapped_columns,
+            buffers,
+            reject_writer: None,
+            report: CsvImportReport::default(),
+        })
+    }
+
+    fn import_record(&mut self, db: &mut Database, record: CsvRecord) -> Result<()> {
+        match self.parse_record(db, &record) {
+            Ok(values) => {
+                for (buffer, value) in self.buffers.iter_mut().zip(values) {
+                    buffer.push(value).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+                }
+                if self.buffers.first().map_or(0, |b| b.len()) >= self.options.batch_size {
+                    self.flush(db)?;
+                }
+            }
+            Err(message) => self.reject(&record, message)?,
+        }
+
+        Ok(())
+    }
+
+    /// Parses every mapped field of a record, or describes the first problem.
+    /// Each value is checked as an append would check it, NOT NULL and size
+    /// included, so a bad field rejects its record rather than the batch.
+    fn parse_record(&self, db: &Database, record: &CsvRecord) -> std::result::Result<Vec<Value>, String> {
+        if record.fields.len() != self.width {
+            return Err(format!("expected {} fields, found {}", self.width, record.fields.len()));
+        }
+
+        self.fields
+            .iter()
+            .zip(&self.columns)
+            .map(|(&field, column)| {
+                let value = match field_text(record, field, self.options) {
+                    None => Value::Null,
+                    Some(text) => Value::parse(text, column.column_type)
+                        .map_err(|e| format!("column '{}': {e}", column.name))?,
+                };
+                db.chunk_manager.check_value(column, &value).map_err(|e| e.to_string())?;
+                Ok(value)
+            })
+            .collect()
+    }
+
+    fn reject(&mut self, record: &CsvRecord, message: String) -> Result<()> {
+        self.report.rows_rejected += 1;
+        if self.report.errors.len() < MAX_REPORTED_ERRORS {
+            self.report.errors.push(RowError { line: record.line, message });
+        }
+
+        let Some(path) = &self.options.reject_file else {
+            return Ok(());
+        };
+
+        // Created on the first rejected row, starting with the header
+        if self.reject_writer.is_none() {
+            let mut writer = BufWriter::new(File::create(path)?);
+            if let Some(header) = &self.header {
+                writeln!(writer, "{}", header.raw)?;
+            }
+            self.reject_writer = Some(writer);
+        }
+
+        let writer = self.reject_writer.as_mut().unwrap();
+        writeln!(writer, "{}", record.raw)
+    }
+
+    fn flush(&mut self, db: &mut Database) -> Result<()> {
+        if let Some(writer) = &mut self.reject_writer {
+            writer.flush()?;
+        }
+
+        if self.buffers.first().is_none_or(|b| b.is_empty()) {
+            return Ok(());
+        }
+
+        let views: Vec<BufferViews> = self.buffers.iter().map(|b| b.views()).collect();
+        let inputs: Vec<ColumnInput> = self.buffers
+            .iter()
+            .zip(&views)
+            .map(|(buffer, views)| buffer.input(views))
+            .collect();
+
+        self.report.rows_imported += db.append_columns(&self.table_name, &inputs)?;
+
+        drop(inputs);
+        drop(views);
+        for buffer in &mut self.buffers {
+            buffer.clear();
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use std::io::{BufRead, Error, ErrorKind, Result};
+
+/// One CSV record, with the text it was parsed from for reject files.
+#[derive(Debug, Clone)]
+pub struct CsvRecord {
+    /// 1-based line the record starts on.
+    pub line: u64,
+    pub fields: Vec<String>,
+    /// `true` for fields that were quoted; a quoted field is never null.
+    pub quoted: Vec<bool>,
+    /// The record as it appeared in the file, without the final line break.
+    pub raw: String,
+}
+
+/// Streaming RFC 4180 reader: quoted fields may contain delimiters, line
+/// breaks and `""` escaped quotes. Blank lines are skipped.
+pub struct CsvReader<R: BufRead> {
+    reader: R,
+    delimiter: char,
+    line: u64,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    pub fn new(reader: R, delimiter: char) -> Self {
+        Self { reader, delimiter, line: 0 }
+    }
+
+    pub fn read_record(&mut self) -> Result<Option<CsvRecord>> {
+        let mut raw = String::new();
+        let mut text = String::new();
+
+        // Skip blank lines between records
+        loop {
+            text.clear();
+            if self.reader.read_line(&mut text)? == 0 {
+                return Ok(None);
+            }
+            self.line += 1;
+            if !text.trim_end_matches(['\r', '\n']).is_empty() {
+                break;
+            }
+        }
+
+        let mut record = CsvRecord {
+            line: self.line,
+            fields: Vec::new(),
+            quoted: Vec::new(),
+            raw: String::new(),
+        };
+
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+
+        loop {
+            raw.push_str(&text);
+            let mut chars = text.chars().peekable();
+
+            while let Some(c) = chars.next() {
+                if in_quotes {
+                    if c == '"' {
+                        if chars.peek() == Some(&'"') {
+                            chars.next();
+                            field.push('"');
+                        } else {
+                            in_quotes = false;
+                        }
+                    } else {
+                        field.push(c);
+                    }
+                } else if c == self.delimiter {
+                    record.fields.push(std::mem::take(&mut field));
+                    record.quoted.push(std::mem::replace(&mut quoted, false));
+                } else if c == '"' && field.is_empty() && !quoted {
+                    in_quotes = true;
+                    quoted = true;
+                } else if c == '\n' || (c == '\r' && chars.peek() == Some(&'\n')) {
+                    // Line break ends the record outside quotes
+                } else {
+                    field.push(c);
+                }
+            }
+
+            if !in_quotes {
+                break;
+            }
+
+            // A quoted field continues on the next line
+            text.clear();
+            if self.reader.read_line(&mut text)? == 0 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("unterminated quoted field in record starting at line {}", record.line),
+                ));
+            }
+            self.line += 1;
+        }
+
+        record.fields.push(field);
+        record.quoted.push(quoted);
+        record.raw = raw.trim_end_matches(['\r', '\n']).to_string();
+
+        Ok(Some(record))
+    }
+}
+
+impl<R: BufRead> Iterator for CsvReader<R> {
+    type Item = Result<CsvRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
@@ -0,0 +1,3 @@
+pub mod csv_reader;
+pub mod csv_import;
+pub mod csv_export;
@@ -0,0 +1,108 @@
+use std::fmt::Write as _;
+use std::io::{BufWriter, Result, Write};
+use crate::engine::database::Database;
+use crate::formats::scan_rows::{for_each_row, RowFilter};
+use crate::helpers::helper::{write_hex, write_json_string};
+use crate::helpers::timestamp::format_timestamp;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::schema::table_column::TableColumn;
+use crate::metadata::value::Value;
+
+/// Writes `columns` of a table (all top-level columns when empty) as JSON
+/// Lines, one object per row keyed by column name, streaming one chunk at a
+/// time. Rows rejected by `filter` are skipped. Returns the number of rows
+/// written.
+///
+/// Lists become arrays and structs objects keyed by field name. Timestamps
+/// are RFC 3339 strings in UTC, binary values `0x` hex strings, and
+/// non-finite floats `null`.
+pub fn export_json_lines<W: Write>(
+    db: &Database,
+    table_name: &str,
+    columns: &[&str],
+    filter: Option<RowFilter>,
+    writer: W,
+) -> Result<usize> {
+    let scan = db.scan(table_name, columns)?;
+    let names = scan.column_names.clone();
+    let schemas = scan.column_schemas.clone();
+
+    let mut writer = BufWriter::new(writer);
+    let mut line = String::new();
+
+    let rows = for_each_row(scan, filter, |row| {
+        line.clear();
+        line.push('{');
+        for (i, value) in row.iter().enumerate() {
+            if i > 0 {
+                line.push(',');
+            }
+            write_json_string(&mut line, &names[i]);
+            line.push(':');
+            write_value(&mut line, value, Some(&schemas[i]));
+        }
+        line.push_str("}\n");
+        writer.write_all(line.as_bytes())
+    })?;
+
+    writer.flush()?;
+    Ok(rows)
+}
+
+fn write_value(out: &mut String, value: &Value, column: Option<&TableColumn>) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Int32(v) => write!(out, "{v}").unwrap(),
+        Value::Int64(v) => write!(out, "{v}").unwrap(),
+        Value::Float32(v) if v.is_finite() => write!(out, "{v}").unwrap(),
+        Value::Float64(v) if v.is_finite() => write!(out, "{v}").unwrap(),
+        Value::Float32(_) | Value::Float64(_) => out.push_str("null"),
+        Value::Bool(v) => write!(out, "{v}").unwrap(),
+        Value::String(s) => write_json_string(out, s),
+        Value::Timestamp(v) => {
+            // `YYYY-MM-DD HH:MM:SS[.ffffff]` -> `YYYY-MM-DDTHH:MM:SS[.ffffff]Z`
+            let text = format_timestamp(*v).replacen(' ', "T", 1);
+            write!(out, "\"{text}Z\"").unwrap();
+        }
+        Value::Binary(bytes) => {
+            out.push_str("\"0x");
+            write_hex(out, bytes).unwrap();
+            out.push('"');
+        }
+        Value::Uuid(_) => write!(out, "\"{value}\"").unwrap(),
+        Value::List(items) => {
+            let element = column
+                .filter(|c| c.column_type == ColumnType::List)
+                .and_then(|c| c.children.first());
+
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item, element);
+            }
+            out.push(']');
+        }
+        Value::Struct(fields) => {
+            let children = column
+                .filter(|c| c.column_type == ColumnType::Struct)
+                .map(|c| c.children.as_slice())
+                .unwrap_or(&[]);
+
+            out.push('{');
+            for (i, field) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                match children.get(i) {
+                    Some(child) => write_json_string(out, &child.name),
+                    None => write_json_string(out, &format!("field_{i}")),
+                }
+                out.push(':');
+                write_value(out, field, children.get(i));
+            }
+            out.push('}');
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod arrow;
+pub mod csv;
+pub mod json;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod scan_rows;
@@ -0,0 +1,2 @@
+pub mod parquet_schema;
+pub mod parquet_import;
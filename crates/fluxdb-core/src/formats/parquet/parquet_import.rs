@@ -0,0 +1,245 @@
+use std::fs::File;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use arrow_array::cast::AsArray;
+use arrow_array::types::*;
+use arrow_array::{Array, ArrowPrimitiveType, OffsetSizeTrait, RecordBatch};
+use arrow_schema::{DataType, TimeUnit};
+use ::parquet::arrow::arrow_reader::{ArrowReaderMetadata, ArrowReaderOptions, ParquetRecordBatchReaderBuilder};
+use crate::engine::column_buffer::{BufferViews, ColumnBuffer};
+use crate::engine::column_slice::ColumnInput;
+use crate::engine::database::Database;
+use crate::formats::parquet::parquet_schema::{map_field, uuid_paths, ParquetColumn};
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+#[derive(Debug, Clone, Default)]
+pub struct ParquetImportReport {
+    pub rows_imported: usize,
+    pub row_groups: usize,
+}
+
+fn invalid(e: impl std::error::Error + Send + Sync + 'static) -> Error {
+    Error::new(ErrorKind::InvalidData, e)
+}
+
+/// Imports a Parquet file into `table_name`, creating the table (nested
+/// columns included) from the file schema when it does not exist.
+///
+/// Row groups are loaded one at a time and the table is sealed after each,
+/// so a row group of up to `ROWS_PER_CHUNK` rows becomes exactly one chunk
+/// and the chunk zone maps line up with the row group statistics. Larger row
+/// groups are split over several chunks.
+pub fn import_parquet(db: &mut Database, table_name: &str, path: &Path) -> Result<ParquetImportReport> {
+    let file = File::open(path)?;
+
+    // Ignore the Arrow schema hint written by Arrow-based writers, so columns
+    // decode to plain (non-dictionary) arrays
+    let options = ArrowReaderOptions::new().with_skip_arrow_metadata(true);
+    let metadata = ArrowReaderMetadata::load(&file, options).map_err(invalid)?;
+
+    let uuids = uuid_paths(metadata.parquet_schema());
+    let columns = metadata.schema()
+        .fields()
+        .iter()
+        .map(|field| map_field(field, field.name(), &uuids))
+        .collect::<Result<Vec<_>>>()?;
+
+    if !db.catalog.tables_by_name.contains_key(table_name) {
+        db.create_table(table_name)?;
+        for column in &columns {
+            db.add_column(table_name, &column.name, column.column_type)?;
+            add_children(db, table_name, &column.name, column)?;
+        }
+    }
+    check_columns(db, table_name, &columns)?;
+
+    // Start the first row group on a fresh chunk
+    db.seal_table(table_name)?;
+
+    let mut report = ParquetImportReport::default();
+    for (index, row_group) in metadata.metadata().row_groups().iter().enumerate() {
+        let reader = ParquetRecordBatchReaderBuilder::new_with_metadata(file.try_clone()?, metadata.clone())
+            .with_row_groups(vec![index])
+            .with_batch_size((row_group.num_rows() as usize).max(1))
+            .build()
+            .map_err(invalid)?;
+
+        for batch in reader {
+            report.rows_imported += append_batch(db, table_name, &columns, &batch.map_err(invalid)?)?;
+        }
+
+        db.seal_table(table_name)?;
+        report.row_groups += 1;
+    }
+
+    Ok(report)
+}
+
+fn add_children(db: &mut Database, table_name: &str, path: &str, column: &ParquetColumn) -> Result<()> {
+    for child in &column.children {
+        db.add_child_column(table_name, path, &child.name, child.column_type)?;
+        add_children(db, table_name, &format!("{path}.{}", child.name), child)?;
+    }
+    Ok(())
+}
+
+/// Checks an existing table has a top-level column of the right type for
+/// every Parquet column.
+fn check_columns(db: &Database, table_name: &str, columns: &[ParquetColumn]) -> Result<()> {
+    let existing = db.catalog.tables_by_name
+        .get(table_name)
+        .and_then(|id| db.catalog.columns_by_table.get(id))
+        .map(|c| c.as_slice())
+        .unwrap_or(&[]);
+
+    for column in columns {
+        let found = existing.iter().find(|c| c.name == column.name).ok_or_else(|| {
+            Error::new(ErrorKind::NotFound, format!("column '{}' not found in table '{table_name}'", column.name))
+        })?;
+
+        if found.column_type != column.column_type {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("column '{}' is {:?}, Parquet column is {:?}", column.name, found.column_type, column.column_type),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn append_batch(db: &mut Database, table_name: &str, columns: &[ParquetColumn], batch: &RecordBatch) -> Result<usize> {
+    let mut buffers = Vec::with_capacity(columns.len());
+    for (column, array) in columns.iter().zip(batch.columns()) {
+        let mut buffer = ColumnBuffer::new(&column.name, column.column_type);
+        for value in array_values(array.as_ref(), column)? {
+            buffer.push(value).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        }
+        buffers.push(buffer);
+    }
+
+    let views: Vec<BufferViews> = buffers.iter().map(|b| b.views()).collect();
+    let inputs: Vec<ColumnInput> = buffers
+        .iter()
+        .zip(&views)
+        .map(|(buffer, views)| buffer.input(views))
+        .collect();
+
+    db.append_columns(table_name, &inputs)
+}
+
+fn primitive<T: ArrowPrimitiveType>(array: &dyn Array, f: impl Fn(T::Native) -> Value) -> Vec<Value> {
+    let array = array.as_primitive::<T>();
+    (0..array.len())
+        .map(|i| if array.is_null(i) { Value::Null } else { f(array.value(i)) })
+        .collect()
+}
+
+fn list<O: OffsetSizeTrait>(array: &dyn Array, column: &ParquetColumn) -> Result<Vec<Value>> {
+    let array = array.as_list::<O>();
+    let items = array_values(array.values().as_ref(), &column.children[0])?;
+    let offsets = array.value_offsets();
+
+    Ok((0..array.len())
+        .map(|i| match array.is_null(i) {
+            true => Value::Null,
+            false => Value::List(items[offsets[i].as_usize()..offsets[i + 1].as_usize()].to_vec()),
+        })
+        .collect())
+}
+
+/// Converts an Arrow array decoded from Parquet to values of `column`'s type.
+fn array_values(array: &dyn Array, column: &ParquetColumn) -> Result<Vec<Value>> {
+    let int32 = |v: i64| Value::Int32(v as i32);
+
+    let values = match array.data_type() {
+        DataType::Boolean => {
+            let array = array.as_boolean();
+            (0..array.len())
+                .map(|i| if array.is_null(i) { Value::Null } else { Value::Bool(array.value(i)) })
+                .collect()
+        }
+        DataType::Int8 => primitive::<Int8Type>(array, |v| int32(v as i64)),
+        DataType::Int16 => primitive::<Int16Type>(array, |v| int32(v as i64)),
+        DataType::Int32 => primitive::<Int32Type>(array, Value::Int32),
+        DataType::UInt8 => primitive::<UInt8Type>(array, |v| int32(v as i64)),
+        DataType::UInt16 => primitive::<UInt16Type>(array, |v| int32(v as i64)),
+        DataType::UInt32 => primitive::<UInt32Type>(array, |v| Value::Int64(v as i64)),
+        DataType::Int64 => primitive::<Int64Type>(array, Value::Int64),
+        DataType::Float32 => primitive::<Float32Type>(array, Value::Float32),
+        DataType::Float64 => primitive::<Float64Type>(array, Value::Float64),
+        DataType::Decimal128(_, scale) => {
+            let divisor = 10f64.powi(*scale as i32);
+            primitive::<Decimal128Type>(array, |v| Value::Float64(v as f64 / divisor))
+        }
+        DataType::Date32 => primitive::<Date32Type>(array, |v| Value::Timestamp(v as i64 * MICROS_PER_DAY)),
+        DataType::Date64 => primitive::<Date64Type>(array, |v| Value::Timestamp(v.saturating_mul(1_000))),
+        DataType::Timestamp(TimeUnit::Second, _) => {
+            primitive::<TimestampSecondType>(array, |v| Value::Timestamp(v.saturating_mul(1_000_000)))
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            primitive::<TimestampMillisecondType>(array, |v| Value::Timestamp(v.saturating_mul(1_000)))
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            primitive::<TimestampMicrosecondType>(array, Value::Timestamp)
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            primitive::<TimestampNanosecondType>(array, |v| Value::Timestamp(v.div_euclid(1_000)))
+        }
+        DataType::Utf8 => collect(array, |i| Value::String(array.as_string::<i32>().value(i).to_string())),
+        DataType::LargeUtf8 => collect(array, |i| Value::String(array.as_string::<i64>().value(i).to_string())),
+        DataType::Utf8View => collect(array, |i| Value::String(array.as_string_view().value(i).to_string())),
+        DataType::Binary => collect(array, |i| Value::Binary(array.as_binary::<i32>().value(i).to_vec())),
+        DataType::LargeBinary => collect(array, |i| Value::Binary(array.as_binary::<i64>().value(i).to_vec())),
+        DataType::BinaryView => collect(array, |i| Value::Binary(array.as_binary_view().value(i).to_vec())),
+        DataType::FixedSizeBinary(_) => {
+            let array = array.as_fixed_size_binary();
+            collect(array, |i| match column.column_type {
+                ColumnType::Uuid => Value::Uuid(array.value(i).try_into().unwrap()),
+                _ => Value::Binary(array.value(i).to_vec()),
+            })
+        }
+        DataType::List(_) => list::<i32>(array, column)?,
+        DataType::LargeList(_) => list::<i64>(array, column)?,
+        DataType::FixedSizeList(_, size) => {
+            let array = array.as_fixed_size_list();
+            let items = array_values(array.values().as_ref(), &column.children[0])?;
+            let size = *size as usize;
+            collect(array, |i| Value::List(items[i * size..(i + 1) * size].to_vec()))
+        }
+        DataType::Struct(_) => {
+            let array = array.as_struct();
+            let mut fields = Vec::with_capacity(column.children.len());
+            for (child, child_array) in column.children.iter().zip(array.columns()) {
+                fields.push(array_values(child_array.as_ref(), child)?);
+            }
+            collect(array, |i| Value::Struct(fields.iter().map(|f| f[i].clone()).collect()))
+        }
+        DataType::Map(_, _) => {
+            let array = array.as_map();
+            let entries = array_values(array.entries(), &column.children[0])?;
+            let offsets = array.value_offsets();
+            collect(array, |i| {
+                Value::List(entries[offsets[i] as usize..offsets[i + 1] as usize].to_vec())
+            })
+        }
+        other => {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("Parquet column '{}' of type {other} cannot be imported", column.name),
+            ));
+        }
+    };
+
+    Ok(values)
+}
+
+/// Values of a non-primitive array, null where the array is null.
+fn collect(array: &dyn Array, value: impl Fn(usize) -> Value) -> Vec<Value> {
+    (0..array.len())
+        .map(|i| if array.is_null(i) { Value::Null } else { value(i) })
+        .collect()
+}
@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+use std::io::{Error, ErrorKind, Result};
+use arrow_schema::{DataType, Field};
+use ::parquet::basic::LogicalType;
+use ::parquet::schema::types::SchemaDescriptor;
+use crate::metadata::schema::column_type::ColumnType;
+
+/// A column to create for a Parquet field, with its nested children.
+#[derive(Debug, Clone)]
+pub struct ParquetColumn {
+    pub name: String,
+    pub column_type: ColumnType,
+    pub children: Vec<ParquetColumn>,
+}
+
+/// Dotted paths of the leaf columns annotated as `UUID`, which the Arrow
+/// reader hands out as plain `FixedSizeBinary(16)`.
+pub fn uuid_paths(schema: &SchemaDescriptor) -> HashSet<String> {
+    schema.columns()
+        .iter()
+        .filter(|c| c.logical_type() == Some(LogicalType::Uuid))
+        .map(|c| c.path().string())
+        .collect()
+}
+
+/// Maps a field of the decoded Arrow schema to a column.
+///
+/// Narrow integers widen to `Integer32` (`Integer64` for `uint32`), dates
+/// and every timestamp unit become `Timestamp`, decimals become `Float64`,
+/// and maps become lists of `{key, value}` structs.
+pub fn map_field(field: &Field, path: &str, uuid_paths: &HashSet<String>) -> Result<ParquetColumn> {
+    let unsupported = || Error::new(
+        ErrorKind::Unsupported,
+        format!("Parquet column '{path}' of type {} cannot be imported", field.data_type()),
+    );
+
+    let mut children = Vec::new();
+    let column_type = match field.data_type() {
+        DataType::Boolean => ColumnType::Boolean,
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::UInt8 | DataType::UInt16 => ColumnType::Integer32,
+        DataType::Int64 | DataType::UInt32 => ColumnType::Integer64,
+        DataType::Float32 => ColumnType::Float32,
+        DataType::Float64 | DataType::Decimal128(_, _) => ColumnType::Float64,
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => ColumnType::Utf8,
+        DataType::Binary | DataType::LargeBinary | DataType::BinaryView => ColumnType::Binary,
+        DataType::FixedSizeBinary(16) if uuid_paths.contains(path) => ColumnType::Uuid,
+        DataType::FixedSizeBinary(width) => {
+            ColumnType::FixedSizeBinary(u16::try_from(*width).map_err(|_| unsupported())?)
+        }
+        DataType::Timestamp(_, _) | DataType::Date32 | DataType::Date64 => ColumnType::Timestamp,
+        DataType::List(element) | DataType::LargeList(element) | DataType::FixedSizeList(element, _) => {
+            children.push(map_field(element, &format!("{path}.list.{}", element.name()), uuid_paths)?);
+            ColumnType::List
+        }
+        DataType::Struct(fields) => {
+            for child in fields {
+                children.push(map_field(child, &format!("{path}.{}", child.name()), uuid_paths)?);
+            }
+            ColumnType::Struct
+        }
+        DataType::Map(entries, _) => {
+            children.push(map_field(entries, &format!("{path}.{}", entries.name()), uuid_paths)?);
+            ColumnType::List
+        }
+        _ => return Err(unsupported()),
+    };
+
+    Ok(ParquetColumn {
+        // Dots separate the parts of a column path
+        name: field.name().replace('.', "_"),
+        column_type,
+        children,
+    })
+}
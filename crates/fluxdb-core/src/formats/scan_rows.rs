@@ -0,0 +1,33 @@
+use std::io::Result;
+use crate::engine::table_scan::TableScan;
+use crate::metadata::value::Value;
+
+/// Row predicate over the projected values of one row, in projection order.
+pub type RowFilter<'a> = &'a dyn Fn(&[&Value]) -> bool;
+
+/// Streams the rows of `scan` one chunk at a time, calling `f` for every row
+/// `filter` accepts. Returns the number of rows passed to `f`.
+pub fn for_each_row(
+    scan: TableScan,
+    filter: Option<RowFilter>,
+    mut f: impl FnMut(&[&Value]) -> Result<()>,
+) -> Result<usize> {
+    let mut rows = 0;
+
+    for batch in scan {
+        let batch = batch?;
+        let mut row: Vec<&Value> = Vec::with_capacity(batch.columns.len());
+
+        for index in 0..batch.row_count {
+            row.clear();
+            row.extend(batch.columns.iter().map(|column| &column[index]));
+
+            if filter.is_none_or(|filter| filter(&row)) {
+                f(&row)?;
+                rows += 1;
+            }
+        }
+    }
+
+    Ok(rows)
+}
@@ -8,7 +8,7 @@ pub const DB_HEADER_SIZE: u16 = 128;
 pub const DB_VERSION: u32 = 1;
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Header{
     pub magic: [u8; 16], // 16 BYTES FOR HEADER MAGIC
     pub header_size: u16, // 2 BYTES FOR HEADER SIZE
@@ -21,7 +21,9 @@ pub struct Header{
     pub page_count: u64, // 8 BYTES FOR PAGE COUNT
     pub checksum: u32, // 4 BYTES FOR CHECKSUM
     pub chunk_catalog_root_page_id: u32, // 4 BYTES FOR CHUNK CATALOG ROOT PAGE ID
-    pub reserved: [u8; 76] // 80 BYTES FOR RESERVED
+    pub free_page_head: u32, // 4 BYTES FOR FIRST FREE PAGE ID, 0 WHEN NONE
+    pub free_page_count: u32, // 4 BYTES FOR FREE PAGE COUNT
+    pub reserved: [u8; 68] // 68 BYTES FOR RESERVED
 }
 
 impl Header{
@@ -56,7 +58,9 @@ impl Header{
             page_count: 0,
             checksum: 0,
             chunk_catalog_root_page_id: 0,
-            reserved: [0; 76],
+            free_page_head: 0,
+            free_page_count: 0,
+            reserved: [0; 68],
         }
     }
 
@@ -100,6 +104,8 @@ impl Header{
         writer.write_all(&self.page_count.to_le_bytes())?;
         writer.write_all(&checksum.to_le_bytes())?; // ✅ write derived value
         writer.write_all(&self.chunk_catalog_root_page_id.to_le_bytes())?;
+        writer.write_all(&self.free_page_head.to_le_bytes())?;
+        writer.write_all(&self.free_page_count.to_le_bytes())?;
         writer.write_all(&self.reserved)?;
 
         Ok(())
@@ -116,6 +122,8 @@ impl Header{
         w.write_all(&self.created_at.to_le_bytes())?;
         w.write_all(&self.page_count.to_le_bytes())?;
         w.write_all(&self.chunk_catalog_root_page_id.to_le_bytes())?;
+        w.write_all(&self.free_page_head.to_le_bytes())?;
+        w.write_all(&self.free_page_count.to_le_bytes())?;
         w.write_all(&self.reserved)?;
         Ok(())
     }
@@ -154,7 +162,10 @@ impl Header{
     /// [ created_at (u64 bytes)   ]
     /// [ page_count (u64 bytes)   ]
     /// [ checksum (u32 bytes)     ]
-    /// [ reserved (80 bytes)      ]
+    /// [ chunk_catalog_root_page_id (u32 bytes) ]
+    /// [ free_page_head (u32 bytes)  ]
+    /// [ free_page_count (u32 bytes) ]
+    /// [ reserved (68 bytes)      ]
     ///
     /// ```
     ///
@@ -194,7 +205,9 @@ impl Header{
         let page_count = read_u64(reader);
         let checksum = read_u32(reader);
         let chunk_catalog_root_page_id = read_u32(reader);
-        let mut reserved = [0u8; 76];
+        let free_page_head = read_u32(reader);
+        let free_page_count = read_u32(reader);
+        let mut reserved = [0u8; 68];
         reader.read_exact(&mut reserved)?;
 
 
@@ -210,6 +223,8 @@ impl Header{
             page_count,
             checksum,
             chunk_catalog_root_page_id,
+            free_page_head,
+            free_page_count,
             reserved,
         };
 
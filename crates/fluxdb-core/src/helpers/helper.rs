@@ -1,3 +1,4 @@
+use std::fmt::Write as _;
 use std::io::Read;
 
 pub fn current_unix_time() -> u64 {
@@ -31,3 +32,27 @@ pub fn read_u64<R: Read>(r: &mut R) -> u64 {
     r.read_exact(&mut b).unwrap();
     u64::from_le_bytes(b)
 }
+
+pub fn write_hex<W: std::fmt::Write>(w: &mut W, bytes: &[u8]) -> std::fmt::Result {
+    for b in bytes {
+        write!(w, "{:02x}", b)?;
+    }
+    Ok(())
+}
+
+/// Appends `s` as a quoted JSON string.
+pub fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
@@ -1,2 +1,3 @@
 pub mod helper;
-pub mod header_flags;
\ No newline at end of file
+pub mod header_flags;
+pub mod timestamp;
\ No newline at end of file
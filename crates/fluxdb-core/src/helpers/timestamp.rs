@@ -0,0 +1,135 @@
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn parse_number(s: &str, digits: usize) -> Option<u32> {
+    if s.len() != digits || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+/// Parses an ISO 8601 date or date-time into microseconds since the Unix epoch.
+///
+/// Accepted forms: `YYYY-MM-DD`, optionally followed by `T` or a space and
+/// `HH:MM[:SS[.ffffff]]`, optionally followed by `Z` or a `±HH:MM` offset.
+/// Times without an offset are taken as UTC.
+pub fn parse_timestamp(s: &str) -> Option<i64> {
+    let s = s.trim();
+    if s.len() < 10 || !s.is_ascii() {
+        return None;
+    }
+
+    let (date, rest) = s.split_at(10);
+    let year = parse_number(&date[0..4], 4)? as i64;
+    let month = parse_number(&date[5..7], 2)?;
+    let day = parse_number(&date[8..10], 2)?;
+    if &date[4..5] != "-" || &date[7..8] != "-" || !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let mut micros = days_from_civil(year, month, day) * SECONDS_PER_DAY * MICROS_PER_SECOND;
+    if rest.is_empty() {
+        return Some(micros);
+    }
+
+    let rest = rest.strip_prefix('T').or_else(|| rest.strip_prefix(' '))?;
+
+    // Split off the UTC offset, if any
+    let (time, offset_seconds) = if let Some(time) = rest.strip_suffix('Z') {
+        (time, 0)
+    } else if rest.len() > 6 && matches!(&rest[rest.len() - 6..rest.len() - 5], "+" | "-") {
+        let (time, offset) = rest.split_at(rest.len() - 6);
+        let hours = parse_number(&offset[1..3], 2)? as i64;
+        let minutes = parse_number(&offset[4..6], 2)? as i64;
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        (time, sign * (hours * 3600 + minutes * 60))
+    } else {
+        (rest, 0)
+    };
+
+    let (clock, fraction) = match time.split_once('.') {
+        Some((clock, fraction)) => (clock, Some(fraction)),
+        None => (time, None),
+    };
+
+    let mut parts = clock.split(':');
+    let hour = parse_number(parts.next()?, 2)?;
+    let minute = parse_number(parts.next()?, 2)?;
+    let second = match parts.next() {
+        Some(second) => parse_number(second, 2)?,
+        None => 0,
+    };
+    if parts.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    micros += (hour as i64 * 3600 + minute as i64 * 60 + second as i64 - offset_seconds) * MICROS_PER_SECOND;
+
+    if let Some(fraction) = fraction {
+        if fraction.is_empty() || fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        // Keep microsecond precision, pad or truncate the fraction to 6 digits
+        let padded = format!("{:0<6}", &fraction[..fraction.len().min(6)]);
+        micros += padded.parse::<i64>().ok()?;
+    }
+
+    Some(micros)
+}
+
+/// Formats microseconds since the Unix epoch as `YYYY-MM-DD HH:MM:SS[.ffffff]` (UTC).
+pub fn format_timestamp(micros: i64) -> String {
+    let seconds = micros.div_euclid(MICROS_PER_SECOND);
+    let fraction = micros.rem_euclid(MICROS_PER_SECOND);
+    let days = seconds.div_euclid(SECONDS_PER_DAY);
+    let time = seconds.rem_euclid(SECONDS_PER_DAY);
+
+    let (year, month, day) = civil_from_days(days);
+    let mut out = format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    );
+
+    if fraction != 0 {
+        out.push_str(&format!(".{:06}", fraction));
+    }
+    out
+}
@@ -3,3 +3,6 @@ mod helpers;
 pub mod storage;
 pub mod metadata;
 pub mod engine;
+pub mod formats;
+pub mod query;
+pub mod sql;
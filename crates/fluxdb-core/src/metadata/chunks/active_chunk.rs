@@ -1,18 +1,91 @@
+use crate::metadata::chunks::distinct_sketch::DistinctSketch;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+use crate::storage::page::Page;
 
-
+#[derive(Clone)]
 pub struct ActiveChunk {
     // Identity
     pub table_id: u32,
+    pub column_id: u32,
     pub column_ordinal: u16,
+    pub column_type: ColumnType,
 
     // Physical layout
     pub first_page_id: u32,
     pub pages: Vec<u32>, // chunk may span multiple pages
+    pub tail: Page,      // last page, written to disk when full or on seal
 
     // Runtime state
+    pub row_start: u64,
     pub value_count: u32,
+    pub null_count: u32,
 
     // Runtime stats (finalized on seal)
-    // pub min: Option<Value>,
-    // pub max: Option<Value>,
-}
\ No newline at end of file
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    pub distinct: DistinctSketch,
+}
+
+impl ActiveChunk {
+    pub fn new(column_id: u32, column_type: ColumnType, row_start: u64, tail: Page) -> Self {
+        let layout = tail.chunk_header();
+        let page_id = tail.header.page_id;
+
+        Self {
+            table_id: layout.table_id,
+            column_id,
+            column_ordinal: layout.column_ordinal,
+            column_type,
+            first_page_id: page_id,
+            pages: vec![page_id],
+            tail,
+            row_start,
+            value_count: 0,
+            null_count: 0,
+            min: None,
+            max: None,
+            distinct: DistinctSketch::default(),
+        }
+    }
+
+    /// Folds an appended value into the chunk statistics.
+    pub fn observe(&mut self, value: &Value) {
+        self.value_count += 1;
+
+        if value.is_null() {
+            self.null_count += 1;
+            return;
+        }
+
+        // Nested streams hold lengths/presence flags, and NaN has no order
+        if self.column_type.is_nested() || value.compare(value).is_none() {
+            return;
+        }
+
+        if self.min.as_ref().is_none_or(|m| value.compare(m).is_some_and(|o| o.is_lt())) {
+            self.min = Some(value.clone());
+        }
+        if self.max.as_ref().is_none_or(|m| value.compare(m).is_some_and(|o| o.is_gt())) {
+            self.max = Some(value.clone());
+        }
+    }
+
+    /// Folds a run of appended values into the chunk statistics, given the
+    /// run's null count and min/max.
+    pub fn observe_bulk(&mut self, count: u32, null_count: u32, min: Option<Value>, max: Option<Value>) {
+        self.value_count += count;
+        self.null_count += null_count;
+
+        if let Some(min) = min {
+            if self.min.as_ref().is_none_or(|m| min.compare(m).is_some_and(|o| o.is_lt())) {
+                self.min = Some(min);
+            }
+        }
+        if let Some(max) = max {
+            if self.max.as_ref().is_none_or(|m| max.compare(m).is_some_and(|o| o.is_gt())) {
+                self.max = Some(max);
+            }
+        }
+    }
+}
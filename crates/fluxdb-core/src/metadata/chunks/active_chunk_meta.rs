@@ -0,0 +1,88 @@
+use crate::metadata::chunks::active_chunk::ActiveChunk;
+use crate::metadata::db_record::DbRecord;
+use crate::metadata::record_type::RecordType;
+use crate::metadata::schema::column_type::ColumnType;
+
+/// Records in the chunk catalog how much of an active chunk a commit
+/// covered: the rows `[row_start, row_end)` of the column, held by the first
+/// `page_count` pages of its chain, the last of which had `tail_value_count`
+/// values ending at `tail_free_start`. Opening the database replays the
+/// chunk from the records of the table's last committed version that wrote
+/// any, so committed rows need no seal to survive it.
+#[derive(Debug, Clone, Copy)]
+pub struct ActiveChunkMeta {
+    pub table_id: u32,
+    pub column_id: u32,
+    pub column_type: ColumnType,
+    pub row_start: u64,
+    pub row_end: u64,
+    pub first_page_id: u32,
+    pub page_count: u32,
+    pub tail_value_count: u16,
+    pub tail_free_start: u16,
+    pub commit_version: u64,
+}
+
+impl ActiveChunkMeta {
+    /// The record of `active` as it is now, committing its rows up to
+    /// `row_end` with `commit_version`.
+    pub fn new(active: &ActiveChunk, row_end: u64, commit_version: u64) -> Self {
+        let tail = active.tail.chunk_header();
+        Self {
+            table_id: active.table_id,
+            column_id: active.column_id,
+            column_type: active.column_type,
+            row_start: active.row_start,
+            row_end,
+            first_page_id: active.first_page_id,
+            page_count: active.pages.len() as u32,
+            tail_value_count: tail.value_count,
+            tail_free_start: tail.free_start,
+            commit_version,
+        }
+    }
+}
+
+impl DbRecord for ActiveChunkMeta {
+    const RECORD_TYPE: RecordType = RecordType::ActiveChunkMeta;
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(4 + 4 + self.column_type.encoded_len() + 8 + 8 + 4 + 4 + 2 + 2 + 8);
+        buf.extend_from_slice(&self.table_id.to_le_bytes());
+        buf.extend_from_slice(&self.column_id.to_le_bytes());
+        self.column_type.write_to(&mut buf);
+        buf.extend_from_slice(&self.row_start.to_le_bytes());
+        buf.extend_from_slice(&self.row_end.to_le_bytes());
+        buf.extend_from_slice(&self.first_page_id.to_le_bytes());
+        buf.extend_from_slice(&self.page_count.to_le_bytes());
+        buf.extend_from_slice(&self.tail_value_count.to_le_bytes());
+        buf.extend_from_slice(&self.tail_free_start.to_le_bytes());
+        buf.extend_from_slice(&self.commit_version.to_le_bytes());
+        buf
+    }
+
+    fn deserialize(payload: &[u8]) -> Result<Self, String> {
+        let truncated = || "truncated active chunk record".to_string();
+        if payload.len() < 8 {
+            return Err(truncated());
+        }
+        let (column_type, type_len) = ColumnType::read_from(&payload[8..])?;
+        let rest = &payload[8 + type_len..];
+        if rest.len() < 36 {
+            return Err(truncated());
+        }
+
+        Ok(Self {
+            table_id: u32::from_le_bytes(payload[0..4].try_into().unwrap()),
+            column_id: u32::from_le_bytes(payload[4..8].try_into().unwrap()),
+            column_type,
+            row_start: u64::from_le_bytes(rest[0..8].try_into().unwrap()),
+            row_end: u64::from_le_bytes(rest[8..16].try_into().unwrap()),
+            first_page_id: u32::from_le_bytes(rest[16..20].try_into().unwrap()),
+            page_count: u32::from_le_bytes(rest[20..24].try_into().unwrap()),
+            tail_value_count: u16::from_le_bytes(rest[24..26].try_into().unwrap()),
+            tail_free_start: u16::from_le_bytes(rest[26..28].try_into().unwrap()),
+            commit_version: u64::from_le_bytes(rest[28..36].try_into().unwrap()),
+        })
+    }
+}
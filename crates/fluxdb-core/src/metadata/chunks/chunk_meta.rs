@@ -1,32 +1,86 @@
+use crate::metadata::chunks::distinct_sketch::DistinctSketch;
 use crate::metadata::db_record::DbRecord;
 use crate::metadata::record_type::RecordType;
 use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::{EncodedValue, Value};
 
+#[derive(Debug, Clone)]
 pub struct ChunkMeta {
     pub table_id: u32,
     pub column_id: u32,
     pub chunk_id: u32,
     pub row_start: u64,
-    pub row_end: u64,
+    pub row_end: u64, // exclusive
     pub column_type: ColumnType,
     pub first_page_id: u64,
     pub page_count: u64,
+
+    // Statistics (zone map), computed when the chunk is sealed
+    pub value_count: u32,
+    pub null_count: u32,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    /// Distinct non-null values, empty for chunks sealed before it was kept.
+    pub distinct: DistinctSketch,
+    /// Database version whose commit makes the chunk's rows visible, 0 for
+    /// chunks sealed before versions were kept.
+    pub commit_version: u64,
+    /// Rows below this one were already committed when the chunk was
+    /// sealed. A chunk sealed part way into a transaction that never
+    /// commits keeps just those rows.
+    pub committed_row_end: u64,
+}
+
+impl ChunkMeta {
+    /// Min/max values longer than this are not kept, so a chunk record always
+    /// fits comfortably on a heap page.
+    pub const MAX_STAT_LEN: usize = 64;
+
+    fn write_stat(buf: &mut Vec<u8>, column_type: ColumnType, stat: &Option<Value>) {
+        match stat.as_ref().map(|v| v.encode(column_type.physical_type())) {
+            Some(Ok(EncodedValue::Bytes(bytes))) if bytes.len() <= Self::MAX_STAT_LEN => {
+                buf.push(1);
+                buf.extend_from_slice(&bytes);
+            }
+            _ => buf.push(0),
+        }
+    }
+
+    fn read_stat(payload: &[u8], offset: &mut usize, column_type: ColumnType) -> Result<Option<Value>, String> {
+        let present = payload[*offset];
+        *offset += 1;
+
+        if present == 0 {
+            return Ok(None);
+        }
+
+        let (value, len) = Value::decode(column_type, &payload[*offset..])?;
+        *offset += len;
+        Ok(Some(value))
+    }
 }
 
 impl DbRecord for ChunkMeta {
     const RECORD_TYPE: RecordType = RecordType::ChunkMeta;
 
     fn serialize(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(4 + 4 + 4 + 8 + 8 + 1 + 8 + 8);
+        let mut buf = Vec::with_capacity(4 + 4 + 4 + 8 + 8 + self.column_type.encoded_len() + 8 + 8 + 4 + 4 + 2);
 
         buf.extend_from_slice(&self.table_id.to_le_bytes());
         buf.extend_from_slice(&self.column_id.to_le_bytes());
         buf.extend_from_slice(&self.chunk_id.to_le_bytes());
         buf.extend_from_slice(&self.row_start.to_le_bytes());
         buf.extend_from_slice(&self.row_end.to_le_bytes());
-        buf.push(self.column_type as u8);
+        self.column_type.write_to(&mut buf);
         buf.extend_from_slice(&self.first_page_id.to_le_bytes());
         buf.extend_from_slice(&self.page_count.to_le_bytes());
+        buf.extend_from_slice(&self.value_count.to_le_bytes());
+        buf.extend_from_slice(&self.null_count.to_le_bytes());
+        Self::write_stat(&mut buf, self.column_type, &self.min);
+        Self::write_stat(&mut buf, self.column_type, &self.max);
+        self.distinct.write_to(&mut buf);
+        buf.extend_from_slice(&self.commit_version.to_le_bytes());
+        buf.extend_from_slice(&self.committed_row_end.to_le_bytes());
 
         buf
     }
@@ -52,11 +106,31 @@ impl DbRecord for ChunkMeta {
         let row_start = read_u64(payload, &mut offset);
         let row_end = read_u64(payload, &mut offset);
 
-        let column_type = ColumnType::from_u8(payload[offset]);
-        offset += 1;
+        let (column_type, type_len) = ColumnType::read_from(&payload[offset..])?;
+        offset += type_len;
 
         let first_page_id = read_u64(payload, &mut offset);
         let page_count = read_u64(payload, &mut offset);
+        let value_count = read_u32(payload, &mut offset);
+        let null_count = read_u32(payload, &mut offset);
+        let min = Self::read_stat(payload, &mut offset, column_type)?;
+        let max = Self::read_stat(payload, &mut offset, column_type)?;
+
+        // Records written before sketches were kept end here
+        let distinct = match offset < payload.len() {
+            true => {
+                let (distinct, len) = DistinctSketch::read_from(&payload[offset..])?;
+                offset += len;
+                distinct
+            }
+            false => DistinctSketch::default(),
+        };
+
+        // And those written before versions were kept here
+        let (commit_version, committed_row_end) = match payload.len() >= offset + 16 {
+            true => (read_u64(payload, &mut offset), read_u64(payload, &mut offset)),
+            false => (0, row_end),
+        };
 
         Ok(Self {
             table_id,
@@ -67,6 +141,13 @@ impl DbRecord for ChunkMeta {
             column_type,
             first_page_id,
             page_count,
+            value_count,
+            null_count,
+            min,
+            max,
+            distinct,
+            commit_version,
+            committed_row_end,
         })
     }
 }
@@ -0,0 +1,50 @@
+use crate::metadata::db_record::DbRecord;
+use crate::metadata::record_type::RecordType;
+
+/// Records in the chunk catalog that the chunks of a table within rows
+/// `[row_start, row_end)` were rewritten, by compaction: chunks and delete
+/// vectors there from before `commit_version` are replaced by the chunks
+/// sealed for it. Like them, it only counts once its version is committed.
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkRewrite {
+    pub table_id: u32,
+    pub row_start: u64,
+    pub row_end: u64,
+    pub commit_version: u64,
+}
+
+impl ChunkRewrite {
+    /// Whether the rewrite replaces a chunk or delete vector of rows
+    /// `[row_start, row_end)` committed at `commit_version`.
+    pub fn replaces(&self, table_id: u32, row_start: u64, row_end: u64, commit_version: u64) -> bool {
+        table_id == self.table_id
+            && commit_version < self.commit_version
+            && row_start >= self.row_start
+            && row_end <= self.row_end
+    }
+}
+
+impl DbRecord for ChunkRewrite {
+    const RECORD_TYPE: RecordType = RecordType::ChunkRewrite;
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(28);
+        buf.extend_from_slice(&self.table_id.to_le_bytes());
+        buf.extend_from_slice(&self.row_start.to_le_bytes());
+        buf.extend_from_slice(&self.row_end.to_le_bytes());
+        buf.extend_from_slice(&self.commit_version.to_le_bytes());
+        buf
+    }
+
+    fn deserialize(payload: &[u8]) -> Result<Self, String> {
+        if payload.len() < 28 {
+            return Err("truncated chunk rewrite record".into());
+        }
+        Ok(Self {
+            table_id: u32::from_le_bytes(payload[0..4].try_into().unwrap()),
+            row_start: u64::from_le_bytes(payload[4..12].try_into().unwrap()),
+            row_end: u64::from_le_bytes(payload[12..20].try_into().unwrap()),
+            commit_version: u64::from_le_bytes(payload[20..28].try_into().unwrap()),
+        })
+    }
+}
@@ -0,0 +1,28 @@
+use crate::metadata::db_record::DbRecord;
+use crate::metadata::record_type::RecordType;
+
+/// Records in the chunk catalog that the chunks sealed with `version` as
+/// their `commit_version` are committed. Chunks whose version has no marker
+/// were sealed by a transaction that never committed.
+#[derive(Debug, Clone, Copy)]
+pub struct CommitMarker {
+    pub version: u64,
+}
+
+impl DbRecord for CommitMarker {
+    const RECORD_TYPE: RecordType = RecordType::CommitMarker;
+
+    fn serialize(&self) -> Vec<u8> {
+        self.version.to_le_bytes().to_vec()
+    }
+
+    fn deserialize(payload: &[u8]) -> Result<Self, String> {
+        let version = payload
+            .get(0..8)
+            .ok_or("truncated commit marker")?
+            .try_into()
+            .map(u64::from_le_bytes)
+            .unwrap();
+        Ok(Self { version })
+    }
+}
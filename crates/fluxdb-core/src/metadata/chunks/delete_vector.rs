@@ -0,0 +1,91 @@
+use crate::metadata::db_record::DbRecord;
+use crate::metadata::record_type::RecordType;
+
+/// Rows deleted from a chunk, as a bitmap over the rows it covers. Each
+/// delete writes a new vector with just the rows it deleted; a row is
+/// deleted when any committed vector has its bit set. Data pages are never
+/// rewritten for a delete.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeleteVector {
+    pub table_id: u32,
+    /// First row covered, the first row of the chunk (or of a later
+    /// [`DeleteVector::MAX_ROWS`] part of it).
+    pub row_start: u64,
+    pub row_count: u32,
+    /// Database version whose commit makes the deletes visible.
+    pub commit_version: u64,
+    /// Bit `i` is set when row `row_start + i` is deleted.
+    bits: Vec<u64>,
+}
+
+impl DeleteVector {
+    /// Most rows one vector covers, so it fits a heap page.
+    pub const MAX_ROWS: u32 = 16_384;
+
+    pub fn new(table_id: u32, row_start: u64, row_count: u32, commit_version: u64) -> Self {
+        let words = row_count.div_ceil(64) as usize;
+        Self { table_id, row_start, row_count, commit_version, bits: vec![0; words] }
+    }
+
+    pub fn row_end(&self) -> u64 {
+        self.row_start + u64::from(self.row_count)
+    }
+
+    /// Marks `row`, a table row id within the vector, deleted.
+    pub fn delete(&mut self, row: u64) {
+        let offset = (row - self.row_start) as usize;
+        self.bits[offset / 64] |= 1 << (offset % 64);
+    }
+
+    /// Whether `row`, a table row id, is deleted here. Rows outside the
+    /// vector are not.
+    pub fn is_deleted(&self, row: u64) -> bool {
+        if row < self.row_start || row >= self.row_end() {
+            return false;
+        }
+        let offset = (row - self.row_start) as usize;
+        self.bits[offset / 64] & (1 << (offset % 64)) != 0
+    }
+
+    pub fn deleted_count(&self) -> u64 {
+        self.bits.iter().map(|word| u64::from(word.count_ones())).sum()
+    }
+}
+
+impl DbRecord for DeleteVector {
+    const RECORD_TYPE: RecordType = RecordType::DeleteVector;
+
+    /// `[ table_id (u32) | row_start (u64) | row_count (u32) |
+    ///    commit_version (u64) | bits (u64 each) ]`
+    fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(24 + self.bits.len() * 8);
+        buf.extend_from_slice(&self.table_id.to_le_bytes());
+        buf.extend_from_slice(&self.row_start.to_le_bytes());
+        buf.extend_from_slice(&self.row_count.to_le_bytes());
+        buf.extend_from_slice(&self.commit_version.to_le_bytes());
+        for word in &self.bits {
+            buf.extend_from_slice(&word.to_le_bytes());
+        }
+        buf
+    }
+
+    fn deserialize(payload: &[u8]) -> Result<Self, String> {
+        if payload.len() < 24 {
+            return Err("truncated delete vector".into());
+        }
+        let table_id = u32::from_le_bytes(payload[0..4].try_into().unwrap());
+        let row_start = u64::from_le_bytes(payload[4..12].try_into().unwrap());
+        let row_count = u32::from_le_bytes(payload[12..16].try_into().unwrap());
+        let commit_version = u64::from_le_bytes(payload[16..24].try_into().unwrap());
+
+        let mut vector = Self::new(table_id, row_start, row_count, commit_version);
+        let bits = &payload[24..];
+        if bits.len() != vector.bits.len() * 8 {
+            return Err("delete vector bitmap does not match its row count".into());
+        }
+        for (word, bytes) in vector.bits.iter_mut().zip(bits.chunks_exact(8)) {
+            *word = u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        Ok(vector)
+    }
+}
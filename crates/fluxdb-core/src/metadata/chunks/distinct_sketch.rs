@@ -0,0 +1,98 @@
+/// Estimates the number of distinct values in a column by keeping the `K`
+/// smallest hashes of the encoded values seen (a k-minimum-values sketch).
+///
+/// Sketches of several chunks merge into the sketch of their union, so a
+/// table-wide estimate comes from the per-chunk sketches alone. Below `K`
+/// distinct values the count is exact (barring hash collisions); above it
+/// the estimate is typically within 1/√K.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DistinctSketch {
+    /// Smallest hashes seen, ascending, without duplicates.
+    hashes: Vec<u64>,
+}
+
+impl DistinctSketch {
+    pub const K: usize = 32;
+
+    /// Size of a full sketch on disk, see [`DistinctSketch::write_to`].
+    pub const MAX_ENCODED_LEN: usize = 1 + Self::K * 8;
+
+    /// Records one non-null value, by its encoded bytes.
+    pub fn insert(&mut self, encoded: &[u8]) {
+        self.insert_hash(hash(encoded));
+    }
+
+    fn insert_hash(&mut self, hash: u64) {
+        if self.hashes.len() == Self::K && hash >= self.hashes[Self::K - 1] {
+            return;
+        }
+        if let Err(position) = self.hashes.binary_search(&hash) {
+            self.hashes.insert(position, hash);
+            self.hashes.truncate(Self::K);
+        }
+    }
+
+    /// Folds `other` in, as if its values had been inserted here.
+    pub fn merge(&mut self, other: &DistinctSketch) {
+        for &hash in &other.hashes {
+            self.insert_hash(hash);
+        }
+    }
+
+    /// Whether no value was recorded, as for chunks sealed before sketches
+    /// were kept.
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Estimated number of distinct values recorded.
+    pub fn estimate(&self) -> u64 {
+        if self.hashes.len() < Self::K {
+            return self.hashes.len() as u64;
+        }
+
+        // The K-th smallest of n uniform hashes sits near K / n of the range
+        let fraction = (self.hashes[Self::K - 1] as f64 + 1.0) / (u64::MAX as f64 + 1.0);
+        ((Self::K - 1) as f64 / fraction).round() as u64
+    }
+
+    /// `[ count (u8) | hashes (u64 each) ]`
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.push(self.hashes.len() as u8);
+        for hash in &self.hashes {
+            buf.extend_from_slice(&hash.to_le_bytes());
+        }
+    }
+
+    /// Reads a sketch written by [`DistinctSketch::write_to`], returning it
+    /// and the bytes it took.
+    pub fn read_from(buf: &[u8]) -> Result<(DistinctSketch, usize), String> {
+        let count = *buf.first().ok_or("truncated distinct sketch")? as usize;
+        let len = 1 + count * 8;
+        if count > Self::K || buf.len() < len {
+            return Err("invalid distinct sketch".to_string());
+        }
+
+        let hashes = buf[1..len]
+            .chunks_exact(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        Ok((DistinctSketch { hashes }, len))
+    }
+}
+
+/// FNV-1a, then a 64-bit finalizer to spread the bits. Fixed so sketches
+/// written by one build read the same in any other.
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in bytes {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
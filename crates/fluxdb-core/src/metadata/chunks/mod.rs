@@ -1,2 +1,8 @@
 pub mod chunk_meta;
-pub mod active_chunk;
\ No newline at end of file
+pub mod active_chunk;
+pub mod active_chunk_meta;
+pub mod distinct_sketch;
+pub mod commit_marker;
+pub mod delete_vector;
+pub mod table_truncate;
+pub mod chunk_rewrite;
@@ -0,0 +1,32 @@
+use crate::metadata::db_record::DbRecord;
+use crate::metadata::record_type::RecordType;
+
+/// Records in the chunk catalog that a table was truncated: its chunks and
+/// delete vectors with a `commit_version` up to `commit_version` are gone.
+/// Like them, it only counts once its version is committed.
+#[derive(Debug, Clone, Copy)]
+pub struct TableTruncate {
+    pub table_id: u32,
+    pub commit_version: u64,
+}
+
+impl DbRecord for TableTruncate {
+    const RECORD_TYPE: RecordType = RecordType::TableTruncate;
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(12);
+        buf.extend_from_slice(&self.table_id.to_le_bytes());
+        buf.extend_from_slice(&self.commit_version.to_le_bytes());
+        buf
+    }
+
+    fn deserialize(payload: &[u8]) -> Result<Self, String> {
+        if payload.len() < 12 {
+            return Err("truncated table truncate record".into());
+        }
+        Ok(Self {
+            table_id: u32::from_le_bytes(payload[0..4].try_into().unwrap()),
+            commit_version: u64::from_le_bytes(payload[4..12].try_into().unwrap()),
+        })
+    }
+}
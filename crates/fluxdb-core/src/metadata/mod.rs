@@ -2,4 +2,5 @@ pub mod record;
 pub mod record_type;
 pub mod schema;
 pub mod chunks;
-pub mod db_record;
\ No newline at end of file
+pub mod db_record;
+pub mod value;
\ No newline at end of file
@@ -15,7 +15,7 @@ impl<'a> Record<'a>{
     }
 
     pub fn decode(buf: &[u8]) -> Option<(RecordType, &[u8])> {
-        if buf.len() < 1 { return None; }
+        if buf.is_empty() { return None; }
 
         let record_type = RecordType::from_u8(buf[0]);
         Some((record_type, &buf[1..]))
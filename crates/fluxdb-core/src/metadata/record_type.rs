@@ -6,6 +6,12 @@ pub enum RecordType {
     CatalogTable = 1,
     CatalogColumn = 2,
     ChunkMeta = 3,
+    CatalogChange = 4,
+    CommitMarker = 5,
+    DeleteVector = 6,
+    TableTruncate = 7,
+    ChunkRewrite = 8,
+    ActiveChunkMeta = 9,
     HeapRow = 10,
     IndexEntry = 20,
 }
@@ -16,7 +22,13 @@ impl RecordType {
             0 => RecordType::CatalogRoot,
             1 => RecordType::CatalogTable,
             2 => RecordType::CatalogColumn,
-            3 => RecordType::CatalogColumn,
+            3 => RecordType::ChunkMeta,
+            4 => RecordType::CatalogChange,
+            5 => RecordType::CommitMarker,
+            6 => RecordType::DeleteVector,
+            7 => RecordType::TableTruncate,
+            8 => RecordType::ChunkRewrite,
+            9 => RecordType::ActiveChunkMeta,
             10 => RecordType::HeapRow,
             20 => RecordType::IndexEntry,
             _ => RecordType::CatalogTable, // or panic, your call
@@ -0,0 +1,61 @@
+use crate::metadata::db_record::DbRecord;
+use crate::metadata::record_type::RecordType;
+
+/// A schema change after creation. The catalog heap is append-only, so
+/// drops and renames are logged as records at its tail and replayed over
+/// the tables and columns when the catalog loads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatalogChange {
+    DropTable { table_id: u32 },
+    DropColumn { table_id: u32, column_id: u32 },
+    RenameTable { table_id: u32, name: String },
+    RenameColumn { table_id: u32, column_id: u32, name: String },
+    /// Keeps the ordinals of dropped columns from being reused once their
+    /// column records are gone, as after a vacuum.
+    ReserveOrdinals { table_id: u32, next_ordinal: u16 },
+}
+
+impl DbRecord for CatalogChange {
+    const RECORD_TYPE: RecordType = RecordType::CatalogChange;
+
+    /// `[ kind (u8) | table_id (u32) | column_id (u32, 0 for tables) | new name ]`,
+    /// the column id holding the next ordinal for `ReserveOrdinals`.
+    fn serialize(&self) -> Vec<u8> {
+        let (kind, table_id, column_id, name) = match self {
+            CatalogChange::DropTable { table_id } => (0u8, *table_id, 0, ""),
+            CatalogChange::DropColumn { table_id, column_id } => (1, *table_id, *column_id, ""),
+            CatalogChange::RenameTable { table_id, name } => (2, *table_id, 0, name.as_str()),
+            CatalogChange::RenameColumn { table_id, column_id, name } => (3, *table_id, *column_id, name.as_str()),
+            CatalogChange::ReserveOrdinals { table_id, next_ordinal } => (4, *table_id, *next_ordinal as u32, ""),
+        };
+
+        let mut buf = Vec::with_capacity(9 + name.len());
+        buf.push(kind);
+        buf.extend_from_slice(&table_id.to_le_bytes());
+        buf.extend_from_slice(&column_id.to_le_bytes());
+        buf.extend_from_slice(name.as_bytes());
+        buf
+    }
+
+    fn deserialize(payload: &[u8]) -> Result<Self, String> {
+        if payload.len() < 9 {
+            return Err("truncated catalog change".to_string());
+        }
+        let table_id = u32::from_le_bytes(payload[1..5].try_into().unwrap());
+        let column_id = u32::from_le_bytes(payload[5..9].try_into().unwrap());
+        let name = || {
+            std::str::from_utf8(&payload[9..])
+                .map(|s| s.to_string())
+                .map_err(|_| "utf8 error".to_string())
+        };
+
+        match payload[0] {
+            0 => Ok(CatalogChange::DropTable { table_id }),
+            1 => Ok(CatalogChange::DropColumn { table_id, column_id }),
+            2 => Ok(CatalogChange::RenameTable { table_id, name: name()? }),
+            3 => Ok(CatalogChange::RenameColumn { table_id, column_id, name: name()? }),
+            4 => Ok(CatalogChange::ReserveOrdinals { table_id, next_ordinal: column_id as u16 }),
+            other => Err(format!("unknown catalog change kind {other}")),
+        }
+    }
+}
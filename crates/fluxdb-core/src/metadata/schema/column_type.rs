@@ -1,4 +1,3 @@
-#[repr(u8)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ColumnType {
     Integer32,
@@ -6,13 +5,92 @@ pub enum ColumnType {
     Float32,
     Float64,
     Utf8,
+    /// Microseconds since the Unix epoch, UTC.
     Timestamp,
-    Boolean
+    Boolean,
+    Binary,
+    FixedSizeBinary(u16),
+    Uuid,
+    List,
+    Struct,
 }
 
 impl ColumnType {
-    pub fn from_u8(v: u8) -> Self {
-        match v {
+    /// On-disk tag of the type. Parameterized types write their
+    /// parameters right after the tag (see [`ColumnType::write_to`]).
+    pub fn tag(&self) -> u8 {
+        match self {
+            ColumnType::Integer32 => 0,
+            ColumnType::Integer64 => 1,
+            ColumnType::Float32 => 2,
+            ColumnType::Float64 => 3,
+            ColumnType::Utf8 => 4,
+            ColumnType::Timestamp => 5,
+            ColumnType::Boolean => 6,
+            ColumnType::Binary => 7,
+            ColumnType::FixedSizeBinary(_) => 8,
+            ColumnType::Uuid => 9,
+            ColumnType::List => 10,
+            ColumnType::Struct => 11,
+        }
+    }
+
+    pub fn is_nested(&self) -> bool {
+        matches!(self, ColumnType::List | ColumnType::Struct)
+    }
+
+    /// Type of the values actually written to a column's data pages.
+    ///
+    /// Nested columns are stored as offset arrays: a `List` column stores the
+    /// element count of every row (its elements live in the child column) and
+    /// a `Struct` column stores only whether the row is present.
+    pub fn physical_type(&self) -> ColumnType {
+        match self {
+            ColumnType::List => ColumnType::Integer32,
+            ColumnType::Struct => ColumnType::Boolean,
+            other => *other,
+        }
+    }
+
+    /// Width in bytes of every encoded value, or `None` for
+    /// length-prefixed types.
+    pub fn fixed_width(&self) -> Option<usize> {
+        match self {
+            ColumnType::List | ColumnType::Struct => self.physical_type().fixed_width(),
+            ColumnType::Integer32 | ColumnType::Float32 => Some(4),
+            ColumnType::Integer64 | ColumnType::Float64 | ColumnType::Timestamp => Some(8),
+            ColumnType::Boolean => Some(1),
+            ColumnType::FixedSizeBinary(width) => Some(*width as usize),
+            ColumnType::Uuid => Some(16),
+            ColumnType::Utf8 | ColumnType::Binary => None,
+        }
+    }
+
+    /// Number of bytes [`ColumnType::write_to`] produces for this type.
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            ColumnType::FixedSizeBinary(_) => 1 + 2,
+            _ => 1,
+        }
+    }
+
+    /// Writes the type as `[ tag (u8) | params ]`.
+    ///
+    /// Only `FixedSizeBinary` carries a parameter (its width as u16), so
+    /// every pre-existing type keeps its single-byte encoding.
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.push(self.tag());
+        if let ColumnType::FixedSizeBinary(width) = self {
+            buf.extend_from_slice(&width.to_le_bytes());
+        }
+    }
+
+    /// Reads a type written by [`ColumnType::write_to`] and returns it
+    /// together with the number of bytes consumed.
+    pub fn read_from(buf: &[u8]) -> Result<(Self, usize), String> {
+        let tag = *buf.first().ok_or("missing column type")?;
+
+        let column_type = match tag {
             0 => ColumnType::Integer32,
             1 => ColumnType::Integer64,
             2 => ColumnType::Float32,
@@ -20,7 +98,17 @@ impl ColumnType {
             4 => ColumnType::Utf8,
             5 => ColumnType::Timestamp,
             6 => ColumnType::Boolean,
-            _ => panic!(), // or panic, your call
-        }
+            7 => ColumnType::Binary,
+            8 => {
+                let raw = buf.get(1..3).ok_or("truncated FixedSizeBinary width")?;
+                ColumnType::FixedSizeBinary(u16::from_le_bytes(raw.try_into().unwrap()))
+            }
+            9 => ColumnType::Uuid,
+            10 => ColumnType::List,
+            11 => ColumnType::Struct,
+            other => return Err(format!("unknown column type tag {other}")),
+        };
+
+        Ok((column_type, column_type.encoded_len()))
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,5 @@
+pub mod catalog_change;
 pub mod catalog_root;
 pub mod column_type;
 pub mod table_column;
-pub mod table_meta;
\ No newline at end of file
+pub mod table_meta;
@@ -2,23 +2,56 @@ use crate::metadata::db_record::DbRecord;
 use crate::metadata::record_type::RecordType;
 use crate::metadata::schema::column_type::ColumnType;
 
+/// Set in the serialized type tag of `NOT NULL` columns. Type tags stay
+/// far below it, so columns written before the flag existed read as nullable.
+const NOT_NULL_FLAG: u8 = 0x80;
+
+#[derive(Debug, Clone)]
 pub struct TableColumn {
     pub table_id: u32,
     pub column_id: u32,
+    /// Storage slot of the column inside its table. Unique across the whole
+    /// column tree, so nested children get their own data pages.
     pub ordinal: u16,
+    /// `column_id` of the enclosing `List`/`Struct` column, 0 for top-level columns.
+    pub parent_column_id: u32,
     pub column_type: ColumnType,
     pub name: String,
+    /// Whether appends reject null values. Only top-level columns can be `NOT NULL`.
+    pub not_null: bool,
+    /// Struct fields, or the single element column of a list.
+    /// Not serialized: rebuilt from `parent_column_id` when the catalog loads.
+    pub children: Vec<TableColumn>,
+}
+
+impl TableColumn {
+    /// The column followed by all of its descendants, depth first.
+    pub fn flatten(&self) -> Vec<&TableColumn> {
+        let mut out = vec![self];
+        for child in &self.children {
+            out.extend(child.flatten());
+        }
+        out
+    }
+
+    pub fn find_child(&self, name: &str) -> Option<&TableColumn> {
+        self.children.iter().find(|c| c.name == name)
+    }
 }
 
 impl DbRecord for TableColumn {
     const RECORD_TYPE: RecordType = RecordType::CatalogColumn;
 
     fn serialize(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(14);
+        let mut buf = Vec::with_capacity(18 + self.name.len());
         buf.extend_from_slice(&self.table_id.to_le_bytes());
         buf.extend_from_slice(&self.column_id.to_le_bytes());
         buf.extend_from_slice(&self.ordinal.to_le_bytes());
-        buf.extend_from_slice(&[self.column_type as u8]);
+        buf.extend_from_slice(&self.parent_column_id.to_le_bytes());
+        self.column_type.write_to(&mut buf);
+        if self.not_null {
+            buf[14] |= NOT_NULL_FLAG;
+        }
         buf.extend_from_slice(self.name.as_bytes());
         buf
     }
@@ -27,13 +60,26 @@ impl DbRecord for TableColumn {
         let table_id = u32::from_le_bytes(payload[0..4].try_into().unwrap());
         let column_id = u32::from_le_bytes(payload[4..8].try_into().unwrap());
         let ordinal = u16::from_le_bytes(payload[8..10].try_into().unwrap());
-        let column_type_raw = u8::from_le_bytes(payload[10..11].try_into().unwrap());
-        let string = std::str::from_utf8(&payload[11..])
+        let parent_column_id = u32::from_le_bytes(payload[10..14].try_into().unwrap());
+        let mut type_buf = payload[14..].to_vec();
+        let not_null = type_buf.first().is_some_and(|tag| tag & NOT_NULL_FLAG != 0);
+        if let Some(tag) = type_buf.first_mut() {
+            *tag &= !NOT_NULL_FLAG;
+        }
+        let (column_type, type_len) = ColumnType::read_from(&type_buf)?;
+        let string = std::str::from_utf8(&payload[14 + type_len..])
             .map_err(|_| "utf8 error")?
             .to_string();
 
-        let column_type = ColumnType::from_u8(column_type_raw);
-        
-        Ok(Self { table_id, column_id, ordinal, column_type, name: string })
+        Ok(Self {
+            table_id,
+            column_id,
+            ordinal,
+            parent_column_id,
+            column_type,
+            name: string,
+            not_null,
+            children: Vec::new(),
+        })
     }
-}
\ No newline at end of file
+}
@@ -1,6 +1,7 @@
 use crate::metadata::db_record::DbRecord;
 use crate::metadata::record_type::RecordType;
 
+#[derive(Clone)]
 pub struct TableMeta {
     pub table_id: u32,
     pub name: String,
@@ -0,0 +1,285 @@
+use std::cmp::Ordering;
+use std::fmt;
+use crate::helpers::helper::write_hex;
+use crate::helpers::timestamp::{format_timestamp, parse_timestamp};
+use crate::metadata::schema::column_type::ColumnType;
+
+pub enum EncodedValue {
+    Bytes(Vec<u8>),
+    Null,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int32(i32),
+    Int64(i64),
+    Float32(f32),
+    Float64(f64),
+    Bool(bool),
+    String(String),
+    Timestamp(i64),
+    Binary(Vec<u8>),
+    Uuid([u8; 16]),
+    List(Vec<Value>),
+    /// Field values in the order of the struct column's children.
+    Struct(Vec<Value>),
+    Null,
+}
+
+impl Value {
+    pub fn matches_column_type(&self, column_type: ColumnType) -> bool {
+        match (self, column_type) {
+            (Value::Int32(_), ColumnType::Integer32) => true,
+            (Value::Int64(_), ColumnType::Integer64) => true,
+            (Value::Float32(_), ColumnType::Float32) => true,
+            (Value::Float64(_), ColumnType::Float64) => true,
+            (Value::Bool(_), ColumnType::Boolean) => true,
+            (Value::String(_), ColumnType::Utf8) => true,
+            (Value::Timestamp(_), ColumnType::Timestamp) => true,
+            (Value::Binary(_), ColumnType::Binary) => true,
+            (Value::Binary(b), ColumnType::FixedSizeBinary(width)) => b.len() == width as usize,
+            (Value::Uuid(_), ColumnType::Uuid) => true,
+            (Value::List(_), ColumnType::List) => true,
+            (Value::Struct(_), ColumnType::Struct) => true,
+            (Value::Null, _) => true,
+            _ => false,
+        }
+    }
+
+    /// Encodes the value for a column of `column_type`.
+    ///
+    /// Fixed-width types are written raw (`FixedSizeBinary(n)` as exactly `n`
+    /// bytes, `Uuid` as 16 bytes); `Utf8` and `Binary` are prefixed with their
+    /// length as u32. Nulls produce no bytes, they live in the page validity bitmap.
+    pub fn encode(&self, column_type: ColumnType) -> Result<EncodedValue, String> {
+        if !self.matches_column_type(column_type) {
+            return Err(format!("value {self} does not match column type {column_type:?}"));
+        }
+
+        let bytes = match self {
+            Value::Int32(v) => v.to_le_bytes().to_vec(),
+            Value::Int64(v) => v.to_le_bytes().to_vec(),
+            Value::Float32(v) => v.to_le_bytes().to_vec(),
+            Value::Float64(v) => v.to_le_bytes().to_vec(),
+            Value::Bool(v) => vec![*v as u8],
+            Value::Timestamp(v) => v.to_le_bytes().to_vec(),
+            Value::Uuid(v) => v.to_vec(),
+            Value::String(s) => {
+                let mut buf = Vec::with_capacity(4 + s.len());
+                buf.extend_from_slice(&(s.len() as u32).to_le_bytes());
+                buf.extend_from_slice(s.as_bytes());
+                buf
+            }
+            Value::Binary(b) => match column_type {
+                ColumnType::FixedSizeBinary(_) => b.clone(),
+                _ => {
+                    let mut buf = Vec::with_capacity(4 + b.len());
+                    buf.extend_from_slice(&(b.len() as u32).to_le_bytes());
+                    buf.extend_from_slice(b);
+                    buf
+                }
+            },
+            Value::List(_) | Value::Struct(_) => {
+                return Err("nested values must be shredded before encoding".to_string());
+            }
+            Value::Null => return Ok(EncodedValue::Null),
+        };
+
+        Ok(EncodedValue::Bytes(bytes))
+    }
+
+    /// Decodes one non-null value of `column_type` from the start of `buf`,
+    /// returning it with the number of bytes consumed.
+    pub fn decode(column_type: ColumnType, buf: &[u8]) -> Result<(Value, usize), String> {
+        let take = |len: usize| {
+            buf.get(..len)
+                .ok_or_else(|| format!("truncated {column_type:?} value"))
+        };
+
+        let value = match column_type.physical_type() {
+            ColumnType::Integer32 => Value::Int32(i32::from_le_bytes(take(4)?.try_into().unwrap())),
+            ColumnType::Integer64 => Value::Int64(i64::from_le_bytes(take(8)?.try_into().unwrap())),
+            ColumnType::Float32 => Value::Float32(f32::from_le_bytes(take(4)?.try_into().unwrap())),
+            ColumnType::Float64 => Value::Float64(f64::from_le_bytes(take(8)?.try_into().unwrap())),
+            ColumnType::Timestamp => Value::Timestamp(i64::from_le_bytes(take(8)?.try_into().unwrap())),
+            ColumnType::Boolean => Value::Bool(take(1)?[0] != 0),
+            ColumnType::Uuid => Value::Uuid(take(16)?.try_into().unwrap()),
+            ColumnType::FixedSizeBinary(width) => Value::Binary(take(width as usize)?.to_vec()),
+            ColumnType::List | ColumnType::Struct => unreachable!("physical types are never nested"),
+            ColumnType::Utf8 | ColumnType::Binary => {
+                let len = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+                let bytes = &take(4 + len)?[4..];
+
+                let value = if column_type == ColumnType::Utf8 {
+                    let s = std::str::from_utf8(bytes).map_err(|_| "utf8 error")?;
+                    Value::String(s.to_string())
+                } else {
+                    Value::Binary(bytes.to_vec())
+                };
+
+                return Ok((value, 4 + len));
+            }
+        };
+
+        Ok((value, column_type.fixed_width().unwrap()))
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// Compares two values for equality and range predicates.
+    ///
+    /// Integers and floats compare numerically across widths, `Binary` and
+    /// `Uuid` compare bytewise (unsigned, lexicographic). Returns `None` when
+    /// either side is null or the types are not comparable.
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        use Value::*;
+
+        match (self, other) {
+            (Null, _) | (_, Null) => None,
+            (Bool(a), Bool(b)) => Some(a.cmp(b)),
+            (String(a), String(b)) => Some(a.cmp(b)),
+            (Timestamp(a), Timestamp(b)) => Some(a.cmp(b)),
+            (Binary(a), Binary(b)) => Some(a.as_slice().cmp(b.as_slice())),
+            (Uuid(a), Uuid(b)) => Some(a.cmp(b)),
+            (Uuid(a), Binary(b)) => Some(a.as_slice().cmp(b.as_slice())),
+            (Binary(a), Uuid(b)) => Some(a.as_slice().cmp(b.as_slice())),
+            _ => match (self.as_i64(), other.as_i64()) {
+                (Some(a), Some(b)) => Some(a.cmp(&b)),
+                _ => self.as_f64()?.partial_cmp(&other.as_f64()?),
+            },
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Int32(v) => Some(*v as i64),
+            Value::Int64(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int32(v) => Some(*v as f64),
+            Value::Int64(v) => Some(*v as f64),
+            Value::Float32(v) => Some(*v as f64),
+            Value::Float64(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Parses the text form of a value of `column_type`, as found in CSV files.
+    ///
+    /// Booleans accept `true/false`, `t/f`, `yes/no` and `1/0`; timestamps
+    /// accept ISO 8601 dates and date-times; binary values are hex, with an
+    /// optional `0x` prefix; UUIDs use the canonical hyphenated form.
+    pub fn parse(text: &str, column_type: ColumnType) -> Result<Value, String> {
+        let invalid = || format!("invalid {column_type:?} value '{text}'");
+        let trimmed = text.trim();
+
+        let value = match column_type {
+            ColumnType::Integer32 => Value::Int32(trimmed.parse().map_err(|_| invalid())?),
+            ColumnType::Integer64 => Value::Int64(trimmed.parse().map_err(|_| invalid())?),
+            ColumnType::Float32 => Value::Float32(trimmed.parse().map_err(|_| invalid())?),
+            ColumnType::Float64 => Value::Float64(trimmed.parse().map_err(|_| invalid())?),
+            ColumnType::Boolean => match trimmed.to_ascii_lowercase().as_str() {
+                "true" | "t" | "yes" | "1" => Value::Bool(true),
+                "false" | "f" | "no" | "0" => Value::Bool(false),
+                _ => return Err(invalid()),
+            },
+            ColumnType::Utf8 => Value::String(text.to_string()),
+            ColumnType::Timestamp => Value::Timestamp(parse_timestamp(trimmed).ok_or_else(invalid)?),
+            ColumnType::Binary | ColumnType::FixedSizeBinary(_) => {
+                let hex = trimmed.strip_prefix("0x").unwrap_or(trimmed);
+                if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+                    return Err(invalid());
+                }
+
+                let bytes = (0..hex.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+                    .collect::<Result<Vec<u8>, _>>()
+                    .map_err(|_| invalid())?;
+
+                let value = Value::Binary(bytes);
+                if !value.matches_column_type(column_type) {
+                    return Err(invalid());
+                }
+                value
+            }
+            ColumnType::Uuid => Value::Uuid(Self::parse_uuid(trimmed).ok_or_else(invalid)?),
+            ColumnType::List | ColumnType::Struct => {
+                return Err(format!("{column_type:?} values cannot be parsed from text"));
+            }
+        };
+
+        Ok(value)
+    }
+
+    /// Parses the canonical `8-4-4-4-12` hex form (hyphens optional).
+    pub fn parse_uuid(s: &str) -> Option<[u8; 16]> {
+        let hex: Vec<u8> = s.bytes().filter(|b| *b != b'-').collect();
+        if hex.len() != 32 {
+            return None;
+        }
+
+        let mut out = [0u8; 16];
+        for (i, pair) in hex.chunks(2).enumerate() {
+            let pair = std::str::from_utf8(pair).ok()?;
+            out[i] = u8::from_str_radix(pair, 16).ok()?;
+        }
+        Some(out)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int32(v) => write!(f, "{v}"),
+            Value::Int64(v) => write!(f, "{v}"),
+            Value::Float32(v) => write!(f, "{v}"),
+            Value::Float64(v) => write!(f, "{v}"),
+            Value::Bool(v) => write!(f, "{v}"),
+            Value::String(s) => write!(f, "{s}"),
+            Value::Timestamp(v) => f.write_str(&format_timestamp(*v)),
+            Value::Binary(b) => {
+                f.write_str("0x")?;
+                write_hex(f, b)
+            }
+            Value::Uuid(u) => {
+                write_hex(f, &u[0..4])?;
+                f.write_str("-")?;
+                write_hex(f, &u[4..6])?;
+                f.write_str("-")?;
+                write_hex(f, &u[6..8])?;
+                f.write_str("-")?;
+                write_hex(f, &u[8..10])?;
+                f.write_str("-")?;
+                write_hex(f, &u[10..16])
+            }
+            Value::List(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_str("]")
+            }
+            Value::Struct(fields) => {
+                f.write_str("{")?;
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{field}")?;
+                }
+                f.write_str("}")
+            }
+            Value::Null => f.write_str("NULL"),
+        }
+    }
+}
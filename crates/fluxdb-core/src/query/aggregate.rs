@@ -0,0 +1,384 @@
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+use crate::query::vector::{Vector, VectorData};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggregateFunction::Count => "COUNT",
+            AggregateFunction::Sum => "SUM",
+            AggregateFunction::Avg => "AVG",
+            AggregateFunction::Min => "MIN",
+            AggregateFunction::Max => "MAX",
+        }
+    }
+}
+
+/// An aggregate over the rows of a group. Nulls are skipped, so an
+/// aggregate over no non-null values is null, except `COUNT`, which is 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aggregate {
+    pub function: AggregateFunction,
+    /// Input column, `None` for `COUNT(*)`.
+    pub column: Option<String>,
+    /// Output column name, defaulting to the SQL text (`SUM(amount)`).
+    pub alias: Option<String>,
+}
+
+impl Aggregate {
+    fn over(function: AggregateFunction, column: &str) -> Aggregate {
+        Aggregate { function, column: Some(column.to_string()), alias: None }
+    }
+
+    /// `COUNT(*)`: every row of the group, nulls included.
+    pub fn count_star() -> Aggregate {
+        Aggregate { function: AggregateFunction::Count, column: None, alias: None }
+    }
+
+    pub fn count(column: &str) -> Aggregate {
+        Self::over(AggregateFunction::Count, column)
+    }
+
+    pub fn sum(column: &str) -> Aggregate {
+        Self::over(AggregateFunction::Sum, column)
+    }
+
+    pub fn avg(column: &str) -> Aggregate {
+        Self::over(AggregateFunction::Avg, column)
+    }
+
+    pub fn min(column: &str) -> Aggregate {
+        Self::over(AggregateFunction::Min, column)
+    }
+
+    pub fn max(column: &str) -> Aggregate {
+        Self::over(AggregateFunction::Max, column)
+    }
+
+    pub fn with_alias(mut self, alias: &str) -> Aggregate {
+        self.alias = Some(alias.to_string());
+        self
+    }
+
+    pub fn output_name(&self) -> String {
+        self.alias.clone().unwrap_or_else(|| self.to_string())
+    }
+
+    /// Type of the aggregate's result for an input column of `input` type
+    /// (`None` for `COUNT(*)`). Errors when the function does not apply to it.
+    pub fn output_type(&self, input: Option<ColumnType>) -> Result<ColumnType> {
+        let unsupported = |column_type: ColumnType| Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} does not apply to {column_type:?} columns", self.function.name()),
+        );
+
+        let Some(input) = input else {
+            return match self.function {
+                AggregateFunction::Count => Ok(ColumnType::Integer64),
+                _ => Err(Error::new(ErrorKind::InvalidInput, format!("{}(*) is not an aggregate", self.function.name()))),
+            };
+        };
+
+        match self.function {
+            AggregateFunction::Count => Ok(ColumnType::Integer64),
+            AggregateFunction::Sum => match input {
+                ColumnType::Integer32 | ColumnType::Integer64 => Ok(ColumnType::Integer64),
+                ColumnType::Float32 | ColumnType::Float64 => Ok(ColumnType::Float64),
+                _ => Err(unsupported(input)),
+            },
+            AggregateFunction::Avg => match input {
+                ColumnType::Integer32 | ColumnType::Integer64 | ColumnType::Float32 | ColumnType::Float64 => {
+                    Ok(ColumnType::Float64)
+                }
+                _ => Err(unsupported(input)),
+            },
+            AggregateFunction::Min | AggregateFunction::Max if input.is_nested() => Err(unsupported(input)),
+            AggregateFunction::Min | AggregateFunction::Max => Ok(input),
+        }
+    }
+
+    /// A fresh accumulator for the aggregate over an `input` column.
+    pub fn accumulator(&self, input: Option<ColumnType>) -> Result<Accumulator> {
+        let output = self.output_type(input)?;
+
+        Ok(match self.function {
+            AggregateFunction::Count if self.column.is_none() => Accumulator::CountRows(0),
+            AggregateFunction::Count => Accumulator::Count(0),
+            AggregateFunction::Sum if output == ColumnType::Integer64 => Accumulator::SumInt(None),
+            AggregateFunction::Sum => Accumulator::SumFloat(None),
+            AggregateFunction::Avg => Accumulator::Avg { sum: 0.0, count: 0 },
+            AggregateFunction::Min => Accumulator::Min(None),
+            AggregateFunction::Max => Accumulator::Max(None),
+        })
+    }
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.function.name(), self.column.as_deref().unwrap_or("*"))
+    }
+}
+
+/// Running state of one aggregate for one group.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Accumulator {
+    CountRows(i64),
+    Count(i64),
+    SumInt(Option<i64>),
+    SumFloat(Option<f64>),
+    Avg { sum: f64, count: i64 },
+    Min(Option<Value>),
+    Max(Option<Value>),
+}
+
+fn overflow() -> Error {
+    Error::new(ErrorKind::InvalidData, "SUM overflowed Integer64")
+}
+
+/// Folds the non-null values at the offsets in `rows` with `f`. Over every
+/// row of a vector without nulls it runs straight down the array.
+#[inline(always)]
+fn fold<T: Copy, A>(data: &[T], validity: Option<&[bool]>, rows: &[u32], init: A, f: impl Fn(A, T) -> A) -> A {
+    match validity {
+        None if rows.len() == data.len() => data.iter().fold(init, |a, &v| f(a, v)),
+        None => rows.iter().fold(init, |a, &row| f(a, data[row as usize])),
+        Some(validity) => rows
+            .iter()
+            .filter(|&&row| validity[row as usize])
+            .fold(init, |a, &row| f(a, data[row as usize])),
+    }
+}
+
+/// Adds integers in 128 bits, which no vector can overflow, checking only
+/// the total against `Integer64`.
+fn sum_int<T: Copy>(sum: &mut Option<i64>, data: &[T], validity: Option<&[bool]>, rows: &[u32], widen: impl Fn(T) -> i64) -> Result<()> {
+    let (total, n) = fold(data, validity, rows, (0i128, 0usize), |(s, n), v| (s + widen(v) as i128, n + 1));
+    if n > 0 {
+        let total = i64::try_from(sum.unwrap_or(0) as i128 + total).map_err(|_| overflow())?;
+        *sum = Some(total);
+    }
+    Ok(())
+}
+
+fn sum_float<T: Copy>(sum: &mut Option<f64>, data: &[T], validity: Option<&[bool]>, rows: &[u32], widen: impl Fn(T) -> f64) {
+    let (total, n) = fold(data, validity, rows, (sum.unwrap_or(0.0), 0usize), |(s, n), v| (s + widen(v), n + 1));
+    if n > 0 {
+        *sum = Some(total);
+    }
+}
+
+fn avg<T: Copy>(sum: &mut f64, count: &mut i64, data: &[T], validity: Option<&[bool]>, rows: &[u32], widen: impl Fn(T) -> f64) {
+    let (total, n) = fold(data, validity, rows, (*sum, 0), |(s, n), v| (s + widen(v), n + 1));
+    (*sum, *count) = (total, *count + n);
+}
+
+/// The smallest, or largest, non-null value at the offsets in `rows`,
+/// keeping the first of equal values and skipping those that do not
+/// compare, as [`Accumulator::update`] does.
+fn extreme<T: Copy + PartialOrd>(data: &[T], validity: Option<&[bool]>, rows: &[u32], min: bool) -> Option<T> {
+    fold(data, validity, rows, None, |extreme, v| match extreme {
+        Some(m) if !(if min { v < m } else { v > m }) => Some(m),
+        _ => Some(v),
+    })
+}
+
+impl Accumulator {
+    /// Folds one input value into the state.
+    pub fn update(&mut self, value: &Value) -> Result<()> {
+        if let Accumulator::CountRows(count) = self {
+            *count += 1;
+            return Ok(());
+        }
+        if value.is_null() {
+            return Ok(());
+        }
+
+        match self {
+            Accumulator::CountRows(_) => unreachable!(),
+            Accumulator::Count(count) => *count += 1,
+            Accumulator::SumInt(sum) => {
+                let value = value.as_i64().unwrap_or_default();
+                *sum = Some(sum.unwrap_or(0).checked_add(value).ok_or_else(overflow)?);
+            }
+            Accumulator::SumFloat(sum) => *sum = Some(sum.unwrap_or(0.0) + value.as_f64().unwrap_or_default()),
+            Accumulator::Avg { sum, count } => {
+                *sum += value.as_f64().unwrap_or_default();
+                *count += 1;
+            }
+            Accumulator::Min(min) => {
+                if min.as_ref().is_none_or(|m| value.compare(m).is_some_and(|o| o.is_lt())) {
+                    *min = Some(value.clone());
+                }
+            }
+            Accumulator::Max(max) => {
+                if max.as_ref().is_none_or(|m| value.compare(m).is_some_and(|o| o.is_gt())) {
+                    *max = Some(value.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Folds the values of `vector` at the offsets in `rows`, as calling
+    /// [`Accumulator::update`] for each would, in one loop over the typed
+    /// array where the vector has one. `None` stands for `COUNT(*)`.
+    pub fn update_vector(&mut self, vector: Option<&Vector>, rows: &[u32]) -> Result<()> {
+        let vector = match (&mut *self, vector) {
+            (Accumulator::CountRows(count), _) => {
+                *count += rows.len() as i64;
+                return Ok(());
+            }
+            (_, Some(vector)) => vector,
+            (_, None) => return Ok(()),
+        };
+
+        let validity = vector.validity.as_deref();
+        let is_min = matches!(self, Accumulator::Min(_));
+        match (&mut *self, &vector.data) {
+            (accumulator, VectorData::Values(_)) => {
+                for &row in rows {
+                    accumulator.update(&vector.value(row as usize))?;
+                }
+            }
+            (Accumulator::Count(count), _) => {
+                *count += match validity {
+                    Some(validity) => rows.iter().filter(|&&row| validity[row as usize]).count(),
+                    None => rows.len(),
+                } as i64;
+            }
+            (Accumulator::SumInt(sum), VectorData::Int32(data)) => sum_int(sum, data, validity, rows, i64::from)?,
+            (Accumulator::SumInt(sum), VectorData::Int64(data)) => sum_int(sum, data, validity, rows, |v| v)?,
+            (Accumulator::SumFloat(sum), VectorData::Float32(data)) => sum_float(sum, data, validity, rows, f64::from),
+            (Accumulator::SumFloat(sum), VectorData::Float64(data)) => sum_float(sum, data, validity, rows, |v| v),
+            (Accumulator::Avg { sum, count }, VectorData::Int32(data)) => avg(sum, count, data, validity, rows, f64::from),
+            (Accumulator::Avg { sum, count }, VectorData::Int64(data)) => avg(sum, count, data, validity, rows, |v| v as f64),
+            (Accumulator::Avg { sum, count }, VectorData::Float32(data)) => avg(sum, count, data, validity, rows, f64::from),
+            (Accumulator::Avg { sum, count }, VectorData::Float64(data)) => avg(sum, count, data, validity, rows, |v| v),
+            (Accumulator::Min(_) | Accumulator::Max(_), data) => {
+                let extreme = match data {
+                    VectorData::Int32(data) => extreme(data, validity, rows, is_min).map(Value::Int32),
+                    VectorData::Int64(data) => extreme(data, validity, rows, is_min).map(Value::Int64),
+                    VectorData::Float32(data) => extreme(data, validity, rows, is_min).map(Value::Float32),
+                    VectorData::Float64(data) => extreme(data, validity, rows, is_min).map(Value::Float64),
+                    VectorData::Bool(data) => extreme(data, validity, rows, is_min).map(Value::Bool),
+                    VectorData::Timestamp(data) => extreme(data, validity, rows, is_min).map(Value::Timestamp),
+                    VectorData::Values(_) => unreachable!(),
+                };
+                if let Some(extreme) = extreme {
+                    self.update(&extreme)?;
+                }
+            }
+            (accumulator, _) => {
+                for &row in rows {
+                    accumulator.update(&vector.value(row as usize))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Folds the state of the same aggregate over other rows of the group.
+    pub fn merge(&mut self, other: &Accumulator) -> Result<()> {
+        match (self, other) {
+            (Accumulator::CountRows(a), Accumulator::CountRows(b)) | (Accumulator::Count(a), Accumulator::Count(b)) => {
+                *a += b
+            }
+            (Accumulator::SumInt(a), Accumulator::SumInt(b)) => {
+                if let Some(b) = b {
+                    *a = Some(a.unwrap_or(0).checked_add(*b).ok_or_else(overflow)?);
+                }
+            }
+            (Accumulator::SumFloat(a), Accumulator::SumFloat(b)) => {
+                if let Some(b) = b {
+                    *a = Some(a.unwrap_or(0.0) + b);
+                }
+            }
+            (Accumulator::Avg { sum, count }, Accumulator::Avg { sum: other_sum, count: other_count }) => {
+                *sum += other_sum;
+                *count += other_count;
+            }
+            (min @ Accumulator::Min(_), Accumulator::Min(Some(value)))
+            | (min @ Accumulator::Max(_), Accumulator::Max(Some(value))) => min.update(value)?,
+            (Accumulator::Min(_), Accumulator::Min(None)) | (Accumulator::Max(_), Accumulator::Max(None)) => {}
+            _ => return Err(Error::new(ErrorKind::InvalidInput, "merging accumulators of different aggregates")),
+        }
+
+        Ok(())
+    }
+
+    /// The state as values, for spilling. [`Accumulator::restore`] reads it back.
+    pub fn state(&self) -> Vec<Value> {
+        let optional = |v: Option<Value>| v.unwrap_or(Value::Null);
+
+        match self {
+            Accumulator::CountRows(count) | Accumulator::Count(count) => vec![Value::Int64(*count)],
+            Accumulator::SumInt(sum) => vec![optional(sum.map(Value::Int64))],
+            Accumulator::SumFloat(sum) => vec![optional(sum.map(Value::Float64))],
+            Accumulator::Avg { sum, count } => vec![Value::Float64(*sum), Value::Int64(*count)],
+            Accumulator::Min(value) | Accumulator::Max(value) => vec![optional(value.clone())],
+        }
+    }
+
+    /// Number of values in [`Accumulator::state`].
+    pub fn state_len(&self) -> usize {
+        match self {
+            Accumulator::Avg { .. } => 2,
+            _ => 1,
+        }
+    }
+
+    /// Replaces the state with one taken by [`Accumulator::state`] from an
+    /// accumulator of the same aggregate.
+    pub fn restore(&mut self, state: &[Value]) {
+        let non_null = |v: &Value| (!v.is_null()).then(|| v.clone());
+
+        match self {
+            Accumulator::CountRows(count) | Accumulator::Count(count) => *count = state[0].as_i64().unwrap_or(0),
+            Accumulator::SumInt(sum) => *sum = state[0].as_i64(),
+            Accumulator::SumFloat(sum) => *sum = state[0].as_f64(),
+            Accumulator::Avg { sum, count } => {
+                *sum = state[0].as_f64().unwrap_or(0.0);
+                *count = state[1].as_i64().unwrap_or(0);
+            }
+            Accumulator::Min(value) | Accumulator::Max(value) => *value = non_null(&state[0]),
+        }
+    }
+
+    /// The aggregate's result.
+    pub fn finish(&self) -> Value {
+        match self {
+            Accumulator::CountRows(count) | Accumulator::Count(count) => Value::Int64(*count),
+            Accumulator::SumInt(sum) => sum.map_or(Value::Null, Value::Int64),
+            Accumulator::SumFloat(sum) => sum.map_or(Value::Null, Value::Float64),
+            Accumulator::Avg { count: 0, .. } => Value::Null,
+            Accumulator::Avg { sum, count } => Value::Float64(sum / *count as f64),
+            Accumulator::Min(value) | Accumulator::Max(value) => value.clone().unwrap_or(Value::Null),
+        }
+    }
+
+    /// Rough heap and inline size of the state, for memory budgeting.
+    pub fn size(&self) -> usize {
+        let value = |v: &Option<Value>| match v {
+            Some(Value::String(s)) => s.len(),
+            Some(Value::Binary(b)) => b.len(),
+            _ => 0,
+        };
+
+        std::mem::size_of::<Accumulator>() + match self {
+            Accumulator::Min(v) | Accumulator::Max(v) => value(v),
+            _ => 0,
+        }
+    }
+}
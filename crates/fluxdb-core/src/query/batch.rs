@@ -0,0 +1,49 @@
+use crate::engine::table_scan::ScanBatch;
+use crate::metadata::value::Value;
+
+/// Rows passed between query operators, stored column by column.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Batch {
+    pub row_count: usize,
+    /// One vector per output column, each `row_count` long.
+    pub columns: Vec<Vec<Value>>,
+}
+
+impl Batch {
+    pub fn new(columns: Vec<Vec<Value>>) -> Batch {
+        let row_count = columns.first().map_or(0, |c| c.len());
+        Batch { row_count, columns }
+    }
+
+    /// The values of row `index`, in column order.
+    pub fn row(&self, index: usize) -> Vec<Value> {
+        self.columns.iter().map(|column| column[index].clone()).collect()
+    }
+
+    /// Builds a batch from rows of equal width.
+    pub fn from_rows(width: usize, rows: Vec<Vec<Value>>) -> Batch {
+        let mut columns: Vec<Vec<Value>> = (0..width).map(|_| Vec::with_capacity(rows.len())).collect();
+        for row in rows {
+            for (column, value) in columns.iter_mut().zip(row) {
+                column.push(value);
+            }
+        }
+        Batch { row_count: columns.first().map_or(0, |c| c.len()), columns }
+    }
+}
+
+impl From<ScanBatch> for Batch {
+    fn from(batch: ScanBatch) -> Batch {
+        Batch { row_count: batch.row_count, columns: batch.columns }
+    }
+}
+
+/// Rough in-memory size of a value, for memory budgeting.
+pub fn value_size(value: &Value) -> usize {
+    std::mem::size_of::<Value>() + match value {
+        Value::String(s) => s.len(),
+        Value::Binary(b) => b.len(),
+        Value::List(items) | Value::Struct(items) => items.iter().map(value_size).sum(),
+        _ => 0,
+    }
+}
@@ -0,0 +1,279 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::Result;
+use crate::engine::database::Database;
+use crate::metadata::value::Value;
+use crate::query::execute::open_scan;
+use crate::query::expr::{CompareOp, Expr};
+use crate::query::hash_join::JoinType;
+use crate::query::plan::{AccessPath, Plan};
+use crate::query::statistics::{ColumnStats, TableStats};
+
+/// Selectivity of an equality on a column without statistics.
+const DEFAULT_EQ: f64 = 0.1;
+/// Selectivity of a range or other predicate the model cannot judge.
+const DEFAULT_RANGE: f64 = 1.0 / 3.0;
+const DEFAULT_LIKE: f64 = 0.1;
+/// Cost of checking one range's zone map for one filter column, relative to
+/// reading one value.
+const ZONE_CHECK: f64 = 4.0;
+/// Cost of inserting a row into a hash table, relative to probing it.
+const HASH_BUILD: f64 = 2.0;
+
+/// What a plan is expected to output, and the work to get there in units
+/// of one value read or one row processed.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Estimate {
+    pub rows: f64,
+    pub cost: f64,
+}
+
+/// Estimates plans over the tables of a database from their statistics,
+/// collected once per table.
+pub struct CostModel<'a> {
+    db: &'a Database,
+    tables: HashMap<String, TableStats>,
+    /// Table scanned under each label of the plans estimated so far.
+    labels: HashMap<String, String>,
+}
+
+impl<'a> CostModel<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, tables: HashMap::new(), labels: HashMap::new() }
+    }
+
+    pub fn table_stats(&mut self, table: &str) -> Result<&TableStats> {
+        if !self.tables.contains_key(table) {
+            let stats = TableStats::collect(self.db, table)?;
+            self.tables.insert(table.to_string(), stats);
+        }
+        Ok(&self.tables[table])
+    }
+
+    /// Statistics of a column by its qualified `label.column` name, for the
+    /// labels of scans estimated so far, with the row count of its table.
+    pub fn column_stats(&self, name: &str) -> Option<(&ColumnStats, u64)> {
+        let (label, column) = name.split_once('.')?;
+        let table = self.tables.get(self.labels.get(label)?)?;
+        Some((table.column(column)?, table.row_count))
+    }
+
+    pub fn estimate(&mut self, plan: &Plan) -> Result<Estimate> {
+        Ok(match plan {
+            Plan::Scan { table, label, columns, filter, access } => {
+                self.labels.insert(label.clone(), table.clone());
+                let stats = self.table_stats(table)?.clone();
+
+                let scan = open_scan(self.db, table, columns, filter.as_ref(), access)?;
+                let read_rows = scan.estimated_rows() as f64;
+                let (ranges, pruned) = scan.estimated_ranges();
+                let checked = (ranges + pruned) as f64;
+                let mut read_columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+
+                let mut cost = 0.0;
+                let mut rows = stats.row_count as f64;
+                if let Some(filter) = filter {
+                    let filter_columns = filter.columns();
+                    let zone_checks = filter_columns.iter().filter(|c| stats.column(c).is_some()).count() as f64;
+                    let checked_ranges = match access {
+                        AccessPath::Full => 0.0,
+                        AccessPath::Pruned => checked,
+                        // A binary search over every range, then the zone maps of those it finds
+                        AccessPath::Lookup { .. } => checked + (checked + scan.stats.ranges_pruned as f64).log2().max(1.0),
+                    };
+                    cost += checked_ranges * zone_checks * ZONE_CHECK + read_rows;
+
+                    let column = |name: &str| stats.column(name).map(|column| (column, stats.row_count));
+                    rows *= selectivity(filter, &column);
+                    for name in filter_columns {
+                        if !read_columns.contains(&name) {
+                            read_columns.push(name);
+                        }
+                    }
+                }
+                cost += read_rows * read_columns.len().max(1) as f64;
+                Estimate { rows: rows.min(read_rows), cost }
+            }
+            Plan::Filter { input, predicate } => {
+                let input = self.estimate(input)?;
+                let rows = input.rows * selectivity(predicate, &|name| self.column_stats(name));
+                Estimate { rows, cost: input.cost + input.rows }
+            }
+            Plan::Join { left, right, on, join_type } => {
+                let left = self.estimate(left)?;
+                let right = self.estimate(right)?;
+
+                // Each key pair matches rows of the side with fewer distinct
+                // keys to those of the other
+                let mut rows = left.rows * right.rows;
+                for (left_key, right_key) in on {
+                    let distinct = |key: &str, side_rows: f64| {
+                        self.column_stats(key).map_or(side_rows, |(stats, _)| (stats.distinct as f64).min(side_rows))
+                    };
+                    rows /= distinct(left_key, left.rows).max(distinct(right_key, right.rows)).max(1.0);
+                }
+                if *join_type == JoinType::Left {
+                    rows = rows.max(left.rows);
+                }
+
+                let (build, probe) = match left.rows < right.rows {
+                    true => (left.rows, right.rows),
+                    false => (right.rows, left.rows),
+                };
+                Estimate { rows, cost: left.cost + right.cost + build * HASH_BUILD + probe + rows }
+            }
+            Plan::Aggregate { input, group_by, .. } => {
+                let input = self.estimate(input)?;
+                let groups: f64 = group_by
+                    .iter()
+                    .map(|column| self.column_stats(column).map_or(input.rows * DEFAULT_EQ, |(stats, _)| stats.distinct as f64))
+                    .product();
+                Estimate { rows: groups.min(input.rows).max(1.0), cost: input.cost + input.rows * HASH_BUILD }
+            }
+            Plan::Sort { input, limit, .. } => {
+                let input = self.estimate(input)?;
+                let kept = limit.map_or(input.rows, |limit| input.rows.min(limit as f64));
+                Estimate { rows: kept, cost: input.cost + input.rows * kept.max(2.0).log2() }
+            }
+            Plan::Limit { input, limit } => {
+                let input = self.estimate(input)?;
+                Estimate { rows: input.rows.min(*limit as f64), cost: input.cost }
+            }
+            Plan::Project { input, .. } => self.estimate(input)?,
+        })
+    }
+}
+
+/// Estimated fraction of rows for which `expr` is true, given the
+/// statistics `column` finds for the columns it names (with their table's
+/// row count). Columns without statistics fall back to fixed guesses.
+///
+/// Conjuncts are taken as independent, which overestimates how much
+/// correlated predicates filter out.
+pub fn selectivity<'s>(expr: &Expr, column: &dyn Fn(&str) -> Option<(&'s ColumnStats, u64)>) -> f64 {
+    let selectivity = |expr: &Expr| selectivity(expr, column);
+    let non_null = |name: &str| column(name).map_or(1.0, |(stats, rows)| 1.0 - stats.null_fraction(rows));
+
+    let estimate = match expr {
+        Expr::Literal(Value::Bool(value)) => f64::from(u8::from(*value)),
+        Expr::Literal(_) => 0.0,
+        Expr::Column(name) => non_null(name) / 2.0,
+        Expr::And(left, right) => selectivity(left) * selectivity(right),
+        Expr::Or(left, right) => {
+            let (left, right) = (selectivity(left), selectivity(right));
+            left + right - left * right
+        }
+        Expr::Not(inner) => 1.0 - selectivity(inner),
+        Expr::Compare { op, left, right } => match (left.as_ref(), right.as_ref()) {
+            (Expr::Column(name), Expr::Literal(value)) => compare(column(name), *op, value),
+            (Expr::Literal(value), Expr::Column(name)) => compare(column(name), op.flip(), value),
+            (Expr::Column(left), Expr::Column(right)) if *op == CompareOp::Eq => {
+                let distinct = |name: &str| column(name).map_or(1.0 / DEFAULT_EQ, |(stats, _)| stats.distinct as f64);
+                non_null(left) * non_null(right) / distinct(left).max(distinct(right)).max(1.0)
+            }
+            _ => DEFAULT_RANGE,
+        },
+        Expr::IsNull { expr, negated } => {
+            let null = match expr.as_ref() {
+                Expr::Column(name) => 1.0 - non_null(name),
+                _ => DEFAULT_EQ,
+            };
+            match negated {
+                true => 1.0 - null,
+                false => null,
+            }
+        }
+        Expr::In { expr, list, negated } => {
+            let Expr::Column(name) = expr.as_ref() else {
+                return DEFAULT_RANGE;
+            };
+            let matched: f64 = list
+                .iter()
+                .map(|item| match item {
+                    Expr::Literal(value) => compare(column(name), CompareOp::Eq, value),
+                    _ => DEFAULT_EQ,
+                })
+                .sum();
+            let matched = matched.min(non_null(name));
+            match negated {
+                true => non_null(name) - matched,
+                false => matched,
+            }
+        }
+        Expr::Between { expr, low, high, negated } => {
+            let (Expr::Column(name), Expr::Literal(low), Expr::Literal(high)) = (expr.as_ref(), low.as_ref(), high.as_ref()) else {
+                return DEFAULT_RANGE;
+            };
+            let stats = column(name);
+            let within = match stats {
+                Some(_) => compare(stats, CompareOp::GtEq, low) + compare(stats, CompareOp::LtEq, high) - non_null(name),
+                None => DEFAULT_RANGE * DEFAULT_RANGE,
+            };
+            let within = within.max(0.0);
+            match negated {
+                true => non_null(name) - within,
+                false => within,
+            }
+        }
+        Expr::Like { negated, .. } => match negated {
+            true => 1.0 - DEFAULT_LIKE,
+            false => DEFAULT_LIKE,
+        },
+    };
+    estimate.clamp(0.0, 1.0)
+}
+
+/// Selectivity of `column op value`.
+fn compare(column: Option<(&ColumnStats, u64)>, op: CompareOp, value: &Value) -> f64 {
+    if value.is_null() {
+        return 0.0;
+    }
+    let Some((stats, rows)) = column else {
+        return match op {
+            CompareOp::Eq => DEFAULT_EQ,
+            CompareOp::NotEq => 1.0 - DEFAULT_EQ,
+            _ => DEFAULT_RANGE,
+        };
+    };
+
+    let non_null = 1.0 - stats.null_fraction(rows);
+    let (Some(min), Some(max)) = (&stats.min, &stats.max) else {
+        // All null, or bounds too long to keep
+        return match stats.null_count >= rows {
+            true => 0.0,
+            false => non_null * DEFAULT_RANGE,
+        };
+    };
+    let equal = match (value.compare(min), value.compare(max)) {
+        (Some(Ordering::Less), _) | (_, Some(Ordering::Greater)) => 0.0,
+        _ => 1.0 / stats.distinct.max(1) as f64,
+    };
+
+    // Fraction of the non-null values below `value`, interpolating between
+    // the bounds of numbers and times
+    let below = match (value.compare(min), value.compare(max)) {
+        (Some(Ordering::Less | Ordering::Equal), _) => 0.0,
+        (_, Some(Ordering::Greater)) => 1.0,
+        _ => match (position(min), position(max), position(value)) {
+            (Some(min), Some(max), Some(value)) if max > min => (value - min) / (max - min),
+            _ => 0.5,
+        },
+    };
+
+    let fraction = match op {
+        CompareOp::Eq => equal,
+        CompareOp::NotEq => 1.0 - equal,
+        CompareOp::Lt => below,
+        CompareOp::LtEq => below + equal,
+        CompareOp::Gt => 1.0 - below - equal,
+        CompareOp::GtEq => 1.0 - below,
+    };
+    non_null * fraction.clamp(0.0, 1.0)
+}
+
+fn position(value: &Value) -> Option<f64> {
+    match value {
+        Value::Timestamp(micros) => Some(*micros as f64),
+        value => value.as_f64(),
+    }
+}
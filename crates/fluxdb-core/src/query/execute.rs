@@ -0,0 +1,236 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Instant;
+use crate::engine::database::Database;
+use crate::engine::table_scan::TableScan;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::query::expr::Expr;
+use crate::query::filter::filter;
+use crate::query::hash_aggregate::{AggregateOptions, HashAggregate};
+use crate::query::hash_join::{hash_join, JoinOptions};
+use crate::query::parallel::aggregate_parallel;
+use crate::query::plan::{AccessPath, Plan};
+use crate::query::profile::{profiled, update, OperatorProfile, StatsCell};
+use crate::query::sort::{Sort, SortOptions};
+use crate::query::stream::BatchStream;
+
+#[derive(Debug, Clone)]
+pub struct QueryOptions {
+    /// Memory budget of each operator that buffers rows (aggregates, sorts,
+    /// join build sides) before it spills.
+    pub memory_budget: usize,
+    /// Directory for spill files.
+    pub spill_dir: PathBuf,
+    /// Rows per output batch of the buffering operators.
+    pub batch_size: usize,
+    /// Worker threads an aggregate spreads the chunks of the table it
+    /// scans over. 1 runs every operator on the calling thread.
+    pub threads: usize,
+}
+
+impl Default for QueryOptions {
+    fn default() -> Self {
+        Self {
+            memory_budget: 64 * 1024 * 1024,
+            spill_dir: std::env::temp_dir(),
+            batch_size: 4096,
+            threads: 1,
+        }
+    }
+}
+
+/// Runs `plan` over the tables of `db`, returning its output rows.
+///
+/// Aggregates and sorts consume their whole input here, before the first
+/// batch is returned; scans, filters, joins' probe sides and projections
+/// run as the batches are read.
+pub fn execute<'a>(db: &'a Database, plan: &Plan, options: &QueryOptions) -> Result<BatchStream<'a>> {
+    execute_profiled(db, plan, options).map(|(stream, _)| stream)
+}
+
+/// Opens the table scan of a [`Plan::Scan`], with its filter attached and
+/// its access path applied.
+pub fn open_scan<'a>(
+    db: &'a Database,
+    table: &str,
+    columns: &[String],
+    filter: Option<&Expr>,
+    access: &AccessPath,
+) -> Result<TableScan<'a>> {
+    let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+    let scan = match filter {
+        Some(filter) => db.scan_where(table, &columns, filter.clone())?,
+        None => db.scan(table, &columns)?,
+    };
+    match access {
+        AccessPath::Full => Ok(scan.without_pruning()),
+        AccessPath::Pruned => Ok(scan),
+        AccessPath::Lookup { column, low, high } => scan.with_lookup(column, low.as_ref(), high.as_ref()),
+    }
+}
+
+/// Runs `plan` like [`execute`], also returning the stats of its operators,
+/// which fill in as the output is read.
+pub fn execute_profiled<'a>(
+    db: &'a Database,
+    plan: &Plan,
+    options: &QueryOptions,
+) -> Result<(BatchStream<'a>, OperatorProfile)> {
+    let stats = StatsCell::default();
+    let started = Instant::now();
+
+    let mut children = Vec::new();
+    let mut execute = |input: &Plan| -> Result<BatchStream<'a>> {
+        let (stream, profile) = execute_profiled(db, input, options)?;
+        children.push(profile);
+        Ok(stream)
+    };
+
+    let stream = match plan {
+        Plan::Scan { table, label, columns, filter, access } => {
+            let scan = open_scan(db, table, columns, filter.as_ref(), access)?;
+            let scan_stats = Rc::clone(&stats);
+            BatchStream::from_scan(scan, move |scan| update(&scan_stats, |stats| stats.scan = Some(*scan)))
+                .qualified(label)
+        }
+        Plan::Filter { input, predicate } => filter(execute(input)?, predicate)?,
+        Plan::Join { left, right, on, join_type } => {
+            let left = execute(left)?;
+            let right = execute(right)?;
+            let estimated_rows = left.estimated_rows.max(right.estimated_rows);
+
+            let on: Vec<(&str, &str)> = on.iter().map(|(l, r)| (l.as_str(), r.as_str())).collect();
+            let join_options = JoinOptions {
+                memory_budget: options.memory_budget,
+                spill_dir: options.spill_dir.clone(),
+                batch_size: options.batch_size,
+            };
+            let results = hash_join(left, right, &on, *join_type, join_options)?;
+            BatchStream::new(results.column_names.clone(), results.column_types.clone(), estimated_rows, results)
+        }
+        Plan::Aggregate { input, group_by, aggregates } => {
+            let group_by: Vec<&str> = group_by.iter().map(String::as_str).collect();
+            let aggregate_options = AggregateOptions {
+                memory_budget: options.memory_budget,
+                spill_dir: options.spill_dir.clone(),
+                batch_size: options.batch_size,
+                threads: options.threads,
+            };
+
+            let (aggregate, estimated_rows) = match input.as_ref() {
+                // A scan hands its vectors to the aggregate as they are decoded
+                Plan::Scan { table, label, columns, filter, access } => {
+                    let scan = open_scan(db, table, columns, filter.as_ref(), access)?;
+                    let estimated_rows = scan.estimated_rows();
+                    let column_names: Vec<String> = scan.column_names.iter().map(|name| format!("{label}.{name}")).collect();
+                    let scan_stats = StatsCell::default();
+                    children.push(OperatorProfile { stats: Rc::clone(&scan_stats), children: Vec::new() });
+
+                    let aggregate = match options.threads {
+                        0 | 1 => {
+                            let column_types: Vec<ColumnType> = scan.column_schemas.iter().map(|c| c.column_type).collect();
+                            let mut aggregate =
+                                HashAggregate::new(&column_names, &column_types, &group_by, aggregates, aggregate_options)?;
+                            aggregate_vectors(scan, &mut aggregate, &scan_stats)?;
+                            aggregate
+                        }
+                        threads => {
+                            let started = Instant::now();
+                            let (aggregate, parallel) =
+                                aggregate_parallel(scan, &column_names, &group_by, aggregates, aggregate_options, threads)?;
+                            update(&scan_stats, |stats| {
+                                stats.elapsed += started.elapsed();
+                                stats.rows += parallel.scan.rows_selected;
+                                stats.batches += parallel.batches;
+                                stats.scan = Some(parallel.scan);
+                            });
+                            aggregate
+                        }
+                    };
+                    (aggregate, estimated_rows)
+                }
+                input => {
+                    let input = execute(input)?;
+                    let estimated_rows = input.estimated_rows;
+                    let mut aggregate = HashAggregate::new(
+                        &input.column_names,
+                        &input.column_types,
+                        &group_by,
+                        aggregates,
+                        aggregate_options,
+                    )?;
+                    for batch in input {
+                        let batch = batch?;
+                        aggregate.push(&batch.columns, batch.row_count)?;
+                    }
+                    (aggregate, estimated_rows)
+                }
+            };
+
+            let results = aggregate.finish()?;
+            BatchStream::new(results.column_names.clone(), results.column_types.clone(), estimated_rows, results)
+        }
+        Plan::Sort { input, keys, limit } => {
+            let input = execute(input)?;
+            let estimated_rows = limit.map_or(input.estimated_rows, |limit| input.estimated_rows.min(limit as u64));
+
+            let sort_options = SortOptions {
+                memory_budget: options.memory_budget,
+                spill_dir: options.spill_dir.clone(),
+                batch_size: options.batch_size,
+            };
+            let mut sort = Sort::new(&input.column_names, &input.column_types, keys, *limit, sort_options)?;
+            for batch in input {
+                let batch = batch?;
+                sort.push(&batch.columns, batch.row_count)?;
+            }
+
+            let results = sort.finish()?;
+            BatchStream::new(results.column_names.clone(), results.column_types.clone(), estimated_rows, results)
+        }
+        Plan::Limit { input, limit } => execute(input)?.limit(*limit),
+        Plan::Project { input, columns } => {
+            let input = execute(input)?;
+            let indices = columns
+                .iter()
+                .map(|(column, _)| {
+                    input.position(column).ok_or_else(|| {
+                        Error::new(ErrorKind::NotFound, format!("column '{column}' not found"))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let names = columns.iter().map(|(_, name)| name.clone()).collect();
+            input.project(indices, names)
+        }
+    };
+
+    // Building the operator may have run its inputs, as aggregates and sorts do
+    let elapsed = started.elapsed();
+    update(&stats, |stats| stats.elapsed += elapsed);
+
+    let profile = OperatorProfile { stats: Rc::clone(&stats), children };
+    Ok((profiled(stream, stats), profile))
+}
+
+/// Feeds every vector batch of `scan` to `aggregate`, counting them in
+/// `stats` as the scan's own operator would its batches.
+fn aggregate_vectors(mut scan: TableScan, aggregate: &mut HashAggregate, stats: &StatsCell) -> Result<()> {
+    loop {
+        let started = Instant::now();
+        let batch = scan.next_vectors();
+        update(stats, |stats| {
+            stats.elapsed += started.elapsed();
+            stats.scan = Some(scan.stats);
+            if let Some(Ok(batch)) = &batch {
+                stats.rows += batch.selected() as u64;
+                stats.batches += 1;
+            }
+        });
+
+        match batch {
+            Some(batch) => aggregate.push_vectors(&batch?)?,
+            None => return Ok(()),
+        }
+    }
+}
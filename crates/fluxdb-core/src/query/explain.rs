@@ -0,0 +1,242 @@
+use std::fmt::Write as _;
+use std::io::Result;
+use std::ops::Bound;
+use crate::engine::database::Database;
+use crate::helpers::helper::write_json_string;
+use crate::query::cost::CostModel;
+use crate::query::execute::{execute_profiled, open_scan, QueryOptions};
+use crate::query::hash_join::JoinType;
+use crate::query::plan::{AccessPath, Plan};
+use crate::query::profile::{OperatorProfile, OperatorStats};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExplainFormat {
+    /// An indented operator tree, one line per operator.
+    #[default]
+    Text,
+    /// One JSON object per operator, nested through `children`.
+    Json,
+}
+
+/// One operator of an explained plan.
+#[derive(Debug, Clone)]
+pub struct ExplainNode {
+    pub operator: &'static str,
+    /// The operator's arguments: table and columns, keys, predicate.
+    pub detail: String,
+    /// Rows the operator is expected to output, see [`CostModel`].
+    pub estimated_rows: u64,
+    /// Work of the operator and its inputs, in the cost model's units.
+    pub estimated_cost: f64,
+    /// For scans, the chunk row ranges to scan and how many of them the
+    /// zone maps rule out, from the chunks' min/max alone.
+    pub estimated_chunks: Option<(usize, usize)>,
+    /// What the operator did, for an analyzed plan.
+    pub actual: Option<OperatorStats>,
+    /// The operator's inputs, left input first.
+    pub children: Vec<ExplainNode>,
+}
+
+/// Describes the operators of `plan` and their estimates, without running it.
+pub fn explain(db: &Database, plan: &Plan) -> Result<ExplainNode> {
+    describe(db, plan, &mut CostModel::new(db))
+}
+
+fn describe(db: &Database, plan: &Plan, model: &mut CostModel) -> Result<ExplainNode> {
+    let children = plan.inputs().into_iter().map(|input| describe(db, input, model)).collect::<Result<Vec<_>>>()?;
+    let estimate = model.estimate(plan)?;
+    let mut estimated_chunks = None;
+
+    let (operator, detail) = match plan {
+        Plan::Scan { table, label, columns, filter, access } => {
+            let scan = open_scan(db, table, columns, filter.as_ref(), access)?;
+            estimated_chunks = Some(scan.estimated_ranges());
+
+            let mut detail = table.clone();
+            if label != table {
+                write!(detail, " AS {label}").unwrap();
+            }
+            write!(detail, " [{}]", columns.join(", ")).unwrap();
+            if let Some(filter) = filter {
+                write!(detail, " filter: {filter}").unwrap();
+            }
+            let operator = match access {
+                AccessPath::Full => "FullScan",
+                AccessPath::Pruned => "PrunedScan",
+                AccessPath::Lookup { column, low, high } => {
+                    let low = match low {
+                        Bound::Included(value) => format!("[{value}"),
+                        Bound::Excluded(value) => format!("({value}"),
+                        Bound::Unbounded => "(-inf".to_string(),
+                    };
+                    let high = match high {
+                        Bound::Included(value) => format!("{value}]"),
+                        Bound::Excluded(value) => format!("{value})"),
+                        Bound::Unbounded => "+inf)".to_string(),
+                    };
+                    write!(detail, " lookup: {column} in {low}, {high}").unwrap();
+                    "IndexLookup"
+                }
+            };
+            (operator, detail)
+        }
+        Plan::Filter { predicate, .. } => ("Filter", predicate.to_string()),
+        Plan::Join { on, join_type, .. } => {
+            let join = match join_type {
+                JoinType::Inner => "inner",
+                JoinType::Left => "left",
+            };
+            let on: Vec<String> = on.iter().map(|(left, right)| format!("{left} = {right}")).collect();
+            ("HashJoin", format!("{join} on {}", on.join(" AND ")))
+        }
+        Plan::Aggregate { group_by, aggregates, .. } => {
+            let aggregates: Vec<String> = aggregates.iter().map(|a| a.to_string()).collect();
+            let detail = match group_by.is_empty() {
+                true => aggregates.join(", "),
+                false => format!("{} group by {}", aggregates.join(", "), group_by.join(", ")),
+            };
+            ("HashAggregate", detail)
+        }
+        Plan::Sort { keys, limit, .. } => {
+            let keys: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+            match limit {
+                Some(limit) => ("TopK", format!("{} limit {limit}", keys.join(", "))),
+                None => ("Sort", keys.join(", ")),
+            }
+        }
+        Plan::Limit { limit, .. } => ("Limit", limit.to_string()),
+        Plan::Project { columns, .. } => {
+            let columns: Vec<String> = columns
+                .iter()
+                .map(|(input, output)| match input == output {
+                    true => input.clone(),
+                    false => format!("{input} AS {output}"),
+                })
+                .collect();
+            ("Project", columns.join(", "))
+        }
+    };
+
+    Ok(ExplainNode {
+        operator,
+        detail,
+        estimated_rows: estimate.rows.round() as u64,
+        estimated_cost: estimate.cost,
+        estimated_chunks,
+        actual: None,
+        children,
+    })
+}
+
+/// Runs `plan` to completion, discarding its output, and describes its
+/// operators with both their estimates and what they actually did.
+pub fn explain_analyze(db: &Database, plan: &Plan, options: &QueryOptions) -> Result<ExplainNode> {
+    let mut node = explain(db, plan)?;
+
+    let (stream, profile) = execute_profiled(db, plan, options)?;
+    for batch in stream {
+        batch?;
+    }
+
+    node.record(&profile);
+    Ok(node)
+}
+
+impl ExplainNode {
+    fn record(&mut self, profile: &OperatorProfile) {
+        self.actual = Some(profile.stats());
+        for (child, profile) in self.children.iter_mut().zip(&profile.children) {
+            child.record(profile);
+        }
+    }
+
+    pub fn render(&self, format: ExplainFormat) -> String {
+        let mut out = String::new();
+        match format {
+            ExplainFormat::Text => self.write_text(&mut out, 0),
+            ExplainFormat::Json => self.write_json(&mut out),
+        }
+        out
+    }
+
+    fn write_text(&self, out: &mut String, depth: usize) {
+        if depth > 0 {
+            write!(out, "{:width$}-> ", "", width = (depth - 1) * 4 + 2).unwrap();
+        }
+        write!(
+            out,
+            "{} {}  (estimated rows={}, cost={:.0}",
+            self.operator, self.detail, self.estimated_rows, self.estimated_cost,
+        )
+        .unwrap();
+        if let Some((scanned, pruned)) = self.estimated_chunks {
+            write!(out, ", chunks={scanned}, pruned={pruned}").unwrap();
+        }
+        out.push(')');
+
+        if let Some(actual) = &self.actual {
+            write!(
+                out,
+                " (actual rows={}, batches={}, time={:.3} ms",
+                actual.rows,
+                actual.batches,
+                actual.elapsed.as_secs_f64() * 1000.0,
+            )
+            .unwrap();
+            if let Some(scan) = &actual.scan {
+                write!(
+                    out,
+                    ", chunks={}, pruned={}, pages read={}, bytes decoded={}",
+                    scan.ranges_scanned, scan.ranges_pruned, scan.pages_read, scan.bytes_decoded,
+                )
+                .unwrap();
+            }
+            out.push(')');
+        }
+        out.push('\n');
+
+        for child in &self.children {
+            child.write_text(out, depth + 1);
+        }
+    }
+
+    fn write_json(&self, out: &mut String) {
+        out.push_str("{\"operator\":");
+        write_json_string(out, self.operator);
+        out.push_str(",\"detail\":");
+        write_json_string(out, &self.detail);
+        write!(out, ",\"estimated_rows\":{},\"estimated_cost\":{:.0}", self.estimated_rows, self.estimated_cost).unwrap();
+        if let Some((scanned, pruned)) = self.estimated_chunks {
+            write!(out, ",\"estimated_chunks\":{{\"scanned\":{scanned},\"pruned\":{pruned}}}").unwrap();
+        }
+
+        if let Some(actual) = &self.actual {
+            write!(
+                out,
+                ",\"actual\":{{\"rows\":{},\"batches\":{},\"time_ms\":{:.3}",
+                actual.rows,
+                actual.batches,
+                actual.elapsed.as_secs_f64() * 1000.0,
+            )
+            .unwrap();
+            if let Some(scan) = &actual.scan {
+                write!(
+                    out,
+                    ",\"chunks_scanned\":{},\"chunks_pruned\":{},\"rows_scanned\":{},\"pages_read\":{},\"bytes_decoded\":{}",
+                    scan.ranges_scanned, scan.ranges_pruned, scan.rows_scanned, scan.pages_read, scan.bytes_decoded,
+                )
+                .unwrap();
+            }
+            out.push('}');
+        }
+
+        out.push_str(",\"children\":[");
+        for (i, child) in self.children.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            child.write_json(out);
+        }
+        out.push_str("]}");
+    }
+}
@@ -0,0 +1,465 @@
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Not;
+use crate::helpers::timestamp::format_timestamp;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl CompareOp {
+    pub fn matches(&self, ordering: std::cmp::Ordering) -> bool {
+        match self {
+            CompareOp::Eq => ordering.is_eq(),
+            CompareOp::NotEq => ordering.is_ne(),
+            CompareOp::Lt => ordering.is_lt(),
+            CompareOp::LtEq => ordering.is_le(),
+            CompareOp::Gt => ordering.is_gt(),
+            CompareOp::GtEq => ordering.is_ge(),
+        }
+    }
+
+    /// The operator with its operands swapped: `a < b` is `b > a`.
+    pub fn flip(&self) -> CompareOp {
+        match self {
+            CompareOp::Lt => CompareOp::Gt,
+            CompareOp::LtEq => CompareOp::GtEq,
+            CompareOp::Gt => CompareOp::Lt,
+            CompareOp::GtEq => CompareOp::LtEq,
+            op => *op,
+        }
+    }
+
+    /// The operator true exactly when this one is false, for non-null operands.
+    pub fn negate(&self) -> CompareOp {
+        match self {
+            CompareOp::Eq => CompareOp::NotEq,
+            CompareOp::NotEq => CompareOp::Eq,
+            CompareOp::Lt => CompareOp::GtEq,
+            CompareOp::LtEq => CompareOp::Gt,
+            CompareOp::Gt => CompareOp::LtEq,
+            CompareOp::GtEq => CompareOp::Lt,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "=",
+            CompareOp::NotEq => "<>",
+            CompareOp::Lt => "<",
+            CompareOp::LtEq => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::GtEq => ">=",
+        }
+    }
+}
+
+/// A filter expression over the columns of a scan.
+///
+/// Evaluation follows SQL three-valued logic: comparisons with a null are
+/// null, and a filter keeps only the rows where the expression is true.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// A column by name, or by dotted path into a nested column.
+    Column(String),
+    Literal(Value),
+    Compare { op: CompareOp, left: Box<Expr>, right: Box<Expr> },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    In { expr: Box<Expr>, list: Vec<Expr>, negated: bool },
+    IsNull { expr: Box<Expr>, negated: bool },
+    Between { expr: Box<Expr>, low: Box<Expr>, high: Box<Expr>, negated: bool },
+    /// SQL `LIKE` on `Utf8` values: `%` matches any run of characters, `_`
+    /// any single character, and `\` escapes the next character.
+    Like { expr: Box<Expr>, pattern: String, negated: bool },
+}
+
+/// Kinds of values that compare with each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueClass {
+    Numeric,
+    Text,
+    Bool,
+    Time,
+    Bytes,
+    Nested,
+    /// A null literal, comparable with anything.
+    Any,
+}
+
+impl ValueClass {
+    fn of_type(column_type: ColumnType) -> ValueClass {
+        match column_type {
+            ColumnType::Integer32 | ColumnType::Integer64 | ColumnType::Float32 | ColumnType::Float64 => ValueClass::Numeric,
+            ColumnType::Utf8 => ValueClass::Text,
+            ColumnType::Boolean => ValueClass::Bool,
+            ColumnType::Timestamp => ValueClass::Time,
+            ColumnType::Binary | ColumnType::FixedSizeBinary(_) | ColumnType::Uuid => ValueClass::Bytes,
+            ColumnType::List | ColumnType::Struct => ValueClass::Nested,
+        }
+    }
+
+    fn of_value(value: &Value) -> ValueClass {
+        match value {
+            Value::Int32(_) | Value::Int64(_) | Value::Float32(_) | Value::Float64(_) => ValueClass::Numeric,
+            Value::String(_) => ValueClass::Text,
+            Value::Bool(_) => ValueClass::Bool,
+            Value::Timestamp(_) => ValueClass::Time,
+            Value::Binary(_) | Value::Uuid(_) => ValueClass::Bytes,
+            Value::List(_) | Value::Struct(_) => ValueClass::Nested,
+            Value::Null => ValueClass::Any,
+        }
+    }
+
+    fn comparable(self, other: ValueClass) -> bool {
+        self == ValueClass::Any || other == ValueClass::Any || (self == other && self != ValueClass::Nested)
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+impl Expr {
+    pub fn column(name: &str) -> Expr {
+        Expr::Column(name.to_string())
+    }
+
+    pub fn literal(value: Value) -> Expr {
+        Expr::Literal(value)
+    }
+
+    fn compare(self, op: CompareOp, other: Expr) -> Expr {
+        Expr::Compare { op, left: Box::new(self), right: Box::new(other) }
+    }
+
+    pub fn eq(self, other: Expr) -> Expr {
+        self.compare(CompareOp::Eq, other)
+    }
+
+    pub fn not_eq(self, other: Expr) -> Expr {
+        self.compare(CompareOp::NotEq, other)
+    }
+
+    pub fn lt(self, other: Expr) -> Expr {
+        self.compare(CompareOp::Lt, other)
+    }
+
+    pub fn lt_eq(self, other: Expr) -> Expr {
+        self.compare(CompareOp::LtEq, other)
+    }
+
+    pub fn gt(self, other: Expr) -> Expr {
+        self.compare(CompareOp::Gt, other)
+    }
+
+    pub fn gt_eq(self, other: Expr) -> Expr {
+        self.compare(CompareOp::GtEq, other)
+    }
+
+    pub fn and(self, other: Expr) -> Expr {
+        Expr::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Expr) -> Expr {
+        Expr::Or(Box::new(self), Box::new(other))
+    }
+
+    pub fn in_list(self, list: Vec<Expr>) -> Expr {
+        Expr::In { expr: Box::new(self), list, negated: false }
+    }
+
+    pub fn not_in_list(self, list: Vec<Expr>) -> Expr {
+        Expr::In { expr: Box::new(self), list, negated: true }
+    }
+
+    pub fn is_null(self) -> Expr {
+        Expr::IsNull { expr: Box::new(self), negated: false }
+    }
+
+    pub fn is_not_null(self) -> Expr {
+        Expr::IsNull { expr: Box::new(self), negated: true }
+    }
+
+    pub fn between(self, low: Expr, high: Expr) -> Expr {
+        Expr::Between { expr: Box::new(self), low: Box::new(low), high: Box::new(high), negated: false }
+    }
+
+    pub fn not_between(self, low: Expr, high: Expr) -> Expr {
+        Expr::Between { expr: Box::new(self), low: Box::new(low), high: Box::new(high), negated: true }
+    }
+
+    pub fn like(self, pattern: &str) -> Expr {
+        Expr::Like { expr: Box::new(self), pattern: pattern.to_string(), negated: false }
+    }
+
+    pub fn not_like(self, pattern: &str) -> Expr {
+        Expr::Like { expr: Box::new(self), pattern: pattern.to_string(), negated: true }
+    }
+
+    /// Names of the columns the expression reads, each once, in order of appearance.
+    pub fn columns(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_columns(&mut names);
+        names
+    }
+
+    fn collect_columns<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Expr::Column(name) => {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+            Expr::Literal(_) => {}
+            Expr::Compare { left, right, .. } | Expr::And(left, right) | Expr::Or(left, right) => {
+                left.collect_columns(names);
+                right.collect_columns(names);
+            }
+            Expr::Not(expr) | Expr::IsNull { expr, .. } | Expr::Like { expr, .. } => expr.collect_columns(names),
+            Expr::In { expr, list, .. } => {
+                expr.collect_columns(names);
+                list.iter().for_each(|item| item.collect_columns(names));
+            }
+            Expr::Between { expr, low, high, .. } => {
+                expr.collect_columns(names);
+                low.collect_columns(names);
+                high.collect_columns(names);
+            }
+        }
+    }
+
+    /// The expression with every column name replaced by `rename(name)`,
+    /// stopping at the first error.
+    pub fn rename_columns(&self, rename: &mut dyn FnMut(&str) -> Result<String>) -> Result<Expr> {
+        let mut boxed = |expr: &Expr| expr.rename_columns(rename).map(Box::new);
+
+        let expr = match self {
+            Expr::Column(name) => Expr::Column(rename(name)?),
+            Expr::Literal(_) => self.clone(),
+            Expr::Compare { op, left, right } => Expr::Compare { op: *op, left: boxed(left)?, right: boxed(right)? },
+            Expr::And(left, right) => Expr::And(boxed(left)?, boxed(right)?),
+            Expr::Or(left, right) => Expr::Or(boxed(left)?, boxed(right)?),
+            Expr::Not(expr) => Expr::Not(boxed(expr)?),
+            Expr::In { expr, list, negated } => Expr::In {
+                expr: boxed(expr)?,
+                list: list.iter().map(|item| boxed(item).map(|item| *item)).collect::<Result<_>>()?,
+                negated: *negated,
+            },
+            Expr::IsNull { expr, negated } => Expr::IsNull { expr: boxed(expr)?, negated: *negated },
+            Expr::Between { expr, low, high, negated } => Expr::Between {
+                expr: boxed(expr)?,
+                low: boxed(low)?,
+                high: boxed(high)?,
+                negated: *negated,
+            },
+            Expr::Like { expr, pattern, negated } => Expr::Like {
+                expr: boxed(expr)?,
+                pattern: pattern.clone(),
+                negated: *negated,
+            },
+        };
+
+        Ok(expr)
+    }
+
+    /// Checks the expression against the types of the columns it reads and
+    /// returns it with literals coerced to the column they are compared
+    /// with: text literals parse as timestamps, UUIDs or hex binary (see
+    /// [`Value::parse`]). Comparing values that have no common order, such
+    /// as text with numbers, is an error.
+    pub fn bind(&self, column_type: &dyn Fn(&str) -> Option<ColumnType>) -> Result<Expr> {
+        let bound = self.coerce(column_type)?;
+        bound.check_boolean(column_type)?;
+        Ok(bound)
+    }
+
+    fn coerce(&self, column_type: &dyn Fn(&str) -> Option<ColumnType>) -> Result<Expr> {
+        // Coerces `literal` to the type of the column on the other side
+        let coerce_to = |literal: &Expr, other: &Expr| -> Result<Expr> {
+            let (Expr::Literal(Value::String(text)), Expr::Column(name)) = (literal, other) else {
+                return literal.coerce(column_type);
+            };
+            match column_type(name) {
+                Some(ColumnType::Utf8) | None => Ok(literal.clone()),
+                Some(target) if target.is_nested() => Ok(literal.clone()),
+                Some(target) => Value::parse(text, target).map(Expr::Literal).map_err(invalid),
+            }
+        };
+
+        let expr = match self {
+            Expr::Column(name) => {
+                column_type(name).ok_or_else(|| Error::new(ErrorKind::NotFound, format!("column '{name}' not found")))?;
+                self.clone()
+            }
+            Expr::Literal(_) => self.clone(),
+            Expr::Compare { op, left, right } => Expr::Compare {
+                op: *op,
+                left: Box::new(coerce_to(left, right)?),
+                right: Box::new(coerce_to(right, left)?),
+            },
+            Expr::And(left, right) => Expr::And(Box::new(left.coerce(column_type)?), Box::new(right.coerce(column_type)?)),
+            Expr::Or(left, right) => Expr::Or(Box::new(left.coerce(column_type)?), Box::new(right.coerce(column_type)?)),
+            Expr::Not(expr) => Expr::Not(Box::new(expr.coerce(column_type)?)),
+            Expr::In { expr, list, negated } => Expr::In {
+                expr: Box::new(expr.coerce(column_type)?),
+                list: list.iter().map(|item| coerce_to(item, expr)).collect::<Result<_>>()?,
+                negated: *negated,
+            },
+            Expr::IsNull { expr, negated } => Expr::IsNull { expr: Box::new(expr.coerce(column_type)?), negated: *negated },
+            Expr::Between { expr, low, high, negated } => Expr::Between {
+                expr: Box::new(expr.coerce(column_type)?),
+                low: Box::new(coerce_to(low, expr)?),
+                high: Box::new(coerce_to(high, expr)?),
+                negated: *negated,
+            },
+            Expr::Like { expr, pattern, negated } => Expr::Like {
+                expr: Box::new(expr.coerce(column_type)?),
+                pattern: pattern.clone(),
+                negated: *negated,
+            },
+        };
+
+        Ok(expr)
+    }
+
+    /// Class of the values the expression produces.
+    fn class(&self, column_type: &dyn Fn(&str) -> Option<ColumnType>) -> Result<ValueClass> {
+        match self {
+            Expr::Column(name) => column_type(name)
+                .map(ValueClass::of_type)
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("column '{name}' not found"))),
+            Expr::Literal(value) => Ok(ValueClass::of_value(value)),
+            _ => {
+                self.check_boolean(column_type)?;
+                Ok(ValueClass::Bool)
+            }
+        }
+    }
+
+    /// Checks the expression yields booleans, type checking its operands.
+    fn check_boolean(&self, column_type: &dyn Fn(&str) -> Option<ColumnType>) -> Result<()> {
+        let comparable = |a: &Expr, b: &Expr| -> Result<()> {
+            let (left, right) = (a.class(column_type)?, b.class(column_type)?);
+            if !left.comparable(right) {
+                return Err(invalid(format!("cannot compare {a} with {b}")));
+            }
+            Ok(())
+        };
+
+        match self {
+            Expr::Column(_) | Expr::Literal(_) => match self.class(column_type)? {
+                ValueClass::Bool | ValueClass::Any => Ok(()),
+                _ => Err(invalid(format!("{self} is not a boolean"))),
+            },
+            Expr::Compare { left, right, .. } => comparable(left, right),
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                left.check_boolean(column_type)?;
+                right.check_boolean(column_type)
+            }
+            Expr::Not(expr) => expr.check_boolean(column_type),
+            Expr::In { expr, list, .. } => list.iter().try_for_each(|item| comparable(expr, item)),
+            Expr::IsNull { expr, .. } => expr.class(column_type).map(|_| ()),
+            Expr::Between { expr, low, high, .. } => {
+                comparable(expr, low)?;
+                comparable(expr, high)
+            }
+            Expr::Like { expr, .. } => match expr.class(column_type)? {
+                ValueClass::Text | ValueClass::Any => Ok(()),
+                _ => Err(invalid(format!("LIKE needs a Utf8 operand, {expr} is not text"))),
+            },
+        }
+    }
+}
+
+impl Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        Expr::Not(Box::new(self))
+    }
+}
+
+fn write_literal(f: &mut fmt::Formatter<'_>, value: &Value) -> fmt::Result {
+    match value {
+        Value::Null => f.write_str("NULL"),
+        Value::Bool(true) => f.write_str("TRUE"),
+        Value::Bool(false) => f.write_str("FALSE"),
+        Value::String(s) => write!(f, "'{}'", s.replace('\'', "''")),
+        Value::Timestamp(v) => write!(f, "TIMESTAMP '{}'", format_timestamp(*v)),
+        Value::Uuid(_) => write!(f, "'{value}'"),
+        _ => write!(f, "{value}"),
+    }
+}
+
+/// Writes an operand, parenthesizing the boolean connectives.
+fn write_operand(f: &mut fmt::Formatter<'_>, expr: &Expr) -> fmt::Result {
+    match expr {
+        Expr::And(..) | Expr::Or(..) => write!(f, "({expr})"),
+        _ => write!(f, "{expr}"),
+    }
+}
+
+/// SQL text of the expression.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let not = |negated: bool| if negated { "NOT " } else { "" };
+
+        match self {
+            Expr::Column(name) => f.write_str(name),
+            Expr::Literal(value) => write_literal(f, value),
+            Expr::Compare { op, left, right } => {
+                write_operand(f, left)?;
+                write!(f, " {} ", op.symbol())?;
+                write_operand(f, right)
+            }
+            Expr::And(left, right) => {
+                write_operand(f, left)?;
+                f.write_str(" AND ")?;
+                write_operand(f, right)
+            }
+            Expr::Or(left, right) => {
+                write_operand(f, left)?;
+                f.write_str(" OR ")?;
+                write_operand(f, right)
+            }
+            Expr::Not(expr) => write!(f, "NOT ({expr})"),
+            Expr::In { expr, list, negated } => {
+                write_operand(f, expr)?;
+                write!(f, " {}IN (", not(*negated))?;
+                for (i, item) in list.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write_operand(f, item)?;
+                }
+                f.write_str(")")
+            }
+            Expr::IsNull { expr, negated } => {
+                write_operand(f, expr)?;
+                write!(f, " IS {}NULL", not(*negated))
+            }
+            Expr::Between { expr, low, high, negated } => {
+                write_operand(f, expr)?;
+                write!(f, " {}BETWEEN ", not(*negated))?;
+                write_operand(f, low)?;
+                f.write_str(" AND ")?;
+                write_operand(f, high)
+            }
+            Expr::Like { expr, pattern, negated } => {
+                write_operand(f, expr)?;
+                write!(f, " {}LIKE ", not(*negated))?;
+                write_literal(f, &Value::String(pattern.clone()))
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::io::{Error, ErrorKind, Result};
+use crate::metadata::value::Value;
+use crate::query::batch::Batch;
+use crate::query::expr::Expr;
+use crate::query::predicate::{select, take};
+use crate::query::stream::BatchStream;
+
+/// Keeps the rows of `input` where `predicate` is true, for predicates that
+/// could not be evaluated in a scan, such as ones reading both sides of a
+/// join. Batches left empty are skipped.
+pub fn filter<'a>(input: BatchStream<'a>, predicate: &Expr) -> Result<BatchStream<'a>> {
+    let position = |name: &str| input.position(name);
+    let predicate = predicate.bind(&|name| position(name).map(|i| input.column_types[i]))?;
+
+    let mut read = Vec::new();
+    for name in predicate.columns() {
+        let index = position(name).ok_or_else(|| Error::new(ErrorKind::NotFound, format!("column '{name}' not found")))?;
+        read.push((name.to_string(), index));
+    }
+
+    let column_names = input.column_names.clone();
+    let column_types = input.column_types.clone();
+    let estimated_rows = input.estimated_rows;
+
+    let batches = input.filter_map(move |batch| {
+        let apply = |batch: Batch| -> Result<Option<Batch>> {
+            let columns: Vec<(&str, &[Value])> = read
+                .iter()
+                .map(|(name, index)| (name.as_str(), batch.columns[*index].as_slice()))
+                .collect();
+            let selection = select(&predicate, &columns, batch.row_count)?;
+            if selection.is_empty() {
+                return Ok(None);
+            }
+
+            let columns = batch.columns.into_iter().map(|values| take(values, &selection)).collect();
+            Ok(Some(Batch { row_count: selection.len(), columns }))
+        };
+        batch.and_then(apply).transpose()
+    });
+
+    Ok(BatchStream::new(column_names, column_types, estimated_rows, batches))
+}
@@ -0,0 +1,455 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+use crate::engine::database::Database;
+use crate::engine::table_scan::TableScan;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+use crate::query::aggregate::{Accumulator, Aggregate};
+use crate::query::batch::Batch;
+use crate::query::expr::Expr;
+use crate::query::hash_key::{encode_key, partition_of};
+use crate::query::parallel::aggregate_parallel;
+use crate::query::spill::SpillFile;
+use crate::query::vector::VectorBatch;
+
+/// Number of files the groups are hash-partitioned over when spilling.
+const SPILL_PARTITIONS: usize = 16;
+
+/// Bookkeeping cost of a group beyond its key and state, for budgeting.
+const GROUP_OVERHEAD: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct AggregateOptions {
+    /// Estimated bytes of group state held in memory before spilling.
+    pub memory_budget: usize,
+    /// Directory for spill files.
+    pub spill_dir: PathBuf,
+    /// Rows per output batch.
+    pub batch_size: usize,
+    /// Worker threads [`aggregate_scan`] spreads the scan over, see
+    /// [`aggregate_parallel`]. 1 scans on the calling thread.
+    pub threads: usize,
+}
+
+impl Default for AggregateOptions {
+    fn default() -> Self {
+        Self {
+            memory_budget: 64 * 1024 * 1024,
+            spill_dir: std::env::temp_dir(),
+            batch_size: 4096,
+            threads: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AggregateStats {
+    pub rows_in: u64,
+    /// Times the in-memory groups were written out to the spill files.
+    pub spills: usize,
+    pub spilled_groups: u64,
+}
+
+struct Group {
+    key: Vec<Value>,
+    accumulators: Vec<Accumulator>,
+}
+
+/// Groups in first-seen order, indexed by their encoded key.
+#[derive(Default)]
+struct GroupTable {
+    index: HashMap<Vec<u8>, usize>,
+    groups: Vec<Group>,
+    memory: usize,
+}
+
+impl GroupTable {
+    /// Index of the group for the encoded `key`, created from `new` if absent.
+    fn group(&mut self, key: &[u8], new: impl FnOnce() -> Group) -> usize {
+        if let Some(&index) = self.index.get(key) {
+            return index;
+        }
+
+        let group = new();
+        self.memory += 2 * key.len() + GROUP_OVERHEAD + group.accumulators.iter().map(|a| a.size()).sum::<usize>();
+        self.index.insert(key.to_vec(), self.groups.len());
+        self.groups.push(group);
+        self.groups.len() - 1
+    }
+}
+
+/// `GROUP BY` by hashing: rows are pushed batch by batch into a table of
+/// groups, each holding one [`Accumulator`] per aggregate.
+///
+/// When the groups outgrow the memory budget they are hash-partitioned by
+/// key into spill files, partial states and all, and the table starts over.
+/// At the end each partition is merged back on its own, so a group's partial
+/// states always meet, and memory holds one partition's groups at a time.
+/// With no `GROUP BY` columns the operator computes global aggregates,
+/// producing one row even for empty input.
+pub struct HashAggregate {
+    key_columns: Vec<usize>,
+    /// Input column of each aggregate, `None` for `COUNT(*)`.
+    aggregate_columns: Vec<Option<usize>>,
+    /// A fresh accumulator per aggregate, cloned for new groups.
+    templates: Vec<Accumulator>,
+    table: GroupTable,
+    partitions: Vec<SpillFile>,
+    options: AggregateOptions,
+    key_buf: Vec<u8>,
+    /// The `GROUP BY` columns, then the aggregates.
+    pub column_names: Vec<String>,
+    pub column_types: Vec<ColumnType>,
+    pub stats: AggregateStats,
+}
+
+impl HashAggregate {
+    /// Creates the operator over input batches whose columns are named
+    /// `input_names` and typed `input_types`.
+    pub fn new(
+        input_names: &[String],
+        input_types: &[ColumnType],
+        group_by: &[&str],
+        aggregates: &[Aggregate],
+        options: AggregateOptions,
+    ) -> Result<Self> {
+        let position = |name: &str| {
+            input_names.iter().position(|n| n == name).ok_or_else(|| {
+                Error::new(ErrorKind::NotFound, format!("column '{name}' not found"))
+            })
+        };
+
+        let mut column_names = Vec::new();
+        let mut column_types = Vec::new();
+
+        let mut key_columns = Vec::with_capacity(group_by.len());
+        for name in group_by {
+            let index = position(name)?;
+            key_columns.push(index);
+            column_names.push(name.to_string());
+            column_types.push(input_types[index]);
+        }
+
+        let mut aggregate_columns = Vec::with_capacity(aggregates.len());
+        let mut templates = Vec::with_capacity(aggregates.len());
+        for aggregate in aggregates {
+            let index = aggregate.column.as_deref().map(position).transpose()?;
+            let input_type = index.map(|i| input_types[i]);
+
+            templates.push(aggregate.accumulator(input_type)?);
+            aggregate_columns.push(index);
+            column_names.push(aggregate.output_name());
+            column_types.push(aggregate.output_type(input_type)?);
+        }
+
+        Ok(Self {
+            key_columns,
+            aggregate_columns,
+            templates,
+            table: GroupTable::default(),
+            partitions: Vec::new(),
+            options,
+            key_buf: Vec::new(),
+            column_names,
+            column_types,
+            stats: AggregateStats::default(),
+        })
+    }
+
+    /// Folds a batch of input rows into the groups, spilling if they have
+    /// outgrown the memory budget.
+    pub fn push(&mut self, columns: &[Vec<Value>], row_count: usize) -> Result<()> {
+        // Assign every row its group first, then update one aggregate at a time
+        let Self { key_columns, templates, table, key_buf, .. } = self;
+        let group_ids: Vec<usize> = (0..row_count)
+            .map(|row| {
+                encode_key(key_columns.iter().map(|&c| &columns[c][row]), key_buf);
+                table.group(key_buf, || Group {
+                    key: key_columns.iter().map(|&c| columns[c][row].clone()).collect(),
+                    accumulators: templates.clone(),
+                })
+            })
+            .collect();
+
+        for (a, column) in self.aggregate_columns.iter().enumerate() {
+            for (row, &group) in group_ids.iter().enumerate() {
+                let value = column.map_or(&Value::Null, |c| &columns[c][row]);
+                self.table.groups[group].accumulators[a].update(value)?;
+            }
+        }
+
+        self.stats.rows_in += row_count as u64;
+        if self.table.memory > self.options.memory_budget {
+            self.spill()?;
+        }
+
+        Ok(())
+    }
+
+    /// Folds the selected rows of a vector batch into the groups, as
+    /// [`HashAggregate::push`] does rows. Without `GROUP BY` every aggregate
+    /// folds the whole batch at once (see [`Accumulator::update_vector`]).
+    pub fn push_vectors(&mut self, batch: &VectorBatch) -> Result<()> {
+        let all: Vec<u32>;
+        let rows = match &batch.selection {
+            Some(selection) => selection.as_slice(),
+            None => {
+                all = (0..batch.row_count as u32).collect();
+                &all
+            }
+        };
+
+        let Self { key_columns, templates, table, key_buf, .. } = self;
+        if key_columns.is_empty() {
+            encode_key(std::iter::empty(), key_buf);
+            let group = table.group(key_buf, || Group { key: Vec::new(), accumulators: templates.clone() });
+            for (a, column) in self.aggregate_columns.iter().enumerate() {
+                let vector = column.map(|c| &batch.columns[c]);
+                self.table.groups[group].accumulators[a].update_vector(vector, rows)?;
+            }
+        } else {
+            let mut key = Vec::with_capacity(key_columns.len());
+            let group_ids: Vec<usize> = rows
+                .iter()
+                .map(|&row| {
+                    key.clear();
+                    key.extend(key_columns.iter().map(|&c| batch.columns[c].value(row as usize)));
+                    encode_key(key.iter(), key_buf);
+                    table.group(key_buf, || Group { key: key.clone(), accumulators: templates.clone() })
+                })
+                .collect();
+
+            for (a, column) in self.aggregate_columns.iter().enumerate() {
+                for (&row, &group) in rows.iter().zip(&group_ids) {
+                    let value = column.map_or(Value::Null, |c| batch.columns[c].value(row as usize));
+                    self.table.groups[group].accumulators[a].update(&value)?;
+                }
+            }
+        }
+
+        self.stats.rows_in += rows.len() as u64;
+        if self.table.memory > self.options.memory_budget {
+            self.spill()?;
+        }
+
+        Ok(())
+    }
+
+    /// Folds in the groups of another aggregate built with the same columns
+    /// and aggregates over other input rows, such as a partial aggregate of
+    /// another thread. Its spilled partitions are appended to the matching
+    /// ones here, to be merged in [`HashAggregate::finish`].
+    pub fn merge(&mut self, mut other: HashAggregate) -> Result<()> {
+        if !other.partitions.is_empty() {
+            other.spill()?;
+            if self.partitions.is_empty() {
+                self.partitions = std::mem::take(&mut other.partitions);
+            } else {
+                for (partition, mut spilled) in self.partitions.iter_mut().zip(other.partitions.drain(..)) {
+                    for row in spilled.read()? {
+                        partition.write_row(&row?)?;
+                    }
+                }
+            }
+        }
+
+        for group in std::mem::take(&mut other.table).groups {
+            encode_key(group.key.iter(), &mut self.key_buf);
+            let templates = &self.templates;
+            let key = &group.key;
+            let index = self.table.group(&self.key_buf, || Group { key: key.clone(), accumulators: templates.clone() });
+            for (accumulator, partial) in self.table.groups[index].accumulators.iter_mut().zip(&group.accumulators) {
+                accumulator.merge(partial)?;
+            }
+        }
+
+        self.stats.rows_in += other.stats.rows_in;
+        self.stats.spills += other.stats.spills;
+        self.stats.spilled_groups += other.stats.spilled_groups;
+        if self.table.memory > self.options.memory_budget {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Writes every in-memory group to its partition's spill file as its key
+    /// followed by the states of its accumulators, then empties the table.
+    fn spill(&mut self) -> Result<()> {
+        if self.partitions.is_empty() {
+            for _ in 0..SPILL_PARTITIONS {
+                self.partitions.push(SpillFile::create(&self.options.spill_dir)?);
+            }
+        }
+
+        let table = std::mem::take(&mut self.table);
+        let mut row = Vec::new();
+        for (key, index) in table.index {
+            let group = &table.groups[index];
+            row.clear();
+            row.extend(group.key.iter().cloned());
+            row.extend(group.accumulators.iter().flat_map(|a| a.state()));
+            self.partitions[partition_of(&key, SPILL_PARTITIONS)].write_row(&row)?;
+        }
+
+        self.stats.spills += 1;
+        self.stats.spilled_groups += table.groups.len() as u64;
+        Ok(())
+    }
+
+    /// Ends the input and returns the groups as batches of output rows.
+    pub fn finish(mut self) -> Result<AggregateResults> {
+        if self.key_columns.is_empty() && self.table.groups.is_empty() && self.partitions.is_empty() {
+            let templates = &self.templates;
+            self.table.group(&[], || Group { key: Vec::new(), accumulators: templates.clone() });
+        }
+
+        if !self.partitions.is_empty() {
+            self.spill()?;
+        }
+
+        Ok(AggregateResults {
+            column_names: self.column_names,
+            column_types: self.column_types,
+            groups: self.table.groups.into_iter(),
+            partitions: self.partitions.into(),
+            key_len: self.key_columns.len(),
+            templates: self.templates,
+            batch_size: self.options.batch_size.max(1),
+            stats: self.stats,
+        })
+    }
+}
+
+/// Output of a [`HashAggregate`]: one row per group, the `GROUP BY` values
+/// followed by the aggregate results, in batches of up to `batch_size` rows.
+/// Groups come out in no particular order.
+pub struct AggregateResults {
+    pub column_names: Vec<String>,
+    pub column_types: Vec<ColumnType>,
+    groups: std::vec::IntoIter<Group>,
+    partitions: VecDeque<SpillFile>,
+    key_len: usize,
+    templates: Vec<Accumulator>,
+    batch_size: usize,
+    pub stats: AggregateStats,
+}
+
+impl AggregateResults {
+    /// Merges the partial states of a spilled partition into its groups.
+    fn load_partition(&mut self, mut partition: SpillFile) -> Result<Vec<Group>> {
+        let mut table = GroupTable::default();
+        let mut key_buf = Vec::new();
+
+        for row in partition.read()? {
+            let row = row?;
+            encode_key(row[..self.key_len].iter(), &mut key_buf);
+
+            let mut partial = self.templates.clone();
+            let mut state = &row[self.key_len..];
+            for accumulator in &mut partial {
+                let (head, rest) = state.split_at(accumulator.state_len());
+                accumulator.restore(head);
+                state = rest;
+            }
+
+            let templates = &self.templates;
+            let index = table.group(&key_buf, || Group {
+                key: row[..self.key_len].to_vec(),
+                accumulators: templates.clone(),
+            });
+            for (accumulator, partial) in table.groups[index].accumulators.iter_mut().zip(&partial) {
+                accumulator.merge(partial)?;
+            }
+        }
+
+        Ok(table.groups)
+    }
+
+    fn next_batch(&mut self) -> Result<Option<Batch>> {
+        while self.groups.len() == 0 {
+            let Some(partition) = self.partitions.pop_front() else {
+                return Ok(None);
+            };
+            self.groups = self.load_partition(partition)?.into_iter();
+        }
+
+        let rows = self.groups
+            .by_ref()
+            .take(self.batch_size)
+            .map(|group| {
+                let mut row = group.key;
+                row.extend(group.accumulators.iter().map(|a| a.finish()));
+                row
+            })
+            .collect();
+
+        Ok(Some(Batch::from_rows(self.column_names.len(), rows)))
+    }
+}
+
+impl Iterator for AggregateResults {
+    type Item = Result<Batch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_batch().transpose()
+    }
+}
+
+/// Runs a [`HashAggregate`] over every vector batch of `scan`, on
+/// `options.threads` worker threads when more than one.
+pub fn aggregate_scan(
+    mut scan: TableScan,
+    group_by: &[&str],
+    aggregates: &[Aggregate],
+    options: AggregateOptions,
+) -> Result<AggregateResults> {
+    if options.threads > 1 {
+        let (names, threads) = (scan.column_names.clone(), options.threads);
+        let (aggregate, _) = aggregate_parallel(scan, &names, group_by, aggregates, options, threads)?;
+        return aggregate.finish();
+    }
+
+    let input_types: Vec<ColumnType> = scan.column_schemas.iter().map(|c| c.column_type).collect();
+    let mut aggregate = HashAggregate::new(&scan.column_names, &input_types, group_by, aggregates, options)?;
+
+    while let Some(batch) = scan.next_vectors() {
+        aggregate.push_vectors(&batch?)?;
+    }
+
+    aggregate.finish()
+}
+
+/// Aggregates the rows of a table where `filter` is true (all rows when
+/// `None`), scanning only the columns the grouping and aggregates read.
+pub fn aggregate_table(
+    db: &Database,
+    table_name: &str,
+    group_by: &[&str],
+    aggregates: &[Aggregate],
+    filter: Option<Expr>,
+    options: AggregateOptions,
+) -> Result<AggregateResults> {
+    let mut columns: Vec<&str> = Vec::new();
+    for name in group_by.iter().copied().chain(aggregates.iter().filter_map(|a| a.column.as_deref())) {
+        if !columns.contains(&name) {
+            columns.push(name);
+        }
+    }
+
+    // An empty projection would scan every column, COUNT(*) needs just one
+    if columns.is_empty() {
+        let table_id = db.catalog.tables_by_name
+            .get(table_name)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "table not found"))?;
+        if let Some(first) = db.catalog.columns_by_table.get(table_id).and_then(|c| c.first()) {
+            columns.push(&first.name);
+        }
+    }
+
+    let scan = match filter {
+        Some(filter) => db.scan_where(table_name, &columns, filter)?,
+        None => db.scan(table_name, &columns)?,
+    };
+    aggregate_scan(scan, group_by, aggregates, options)
+}
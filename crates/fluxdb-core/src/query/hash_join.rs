@@ -0,0 +1,494 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+use crate::engine::database::Database;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+use crate::query::batch::{value_size, Batch};
+use crate::query::expr::Expr;
+use crate::query::hash_key::{encode_key, partition_of};
+use crate::query::spill::{SpillFile, SpillReader};
+use crate::query::stream::BatchStream;
+
+/// Number of partitions per side when the build side does not fit in memory.
+const JOIN_PARTITIONS: usize = 16;
+
+/// Bookkeeping cost of a build row beyond its values.
+const ROW_OVERHEAD: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinType {
+    Inner,
+    /// Every left row, with nulls for the right columns when nothing matches.
+    Left,
+}
+
+#[derive(Debug, Clone)]
+pub struct JoinOptions {
+    /// Estimated bytes of build rows held in memory before falling back to
+    /// partitioning both sides to disk.
+    pub memory_budget: usize,
+    /// Directory for partition files.
+    pub spill_dir: PathBuf,
+    /// Rows per output batch.
+    pub batch_size: usize,
+}
+
+impl Default for JoinOptions {
+    fn default() -> Self {
+        Self {
+            memory_budget: 64 * 1024 * 1024,
+            spill_dir: std::env::temp_dir(),
+            batch_size: 4096,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JoinStats {
+    /// Whether the left input was the build side.
+    pub build_left: bool,
+    pub build_rows: u64,
+    pub probe_rows: u64,
+    pub rows_out: u64,
+    /// Partitions per side of the grace-hash fallback, 0 when the build
+    /// side fit in memory.
+    pub partitions: usize,
+}
+
+/// How a key column's values are normalized so equal keys of different
+/// column types encode equally.
+#[derive(Debug, Clone, Copy)]
+enum KeyCast {
+    None,
+    Int64,
+    Float64,
+}
+
+impl KeyCast {
+    fn apply(&self, value: &Value) -> Value {
+        match self {
+            KeyCast::Int64 => value.as_i64().map_or(Value::Null, Value::Int64),
+            KeyCast::Float64 => value.as_f64().map_or(Value::Null, Value::Float64),
+            KeyCast::None => value.clone(),
+        }
+    }
+}
+
+fn key_cast(left: ColumnType, right: ColumnType) -> Option<KeyCast> {
+    use ColumnType::*;
+
+    match (left, right) {
+        (Integer32 | Integer64, Integer32 | Integer64) => Some(KeyCast::Int64),
+        (Integer32 | Integer64 | Float32 | Float64, Integer32 | Integer64 | Float32 | Float64) => Some(KeyCast::Float64),
+        (List | Struct, _) | (_, List | Struct) => None,
+        (left, right) if left == right => Some(KeyCast::None),
+        _ => None,
+    }
+}
+
+/// Key columns of one side of the join.
+struct KeySpec {
+    columns: Vec<usize>,
+    casts: Vec<KeyCast>,
+}
+
+impl KeySpec {
+    /// Encodes the key of `row` into `buf`, `false` when part of it is null:
+    /// null keys match nothing.
+    fn encode(&self, row: &[Value], buf: &mut Vec<u8>) -> bool {
+        let values: Vec<Value> = self.columns.iter().zip(&self.casts).map(|(&c, cast)| cast.apply(&row[c])).collect();
+        if values.iter().any(Value::is_null) {
+            return false;
+        }
+        encode_key(values.iter(), buf);
+        true
+    }
+}
+
+/// The build side's rows, indexed by key.
+#[derive(Default)]
+struct BuildTable {
+    rows: Vec<Vec<Value>>,
+    index: HashMap<Vec<u8>, Vec<usize>>,
+    matched: Vec<bool>,
+    memory: usize,
+}
+
+impl BuildTable {
+    /// Adds a row; rows without a key are kept only to be emitted unmatched.
+    fn insert(&mut self, key: Option<&[u8]>, row: Vec<Value>) {
+        self.memory += ROW_OVERHEAD + row.iter().map(value_size).sum::<usize>();
+        if let Some(key) = key {
+            self.memory += key.len();
+            self.index.entry(key.to_vec()).or_default().push(self.rows.len());
+        }
+        self.rows.push(row);
+        self.matched.push(false);
+    }
+}
+
+fn rows_of(batch: Batch) -> impl Iterator<Item = Vec<Value>> {
+    let mut columns: Vec<_> = batch.columns.into_iter().map(|c| c.into_iter()).collect();
+    (0..batch.row_count).map(move |_| columns.iter_mut().map(|c| c.next().unwrap()).collect())
+}
+
+/// Where probe rows come from.
+enum ProbeSource<'a> {
+    /// Straight from the probe input, when the build side fit in memory.
+    Stream { stream: BatchStream<'a>, rows: VecDeque<Vec<Value>> },
+    /// From the probe partition matching the loaded build partition.
+    Partition(SpillReader),
+    Done,
+}
+
+/// Equi-join of two inputs by hashing.
+///
+/// The smaller input by estimated size (rows times columns) is loaded
+/// into a hash table and the other streamed past it. For left joins either
+/// side may be the build side: when it is the left one, build rows track
+/// whether they matched and the unmatched ones come out at the end.
+///
+/// If the build side outgrows the memory budget, the join falls back to
+/// grace hashing: both sides are hash-partitioned by key into spill files
+/// and each pair of partitions is joined in memory in turn.
+///
+/// Output rows hold the left columns, then the right ones, under the
+/// inputs' column names: qualify the inputs (see [`BatchStream::qualified`])
+/// when their names overlap.
+pub struct JoinResults<'a> {
+    pub column_names: Vec<String>,
+    pub column_types: Vec<ColumnType>,
+    join_type: JoinType,
+    build_keys: KeySpec,
+    probe_keys: KeySpec,
+    build_width: usize,
+    probe_width: usize,
+    build: BuildTable,
+    probe: ProbeSource<'a>,
+    /// Remaining partition pairs, build then probe, for the grace fallback.
+    partitions: VecDeque<(SpillFile, SpillFile)>,
+    /// Files of the partition being joined, kept until it is done.
+    current_partition: Option<(SpillFile, SpillFile)>,
+    output: Vec<Vec<Value>>,
+    key_buf: Vec<u8>,
+    batch_size: usize,
+    pub stats: JoinStats,
+}
+
+/// Joins `left` and `right` on pairs of equal (left column, right column).
+/// Numeric key columns of different types compare by value.
+pub fn hash_join<'a>(
+    left: BatchStream<'a>,
+    right: BatchStream<'a>,
+    on: &[(&str, &str)],
+    join_type: JoinType,
+    options: JoinOptions,
+) -> Result<JoinResults<'a>> {
+    if on.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, "a hash join needs at least one key"));
+    }
+
+    let position = |input: &BatchStream, name: &str| {
+        input.position(name).ok_or_else(|| Error::new(ErrorKind::NotFound, format!("column '{name}' not found")))
+    };
+    let type_of = |input: &BatchStream, index: usize| input.column_types[index];
+
+    let mut left_keys = KeySpec { columns: Vec::new(), casts: Vec::new() };
+    let mut right_keys = KeySpec { columns: Vec::new(), casts: Vec::new() };
+    for (left_name, right_name) in on {
+        let (l, r) = (position(&left, left_name)?, position(&right, right_name)?);
+        let cast = key_cast(type_of(&left, l), type_of(&right, r)).ok_or_else(|| Error::new(
+            ErrorKind::InvalidInput,
+            format!("cannot join {left_name} ({:?}) with {right_name} ({:?})", type_of(&left, l), type_of(&right, r)),
+        ))?;
+
+        left_keys.columns.push(l);
+        left_keys.casts.push(cast);
+        right_keys.columns.push(r);
+        right_keys.casts.push(cast);
+    }
+
+    let column_names = [left.column_names.as_slice(), &right.column_names].concat();
+    let column_types = [left.column_types.as_slice(), &right.column_types].concat();
+
+    let size = |input: &BatchStream| input.estimated_rows * input.column_names.len().max(1) as u64;
+    let build_left = size(&left) < size(&right);
+
+    let (build, probe, build_keys, probe_keys) = match build_left {
+        true => (left, right, left_keys, right_keys),
+        false => (right, left, right_keys, left_keys),
+    };
+
+    let mut results = JoinResults {
+        column_names,
+        column_types,
+        join_type,
+        build_keys,
+        probe_keys,
+        build_width: build.column_names.len(),
+        probe_width: probe.column_names.len(),
+        build: BuildTable::default(),
+        probe: ProbeSource::Done,
+        partitions: VecDeque::new(),
+        current_partition: None,
+        output: Vec::new(),
+        key_buf: Vec::new(),
+        batch_size: options.batch_size.max(1),
+        stats: JoinStats { build_left, ..JoinStats::default() },
+    };
+
+    results.load_build(build, probe, &options)?;
+    Ok(results)
+}
+
+impl<'a> JoinResults<'a> {
+    /// Whether build rows that match nothing are still output.
+    fn keep_unmatched_build(&self) -> bool {
+        self.join_type == JoinType::Left && self.stats.build_left
+    }
+
+    fn keep_unmatched_probe(&self) -> bool {
+        self.join_type == JoinType::Left && !self.stats.build_left
+    }
+
+    /// Reads the build side into memory, or partitions both sides if it
+    /// does not fit.
+    fn load_build(&mut self, build: BatchStream<'a>, probe: BatchStream<'a>, options: &JoinOptions) -> Result<()> {
+        let keep_unmatched = self.keep_unmatched_build();
+        let mut build_parts: Vec<SpillFile> = Vec::new();
+
+        for batch in build {
+            for row in rows_of(batch?) {
+                self.stats.build_rows += 1;
+                let has_key = self.build_keys.encode(&row, &mut self.key_buf);
+                if !has_key && !keep_unmatched {
+                    continue;
+                }
+
+                // Key-less rows go to partition 0, where they come out unmatched
+                if !build_parts.is_empty() {
+                    let part = if has_key { partition_of(&self.key_buf, JOIN_PARTITIONS) } else { 0 };
+                    build_parts[part].write_row(&row)?;
+                    continue;
+                }
+
+                self.build.insert(has_key.then_some(self.key_buf.as_slice()), row);
+                if self.build.memory > options.memory_budget {
+                    build_parts = self.spill_build(options)?;
+                }
+            }
+        }
+
+        if build_parts.is_empty() {
+            self.probe = ProbeSource::Stream { stream: probe, rows: VecDeque::new() };
+            return Ok(());
+        }
+
+        let keep_unmatched = self.keep_unmatched_probe();
+        let mut probe_parts = (0..JOIN_PARTITIONS)
+            .map(|_| SpillFile::create(&options.spill_dir))
+            .collect::<Result<Vec<_>>>()?;
+        for batch in probe {
+            for row in rows_of(batch?) {
+                self.stats.probe_rows += 1;
+                let part = match self.probe_keys.encode(&row, &mut self.key_buf) {
+                    true => partition_of(&self.key_buf, JOIN_PARTITIONS),
+                    false if keep_unmatched => 0,
+                    false => continue,
+                };
+                probe_parts[part].write_row(&row)?;
+            }
+        }
+
+        self.stats.partitions = JOIN_PARTITIONS;
+        self.partitions = build_parts.into_iter().zip(probe_parts).collect();
+        self.next_partition()
+    }
+
+    /// Moves the in-memory build rows into new partition files.
+    fn spill_build(&mut self, options: &JoinOptions) -> Result<Vec<SpillFile>> {
+        let mut parts = (0..JOIN_PARTITIONS)
+            .map(|_| SpillFile::create(&options.spill_dir))
+            .collect::<Result<Vec<_>>>()?;
+
+        for row in std::mem::take(&mut self.build).rows {
+            let part = match self.build_keys.encode(&row, &mut self.key_buf) {
+                true => partition_of(&self.key_buf, JOIN_PARTITIONS),
+                false => 0,
+            };
+            parts[part].write_row(&row)?;
+        }
+
+        Ok(parts)
+    }
+
+    /// Loads the next build partition and starts reading its probe partition.
+    fn next_partition(&mut self) -> Result<()> {
+        self.build = BuildTable::default();
+        self.current_partition = None;
+
+        let Some((mut build, mut probe)) = self.partitions.pop_front() else {
+            self.probe = ProbeSource::Done;
+            return Ok(());
+        };
+
+        for row in build.read()? {
+            let row = row?;
+            let has_key = self.build_keys.encode(&row, &mut self.key_buf);
+            self.build.insert(has_key.then_some(self.key_buf.as_slice()), row);
+        }
+
+        self.probe = ProbeSource::Partition(probe.read()?);
+        self.current_partition = Some((build, probe));
+        Ok(())
+    }
+
+    fn next_probe_row(&mut self) -> Result<Option<Vec<Value>>> {
+        match &mut self.probe {
+            ProbeSource::Stream { stream, rows } => {
+                while rows.is_empty() {
+                    match stream.next() {
+                        Some(batch) => rows.extend(rows_of(batch?)),
+                        None => return Ok(None),
+                    }
+                }
+                self.stats.probe_rows += 1;
+                Ok(rows.pop_front())
+            }
+            ProbeSource::Partition(reader) => reader.read_row(),
+            ProbeSource::Done => Ok(None),
+        }
+    }
+
+    /// A combined output row, left columns first.
+    fn emit(&mut self, build_row: Option<&[Value]>, probe_row: Option<&[Value]>) {
+        let nulls = |width: usize| vec![Value::Null; width];
+        let build_row = build_row.map_or_else(|| nulls(self.build_width), |r| r.to_vec());
+        let probe_row = probe_row.map_or_else(|| nulls(self.probe_width), |r| r.to_vec());
+
+        let (mut row, rest) = match self.stats.build_left {
+            true => (build_row, probe_row),
+            false => (probe_row, build_row),
+        };
+        row.extend(rest);
+        self.output.push(row);
+        self.stats.rows_out += 1;
+    }
+
+    fn probe_row(&mut self, row: Vec<Value>) {
+        let matches = match self.probe_keys.encode(&row, &mut self.key_buf) {
+            true => self.build.index.get(&self.key_buf).cloned().unwrap_or_default(),
+            false => Vec::new(),
+        };
+
+        if matches.is_empty() && self.keep_unmatched_probe() {
+            self.emit(None, Some(&row));
+        }
+        for index in matches {
+            self.build.matched[index] = true;
+            let build_row = std::mem::take(&mut self.build.rows[index]);
+            self.emit(Some(&build_row), Some(&row));
+            self.build.rows[index] = build_row;
+        }
+    }
+
+    /// Ends the current build table, emitting its unmatched rows if kept.
+    fn finish_build(&mut self) {
+        if self.keep_unmatched_build() {
+            let build = std::mem::take(&mut self.build);
+            for (row, matched) in build.rows.iter().zip(&build.matched) {
+                if !matched {
+                    self.emit(Some(row), None);
+                }
+            }
+        }
+    }
+
+    fn next_batch(&mut self) -> Result<Option<Batch>> {
+        while self.output.len() < self.batch_size {
+            if let Some(row) = self.next_probe_row()? {
+                self.probe_row(row);
+                continue;
+            }
+            if matches!(self.probe, ProbeSource::Done) {
+                break;
+            }
+
+            self.finish_build();
+            self.next_partition()?;
+        }
+
+        if self.output.is_empty() {
+            return Ok(None);
+        }
+
+        let rows = std::mem::take(&mut self.output);
+        Ok(Some(Batch::from_rows(self.column_names.len(), rows)))
+    }
+}
+
+impl Iterator for JoinResults<'_> {
+    type Item = Result<Batch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_batch().transpose()
+    }
+}
+
+/// A table to join: the columns to return and an optional filter on its rows.
+pub struct JoinTable<'s> {
+    pub table: &'s str,
+    /// Columns to return, every top-level column when empty. Join key
+    /// columns are added when missing.
+    pub columns: &'s [&'s str],
+    pub filter: Option<Expr>,
+}
+
+impl<'s> JoinTable<'s> {
+    pub fn new(table: &'s str, columns: &'s [&'s str]) -> Self {
+        Self { table, columns, filter: None }
+    }
+
+    pub fn with_filter(mut self, filter: Expr) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    fn input<'a>(self, db: &'a Database, keys: impl Iterator<Item = &'s str>) -> Result<BatchStream<'a>> {
+        let mut columns: Vec<&str> = self.columns.to_vec();
+        if !columns.is_empty() {
+            for key in keys {
+                if !columns.contains(&key) {
+                    columns.push(key);
+                }
+            }
+        }
+
+        let scan = match self.filter {
+            Some(filter) => db.scan_where(self.table, &columns, filter)?,
+            None => db.scan(self.table, &columns)?,
+        };
+        Ok(BatchStream::from(scan).qualified(self.table))
+    }
+}
+
+/// Joins two tables of `db`; output columns are named `table.column`.
+pub fn join_tables<'a, 's>(
+    db: &'a Database,
+    left: JoinTable<'s>,
+    right: JoinTable<'s>,
+    on: &[(&'s str, &'s str)],
+    join_type: JoinType,
+    options: JoinOptions,
+) -> Result<JoinResults<'a>> {
+    let qualified: Vec<(String, String)> = on
+        .iter()
+        .map(|(l, r)| (format!("{}.{l}", left.table), format!("{}.{r}", right.table)))
+        .collect();
+    let qualified: Vec<(&str, &str)> = qualified.iter().map(|(l, r)| (l.as_str(), r.as_str())).collect();
+
+    let left = left.input(db, on.iter().map(|(l, _)| *l))?;
+    let right = right.input(db, on.iter().map(|(_, r)| *r))?;
+    hash_join(left, right, &qualified, join_type, options)
+}
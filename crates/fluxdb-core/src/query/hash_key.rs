@@ -0,0 +1,25 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use crate::metadata::value::Value;
+use crate::query::spill::encode_value;
+
+/// Encodes a hash key so that equal keys encode equally: `-0.0` matches
+/// `0.0`, and every NaN every other.
+pub fn encode_key<'a>(values: impl Iterator<Item = &'a Value>, buf: &mut Vec<u8>) {
+    buf.clear();
+    for value in values {
+        match value {
+            Value::Float32(v) if *v == 0.0 => encode_value(&Value::Float32(0.0), buf),
+            Value::Float32(v) if v.is_nan() => encode_value(&Value::Float32(f32::NAN), buf),
+            Value::Float64(v) if *v == 0.0 => encode_value(&Value::Float64(0.0), buf),
+            Value::Float64(v) if v.is_nan() => encode_value(&Value::Float64(f64::NAN), buf),
+            value => encode_value(value, buf),
+        }
+    }
+}
+
+/// Partition of an encoded key among `partitions`, the same in every run.
+pub fn partition_of(key: &[u8], partitions: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish() as usize % partitions
+}
@@ -0,0 +1,120 @@
+/// A compiled SQL `LIKE` pattern.
+///
+/// Patterns made of a literal with `%` at one or both ends match with a
+/// single string search; anything else runs the general wildcard matcher.
+#[derive(Debug, Clone)]
+pub enum LikePattern {
+    Exact(String),
+    Prefix(String),
+    Suffix(String),
+    Contains(String),
+    General(Vec<LikeToken>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LikeToken {
+    Char(char),
+    /// `_`
+    AnyChar,
+    /// `%`
+    AnyString,
+}
+
+impl LikePattern {
+    pub fn new(pattern: &str) -> LikePattern {
+        let mut tokens = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            tokens.push(match c {
+                // A trailing backslash stands for itself
+                '\\' => LikeToken::Char(chars.next().unwrap_or('\\')),
+                '%' => LikeToken::AnyString,
+                '_' => LikeToken::AnyChar,
+                c => LikeToken::Char(c),
+            });
+        }
+        tokens.dedup_by(|a, b| *a == LikeToken::AnyString && *b == LikeToken::AnyString);
+
+        let leading = tokens.first() == Some(&LikeToken::AnyString);
+        let trailing = tokens.len() > usize::from(leading) && tokens.last() == Some(&LikeToken::AnyString);
+        let inner = &tokens[usize::from(leading)..tokens.len() - usize::from(trailing)];
+
+        let literal: Option<String> = inner
+            .iter()
+            .map(|t| match t {
+                LikeToken::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect();
+
+        match (literal, leading, trailing) {
+            (Some(text), false, false) => LikePattern::Exact(text),
+            (Some(text), false, true) => LikePattern::Prefix(text),
+            (Some(text), true, false) => LikePattern::Suffix(text),
+            (Some(text), true, true) => LikePattern::Contains(text),
+            (None, _, _) => LikePattern::General(tokens),
+        }
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        match self {
+            LikePattern::Exact(s) => text == s,
+            LikePattern::Prefix(s) => text.starts_with(s.as_str()),
+            LikePattern::Suffix(s) => text.ends_with(s.as_str()),
+            LikePattern::Contains(s) => text.contains(s.as_str()),
+            LikePattern::General(tokens) => matches_tokens(tokens, text),
+        }
+    }
+
+    /// The literal every matching string starts with, if any.
+    pub fn prefix(&self) -> Option<String> {
+        let prefix: String = match self {
+            LikePattern::Exact(s) | LikePattern::Prefix(s) => s.clone(),
+            LikePattern::Suffix(_) | LikePattern::Contains(_) => String::new(),
+            LikePattern::General(tokens) => tokens
+                .iter()
+                .map_while(|t| match t {
+                    LikeToken::Char(c) => Some(*c),
+                    _ => None,
+                })
+                .collect(),
+        };
+        (!prefix.is_empty()).then_some(prefix)
+    }
+}
+
+/// Wildcard matching that backtracks only to the most recent `%`, which is
+/// enough since a later `%` can absorb anything an earlier one could.
+fn matches_tokens(tokens: &[LikeToken], text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    let (mut t, mut c) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while c < chars.len() {
+        match tokens.get(t) {
+            Some(LikeToken::AnyString) => {
+                t += 1;
+                backtrack = Some((t, c));
+            }
+            Some(LikeToken::AnyChar) => {
+                t += 1;
+                c += 1;
+            }
+            Some(LikeToken::Char(expected)) if *expected == chars[c] => {
+                t += 1;
+                c += 1;
+            }
+            _ => match backtrack {
+                // Let the last `%` take one more character
+                Some((after, start)) => {
+                    t = after;
+                    c = start + 1;
+                    backtrack = Some((after, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    tokens[t..].iter().all(|token| *token == LikeToken::AnyString)
+}
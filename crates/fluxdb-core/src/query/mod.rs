@@ -0,0 +1,24 @@
+pub mod aggregate;
+pub mod batch;
+pub mod cost;
+pub mod execute;
+pub mod explain;
+pub mod expr;
+pub mod filter;
+pub mod hash_aggregate;
+pub mod hash_join;
+pub mod hash_key;
+pub mod like;
+pub mod optimizer;
+pub mod parallel;
+pub mod plan;
+pub mod predicate;
+pub mod profile;
+pub mod sort;
+pub mod sort_key;
+pub mod spill;
+pub mod statistics;
+pub mod stream;
+pub mod vector;
+pub mod vector_predicate;
+pub mod zone_map;
@@ -0,0 +1,350 @@
+use std::cmp::Ordering;
+use std::io::Result;
+use std::ops::Bound;
+use crate::engine::database::Database;
+use crate::metadata::value::Value;
+use crate::query::cost::CostModel;
+use crate::query::expr::{CompareOp, Expr};
+use crate::query::hash_join::JoinType;
+use crate::query::plan::{AccessPath, Plan};
+
+/// Rewrites a plan as [`plan_select`](crate::sql::planner::plan_select)
+/// builds it into the plan to run, with the same output:
+///
+/// 1. Each `AND`-ed part of a filter moves down to the lowest operator that
+///    has its columns, into the scans where it names one table. Parts naming
+///    the right side of a left join stay above it.
+/// 2. Scans read only the columns the operators above use.
+/// 3. Chains of inner joins are reordered, from the input with the fewest
+///    estimated rows, adding the input that keeps the estimated cost lowest.
+/// 4. Each filtered scan picks its access path: a full scan, a scan pruned
+///    by zone maps, or a lookup on a column whose chunks ascend, by cost.
+///
+/// Estimates come from the tables' statistics, see [`CostModel`].
+pub fn optimize(db: &Database, plan: Plan) -> Result<Plan> {
+    let output = plan.output_columns();
+    let plan = push_down_filters(plan)?;
+    let plan = prune_columns(plan, &output)?;
+
+    let mut model = CostModel::new(db);
+    let plan = reorder_joins(plan, &mut model)?;
+    let plan = choose_access_paths(db, plan, &mut model)?;
+
+    // Reordered joins change the column order of a plan that does not end
+    // in a projection
+    match plan.output_columns() == output {
+        true => Ok(plan),
+        false => Ok(Plan::Project {
+            input: Box::new(plan),
+            columns: output.into_iter().map(|name| (name.clone(), name)).collect(),
+        }),
+    }
+}
+
+fn push_down_filters(plan: Plan) -> Result<Plan> {
+    match plan {
+        Plan::Filter { input, predicate } => push_conjuncts(push_down_filters(*input)?, conjuncts(predicate)),
+        plan => plan.map_inputs(&mut push_down_filters),
+    }
+}
+
+/// Applies `conjuncts` to the output of `plan`, each as far down as its
+/// columns allow.
+fn push_conjuncts(plan: Plan, conjuncts: Vec<Expr>) -> Result<Plan> {
+    if conjuncts.is_empty() {
+        return Ok(plan);
+    }
+
+    match plan {
+        Plan::Scan { table, label, columns, mut filter, access } => {
+            let prefix = format!("{label}.");
+            let mut rest = Vec::new();
+            for conjunct in conjuncts {
+                if !conjunct.columns().iter().all(|name| name.starts_with(&prefix)) {
+                    rest.push(conjunct);
+                    continue;
+                }
+
+                // Scan filters name the table's columns unqualified
+                let conjunct = conjunct.rename_columns(&mut |name| Ok(name[prefix.len()..].to_string()))?;
+                filter = Some(match filter {
+                    Some(filter) => filter.and(conjunct),
+                    None => conjunct,
+                });
+            }
+            Ok(filtered(Plan::Scan { table, label, columns, filter, access }, rest))
+        }
+        Plan::Filter { input, predicate } => {
+            let mut all = self::conjuncts(predicate);
+            all.extend(conjuncts);
+            push_conjuncts(*input, all)
+        }
+        Plan::Join { left, right, on, join_type } => {
+            let left_columns = left.output_columns();
+            let right_columns = right.output_columns();
+            let within = |conjunct: &Expr, columns: &[String]| {
+                conjunct.columns().iter().all(|name| columns.iter().any(|column| column == name))
+            };
+
+            let (mut to_left, mut to_right, mut rest) = (Vec::new(), Vec::new(), Vec::new());
+            for conjunct in conjuncts {
+                if within(&conjunct, &left_columns) {
+                    to_left.push(conjunct);
+                } else if join_type == JoinType::Inner && within(&conjunct, &right_columns) {
+                    to_right.push(conjunct);
+                } else {
+                    rest.push(conjunct);
+                }
+            }
+
+            let left = Box::new(push_conjuncts(*left, to_left)?);
+            let right = Box::new(push_conjuncts(*right, to_right)?);
+            Ok(filtered(Plan::Join { left, right, on, join_type }, rest))
+        }
+        plan => Ok(filtered(plan, conjuncts)),
+    }
+}
+
+/// Splits `expr` into the parts it `AND`s together.
+fn conjuncts(expr: Expr) -> Vec<Expr> {
+    match expr {
+        Expr::And(left, right) => {
+            let mut parts = conjuncts(*left);
+            parts.extend(conjuncts(*right));
+            parts
+        }
+        expr => vec![expr],
+    }
+}
+
+fn filtered(plan: Plan, conjuncts: Vec<Expr>) -> Plan {
+    match conjuncts.into_iter().reduce(Expr::and) {
+        Some(predicate) => Plan::Filter { input: Box::new(plan), predicate },
+        None => plan,
+    }
+}
+
+/// Drops the scan columns that neither `required` (by qualified name) nor
+/// the operators between lists.
+fn prune_columns(plan: Plan, required: &[String]) -> Result<Plan> {
+    let mut required = required.to_vec();
+    let mut require = |columns: Vec<&str>| {
+        for column in columns {
+            if !required.iter().any(|r| r == column) {
+                required.push(column.to_string());
+            }
+        }
+    };
+
+    match plan {
+        Plan::Scan { table, label, columns, filter, access } => {
+            let mut kept: Vec<String> = columns
+                .iter()
+                .filter(|column| required.contains(&format!("{label}.{column}")))
+                .cloned()
+                .collect();
+            // Rows are still counted by a column, as for `COUNT(*)`
+            if kept.is_empty() {
+                kept.extend(columns.into_iter().next());
+            }
+            return Ok(Plan::Scan { table, label, columns: kept, filter, access });
+        }
+        Plan::Filter { ref predicate, .. } => require(predicate.columns()),
+        Plan::Join { ref on, .. } => require(on.iter().flat_map(|(l, r)| [l.as_str(), r.as_str()]).collect()),
+        Plan::Sort { ref keys, .. } => require(keys.iter().map(|key| key.column.as_str()).collect()),
+        Plan::Limit { .. } => {}
+        // These output only what they compute from their input
+        Plan::Aggregate { ref group_by, ref aggregates, .. } => {
+            required.clear();
+            required.extend(group_by.iter().cloned());
+            required.extend(aggregates.iter().filter_map(|a| a.column.clone()));
+        }
+        Plan::Project { ref columns, .. } => {
+            required = columns.iter().map(|(input, _)| input.clone()).collect();
+        }
+    }
+
+    plan.map_inputs(&mut |input| prune_columns(input, &required))
+}
+
+fn reorder_joins(plan: Plan, model: &mut CostModel) -> Result<Plan> {
+    if !matches!(plan, Plan::Join { join_type: JoinType::Inner, .. }) {
+        return plan.map_inputs(&mut |input| reorder_joins(input, model));
+    }
+
+    let mut inputs = Vec::new();
+    let mut on = Vec::new();
+    collect_inner_joins(plan.clone(), &mut inputs, &mut on);
+    let inputs = inputs
+        .into_iter()
+        .map(|input| reorder_joins(input, model))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(join_greedily(inputs, &on, model)?.unwrap_or(plan))
+}
+
+/// Flattens a tree of inner joins into its inputs and all its key pairs.
+fn collect_inner_joins(plan: Plan, inputs: &mut Vec<Plan>, on: &mut Vec<(String, String)>) {
+    match plan {
+        Plan::Join { left, right, on: keys, join_type: JoinType::Inner } => {
+            collect_inner_joins(*left, inputs, on);
+            collect_inner_joins(*right, inputs, on);
+            on.extend(keys);
+        }
+        plan => inputs.push(plan),
+    }
+}
+
+/// Joins `inputs` left-deep, starting from the one with the fewest estimated
+/// rows and then adding, of the inputs sharing a key with those joined, the
+/// one that keeps the estimated cost lowest. `None` when some input shares
+/// no key with the others.
+fn join_greedily(inputs: Vec<Plan>, on: &[(String, String)], model: &mut CostModel) -> Result<Option<Plan>> {
+    let mut remaining = Vec::new();
+    for input in inputs {
+        let rows = model.estimate(&input)?.rows;
+        remaining.push((input, rows));
+    }
+
+    let first = (0..remaining.len())
+        .min_by(|&a, &b| remaining[a].1.total_cmp(&remaining[b].1))
+        .unwrap();
+    let mut plan = remaining.remove(first).0;
+
+    while !remaining.is_empty() {
+        let joined = plan.output_columns();
+        let mut best: Option<(usize, Plan, f64)> = None;
+
+        for (index, (input, _)) in remaining.iter().enumerate() {
+            let columns = input.output_columns();
+            let keys: Vec<(String, String)> = on
+                .iter()
+                .filter_map(|(a, b)| match (joined.contains(a), joined.contains(b)) {
+                    (true, false) if columns.contains(b) => Some((a.clone(), b.clone())),
+                    (false, true) if columns.contains(a) => Some((b.clone(), a.clone())),
+                    _ => None,
+                })
+                .collect();
+            if keys.is_empty() {
+                continue;
+            }
+
+            let candidate = Plan::Join {
+                left: Box::new(plan.clone()),
+                right: Box::new(input.clone()),
+                on: keys,
+                join_type: JoinType::Inner,
+            };
+            let cost = model.estimate(&candidate)?.cost;
+            if best.as_ref().is_none_or(|(_, _, best)| cost < *best) {
+                best = Some((index, candidate, cost));
+            }
+        }
+
+        let Some((index, candidate, _)) = best else {
+            return Ok(None);
+        };
+        remaining.remove(index);
+        plan = candidate;
+    }
+    Ok(Some(plan))
+}
+
+fn choose_access_paths(db: &Database, plan: Plan, model: &mut CostModel) -> Result<Plan> {
+    let Plan::Scan { table, label, columns, filter, .. } = plan else {
+        return plan.map_inputs(&mut |input| choose_access_paths(db, input, model));
+    };
+    let Some(filter) = filter else {
+        return Ok(Plan::Scan { table, label, columns, filter, access: AccessPath::Full });
+    };
+
+    // Lookup bounds come from the filter as bound to the column types
+    let column_names: Vec<&str> = columns.iter().map(String::as_str).collect();
+    let bound = db.scan_where(&table, &column_names, filter.clone())?.bound_filter().cloned().unwrap();
+
+    let mut paths = vec![AccessPath::Pruned, AccessPath::Full];
+    let stats = model.table_stats(&table)?;
+    for (column, low, high) in lookup_bounds(bound) {
+        if stats.column(&column).is_some_and(|stats| stats.ascending) {
+            paths.push(AccessPath::Lookup { column, low, high });
+        }
+    }
+
+    let mut best: Option<(Plan, f64)> = None;
+    for access in paths {
+        let candidate = Plan::Scan {
+            table: table.clone(),
+            label: label.clone(),
+            columns: columns.clone(),
+            filter: Some(filter.clone()),
+            access,
+        };
+        let cost = model.estimate(&candidate)?.cost;
+        if best.as_ref().is_none_or(|(_, best)| cost < *best) {
+            best = Some((candidate, cost));
+        }
+    }
+    Ok(best.unwrap().0)
+}
+
+/// The range of values each column is limited to by the comparisons with
+/// literals among the conjuncts of `filter`.
+fn lookup_bounds(filter: Expr) -> Vec<(String, Bound<Value>, Bound<Value>)> {
+    let mut bounds: Vec<(String, Bound<Value>, Bound<Value>)> = Vec::new();
+    for conjunct in conjuncts(filter) {
+        let (column, low, high) = match conjunct {
+            Expr::Compare { op, left, right } => {
+                let (column, op, value) = match (*left, *right) {
+                    (Expr::Column(column), Expr::Literal(value)) => (column, op, value),
+                    (Expr::Literal(value), Expr::Column(column)) => (column, op.flip(), value),
+                    _ => continue,
+                };
+                if value.is_null() {
+                    continue;
+                }
+                match op {
+                    CompareOp::Eq => (column, Bound::Included(value.clone()), Bound::Included(value)),
+                    CompareOp::Lt => (column, Bound::Unbounded, Bound::Excluded(value)),
+                    CompareOp::LtEq => (column, Bound::Unbounded, Bound::Included(value)),
+                    CompareOp::Gt => (column, Bound::Excluded(value), Bound::Unbounded),
+                    CompareOp::GtEq => (column, Bound::Included(value), Bound::Unbounded),
+                    CompareOp::NotEq => continue,
+                }
+            }
+            Expr::Between { expr, low, high, negated: false } => match (*expr, *low, *high) {
+                (Expr::Column(column), Expr::Literal(low), Expr::Literal(high)) if !low.is_null() && !high.is_null() => {
+                    (column, Bound::Included(low), Bound::Included(high))
+                }
+                _ => continue,
+            },
+            _ => continue,
+        };
+
+        match bounds.iter_mut().find(|(name, ..)| *name == column) {
+            Some((_, current_low, current_high)) => {
+                tighten(current_low, low, Ordering::Greater);
+                tighten(current_high, high, Ordering::Less);
+            }
+            None => bounds.push((column, low, high)),
+        }
+    }
+    bounds
+}
+
+/// Replaces `current` by `other` if it bounds tighter, that is, if its value
+/// lies `inward` of the current one, or equals it and excludes it.
+fn tighten(current: &mut Bound<Value>, other: Bound<Value>, inward: Ordering) {
+    let tighter = match (&*current, &other) {
+        (_, Bound::Unbounded) => false,
+        (Bound::Unbounded, _) => true,
+        (Bound::Included(value) | Bound::Excluded(value), Bound::Included(candidate) | Bound::Excluded(candidate)) => {
+            match candidate.compare(value) {
+                Some(Ordering::Equal) => matches!(other, Bound::Excluded(_)),
+                order => order == Some(inward),
+            }
+        }
+    };
+    if tighter {
+        *current = other;
+    }
+}
@@ -0,0 +1,75 @@
+use std::io::{Error, Result};
+use std::num::NonZeroUsize;
+use std::thread;
+use crate::engine::table_scan::{ScanStats, TableScan};
+use crate::metadata::schema::column_type::ColumnType;
+use crate::query::aggregate::Aggregate;
+use crate::query::hash_aggregate::{AggregateOptions, HashAggregate};
+
+/// One worker thread per available core.
+pub fn available_threads() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// What the workers of a parallel scan read, summed over them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParallelScanStats {
+    pub threads: usize,
+    /// Vector batches the workers aggregated.
+    pub batches: u64,
+    pub scan: ScanStats,
+}
+
+/// Aggregates the rows of `scan` on up to `threads` worker threads.
+///
+/// Sealed chunks are independent, so the scan's row ranges are dealt out
+/// to the workers (see [`TableScan::split`]). Each folds its vectors into a
+/// partial [`HashAggregate`] with an even share of the memory budget, and
+/// the partials are merged into one with the whole budget once every
+/// worker is done. The scan's columns are named `column_names`.
+pub fn aggregate_parallel(
+    scan: TableScan,
+    column_names: &[String],
+    group_by: &[&str],
+    aggregates: &[Aggregate],
+    options: AggregateOptions,
+    threads: usize,
+) -> Result<(HashAggregate, ParallelScanStats)> {
+    let column_types: Vec<ColumnType> = scan.column_schemas.iter().map(|c| c.column_type).collect();
+    let threads = threads.clamp(1, scan.estimated_ranges().0.max(1));
+    let worker_options = AggregateOptions { memory_budget: options.memory_budget / threads, ..options.clone() };
+
+    let mut merged = HashAggregate::new(column_names, &column_types, group_by, aggregates, options)?;
+    let mut stats = ParallelScanStats { threads, ..ParallelScanStats::default() };
+
+    let partials = thread::scope(|scope| {
+        let workers: Vec<_> = scan
+            .split(threads)
+            .into_iter()
+            .map(|mut part| {
+                let (column_types, options) = (&column_types, worker_options.clone());
+                scope.spawn(move || -> Result<(HashAggregate, u64, ScanStats)> {
+                    let mut aggregate = HashAggregate::new(column_names, column_types, group_by, aggregates, options)?;
+                    let mut batches = 0;
+                    while let Some(batch) = part.next_vectors() {
+                        aggregate.push_vectors(&batch?)?;
+                        batches += 1;
+                    }
+                    Ok((aggregate, batches, part.stats))
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .map(|worker| worker.join().map_err(|_| Error::other("aggregate worker panicked"))?)
+            .collect::<Result<Vec<_>>>()
+    })?;
+
+    for (partial, batches, scan) in partials {
+        merged.merge(partial)?;
+        stats.batches += batches;
+        stats.scan.merge(&scan);
+    }
+    Ok((merged, stats))
+}
@@ -0,0 +1,92 @@
+use std::io::Result;
+use std::ops::Bound;
+use crate::metadata::value::Value;
+use crate::query::aggregate::Aggregate;
+use crate::query::expr::Expr;
+use crate::query::hash_join::JoinType;
+use crate::query::sort_key::SortKey;
+
+/// A tree of operators computing a query, run by [`execute`](crate::query::execute::execute).
+///
+/// Scans name their output columns `label.column`; every operator above
+/// refers to columns by those qualified names, until a `Project` gives the
+/// result its final names.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Plan {
+    /// Reads `columns` of a table, keeping the rows where `filter` is true.
+    /// The filter names columns unqualified, as the table does.
+    Scan { table: String, label: String, columns: Vec<String>, filter: Option<Expr>, access: AccessPath },
+    Filter { input: Box<Plan>, predicate: Expr },
+    /// Hash join on pairs of equal (left column, right column).
+    Join { left: Box<Plan>, right: Box<Plan>, on: Vec<(String, String)>, join_type: JoinType },
+    Aggregate { input: Box<Plan>, group_by: Vec<String>, aggregates: Vec<Aggregate> },
+    Sort { input: Box<Plan>, keys: Vec<SortKey>, limit: Option<usize> },
+    Limit { input: Box<Plan>, limit: usize },
+    /// Outputs input columns, each `(input column, output name)`.
+    Project { input: Box<Plan>, columns: Vec<(String, String)> },
+}
+
+/// How a scan finds the chunk row ranges to read.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum AccessPath {
+    /// Reads every range.
+    Full,
+    /// Skips the ranges whose zone maps rule out the filter.
+    #[default]
+    Pruned,
+    /// Reads the ranges a binary search over the chunk min/max of an
+    /// ascending column finds for `low..high`, see
+    /// [`TableScan::with_lookup`](crate::engine::table_scan::TableScan::with_lookup).
+    /// The filter must imply the bounds.
+    Lookup { column: String, low: Bound<Value>, high: Bound<Value> },
+}
+
+impl Plan {
+    /// The operators feeding this one, left input first.
+    pub fn inputs(&self) -> Vec<&Plan> {
+        match self {
+            Plan::Scan { .. } => Vec::new(),
+            Plan::Join { left, right, .. } => vec![left, right],
+            Plan::Filter { input, .. }
+            | Plan::Aggregate { input, .. }
+            | Plan::Sort { input, .. }
+            | Plan::Limit { input, .. }
+            | Plan::Project { input, .. } => vec![input],
+        }
+    }
+
+    /// Rebuilds the operator over its inputs as `rewrite` returns them.
+    pub fn map_inputs(self, rewrite: &mut impl FnMut(Plan) -> Result<Plan>) -> Result<Plan> {
+        let mut rewrite = |input: Box<Plan>| rewrite(*input).map(Box::new);
+        Ok(match self {
+            Plan::Scan { .. } => self,
+            Plan::Filter { input, predicate } => Plan::Filter { input: rewrite(input)?, predicate },
+            Plan::Join { left, right, on, join_type } => {
+                Plan::Join { left: rewrite(left)?, right: rewrite(right)?, on, join_type }
+            }
+            Plan::Aggregate { input, group_by, aggregates } => {
+                Plan::Aggregate { input: rewrite(input)?, group_by, aggregates }
+            }
+            Plan::Sort { input, keys, limit } => Plan::Sort { input: rewrite(input)?, keys, limit },
+            Plan::Limit { input, limit } => Plan::Limit { input: rewrite(input)?, limit },
+            Plan::Project { input, columns } => Plan::Project { input: rewrite(input)?, columns },
+        })
+    }
+
+    /// Names of the columns the plan outputs, in order.
+    pub fn output_columns(&self) -> Vec<String> {
+        match self {
+            Plan::Scan { label, columns, .. } => columns.iter().map(|column| format!("{label}.{column}")).collect(),
+            Plan::Join { left, right, .. } => {
+                let mut columns = left.output_columns();
+                columns.extend(right.output_columns());
+                columns
+            }
+            Plan::Aggregate { group_by, aggregates, .. } => {
+                group_by.iter().cloned().chain(aggregates.iter().map(|a| a.output_name())).collect()
+            }
+            Plan::Project { columns, .. } => columns.iter().map(|(_, name)| name.clone()).collect(),
+            Plan::Filter { input, .. } | Plan::Sort { input, .. } | Plan::Limit { input, .. } => input.output_columns(),
+        }
+    }
+}
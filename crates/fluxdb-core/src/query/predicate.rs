@@ -0,0 +1,192 @@
+use std::io::{Error, ErrorKind, Result};
+use crate::metadata::value::Value;
+use crate::query::expr::Expr;
+use crate::query::like::LikePattern;
+
+/// Offsets of the selected rows within a batch, ascending.
+pub type SelectionVector = Vec<u32>;
+
+/// Truth value of a predicate under three-valued logic, `None` for null.
+type Truth = Option<bool>;
+
+/// Values of an operand for the rows of a selection.
+enum Operand<'a> {
+    /// A whole batch column, indexed by row offset.
+    Column(&'a [Value]),
+    Scalar(&'a Value),
+    /// Computed values, one per selected row.
+    Values(Vec<Value>),
+}
+
+impl Operand<'_> {
+    /// Value for the `i`-th selected row, whose offset is `row`.
+    fn get(&self, i: usize, row: u32) -> &Value {
+        match self {
+            Operand::Column(values) => &values[row as usize],
+            Operand::Scalar(value) => value,
+            Operand::Values(values) => &values[i],
+        }
+    }
+}
+
+/// Evaluates `expr` over a batch of `row_count` rows, returning the offsets
+/// of the rows where it is true.
+///
+/// `columns` pairs each column name the expression reads with its values.
+/// Evaluation is column at a time: each node runs over every row still
+/// selected, and `AND`/`OR` evaluate their right side only for the rows the
+/// left side leaves undecided.
+pub fn select(expr: &Expr, columns: &[(&str, &[Value])], row_count: usize) -> Result<SelectionVector> {
+    let selection: SelectionVector = (0..row_count as u32).collect();
+    let truth = Evaluator { columns }.truth(expr, &selection)?;
+
+    Ok(selection
+        .into_iter()
+        .zip(truth)
+        .filter_map(|(row, t)| (t == Some(true)).then_some(row))
+        .collect())
+}
+
+/// Keeps the values at the offsets in `selection`.
+pub fn take(values: Vec<Value>, selection: &[u32]) -> Vec<Value> {
+    if selection.len() == values.len() {
+        return values;
+    }
+
+    let mut values: Vec<Option<Value>> = values.into_iter().map(Some).collect();
+    selection.iter().map(|&row| values[row as usize].take().unwrap_or(Value::Null)).collect()
+}
+
+struct Evaluator<'a> {
+    columns: &'a [(&'a str, &'a [Value])],
+}
+
+fn and(left: Truth, right: Truth) -> Truth {
+    match (left, right) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
+    }
+}
+
+fn negate(truth: Truth, negated: bool) -> Truth {
+    truth.map(|t| t != negated)
+}
+
+impl<'a> Evaluator<'a> {
+    fn operand(&self, expr: &'a Expr, selection: &[u32]) -> Result<Operand<'a>> {
+        match expr {
+            Expr::Column(name) => self.columns
+                .iter()
+                .find(|(column, _)| column == name)
+                .map(|(_, values)| Operand::Column(values))
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("column '{name}' not found"))),
+            Expr::Literal(value) => Ok(Operand::Scalar(value)),
+            _ => Ok(Operand::Values(
+                self.truth(expr, selection)?
+                    .into_iter()
+                    .map(|t| t.map_or(Value::Null, Value::Bool))
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Evaluates a boolean expression for the rows in `selection`, returning
+    /// one truth value per selected row.
+    fn truth(&self, expr: &'a Expr, selection: &[u32]) -> Result<Vec<Truth>> {
+        let rows = selection.iter().enumerate();
+
+        let truth = match expr {
+            Expr::Column(_) | Expr::Literal(_) => {
+                let operand = self.operand(expr, selection)?;
+                rows.map(|(i, &row)| match operand.get(i, row) {
+                    Value::Bool(b) => Ok(Some(*b)),
+                    Value::Null => Ok(None),
+                    value => Err(Error::new(ErrorKind::InvalidInput, format!("{value} is not a boolean"))),
+                })
+                .collect::<Result<_>>()?
+            }
+            Expr::Compare { op, left, right } => {
+                let (left, right) = (self.operand(left, selection)?, self.operand(right, selection)?);
+                rows.map(|(i, &row)| left.get(i, row).compare(right.get(i, row)).map(|o| op.matches(o)))
+                    .collect()
+            }
+            Expr::And(left, right) => self.connective(left, right, selection, false)?,
+            Expr::Or(left, right) => self.connective(left, right, selection, true)?,
+            Expr::Not(expr) => self.truth(expr, selection)?.into_iter().map(|t| negate(t, true)).collect(),
+            Expr::In { expr, list, negated } => {
+                let value = self.operand(expr, selection)?;
+                let list = list.iter().map(|item| self.operand(item, selection)).collect::<Result<Vec<_>>>()?;
+                rows.map(|(i, &row)| {
+                    let value = value.get(i, row);
+                    if value.is_null() {
+                        return None;
+                    }
+
+                    // x IN (.., NULL) is null rather than false when nothing matches
+                    let mut truth = Some(false);
+                    for item in &list {
+                        match value.compare(item.get(i, row)) {
+                            Some(ordering) if ordering.is_eq() => return negate(Some(true), *negated),
+                            None => truth = None,
+                            _ => {}
+                        }
+                    }
+                    negate(truth, *negated)
+                })
+                .collect()
+            }
+            Expr::IsNull { expr, negated } => {
+                let value = self.operand(expr, selection)?;
+                rows.map(|(i, &row)| Some(value.get(i, row).is_null() != *negated)).collect()
+            }
+            Expr::Between { expr, low, high, negated } => {
+                let value = self.operand(expr, selection)?;
+                let (low, high) = (self.operand(low, selection)?, self.operand(high, selection)?);
+                rows.map(|(i, &row)| {
+                    let value = value.get(i, row);
+                    let above = value.compare(low.get(i, row)).map(|o| o.is_ge());
+                    let below = value.compare(high.get(i, row)).map(|o| o.is_le());
+                    negate(and(above, below), *negated)
+                })
+                .collect()
+            }
+            Expr::Like { expr, pattern, negated } => {
+                let value = self.operand(expr, selection)?;
+                let pattern = LikePattern::new(pattern);
+                rows.map(|(i, &row)| match value.get(i, row) {
+                    Value::String(s) => Ok(negate(Some(pattern.matches(s)), *negated)),
+                    Value::Null => Ok(None),
+                    value => Err(Error::new(ErrorKind::InvalidInput, format!("LIKE needs text, got {value}"))),
+                })
+                .collect::<Result<_>>()?
+            }
+        };
+
+        Ok(truth)
+    }
+
+    /// `AND` (`is_or` false) or `OR`: the right side only runs for the rows
+    /// the left side does not decide on its own.
+    fn connective(&self, left: &'a Expr, right: &'a Expr, selection: &[u32], is_or: bool) -> Result<Vec<Truth>> {
+        let mut truth = self.truth(left, selection)?;
+
+        let undecided: Vec<usize> = (0..selection.len()).filter(|&i| truth[i] != Some(is_or)).collect();
+        if undecided.is_empty() {
+            return Ok(truth);
+        }
+
+        let rows: SelectionVector = undecided.iter().map(|&i| selection[i]).collect();
+        let right = self.truth(right, &rows)?;
+
+        for (i, r) in undecided.into_iter().zip(right) {
+            truth[i] = match is_or {
+                false => and(truth[i], r),
+                // a OR b is NOT (NOT a AND NOT b)
+                true => negate(and(negate(truth[i], true), negate(r, true)), true),
+            };
+        }
+
+        Ok(truth)
+    }
+}
@@ -0,0 +1,67 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use crate::engine::table_scan::ScanStats;
+use crate::query::stream::BatchStream;
+
+/// What one operator did while its query ran.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OperatorStats {
+    /// Rows the operator output.
+    pub rows: u64,
+    pub batches: u64,
+    /// Time spent in the operator and the operators feeding it: building
+    /// it (where aggregates and sorts consume their input) and producing
+    /// its batches.
+    pub elapsed: Duration,
+    /// Counters of a scan, `None` for other operators.
+    pub scan: Option<ScanStats>,
+}
+
+/// Shared slot the running operator writes its [`OperatorStats`] to.
+pub type StatsCell = Rc<Cell<OperatorStats>>;
+
+/// Stats of every operator of a running plan, shaped like the plan tree.
+/// They keep filling in as the query's output is read.
+#[derive(Debug, Clone, Default)]
+pub struct OperatorProfile {
+    pub(crate) stats: StatsCell,
+    /// Profiles of the operator's inputs, left input first.
+    pub children: Vec<OperatorProfile>,
+}
+
+impl OperatorProfile {
+    pub fn stats(&self) -> OperatorStats {
+        self.stats.get()
+    }
+}
+
+/// Applies `change` to the stats in `stats`.
+pub fn update(stats: &StatsCell, change: impl FnOnce(&mut OperatorStats)) {
+    let mut current = stats.get();
+    change(&mut current);
+    stats.set(current);
+}
+
+/// Counts the rows and batches of `stream`, and the time spent producing them.
+pub fn profiled<'a>(stream: BatchStream<'a>, stats: StatsCell) -> BatchStream<'a> {
+    let column_names = stream.column_names.clone();
+    let column_types = stream.column_types.clone();
+    let estimated_rows = stream.estimated_rows;
+
+    let mut input = stream;
+    let batches = std::iter::from_fn(move || {
+        let started = Instant::now();
+        let batch = input.next();
+        update(&stats, |stats| {
+            stats.elapsed += started.elapsed();
+            if let Some(Ok(batch)) = &batch {
+                stats.rows += batch.row_count as u64;
+                stats.batches += 1;
+            }
+        });
+        batch
+    });
+
+    BatchStream::new(column_names, column_types, estimated_rows, batches)
+}
@@ -0,0 +1,384 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+use crate::engine::database::Database;
+use crate::engine::table_scan::TableScan;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+use crate::query::batch::{value_size, Batch};
+use crate::query::expr::Expr;
+use crate::query::sort_key::{encode_sort_value, SortKey};
+use crate::query::spill::{SpillFile, SpillReader};
+
+/// Most runs merged at once; more runs are first merged into longer ones.
+const MERGE_FAN_IN: usize = 64;
+
+/// Bookkeeping cost of a buffered row beyond its key and values.
+const ROW_OVERHEAD: usize = 48;
+
+#[derive(Debug, Clone)]
+pub struct SortOptions {
+    /// Estimated bytes of rows buffered in memory before a run is spilled.
+    pub memory_budget: usize,
+    /// Directory for spilled runs.
+    pub spill_dir: PathBuf,
+    /// Rows per output batch.
+    pub batch_size: usize,
+}
+
+impl Default for SortOptions {
+    fn default() -> Self {
+        Self {
+            memory_budget: 64 * 1024 * 1024,
+            spill_dir: std::env::temp_dir(),
+            batch_size: 4096,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SortStats {
+    pub rows_in: u64,
+    /// Sorted runs written to spill files, intermediate merges included.
+    pub runs_spilled: usize,
+    /// Merges of `MERGE_FAN_IN` runs into one before the final merge.
+    pub merge_passes: usize,
+}
+
+/// A buffered row, ordered by its encoded sort key, then by arrival so
+/// the sort is stable.
+struct SortRow {
+    key: Vec<u8>,
+    seq: u64,
+    values: Vec<Value>,
+}
+
+impl PartialEq for SortRow {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for SortRow {}
+
+impl PartialOrd for SortRow {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SortRow {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key).then(self.seq.cmp(&other.seq))
+    }
+}
+
+fn write_run(run: &mut SpillFile, row: &SortRow) -> Result<()> {
+    let mut values = Vec::with_capacity(row.values.len() + 2);
+    values.push(Value::Binary(row.key.clone()));
+    values.push(Value::Int64(row.seq as i64));
+    values.extend(row.values.iter().cloned());
+    run.write_row(&values)
+}
+
+fn read_run(reader: &mut SpillReader) -> Result<Option<SortRow>> {
+    let Some(mut values) = reader.read_row()? else {
+        return Ok(None);
+    };
+
+    let mut head = values.drain(..2);
+    let (Some(Value::Binary(key)), Some(Value::Int64(seq))) = (head.next(), head.next()) else {
+        return Err(Error::new(ErrorKind::InvalidData, "corrupt sort run"));
+    };
+    drop(head);
+
+    Ok(Some(SortRow { key, seq: seq as u64, values }))
+}
+
+/// `ORDER BY`, optionally with a `LIMIT`.
+///
+/// With a limit the operator keeps the best `limit` rows in a bounded heap
+/// and never spills. Otherwise rows are buffered up to the memory budget;
+/// each time the budget is exceeded the buffer is sorted and written out as
+/// a run, and the runs are merged when the input ends.
+///
+/// Rows are ordered by byte-comparable keys (see [`encode_sort_value`]),
+/// ties keep their input order.
+pub struct Sort {
+    keys: Vec<SortKey>,
+    key_columns: Vec<usize>,
+    output_width: usize,
+    limit: Option<usize>,
+    buffer: Vec<SortRow>,
+    memory: usize,
+    /// Best `limit` rows so far, worst on top.
+    top: BinaryHeap<SortRow>,
+    runs: Vec<SpillFile>,
+    options: SortOptions,
+    pub column_names: Vec<String>,
+    pub column_types: Vec<ColumnType>,
+    pub stats: SortStats,
+}
+
+impl Sort {
+    /// Creates the operator over input batches whose columns are named
+    /// `input_names` and typed `input_types`. The output has the same columns.
+    pub fn new(
+        input_names: &[String],
+        input_types: &[ColumnType],
+        keys: &[SortKey],
+        limit: Option<usize>,
+        options: SortOptions,
+    ) -> Result<Self> {
+        let mut key_columns = Vec::with_capacity(keys.len());
+        for key in keys {
+            let index = input_names.iter().position(|name| *name == key.column).ok_or_else(|| {
+                Error::new(ErrorKind::NotFound, format!("column '{}' not found", key.column))
+            })?;
+            key.check_type(input_types[index])?;
+            key_columns.push(index);
+        }
+
+        Ok(Self {
+            keys: keys.to_vec(),
+            key_columns,
+            output_width: input_names.len(),
+            limit,
+            buffer: Vec::new(),
+            memory: 0,
+            top: BinaryHeap::new(),
+            runs: Vec::new(),
+            options,
+            column_names: input_names.to_vec(),
+            column_types: input_types.to_vec(),
+            stats: SortStats::default(),
+        })
+    }
+
+    /// Outputs only the first `width` input columns, for sort keys that are
+    /// not part of the result.
+    pub fn output_columns(mut self, width: usize) -> Self {
+        self.output_width = width.min(self.output_width);
+        self.column_names.truncate(self.output_width);
+        self.column_types.truncate(self.output_width);
+        self
+    }
+
+    pub fn push(&mut self, columns: &[Vec<Value>], row_count: usize) -> Result<()> {
+        for row in 0..row_count {
+            let seq = self.stats.rows_in;
+            self.stats.rows_in += 1;
+
+            let mut key = Vec::new();
+            for (sort_key, &column) in self.keys.iter().zip(&self.key_columns) {
+                encode_sort_value(sort_key, &columns[column][row], &mut key);
+            }
+
+            if let Some(limit) = self.limit {
+                // Skip rows that cannot make the cut before copying their values
+                if self.top.len() >= limit && self.top.peek().is_none_or(|worst| key >= worst.key) {
+                    continue;
+                }
+                let values = columns[..self.output_width].iter().map(|c| c[row].clone()).collect();
+                self.top.push(SortRow { key, seq, values });
+                if self.top.len() > limit {
+                    self.top.pop();
+                }
+                continue;
+            }
+
+            let values: Vec<Value> = columns[..self.output_width].iter().map(|c| c[row].clone()).collect();
+            self.memory += ROW_OVERHEAD + key.len() + values.iter().map(value_size).sum::<usize>();
+            self.buffer.push(SortRow { key, seq, values });
+        }
+
+        if self.memory > self.options.memory_budget {
+            self.spill_run()?;
+        }
+
+        Ok(())
+    }
+
+    /// Sorts the buffered rows and writes them out as a run.
+    fn spill_run(&mut self) -> Result<()> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.sort_unstable();
+        self.memory = 0;
+
+        let mut run = SpillFile::create(&self.options.spill_dir)?;
+        for row in &buffer {
+            write_run(&mut run, row)?;
+        }
+
+        self.runs.push(run);
+        self.stats.runs_spilled += 1;
+        Ok(())
+    }
+
+    /// Ends the input and returns the rows in order.
+    pub fn finish(mut self) -> Result<SortResults> {
+        let source = if self.limit.is_some() {
+            Source::Memory(std::mem::take(&mut self.top).into_sorted_vec().into_iter())
+        } else if self.runs.is_empty() {
+            self.buffer.sort_unstable();
+            Source::Memory(std::mem::take(&mut self.buffer).into_iter())
+        } else {
+            if !self.buffer.is_empty() {
+                self.spill_run()?;
+            }
+
+            // Merge the oldest runs until one final merge can take them all
+            while self.runs.len() > MERGE_FAN_IN {
+                let runs: Vec<SpillFile> = self.runs.drain(..MERGE_FAN_IN).collect();
+                let mut merge = Merge::new(runs)?;
+                let mut merged = SpillFile::create(&self.options.spill_dir)?;
+                while let Some(row) = merge.next_row()? {
+                    write_run(&mut merged, &row)?;
+                }
+                self.runs.push(merged);
+                self.stats.runs_spilled += 1;
+                self.stats.merge_passes += 1;
+            }
+
+            Source::Merge(Merge::new(std::mem::take(&mut self.runs))?)
+        };
+
+        Ok(SortResults {
+            column_names: self.column_names,
+            column_types: self.column_types,
+            source,
+            batch_size: self.options.batch_size.max(1),
+            stats: self.stats,
+        })
+    }
+}
+
+/// K-way merge of sorted runs.
+struct Merge {
+    /// Kept so the files live until the merge is done.
+    _runs: Vec<SpillFile>,
+    readers: Vec<SpillReader>,
+    heads: BinaryHeap<Reverse<(SortRow, usize)>>,
+}
+
+impl Merge {
+    fn new(mut runs: Vec<SpillFile>) -> Result<Merge> {
+        let mut readers = Vec::with_capacity(runs.len());
+        let mut heads = BinaryHeap::with_capacity(runs.len());
+
+        for (index, run) in runs.iter_mut().enumerate() {
+            let mut reader = run.read()?;
+            if let Some(row) = read_run(&mut reader)? {
+                heads.push(Reverse((row, index)));
+            }
+            readers.push(reader);
+        }
+
+        Ok(Merge { _runs: runs, readers, heads })
+    }
+
+    fn next_row(&mut self) -> Result<Option<SortRow>> {
+        let Some(Reverse((row, index))) = self.heads.pop() else {
+            return Ok(None);
+        };
+
+        if let Some(next) = read_run(&mut self.readers[index])? {
+            self.heads.push(Reverse((next, index)));
+        }
+        Ok(Some(row))
+    }
+}
+
+enum Source {
+    Memory(std::vec::IntoIter<SortRow>),
+    Merge(Merge),
+}
+
+/// Output of a [`Sort`], in batches of up to `batch_size` rows.
+pub struct SortResults {
+    pub column_names: Vec<String>,
+    pub column_types: Vec<ColumnType>,
+    source: Source,
+    batch_size: usize,
+    pub stats: SortStats,
+}
+
+impl SortResults {
+    fn next_batch(&mut self) -> Result<Option<Batch>> {
+        let mut rows = Vec::with_capacity(self.batch_size);
+        while rows.len() < self.batch_size {
+            let row = match &mut self.source {
+                Source::Memory(rows) => rows.next(),
+                Source::Merge(merge) => merge.next_row()?,
+            };
+            match row {
+                Some(row) => rows.push(row.values),
+                None => break,
+            }
+        }
+
+        if rows.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Batch::from_rows(self.column_names.len(), rows)))
+    }
+}
+
+impl Iterator for SortResults {
+    type Item = Result<Batch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_batch().transpose()
+    }
+}
+
+/// Runs a [`Sort`] over every batch of `scan`.
+pub fn sort_scan(scan: TableScan, keys: &[SortKey], limit: Option<usize>, options: SortOptions) -> Result<SortResults> {
+    let input_types: Vec<ColumnType> = scan.column_schemas.iter().map(|c| c.column_type).collect();
+    let sort = Sort::new(&scan.column_names, &input_types, keys, limit, options)?;
+    run(sort, scan)
+}
+
+fn run(mut sort: Sort, scan: TableScan) -> Result<SortResults> {
+    for batch in scan {
+        let batch = batch?;
+        sort.push(&batch.columns, batch.row_count)?;
+    }
+
+    sort.finish()
+}
+
+/// Sorts the `columns` of a table's rows where `filter` is true (all rows
+/// when `None`); an empty `columns` list returns every top-level column.
+/// Sort keys need not be among the returned columns.
+pub fn sort_table(
+    db: &Database,
+    table_name: &str,
+    columns: &[&str],
+    keys: &[SortKey],
+    filter: Option<Expr>,
+    limit: Option<usize>,
+    options: SortOptions,
+) -> Result<SortResults> {
+    let mut projection: Vec<String> = match columns.is_empty() {
+        true => db.scan(table_name, &[])?.column_names,
+        false => columns.iter().map(|c| c.to_string()).collect(),
+    };
+    let output_width = projection.len();
+    for key in keys {
+        if !projection.contains(&key.column) {
+            projection.push(key.column.clone());
+        }
+    }
+
+    let projection: Vec<&str> = projection.iter().map(|c| c.as_str()).collect();
+    let scan = match filter {
+        Some(filter) => db.scan_where(table_name, &projection, filter)?,
+        None => db.scan(table_name, &projection)?,
+    };
+
+    let input_types: Vec<ColumnType> = scan.column_schemas.iter().map(|c| c.column_type).collect();
+    let sort = Sort::new(&scan.column_names, &input_types, keys, limit, options)?.output_columns(output_width);
+    run(sort, scan)
+}
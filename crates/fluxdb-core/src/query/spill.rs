@@ -0,0 +1,204 @@
+//! Temporary files for operators whose state outgrows their memory budget.
+//!
+//! A spill file holds rows of values, each written as a length-prefixed
+//! record in a self-describing encoding, so operators can write any mix of
+//! keys, values and intermediate state and read it back in order.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Read, Result, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::metadata::value::Value;
+
+static NEXT_SPILL_ID: AtomicU64 = AtomicU64::new(0);
+
+const TAG_NULL: u8 = 0;
+const TAG_INT32: u8 = 1;
+const TAG_INT64: u8 = 2;
+const TAG_FLOAT32: u8 = 3;
+const TAG_FLOAT64: u8 = 4;
+const TAG_BOOL: u8 = 5;
+const TAG_STRING: u8 = 6;
+const TAG_TIMESTAMP: u8 = 7;
+const TAG_BINARY: u8 = 8;
+const TAG_UUID: u8 = 9;
+const TAG_LIST: u8 = 10;
+const TAG_STRUCT: u8 = 11;
+
+/// Appends the tagged encoding of `value` to `buf`.
+pub fn encode_value(value: &Value, buf: &mut Vec<u8>) {
+    let bytes = |buf: &mut Vec<u8>, tag: u8, bytes: &[u8]| {
+        buf.push(tag);
+        buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        buf.extend_from_slice(bytes);
+    };
+    let values = |buf: &mut Vec<u8>, tag: u8, items: &[Value]| {
+        buf.push(tag);
+        buf.extend_from_slice(&(items.len() as u32).to_le_bytes());
+        items.iter().for_each(|item| encode_value(item, buf));
+    };
+
+    match value {
+        Value::Null => buf.push(TAG_NULL),
+        Value::Int32(v) => {
+            buf.push(TAG_INT32);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Int64(v) => {
+            buf.push(TAG_INT64);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Float32(v) => {
+            buf.push(TAG_FLOAT32);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Float64(v) => {
+            buf.push(TAG_FLOAT64);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Bool(v) => buf.extend_from_slice(&[TAG_BOOL, *v as u8]),
+        Value::String(s) => bytes(buf, TAG_STRING, s.as_bytes()),
+        Value::Timestamp(v) => {
+            buf.push(TAG_TIMESTAMP);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Binary(b) => bytes(buf, TAG_BINARY, b),
+        Value::Uuid(u) => {
+            buf.push(TAG_UUID);
+            buf.extend_from_slice(u);
+        }
+        Value::List(items) => values(buf, TAG_LIST, items),
+        Value::Struct(fields) => values(buf, TAG_STRUCT, fields),
+    }
+}
+
+fn corrupt() -> Error {
+    Error::new(ErrorKind::InvalidData, "corrupt spill record")
+}
+
+fn take<const N: usize>(buf: &mut &[u8]) -> Result<[u8; N]> {
+    let (head, rest) = buf.split_at_checked(N).ok_or_else(corrupt)?;
+    *buf = rest;
+    Ok(head.try_into().unwrap())
+}
+
+fn take_slice<'a>(buf: &mut &'a [u8]) -> Result<&'a [u8]> {
+    let len = u32::from_le_bytes(take(buf)?) as usize;
+    let (head, rest) = buf.split_at_checked(len).ok_or_else(corrupt)?;
+    *buf = rest;
+    Ok(head)
+}
+
+/// Decodes one value written by [`encode_value`] from the front of `buf`.
+pub fn decode_value(buf: &mut &[u8]) -> Result<Value> {
+    let [tag] = take::<1>(buf)?;
+
+    let value = match tag {
+        TAG_NULL => Value::Null,
+        TAG_INT32 => Value::Int32(i32::from_le_bytes(take(buf)?)),
+        TAG_INT64 => Value::Int64(i64::from_le_bytes(take(buf)?)),
+        TAG_FLOAT32 => Value::Float32(f32::from_le_bytes(take(buf)?)),
+        TAG_FLOAT64 => Value::Float64(f64::from_le_bytes(take(buf)?)),
+        TAG_BOOL => Value::Bool(take::<1>(buf)?[0] != 0),
+        TAG_STRING => Value::String(String::from_utf8(take_slice(buf)?.to_vec()).map_err(|_| corrupt())?),
+        TAG_TIMESTAMP => Value::Timestamp(i64::from_le_bytes(take(buf)?)),
+        TAG_BINARY => Value::Binary(take_slice(buf)?.to_vec()),
+        TAG_UUID => Value::Uuid(take(buf)?),
+        TAG_LIST | TAG_STRUCT => {
+            let len = u32::from_le_bytes(take(buf)?) as usize;
+            let items = (0..len).map(|_| decode_value(buf)).collect::<Result<Vec<_>>>()?;
+            if tag == TAG_LIST { Value::List(items) } else { Value::Struct(items) }
+        }
+        _ => return Err(corrupt()),
+    };
+
+    Ok(value)
+}
+
+/// A temporary file of rows, deleted when dropped.
+pub struct SpillFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    buf: Vec<u8>,
+    pub rows: u64,
+    pub bytes: u64,
+}
+
+impl SpillFile {
+    /// Creates an empty spill file in `dir`.
+    pub fn create(dir: &Path) -> Result<SpillFile> {
+        let id = NEXT_SPILL_ID.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("fluxdb-spill-{}-{id}.tmp", std::process::id()));
+        let file = OpenOptions::new().write(true).create_new(true).open(&path)?;
+
+        Ok(SpillFile {
+            path,
+            writer: BufWriter::new(file),
+            buf: Vec::new(),
+            rows: 0,
+            bytes: 0,
+        })
+    }
+
+    pub fn write_row(&mut self, row: &[Value]) -> Result<()> {
+        self.buf.clear();
+        for value in row {
+            encode_value(value, &mut self.buf);
+        }
+
+        self.writer.write_all(&(row.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&(self.buf.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&self.buf)?;
+        self.rows += 1;
+        self.bytes += 8 + self.buf.len() as u64;
+        Ok(())
+    }
+
+    /// Reads the rows written so far, from the start. Writing can go on
+    /// afterwards; rows written later are not seen by this reader.
+    pub fn read(&mut self) -> Result<SpillReader> {
+        self.writer.flush()?;
+        let file = File::open(&self.path)?;
+        Ok(SpillReader { reader: BufReader::new(file.take(self.bytes)), buf: Vec::new() })
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Rows of a [`SpillFile`], in the order they were written.
+pub struct SpillReader {
+    reader: BufReader<std::io::Take<File>>,
+    buf: Vec<u8>,
+}
+
+impl SpillReader {
+    pub fn read_row(&mut self) -> Result<Option<Vec<Value>>> {
+        let mut header = [0u8; 8];
+        match self.reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let width = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let len = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+        self.buf.resize(len, 0);
+        self.reader.read_exact(&mut self.buf)?;
+
+        let mut bytes = self.buf.as_slice();
+        let row = (0..width).map(|_| decode_value(&mut bytes)).collect::<Result<Vec<_>>>()?;
+        Ok(Some(row))
+    }
+}
+
+impl Iterator for SpillReader {
+    type Item = Result<Vec<Value>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_row().transpose()
+    }
+}
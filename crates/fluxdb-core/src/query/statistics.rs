@@ -0,0 +1,118 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use crate::engine::database::Database;
+use crate::metadata::chunks::distinct_sketch::DistinctSketch;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+use crate::query::zone_map;
+
+/// Table-wide statistics of one top-level column, merged from the zone maps
+/// and distinct sketches of its sealed and active chunks.
+#[derive(Debug, Clone)]
+pub struct ColumnStats {
+    pub column_type: ColumnType,
+    /// Null rows, counting the rows from before the column was added.
+    pub null_count: u64,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    /// Estimated distinct non-null values.
+    pub distinct: u64,
+    /// Whether the sealed chunks ascend, so a lookup can binary search them.
+    pub ascending: bool,
+}
+
+/// What the cost model knows about a table without reading its data.
+#[derive(Debug, Clone, Default)]
+pub struct TableStats {
+    pub row_count: u64,
+    /// Top-level columns by name. Nested columns have no statistics.
+    pub columns: HashMap<String, ColumnStats>,
+}
+
+impl TableStats {
+    pub fn collect(db: &Database, table: &str) -> Result<TableStats> {
+        let table_id = *db.catalog.tables_by_name
+            .get(table)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("table '{table}' not found")))?;
+        let row_count = db.chunk_manager.table_rows(table_id).committed_row_end;
+
+        let mut columns = HashMap::new();
+        for column in db.catalog.columns_by_table.get(&table_id).into_iter().flatten() {
+            if column.column_type.is_nested() {
+                continue;
+            }
+
+            let chunks = db.chunk_manager.chunks_for(table_id, column.column_id);
+            let active = db.chunk_manager.active_chunks.get(&(table_id, column.ordinal));
+
+            let mut values = 0;
+            let mut nulls = 0;
+            let mut min: Option<Value> = None;
+            let mut max: Option<Value> = None;
+            let mut sketch = DistinctSketch::default();
+            let mut sketched = true;
+
+            let stats = chunks
+                .iter()
+                .map(|c| (c.value_count, c.null_count, &c.min, &c.max, &c.distinct))
+                .chain(active.map(|a| (a.value_count, a.null_count, &a.min, &a.max, &a.distinct)));
+            for (value_count, null_count, chunk_min, chunk_max, distinct) in stats {
+                values += u64::from(value_count);
+                nulls += u64::from(null_count);
+                if let Some(chunk_min) = chunk_min {
+                    if min.as_ref().is_none_or(|min| chunk_min.compare(min) == Some(Ordering::Less)) {
+                        min = Some(chunk_min.clone());
+                    }
+                }
+                if let Some(chunk_max) = chunk_max {
+                    if max.as_ref().is_none_or(|max| chunk_max.compare(max) == Some(Ordering::Greater)) {
+                        max = Some(chunk_max.clone());
+                    }
+                }
+                sketched &= value_count == null_count || !distinct.is_empty();
+                sketch.merge(distinct);
+            }
+
+            let non_null = values - nulls;
+            let mut distinct = match sketched {
+                true => sketch.estimate(),
+                // Chunks sealed before sketches were kept: assume all distinct
+                false => non_null,
+            };
+            distinct = distinct.min(non_null);
+            if let (Some(low), Some(high)) = (min.as_ref().and_then(Value::as_i64), max.as_ref().and_then(Value::as_i64)) {
+                distinct = distinct.min(high.abs_diff(low).saturating_add(1));
+            }
+            if column.column_type == ColumnType::Boolean {
+                distinct = distinct.min(2);
+            }
+
+            columns.insert(column.name.clone(), ColumnStats {
+                column_type: column.column_type,
+                // Rows appended before the column existed read as null
+                null_count: nulls + row_count.saturating_sub(values),
+                min,
+                max,
+                distinct,
+                ascending: !chunks.is_empty() && zone_map::ascending(chunks),
+            });
+        }
+
+        Ok(TableStats { row_count, columns })
+    }
+
+    pub fn column(&self, name: &str) -> Option<&ColumnStats> {
+        self.columns.get(name)
+    }
+}
+
+impl ColumnStats {
+    /// Fraction of the table's `rows` that are null.
+    pub fn null_fraction(&self, rows: u64) -> f64 {
+        match rows {
+            0 => 0.0,
+            rows => (self.null_count as f64 / rows as f64).min(1.0),
+        }
+    }
+}
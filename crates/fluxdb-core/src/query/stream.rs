@@ -0,0 +1,105 @@
+use std::io::Result;
+use crate::engine::table_scan::{ScanStats, TableScan};
+use crate::metadata::schema::column_type::ColumnType;
+use crate::query::batch::Batch;
+use crate::query::vector::VectorBatch;
+
+/// The output of any operator: its batches, and the names and types of
+/// their columns. Lets operators take each other's output as input.
+pub struct BatchStream<'a> {
+    pub column_names: Vec<String>,
+    pub column_types: Vec<ColumnType>,
+    /// Expected number of rows, for sizing decisions such as a join's
+    /// build side. Not a bound.
+    pub estimated_rows: u64,
+    batches: Box<dyn Iterator<Item = Result<Batch>> + 'a>,
+}
+
+impl<'a> BatchStream<'a> {
+    pub fn new(
+        column_names: Vec<String>,
+        column_types: Vec<ColumnType>,
+        estimated_rows: u64,
+        batches: impl Iterator<Item = Result<Batch>> + 'a,
+    ) -> Self {
+        Self { column_names, column_types, estimated_rows, batches: Box::new(batches) }
+    }
+
+    /// The output of `scan`, handing its counters to `on_stats` after every
+    /// batch it reads.
+    pub fn from_scan(mut scan: TableScan<'a>, mut on_stats: impl FnMut(&ScanStats) + 'a) -> Self {
+        let column_names = scan.column_names.clone();
+        let column_types = scan.column_schemas.iter().map(|c| c.column_type).collect();
+        let estimated_rows = scan.estimated_rows();
+        let batches = std::iter::from_fn(move || {
+            let batch = scan.next_vectors();
+            on_stats(&scan.stats);
+            batch.map(|batch| batch.map(VectorBatch::into_batch))
+        });
+        BatchStream::new(column_names, column_types, estimated_rows, batches)
+    }
+
+    /// Qualifies every column name with `label`, as `label.column`.
+    pub fn qualified(mut self, label: &str) -> Self {
+        for name in &mut self.column_names {
+            *name = format!("{label}.{name}");
+        }
+        self
+    }
+
+    /// Index of the column called `name`.
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.column_names.iter().position(|n| n == name)
+    }
+
+    /// Outputs the input columns at `indices`, in that order, named `names`.
+    /// A column may be picked more than once.
+    pub fn project(self, indices: Vec<usize>, names: Vec<String>) -> Self {
+        let column_types = indices.iter().map(|&i| self.column_types[i]).collect();
+        let batches = self.batches.map(move |batch| {
+            let batch = batch?;
+            let columns = indices.iter().map(|&i| batch.columns[i].clone()).collect();
+            Ok(Batch { row_count: batch.row_count, columns })
+        });
+
+        BatchStream::new(names, column_types, self.estimated_rows, batches)
+    }
+
+    /// Ends the stream after its first `limit` rows.
+    pub fn limit(self, limit: usize) -> Self {
+        let mut input = self.batches;
+        let mut remaining = limit;
+        let batches = std::iter::from_fn(move || {
+            if remaining == 0 {
+                return None;
+            }
+            let mut batch = match input.next()? {
+                Ok(batch) => batch,
+                Err(e) => return Some(Err(e)),
+            };
+
+            if batch.row_count > remaining {
+                batch.row_count = remaining;
+                batch.columns.iter_mut().for_each(|column| column.truncate(remaining));
+            }
+            remaining -= batch.row_count;
+            Some(Ok(batch))
+        });
+
+        BatchStream::new(self.column_names, self.column_types, self.estimated_rows.min(limit as u64), batches)
+    }
+}
+
+impl<'a> From<TableScan<'a>> for BatchStream<'a> {
+    fn from(scan: TableScan<'a>) -> Self {
+        BatchStream::from_scan(scan, |_| {})
+    }
+}
+
+impl Iterator for BatchStream<'_> {
+    type Item = Result<Batch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.batches.next()
+    }
+}
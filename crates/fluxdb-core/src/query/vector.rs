@@ -0,0 +1,280 @@
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Range;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+use crate::query::batch::Batch;
+use crate::query::predicate::{take, SelectionVector};
+use crate::storage::page::Page;
+
+/// Rows per vector. Small enough for the vectors of every column a pipeline
+/// reads to stay in the CPU caches, large enough to spread the per-vector
+/// dispatch over many values.
+pub const VECTOR_SIZE: usize = 2048;
+
+/// The values of one column for a run of rows.
+///
+/// Fixed-width types live in typed arrays, so kernels over them are plain
+/// loops over primitives the compiler can vectorize. A null row holds the
+/// type's default there and is flagged in `validity`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vector {
+    pub data: VectorData,
+    /// Whether each row is non-null, `None` when every row is.
+    pub validity: Option<Vec<bool>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VectorData {
+    Int32(Vec<i32>),
+    Int64(Vec<i64>),
+    Float32(Vec<f32>),
+    Float64(Vec<f64>),
+    Bool(Vec<bool>),
+    /// Microseconds since the Unix epoch, as [`Value::Timestamp`].
+    Timestamp(Vec<i64>),
+    /// Every other type, nulls included as [`Value::Null`].
+    Values(Vec<Value>),
+}
+
+impl Vector {
+    /// An empty vector for values of `column_type`.
+    pub fn new(column_type: ColumnType) -> Vector {
+        let data = match column_type {
+            ColumnType::Integer32 => VectorData::Int32(Vec::new()),
+            ColumnType::Integer64 => VectorData::Int64(Vec::new()),
+            ColumnType::Float32 => VectorData::Float32(Vec::new()),
+            ColumnType::Float64 => VectorData::Float64(Vec::new()),
+            ColumnType::Boolean => VectorData::Bool(Vec::new()),
+            ColumnType::Timestamp => VectorData::Timestamp(Vec::new()),
+            _ => VectorData::Values(Vec::new()),
+        };
+        Vector { data, validity: None }
+    }
+
+    pub fn from_values(column_type: ColumnType, values: Vec<Value>) -> Vector {
+        let mut vector = Vector::new(column_type);
+        if let VectorData::Values(_) = vector.data {
+            vector.data = VectorData::Values(values);
+            return vector;
+        }
+        for value in &values {
+            vector.push(value);
+        }
+        vector
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.data {
+            VectorData::Int32(values) => values.len(),
+            VectorData::Int64(values) | VectorData::Timestamp(values) => values.len(),
+            VectorData::Float32(values) => values.len(),
+            VectorData::Float64(values) => values.len(),
+            VectorData::Bool(values) => values.len(),
+            VectorData::Values(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_valid(&self, row: usize) -> bool {
+        match (&self.validity, &self.data) {
+            (_, VectorData::Values(values)) => !values[row].is_null(),
+            (Some(validity), _) => validity[row],
+            (None, _) => true,
+        }
+    }
+
+    /// The value of one row.
+    pub fn value(&self, row: usize) -> Value {
+        if !self.is_valid(row) {
+            return Value::Null;
+        }
+        match &self.data {
+            VectorData::Int32(values) => Value::Int32(values[row]),
+            VectorData::Int64(values) => Value::Int64(values[row]),
+            VectorData::Float32(values) => Value::Float32(values[row]),
+            VectorData::Float64(values) => Value::Float64(values[row]),
+            VectorData::Bool(values) => Value::Bool(values[row]),
+            VectorData::Timestamp(values) => Value::Timestamp(values[row]),
+            VectorData::Values(values) => values[row].clone(),
+        }
+    }
+
+    /// Appends a value of the vector's type, or a null.
+    pub fn push(&mut self, value: &Value) {
+        let valid = !value.is_null();
+        match (&mut self.data, value) {
+            (VectorData::Int32(values), Value::Int32(v)) => values.push(*v),
+            (VectorData::Int64(values), Value::Int64(v)) => values.push(*v),
+            (VectorData::Float32(values), Value::Float32(v)) => values.push(*v),
+            (VectorData::Float64(values), Value::Float64(v)) => values.push(*v),
+            (VectorData::Bool(values), Value::Bool(v)) => values.push(*v),
+            (VectorData::Timestamp(values), Value::Timestamp(v)) => values.push(*v),
+            (VectorData::Values(values), value) => return values.push(value.clone()),
+            (VectorData::Int32(values), _) => values.push(0),
+            (VectorData::Int64(values) | VectorData::Timestamp(values), _) => values.push(0),
+            (VectorData::Float32(values), _) => values.push(0.0),
+            (VectorData::Float64(values), _) => values.push(0.0),
+            (VectorData::Bool(values), _) => values.push(false),
+        }
+        self.push_validity(valid);
+    }
+
+    fn push_validity(&mut self, valid: bool) {
+        match &mut self.validity {
+            Some(validity) => validity.push(valid),
+            None if valid => {}
+            None => {
+                let mut validity = vec![true; self.len() - 1];
+                validity.push(false);
+                self.validity = Some(validity);
+            }
+        }
+    }
+
+    /// Appends nulls up to `len` rows, for rows the column has no values for.
+    pub fn pad_nulls(&mut self, len: usize) {
+        while self.len() < len {
+            self.push(&Value::Null);
+        }
+    }
+
+    /// Drops the rows past `len`.
+    pub fn truncate(&mut self, len: usize) {
+        match &mut self.data {
+            VectorData::Int32(values) => values.truncate(len),
+            VectorData::Int64(values) | VectorData::Timestamp(values) => values.truncate(len),
+            VectorData::Float32(values) => values.truncate(len),
+            VectorData::Float64(values) => values.truncate(len),
+            VectorData::Bool(values) => values.truncate(len),
+            VectorData::Values(values) => values.truncate(len),
+        }
+        if let Some(validity) = &mut self.validity {
+            validity.truncate(len);
+        }
+    }
+
+    /// Appends every value of a data page holding values of `column_type`.
+    /// Fixed-width values are decoded straight into the typed array.
+    pub fn extend_from_page(&mut self, page: &Page, column_type: ColumnType) -> Result<()> {
+        if let VectorData::Values(values) = &mut self.data {
+            values.extend(page.read_values(column_type)?);
+            return Ok(());
+        }
+
+        let valid: Vec<bool> = (0..page.chunk_header().value_count).map(|i| page.is_value_valid(i)).collect();
+        let width = column_type.fixed_width().unwrap();
+        let data = page.value_data();
+        if data.len() < valid.iter().filter(|v| **v).count() * width {
+            return Err(Error::new(ErrorKind::InvalidData, format!("truncated {column_type:?} values")));
+        }
+
+        match &mut self.data {
+            VectorData::Int32(values) => decode(values, data, &valid, |b| i32::from_le_bytes(b.try_into().unwrap())),
+            VectorData::Int64(values) | VectorData::Timestamp(values) => {
+                decode(values, data, &valid, |b| i64::from_le_bytes(b.try_into().unwrap()))
+            }
+            VectorData::Float32(values) => decode(values, data, &valid, |b| f32::from_le_bytes(b.try_into().unwrap())),
+            VectorData::Float64(values) => decode(values, data, &valid, |b| f64::from_le_bytes(b.try_into().unwrap())),
+            VectorData::Bool(values) => decode(values, data, &valid, |b| b[0] != 0),
+            VectorData::Values(_) => unreachable!(),
+        }
+
+        match (&mut self.validity, valid.iter().all(|v| *v)) {
+            (Some(validity), _) => validity.extend(valid),
+            (None, true) => {}
+            (None, false) => {
+                let mut validity = vec![true; self.len() - valid.len()];
+                validity.extend(valid);
+                self.validity = Some(validity);
+            }
+        }
+        Ok(())
+    }
+
+    /// The rows in `range`, as a vector of their own.
+    pub fn slice(&self, range: Range<usize>) -> Vector {
+        let data = match &self.data {
+            VectorData::Int32(values) => VectorData::Int32(values[range.clone()].to_vec()),
+            VectorData::Int64(values) => VectorData::Int64(values[range.clone()].to_vec()),
+            VectorData::Float32(values) => VectorData::Float32(values[range.clone()].to_vec()),
+            VectorData::Float64(values) => VectorData::Float64(values[range.clone()].to_vec()),
+            VectorData::Bool(values) => VectorData::Bool(values[range.clone()].to_vec()),
+            VectorData::Timestamp(values) => VectorData::Timestamp(values[range.clone()].to_vec()),
+            VectorData::Values(values) => VectorData::Values(values[range.clone()].to_vec()),
+        };
+        let validity = self.validity.as_ref().map(|validity| validity[range].to_vec());
+        Vector { data, validity }
+    }
+
+    /// Splits the vector into vectors of up to [`VECTOR_SIZE`] rows.
+    pub fn split(self) -> Vec<Vector> {
+        if self.len() <= VECTOR_SIZE {
+            return vec![self];
+        }
+        (0..self.len())
+            .step_by(VECTOR_SIZE)
+            .map(|start| self.slice(start..(start + VECTOR_SIZE).min(self.len())))
+            .collect()
+    }
+
+    /// The values of the rows at the offsets in `selection`, or of every
+    /// row when `None`.
+    pub fn into_values(self, selection: Option<&[u32]>) -> Vec<Value> {
+        if let VectorData::Values(values) = self.data {
+            return match selection {
+                Some(selection) => take(values, selection),
+                None => values,
+            };
+        }
+        match selection {
+            Some(selection) => selection.iter().map(|&row| self.value(row as usize)).collect(),
+            None => (0..self.len()).map(|row| self.value(row)).collect(),
+        }
+    }
+}
+
+/// Rows passed between the vectorized parts of a query: a vector per
+/// column, and which of their rows are still selected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorBatch {
+    /// Rows in each vector, selected or not.
+    pub row_count: usize,
+    pub columns: Vec<Vector>,
+    /// Offsets of the selected rows, ascending, `None` when all are.
+    pub selection: Option<SelectionVector>,
+}
+
+impl VectorBatch {
+    /// Number of selected rows.
+    pub fn selected(&self) -> usize {
+        self.selection.as_ref().map_or(self.row_count, |s| s.len())
+    }
+
+    /// The selected rows as a row-at-a-time [`Batch`].
+    pub fn into_batch(self) -> Batch {
+        let selection = self.selection;
+        let columns = self.columns.into_iter().map(|c| c.into_values(selection.as_deref())).collect();
+        Batch { row_count: selection.map_or(self.row_count, |s| s.len()), columns }
+    }
+}
+
+/// Decodes the values of a page's rows, `valid` flagging the non-null ones;
+/// nulls take no bytes in `data` and read as the default. Pages without
+/// nulls decode in one pass over fixed-size chunks of the data.
+fn decode<T: Default>(values: &mut Vec<T>, data: &[u8], valid: &[bool], read: impl Fn(&[u8]) -> T) {
+    let width = std::mem::size_of::<T>();
+    let mut encoded = data.chunks_exact(width);
+    if valid.iter().all(|v| *v) {
+        values.extend(encoded.take(valid.len()).map(read));
+        return;
+    }
+    for &valid in valid {
+        values.push(match valid {
+            true => read(encoded.next().unwrap()),
+            false => T::default(),
+        });
+    }
+}
@@ -0,0 +1,254 @@
+use std::io::{Error, ErrorKind, Result};
+use crate::metadata::value::Value;
+use crate::query::expr::{CompareOp, Expr};
+use crate::query::predicate::{self, SelectionVector};
+use crate::query::vector::{Vector, VectorData};
+
+/// Evaluates `expr` over vectors of `row_count` rows, returning the offsets
+/// of the rows where it is true, as [`predicate::select`] does over values.
+///
+/// Comparisons of a column with a literal, `BETWEEN` and `IN` over literals
+/// and `IS NULL` run as kernels over the typed arrays, each narrowing a
+/// selection vector: `AND` hands the rows its left side selects to its right
+/// side, and `OR` only tries its right side on the rows its left side
+/// rejected. Anything else, such as `NOT` or `LIKE`, goes to
+/// [`predicate::select`] for the rows still selected.
+pub fn select_vectors(expr: &Expr, columns: &[(&str, &Vector)], row_count: usize) -> Result<SelectionVector> {
+    let selection: SelectionVector = (0..row_count as u32).collect();
+    Kernels { columns }.select(expr, &selection)
+}
+
+struct Kernels<'a> {
+    columns: &'a [(&'a str, &'a Vector)],
+}
+
+impl Kernels<'_> {
+    fn column(&self, name: &str) -> Result<&Vector> {
+        self.columns
+            .iter()
+            .find(|(column, _)| *column == name)
+            .map(|(_, vector)| *vector)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("column '{name}' not found")))
+    }
+
+    /// The rows of `selection` where `expr` is true.
+    fn select(&self, expr: &Expr, selection: &[u32]) -> Result<SelectionVector> {
+        if selection.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let selected = match expr {
+            Expr::And(left, right) => {
+                let left = self.select(left, selection)?;
+                self.select(right, &left)?
+            }
+            Expr::Or(left, right) => {
+                let left = self.select(left, selection)?;
+                let rejected = difference(selection, &left);
+                union(&left, &self.select(right, &rejected)?)
+            }
+            Expr::Literal(Value::Bool(true)) => selection.to_vec(),
+            Expr::Literal(Value::Bool(false) | Value::Null) => Vec::new(),
+            Expr::Compare { op, left, right } => {
+                let compared = match (left.as_ref(), right.as_ref()) {
+                    (Expr::Column(name), Expr::Literal(value)) => compare(self.column(name)?, *op, value, selection),
+                    (Expr::Literal(value), Expr::Column(name)) => compare(self.column(name)?, op.flip(), value, selection),
+                    _ => None,
+                };
+                match compared {
+                    Some(selected) => selected,
+                    None => self.fallback(expr, selection)?,
+                }
+            }
+            Expr::Between { expr: column, low, high, negated: false } => {
+                let bounds = match (column.as_ref(), low.as_ref(), high.as_ref()) {
+                    (Expr::Column(name), Expr::Literal(low), Expr::Literal(high)) => Some((self.column(name)?, low, high)),
+                    _ => None,
+                };
+                let selected = bounds.and_then(|(vector, low, high)| {
+                    let above = compare(vector, CompareOp::GtEq, low, selection)?;
+                    compare(vector, CompareOp::LtEq, high, &above)
+                });
+                match selected {
+                    Some(selected) => selected,
+                    None => self.fallback(expr, selection)?,
+                }
+            }
+            Expr::In { expr: column, list, negated: false } => {
+                // Null items only make a non-match null instead of false,
+                // which selects the row no more than false does
+                let selected = match column.as_ref() {
+                    Expr::Column(name) => {
+                        let vector = self.column(name)?;
+                        list.iter().try_fold(Vec::new(), |selected, item| match item {
+                            Expr::Literal(value) if value.is_null() => Some(selected),
+                            Expr::Literal(value) => {
+                                let rest = difference(selection, &selected);
+                                Some(union(&selected, &compare(vector, CompareOp::Eq, value, &rest)?))
+                            }
+                            _ => None,
+                        })
+                    }
+                    _ => None,
+                };
+                match selected {
+                    Some(selected) => selected,
+                    None => self.fallback(expr, selection)?,
+                }
+            }
+            Expr::IsNull { expr: column, negated } => match column.as_ref() {
+                Expr::Column(name) => {
+                    let vector = self.column(name)?;
+                    selection.iter().copied().filter(|&row| vector.is_valid(row as usize) == *negated).collect()
+                }
+                _ => self.fallback(expr, selection)?,
+            },
+            _ => self.fallback(expr, selection)?,
+        };
+
+        Ok(selected)
+    }
+
+    /// Evaluates `expr` value by value over the rows of `selection`.
+    fn fallback(&self, expr: &Expr, selection: &[u32]) -> Result<SelectionVector> {
+        let mut values = Vec::new();
+        for name in expr.columns() {
+            let vector = self.column(name)?;
+            values.push((name, selection.iter().map(|&row| vector.value(row as usize)).collect::<Vec<_>>()));
+        }
+
+        let columns: Vec<(&str, &[Value])> = values.iter().map(|(name, values)| (*name, values.as_slice())).collect();
+        let selected = predicate::select(expr, &columns, selection.len())?;
+        Ok(selected.into_iter().map(|i| selection[i as usize]).collect())
+    }
+}
+
+/// The rows of `selection` where `vector op literal` holds, `None` for a
+/// literal the vector's type does not compare with here.
+///
+/// Integers compare with integer literals as integers and with other
+/// numbers as floats, as [`Value::compare`] does.
+fn compare(vector: &Vector, op: CompareOp, literal: &Value, selection: &[u32]) -> Option<SelectionVector> {
+    if literal.is_null() {
+        return Some(Vec::new());
+    }
+
+    let validity = vector.validity.as_deref();
+    Some(match (&vector.data, literal) {
+        (VectorData::Int32(data), _) => match (literal.as_i64(), literal.as_f64()) {
+            (Some(literal), _) => kernel(data, validity, selection, op, literal, |v| v as i64),
+            (None, Some(literal)) => kernel(data, validity, selection, op, literal, |v| v as f64),
+            _ => return None,
+        },
+        (VectorData::Int64(data), _) => match (literal.as_i64(), literal.as_f64()) {
+            (Some(literal), _) => kernel(data, validity, selection, op, literal, |v| v),
+            (None, Some(literal)) => kernel(data, validity, selection, op, literal, |v| v as f64),
+            _ => return None,
+        },
+        (VectorData::Float32(data), _) => kernel(data, validity, selection, op, literal.as_f64()?, |v| v as f64),
+        (VectorData::Float64(data), _) => kernel(data, validity, selection, op, literal.as_f64()?, |v| v),
+        (VectorData::Timestamp(data), Value::Timestamp(literal)) => kernel(data, validity, selection, op, *literal, |v| v),
+        (VectorData::Bool(data), Value::Bool(literal)) => kernel(data, validity, selection, op, *literal, |v| v),
+        (VectorData::Values(values), _) => selection
+            .iter()
+            .copied()
+            .filter(|&row| values[row as usize].compare(literal).is_some_and(|o| op.matches(o)))
+            .collect(),
+        _ => return None,
+    })
+}
+
+/// Picks the loop for `op`, so the comparison inside it is fixed.
+fn kernel<T: Copy, U: PartialOrd + Copy>(
+    data: &[T],
+    validity: Option<&[bool]>,
+    selection: &[u32],
+    op: CompareOp,
+    literal: U,
+    cast: impl Fn(T) -> U,
+) -> SelectionVector {
+    match op {
+        CompareOp::Eq => filter(data, validity, selection, |v| cast(v) == literal),
+        // Written as two comparisons so NaN is unequal to nothing, like a null
+        CompareOp::NotEq => filter(data, validity, selection, |v| cast(v) < literal || cast(v) > literal),
+        CompareOp::Lt => filter(data, validity, selection, |v| cast(v) < literal),
+        CompareOp::LtEq => filter(data, validity, selection, |v| cast(v) <= literal),
+        CompareOp::Gt => filter(data, validity, selection, |v| cast(v) > literal),
+        CompareOp::GtEq => filter(data, validity, selection, |v| cast(v) >= literal),
+    }
+}
+
+/// The rows of `selection` with a non-null value passing `test`.
+///
+/// Every row is written out and the output only advances past the ones
+/// that pass, so the loop has no branch on the outcome. When every row is
+/// selected it runs straight over the array.
+#[inline(always)]
+fn filter<T: Copy>(data: &[T], validity: Option<&[bool]>, selection: &[u32], test: impl Fn(T) -> bool) -> SelectionVector {
+    let mut out = vec![0u32; selection.len()];
+    let mut n = 0;
+    let dense = selection.len() == data.len();
+
+    match (validity, dense) {
+        (None, true) => {
+            for (row, &value) in data.iter().enumerate() {
+                out[n] = row as u32;
+                n += test(value) as usize;
+            }
+        }
+        (Some(validity), true) => {
+            for (row, (&value, &valid)) in data.iter().zip(validity).enumerate() {
+                out[n] = row as u32;
+                n += (test(value) & valid) as usize;
+            }
+        }
+        (None, false) => {
+            for &row in selection {
+                out[n] = row;
+                n += test(data[row as usize]) as usize;
+            }
+        }
+        (Some(validity), false) => {
+            for &row in selection {
+                out[n] = row;
+                n += (test(data[row as usize]) & validity[row as usize]) as usize;
+            }
+        }
+    }
+
+    out.truncate(n);
+    out
+}
+
+/// Rows of the ascending `all` not in its subset `subset`.
+fn difference(all: &[u32], subset: &[u32]) -> SelectionVector {
+    let mut subset = subset.iter().peekable();
+    all.iter()
+        .copied()
+        .filter(|row| match subset.peek() {
+            Some(&&next) if next == *row => {
+                subset.next();
+                false
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+/// Rows in either of two disjoint ascending selections, ascending.
+fn union(a: &[u32], b: &[u32]) -> SelectionVector {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] < b[j] {
+            out.push(a[i]);
+            i += 1;
+        } else {
+            out.push(b[j]);
+            j += 1;
+        }
+    }
+    out.extend_from_slice(&a[i..]);
+    out.extend_from_slice(&b[j..]);
+    out
+}
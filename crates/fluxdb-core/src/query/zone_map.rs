@@ -0,0 +1,158 @@
+use crate::metadata::chunks::chunk_meta::ChunkMeta;
+use crate::metadata::value::Value;
+use crate::query::expr::{CompareOp, Expr};
+use crate::query::like::LikePattern;
+
+/// Statistics of one column over a chunk row range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneMap {
+    /// `None` when every value is null, or the bound was too long to keep.
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    pub null_count: u64,
+    pub row_count: u64,
+}
+
+impl ZoneMap {
+    pub fn from_chunk(chunk: &ChunkMeta) -> ZoneMap {
+        ZoneMap {
+            min: chunk.min.clone(),
+            max: chunk.max.clone(),
+            null_count: u64::from(chunk.null_count),
+            row_count: u64::from(chunk.value_count),
+        }
+    }
+
+    fn all_null(&self) -> bool {
+        self.null_count == self.row_count
+    }
+
+    /// Whether some value `v` of the range may satisfy `v op literal`.
+    fn may_compare(&self, op: CompareOp, literal: &Value) -> bool {
+        if self.all_null() || literal.is_null() {
+            return false;
+        }
+        let (Some(min), Some(max)) = (&self.min, &self.max) else {
+            return true;
+        };
+
+        // Values with no order against the literal (NaN) never compare true
+        let (Some(low), Some(high)) = (min.compare(literal), max.compare(literal)) else {
+            return true;
+        };
+
+        match op {
+            CompareOp::Eq => low.is_le() && high.is_ge(),
+            CompareOp::NotEq => !(low.is_eq() && high.is_eq()),
+            CompareOp::Lt => low.is_lt(),
+            CompareOp::LtEq => low.is_le(),
+            CompareOp::Gt => high.is_gt(),
+            CompareOp::GtEq => high.is_ge(),
+        }
+    }
+
+    /// Whether some value may match a `LIKE` pattern, judged on its prefix.
+    fn may_like(&self, pattern: &str) -> bool {
+        if self.all_null() {
+            return false;
+        }
+        let (Some(Value::String(min)), Some(Value::String(max)), Some(prefix)) =
+            (&self.min, &self.max, LikePattern::new(pattern).prefix())
+        else {
+            return true;
+        };
+
+        // Strings starting with the prefix sort together, right from the prefix itself
+        let after_prefix = |s: &str| s > prefix.as_str() && !s.starts_with(prefix.as_str());
+        !(max.as_str() < prefix.as_str() || after_prefix(min))
+    }
+}
+
+/// Whether the chunks of a column ascend: every chunk's min is at or above
+/// the previous chunk's max, as for a column appended in order. The chunk
+/// index of such a column works as a sparse index on it. Chunks without
+/// bounds (all null, or too long to keep) break the order.
+pub fn ascending(chunks: &[ChunkMeta]) -> bool {
+    chunks.iter().all(|chunk| chunk.min.is_some() && chunk.max.is_some())
+        && chunks.windows(2).all(|pair| {
+            let (Some(max), Some(min)) = (&pair[0].max, &pair[1].min) else {
+                return false;
+            };
+            min.compare(max).is_some_and(|order| order.is_ge())
+        })
+}
+
+/// Whether some row of a chunk range may satisfy `expr`, judging from the
+/// zone maps `zone_map` returns by column name. Returning `false` proves no
+/// row does, so the range can be skipped without reading it; any part of
+/// the expression the zone maps cannot decide counts as a possible match.
+pub fn may_match(expr: &Expr, zone_map: &dyn Fn(&str) -> Option<ZoneMap>) -> bool {
+    may_be(expr, true, zone_map)
+}
+
+/// Whether `expr` may evaluate to `want` for some row. Nulls never do, so
+/// negation is exact: `NOT e` may be true only where `e` may be false.
+fn may_be(expr: &Expr, want: bool, zone_map: &dyn Fn(&str) -> Option<ZoneMap>) -> bool {
+    let column = |expr: &Expr| match expr {
+        Expr::Column(name) => zone_map(name),
+        _ => None,
+    };
+    let literal = |expr: &Expr| match expr {
+        Expr::Literal(value) => Some(value.clone()),
+        _ => None,
+    };
+
+    match expr {
+        Expr::Literal(value) => *value == Value::Bool(want),
+        Expr::Column(_) => column(expr).is_none_or(|zone| zone.may_compare(CompareOp::Eq, &Value::Bool(want))),
+        Expr::And(left, right) if want => may_be(left, true, zone_map) && may_be(right, true, zone_map),
+        Expr::And(left, right) => may_be(left, false, zone_map) || may_be(right, false, zone_map),
+        Expr::Or(left, right) if want => may_be(left, true, zone_map) || may_be(right, true, zone_map),
+        Expr::Or(left, right) => may_be(left, false, zone_map) && may_be(right, false, zone_map),
+        Expr::Not(inner) => may_be(inner, !want, zone_map),
+        Expr::Compare { op, left, right } => {
+            let op = if want { *op } else { op.negate() };
+            match (column(left), literal(right), column(right), literal(left)) {
+                (Some(zone), Some(value), _, _) => zone.may_compare(op, &value),
+                (_, _, Some(zone), Some(value)) => zone.may_compare(op.flip(), &value),
+                _ => true,
+            }
+        }
+        Expr::IsNull { expr, negated } => match column(expr) {
+            Some(zone) if want != *negated => zone.null_count > 0,
+            Some(zone) => !zone.all_null(),
+            None => true,
+        },
+        Expr::In { expr, list, negated } => {
+            let Some(zone) = column(expr) else {
+                return true;
+            };
+            let Some(values) = list.iter().map(literal).collect::<Option<Vec<_>>>() else {
+                return true;
+            };
+
+            if want != *negated {
+                values.iter().any(|value| zone.may_compare(CompareOp::Eq, value))
+            } else {
+                // NOT IN is null, not true, when the list holds a null
+                !values.iter().any(Value::is_null) && values.iter().all(|value| zone.may_compare(CompareOp::NotEq, value))
+            }
+        }
+        Expr::Between { expr, low, high, negated } => {
+            let (Some(zone), Some(low), Some(high)) = (column(expr), literal(low), literal(high)) else {
+                return true;
+            };
+
+            if want != *negated {
+                zone.may_compare(CompareOp::GtEq, &low) && zone.may_compare(CompareOp::LtEq, &high)
+            } else {
+                zone.may_compare(CompareOp::Lt, &low) || zone.may_compare(CompareOp::Gt, &high)
+            }
+        }
+        Expr::Like { expr, pattern, negated } => match column(expr) {
+            Some(zone) if want != *negated => zone.may_like(pattern),
+            Some(zone) => !zone.all_null(),
+            None => true,
+        },
+    }
+}
@@ -0,0 +1,116 @@
+use crate::metadata::schema::column_type::ColumnType;
+use crate::query::aggregate::Aggregate;
+use crate::query::explain::ExplainFormat;
+use crate::query::expr::Expr;
+use crate::query::hash_join::JoinType;
+
+/// A parsed SQL statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Select(Select),
+    /// `EXPLAIN [ANALYZE] [FORMAT TEXT | JSON] SELECT ...`.
+    Explain { analyze: bool, format: ExplainFormat, select: Select },
+    CreateTable(CreateTable),
+    AlterTable(AlterTable),
+    /// `DROP TABLE [IF EXISTS] name`.
+    DropTable { name: String, if_exists: bool },
+}
+
+/// `SELECT items FROM table [JOIN ...] [WHERE ...] [GROUP BY ...]
+/// [ORDER BY ...] [LIMIT n]`. Column names are as written, possibly
+/// qualified by a table name or alias (`u.id`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Select {
+    pub items: Vec<SelectItem>,
+    pub from: TableRef,
+    pub joins: Vec<Join>,
+    pub filter: Option<Expr>,
+    pub group_by: Vec<String>,
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectItem {
+    /// `*`: every top-level column of every table.
+    Wildcard,
+    Column { name: String, alias: Option<String> },
+    /// An aggregate, its alias in [`Aggregate::alias`].
+    Aggregate(Aggregate),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRef {
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+impl TableRef {
+    /// The name the query refers to the table by: its alias, if any.
+    pub fn label(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// `[INNER | LEFT [OUTER]] JOIN table ON a = b [AND c = d ...]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join {
+    pub join_type: JoinType,
+    pub table: TableRef,
+    /// Pairs of columns required equal, as written.
+    pub on: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderTarget {
+    /// A column, or the alias of a select item.
+    Column(String),
+    Aggregate(Aggregate),
+    /// A select item by 1-based position.
+    Position(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    pub target: OrderTarget,
+    pub descending: bool,
+    /// `NULLS FIRST` / `NULLS LAST`, `None` for the direction's default.
+    pub nulls_first: Option<bool>,
+}
+
+/// `CREATE TABLE [IF NOT EXISTS] name (column type [NOT NULL], ...)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateTable {
+    pub name: String,
+    pub if_not_exists: bool,
+    pub columns: Vec<ColumnDef>,
+}
+
+/// A column definition. `List` and `Struct` columns carry their element
+/// column or fields as children.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnDef {
+    pub name: String,
+    pub column_type: ColumnType,
+    pub not_null: bool,
+    pub children: Vec<ColumnDef>,
+}
+
+/// `ALTER TABLE name action`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlterTable {
+    pub table: String,
+    pub action: AlterAction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlterAction {
+    /// `ADD [COLUMN] column type [NOT NULL]`.
+    AddColumn(ColumnDef),
+    /// `DROP [COLUMN] column`.
+    DropColumn(String),
+    /// `RENAME TO name`.
+    RenameTable(String),
+    /// `RENAME [COLUMN] column TO name`.
+    RenameColumn { from: String, to: String },
+}
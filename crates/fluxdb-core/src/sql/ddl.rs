@@ -0,0 +1,118 @@
+use std::io::{Error, ErrorKind, Result};
+use crate::engine::database::Database;
+use crate::sql::ast::{AlterAction, ColumnDef, CreateTable, Statement};
+use crate::sql::parser::parse_script;
+
+/// Runs a script of `;`-separated `CREATE TABLE`, `ALTER TABLE` and `DROP
+/// TABLE` statements against `db`, in order, and returns how many ran.
+/// Stops at the first failing statement; the ones before it stay applied.
+pub fn execute_script(db: &mut Database, sql: &str) -> Result<usize> {
+    let statements = parse_script(sql)?;
+    for statement in &statements {
+        execute_ddl(db, statement)?;
+    }
+    Ok(statements.len())
+}
+
+/// Applies one schema statement to `db`.
+pub fn execute_ddl(db: &mut Database, statement: &Statement) -> Result<()> {
+    match statement {
+        Statement::Select(_) | Statement::Explain { .. } => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "SELECT and EXPLAIN return rows, run them with Database::query",
+        )),
+        Statement::CreateTable(create) => create_table(db, create),
+        Statement::AlterTable(alter) => {
+            let table = alter.table.as_str();
+            match &alter.action {
+                AlterAction::AddColumn(column) => {
+                    let table_id = *db.catalog.tables_by_name.get(table).ok_or_else(|| table_not_found(table))?;
+                    let exists = db.catalog.columns_by_table
+                        .get(&table_id)
+                        .is_some_and(|columns| columns.iter().any(|c| c.name == column.name));
+                    if exists {
+                        return Err(Error::new(
+                            ErrorKind::AlreadyExists,
+                            format!("column '{}' already exists in table '{table}'", column.name),
+                        ));
+                    }
+                    check_column(column)?;
+                    add_column(db, table, column)
+                }
+                AlterAction::DropColumn(column) => db.drop_column(table, column),
+                AlterAction::RenameTable(name) => db.rename_table(table, name),
+                AlterAction::RenameColumn { from, to } => db.rename_column(table, from, to),
+            }
+        }
+        Statement::DropTable { name, if_exists } => {
+            if *if_exists && !db.catalog.tables_by_name.contains_key(name) {
+                return Ok(());
+            }
+            db.drop_table(name)
+        }
+    }
+}
+
+fn create_table(db: &mut Database, create: &CreateTable) -> Result<()> {
+    if db.catalog.tables_by_name.contains_key(&create.name) {
+        return match create.if_not_exists {
+            true => Ok(()),
+            false => Err(Error::new(ErrorKind::AlreadyExists, format!("table '{}' already exists", create.name))),
+        };
+    }
+
+    // Everything is checked up front so a bad definition creates nothing
+    check_unique(&create.columns, &create.name)?;
+    for column in &create.columns {
+        check_column(column)?;
+    }
+
+    db.create_table(&create.name)?;
+    for column in &create.columns {
+        add_column(db, &create.name, column)?;
+    }
+    Ok(())
+}
+
+/// Adds a top-level column and its nested children.
+fn add_column(db: &mut Database, table: &str, column: &ColumnDef) -> Result<()> {
+    match column.not_null {
+        true => db.add_not_null_column(table, &column.name, column.column_type)?,
+        false => db.add_column(table, &column.name, column.column_type)?,
+    }
+    add_children(db, table, &column.name, column)
+}
+
+fn add_children(db: &mut Database, table: &str, path: &str, column: &ColumnDef) -> Result<()> {
+    for child in &column.children {
+        db.add_child_column(table, path, &child.name, child.column_type)?;
+        add_children(db, table, &format!("{path}.{}", child.name), child)?;
+    }
+    Ok(())
+}
+
+/// Checks the name of a column and of its nested fields. A dot would make
+/// the column unreachable by its dotted path.
+fn check_column(column: &ColumnDef) -> Result<()> {
+    if column.name.contains('.') {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("column name '{}' contains a '.'", column.name)));
+    }
+    check_unique(&column.children, &column.name)?;
+    column.children.iter().try_for_each(check_column)
+}
+
+fn check_unique(columns: &[ColumnDef], owner: &str) -> Result<()> {
+    for (i, column) in columns.iter().enumerate() {
+        if columns[..i].iter().any(|c| c.name == column.name) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("column '{}' is defined twice in '{owner}'", column.name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn table_not_found(table: &str) -> Error {
+    Error::new(ErrorKind::NotFound, format!("table '{table}' not found"))
+}
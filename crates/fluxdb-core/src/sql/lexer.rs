@@ -0,0 +1,179 @@
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use crate::query::expr::CompareOp;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    /// A keyword or an unquoted identifier, as written.
+    Word(String),
+    /// A `"double quoted"` identifier, never a keyword.
+    QuotedIdent(String),
+    /// Number literal text, parsed by the parser.
+    Number(String),
+    /// A `'single quoted'` string literal.
+    Text(String),
+    Compare(CompareOp),
+    /// One of `, . ( ) * ; - +`.
+    Symbol(char),
+    End,
+}
+
+impl Token {
+    /// Whether the token is the keyword `keyword`, matched case-insensitively.
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => f.write_str(word),
+            Token::QuotedIdent(name) => write!(f, "\"{name}\""),
+            Token::Number(text) => f.write_str(text),
+            Token::Text(text) => write!(f, "'{text}'"),
+            Token::Compare(op) => f.write_str(op.symbol()),
+            Token::Symbol(c) => write!(f, "{c}"),
+            Token::End => f.write_str("end of input"),
+        }
+    }
+}
+
+/// A token and the byte offset it starts at, for error messages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned {
+    pub token: Token,
+    pub offset: usize,
+}
+
+pub fn syntax_error(offset: usize, message: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, format!("syntax error at offset {offset}: {message}"))
+}
+
+/// Splits SQL text into tokens, ending with [`Token::End`]. Skips
+/// whitespace, `-- line` comments and `/* block */` comments.
+pub fn tokenize(sql: &str) -> Result<Vec<Spanned>> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let start = pos;
+        let c = bytes[pos];
+
+        let token = match c {
+            b if b.is_ascii_whitespace() => {
+                pos += 1;
+                continue;
+            }
+            b'-' if bytes.get(pos + 1) == Some(&b'-') => {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+                continue;
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                let end = sql[pos + 2..]
+                    .find("*/")
+                    .ok_or_else(|| syntax_error(start, "unterminated comment"))?;
+                pos += end + 4;
+                continue;
+            }
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
+                while pos < bytes.len() && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_') {
+                    pos += 1;
+                }
+                Token::Word(sql[start..pos].to_string())
+            }
+            b'0'..=b'9' => {
+                while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                    pos += 1;
+                }
+                if bytes.get(pos) == Some(&b'.') && bytes.get(pos + 1).is_some_and(u8::is_ascii_digit) {
+                    pos += 1;
+                    while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                        pos += 1;
+                    }
+                }
+                if matches!(bytes.get(pos), Some(b'e' | b'E')) {
+                    let digits = match bytes.get(pos + 1) {
+                        Some(b'+' | b'-') => pos + 2,
+                        _ => pos + 1,
+                    };
+                    if bytes.get(digits).is_some_and(u8::is_ascii_digit) {
+                        pos = digits;
+                        while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                            pos += 1;
+                        }
+                    }
+                }
+                Token::Number(sql[start..pos].to_string())
+            }
+            b'\'' | b'"' => {
+                let (text, end) = quoted(sql, pos, c)?;
+                pos = end;
+                match c {
+                    b'\'' => Token::Text(text),
+                    _ => Token::QuotedIdent(text),
+                }
+            }
+            b'=' => {
+                pos += 1;
+                Token::Compare(CompareOp::Eq)
+            }
+            b'<' | b'>' | b'!' => {
+                let next = bytes.get(pos + 1).copied();
+                let (op, len) = match (c, next) {
+                    (b'<', Some(b'=')) => (CompareOp::LtEq, 2),
+                    (b'<', Some(b'>')) => (CompareOp::NotEq, 2),
+                    (b'<', _) => (CompareOp::Lt, 1),
+                    (b'>', Some(b'=')) => (CompareOp::GtEq, 2),
+                    (b'>', _) => (CompareOp::Gt, 1),
+                    (_, Some(b'=')) => (CompareOp::NotEq, 2),
+                    _ => return Err(syntax_error(start, "unexpected character '!'")),
+                };
+                pos += len;
+                Token::Compare(op)
+            }
+            b',' | b'.' | b'(' | b')' | b'*' | b';' | b'-' | b'+' => {
+                pos += 1;
+                Token::Symbol(c as char)
+            }
+            _ => {
+                let c = sql[pos..].chars().next().unwrap_or('?');
+                return Err(syntax_error(start, &format!("unexpected character '{c}'")));
+            }
+        };
+
+        tokens.push(Spanned { token, offset: start });
+    }
+
+    tokens.push(Spanned { token: Token::End, offset: sql.len() });
+    Ok(tokens)
+}
+
+/// Reads the text quoted by `quote` starting at `start`, where a doubled
+/// quote stands for one. Returns the text and the offset past the closing quote.
+fn quoted(sql: &str, start: usize, quote: u8) -> Result<(String, usize)> {
+    let bytes = sql.as_bytes();
+    let mut text = String::new();
+    let mut pos = start + 1;
+    let mut run = pos;
+
+    loop {
+        match bytes.get(pos) {
+            None => return Err(syntax_error(start, "unterminated quoted text")),
+            Some(&b) if b == quote => {
+                text.push_str(&sql[run..pos]);
+                if bytes.get(pos + 1) == Some(&quote) {
+                    text.push(quote as char);
+                    pos += 2;
+                    run = pos;
+                } else {
+                    return Ok((text, pos + 1));
+                }
+            }
+            Some(_) => pos += 1,
+        }
+    }
+}
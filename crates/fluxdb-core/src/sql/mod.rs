@@ -0,0 +1,6 @@
+pub mod ast;
+pub mod ddl;
+pub mod lexer;
+pub mod parser;
+pub mod planner;
+pub mod query;
@@ -0,0 +1,626 @@
+use std::io::{Error, Result};
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+use crate::query::aggregate::{Aggregate, AggregateFunction};
+use crate::query::explain::ExplainFormat;
+use crate::query::expr::{CompareOp, Expr};
+use crate::query::hash_join::JoinType;
+use crate::sql::ast::{
+    AlterAction, AlterTable, ColumnDef, CreateTable, Join, OrderBy, OrderTarget, Select, SelectItem, Statement,
+    TableRef,
+};
+use crate::sql::lexer::{syntax_error, tokenize, Spanned, Token};
+
+/// Words that cannot be used as unquoted identifiers or aliases.
+const RESERVED: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "GROUP", "BY", "ORDER", "LIMIT", "JOIN", "INNER", "LEFT", "OUTER", "ON",
+    "AS", "AND", "OR", "NOT", "IN", "IS", "NULL", "BETWEEN", "LIKE", "ASC", "DESC", "NULLS", "TRUE",
+    "FALSE",
+];
+
+/// Parses one SQL statement, optionally ended by `;`.
+pub fn parse(sql: &str) -> Result<Statement> {
+    let mut parser = Parser { tokens: tokenize(sql)?, pos: 0 };
+    let statement = parser.statement()?;
+
+    parser.symbol(';');
+    if *parser.peek() != Token::End {
+        return Err(parser.unexpected("end of statement"));
+    }
+    Ok(statement)
+}
+
+/// Parses a script of statements separated by `;`.
+pub fn parse_script(sql: &str) -> Result<Vec<Statement>> {
+    let mut parser = Parser { tokens: tokenize(sql)?, pos: 0 };
+    let mut statements = Vec::new();
+
+    loop {
+        while parser.symbol(';') {}
+        if *parser.peek() == Token::End {
+            return Ok(statements);
+        }
+        statements.push(parser.statement()?);
+        if !parser.symbol(';') && *parser.peek() != Token::End {
+            return Err(parser.unexpected("';' or end of input"));
+        }
+    }
+}
+
+/// Recursive-descent parser over the tokens of one statement.
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        self.peek_at(0)
+    }
+
+    /// The token `n` places ahead; the end token repeats past the end.
+    fn peek_at(&self, n: usize) -> &Token {
+        let index = (self.pos + n).min(self.tokens.len() - 1);
+        &self.tokens[index].token
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens[self.pos.min(self.tokens.len() - 1)].offset
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn unexpected(&self, expected: &str) -> Error {
+        syntax_error(self.offset(), &format!("expected {expected}, found {}", self.peek()))
+    }
+
+    /// Consumes the keyword if it is next.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek().is_keyword(keyword);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        match self.keyword(keyword) {
+            true => Ok(()),
+            false => Err(self.unexpected(keyword)),
+        }
+    }
+
+    /// Consumes the symbol if it is next.
+    fn symbol(&mut self, symbol: char) -> bool {
+        let found = *self.peek() == Token::Symbol(symbol);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<()> {
+        match self.symbol(symbol) {
+            true => Ok(()),
+            false => Err(self.unexpected(&format!("'{symbol}'"))),
+        }
+    }
+
+    fn is_identifier(&self) -> bool {
+        match self.peek() {
+            Token::Word(word) => !RESERVED.iter().any(|r| word.eq_ignore_ascii_case(r)),
+            Token::QuotedIdent(_) => true,
+            _ => false,
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String> {
+        if !self.is_identifier() {
+            return Err(self.unexpected("an identifier"));
+        }
+        match self.advance() {
+            Token::Word(name) | Token::QuotedIdent(name) => Ok(name),
+            _ => unreachable!(),
+        }
+    }
+
+    /// A possibly qualified or nested column name, `a.b.c`.
+    fn name(&mut self) -> Result<String> {
+        let mut name = self.identifier()?;
+        while self.symbol('.') {
+            name.push('.');
+            name.push_str(&self.identifier()?);
+        }
+        Ok(name)
+    }
+
+    /// `[AS] alias`, where `AS` may only be left out before a plain identifier.
+    fn alias(&mut self) -> Result<Option<String>> {
+        if self.keyword("AS") || self.is_identifier() {
+            return self.identifier().map(Some);
+        }
+        Ok(None)
+    }
+
+    fn statement(&mut self) -> Result<Statement> {
+        if self.peek().is_keyword("SELECT") {
+            return self.select().map(Statement::Select);
+        }
+        if self.keyword("EXPLAIN") {
+            let analyze = self.keyword("ANALYZE");
+            let mut format = ExplainFormat::Text;
+            if self.keyword("FORMAT") {
+                format = match self.keyword("JSON") {
+                    true => ExplainFormat::Json,
+                    false => {
+                        self.expect_keyword("TEXT")?;
+                        ExplainFormat::Text
+                    }
+                };
+            }
+            if !self.peek().is_keyword("SELECT") {
+                return Err(self.unexpected("SELECT"));
+            }
+            return Ok(Statement::Explain { analyze, format, select: self.select()? });
+        }
+        if self.keyword("CREATE") {
+            return self.create_table().map(Statement::CreateTable);
+        }
+        if self.keyword("ALTER") {
+            return self.alter_table().map(Statement::AlterTable);
+        }
+        if self.keyword("DROP") {
+            self.expect_keyword("TABLE")?;
+            let if_exists = self.keyword("IF");
+            if if_exists {
+                self.expect_keyword("EXISTS")?;
+            }
+            return Ok(Statement::DropTable { name: self.identifier()?, if_exists });
+        }
+        Err(self.unexpected("SELECT, EXPLAIN, CREATE, ALTER or DROP"))
+    }
+
+    fn create_table(&mut self) -> Result<CreateTable> {
+        self.expect_keyword("TABLE")?;
+        let if_not_exists = self.keyword("IF");
+        if if_not_exists {
+            self.expect_keyword("NOT")?;
+            self.expect_keyword("EXISTS")?;
+        }
+        let name = self.identifier()?;
+
+        self.expect_symbol('(')?;
+        let mut columns = vec![self.column_def()?];
+        while self.symbol(',') {
+            columns.push(self.column_def()?);
+        }
+        self.expect_symbol(')')?;
+
+        Ok(CreateTable { name, if_not_exists, columns })
+    }
+
+    fn alter_table(&mut self) -> Result<AlterTable> {
+        self.expect_keyword("TABLE")?;
+        let table = self.identifier()?;
+
+        let action = if self.keyword("ADD") {
+            self.keyword("COLUMN");
+            AlterAction::AddColumn(self.column_def()?)
+        } else if self.keyword("DROP") {
+            self.keyword("COLUMN");
+            AlterAction::DropColumn(self.identifier()?)
+        } else if self.keyword("RENAME") {
+            if self.keyword("TO") {
+                AlterAction::RenameTable(self.identifier()?)
+            } else {
+                self.keyword("COLUMN");
+                let from = self.identifier()?;
+                self.expect_keyword("TO")?;
+                AlterAction::RenameColumn { from, to: self.identifier()? }
+            }
+        } else {
+            return Err(self.unexpected("ADD, DROP or RENAME"));
+        };
+
+        Ok(AlterTable { table, action })
+    }
+
+    /// `name type [NOT NULL | NULL]`.
+    fn column_def(&mut self) -> Result<ColumnDef> {
+        let mut column = self.field_def()?;
+        if self.keyword("NOT") {
+            self.expect_keyword("NULL")?;
+            column.not_null = true;
+        } else {
+            self.keyword("NULL");
+        }
+        Ok(column)
+    }
+
+    /// `name type`, as a column or a struct field.
+    fn field_def(&mut self) -> Result<ColumnDef> {
+        let name = self.identifier()?;
+        let (column_type, children) = self.column_type()?;
+        Ok(ColumnDef { name, column_type, not_null: false, children })
+    }
+
+    /// A type name, with the element column of a `LIST<type>` or the fields
+    /// of a `STRUCT<name type, ...>`.
+    fn column_type(&mut self) -> Result<(ColumnType, Vec<ColumnDef>)> {
+        let offset = self.offset();
+        let Token::Word(word) = self.advance() else {
+            return Err(syntax_error(offset, "expected a type name"));
+        };
+
+        let column_type = match word.to_ascii_uppercase().as_str() {
+            "INT" | "INTEGER" | "INT32" => ColumnType::Integer32,
+            "BIGINT" | "INT64" => ColumnType::Integer64,
+            "REAL" | "FLOAT" | "FLOAT32" => ColumnType::Float32,
+            "DOUBLE" => {
+                self.keyword("PRECISION");
+                ColumnType::Float64
+            }
+            "FLOAT64" => ColumnType::Float64,
+            "TEXT" | "STRING" | "UTF8" => ColumnType::Utf8,
+            "VARCHAR" => {
+                // The length is accepted but not enforced
+                if self.symbol('(') {
+                    self.count()?;
+                    self.expect_symbol(')')?;
+                }
+                ColumnType::Utf8
+            }
+            "TIMESTAMP" => ColumnType::Timestamp,
+            "BOOLEAN" | "BOOL" => ColumnType::Boolean,
+            "BINARY" if *self.peek() == Token::Symbol('(') => {
+                self.advance();
+                let size_offset = self.offset();
+                let size = self.count()?;
+                self.expect_symbol(')')?;
+                let size = u16::try_from(size)
+                    .ok()
+                    .filter(|size| *size > 0)
+                    .ok_or_else(|| syntax_error(size_offset, &format!("BINARY size {size} is out of range")))?;
+                ColumnType::FixedSizeBinary(size)
+            }
+            "BINARY" | "VARBINARY" | "BLOB" | "BYTEA" => ColumnType::Binary,
+            "UUID" => ColumnType::Uuid,
+            "LIST" => {
+                self.expect_angle(CompareOp::Lt, "'<'")?;
+                let (column_type, children) = self.column_type()?;
+                self.expect_angle(CompareOp::Gt, "'>'")?;
+                let item = ColumnDef { name: "item".to_string(), column_type, not_null: false, children };
+                return Ok((ColumnType::List, vec![item]));
+            }
+            "STRUCT" => {
+                self.expect_angle(CompareOp::Lt, "'<'")?;
+                let mut fields = vec![self.field_def()?];
+                while self.symbol(',') {
+                    fields.push(self.field_def()?);
+                }
+                self.expect_angle(CompareOp::Gt, "'>'")?;
+                return Ok((ColumnType::Struct, fields));
+            }
+            _ => return Err(syntax_error(offset, &format!("unknown type {word}"))),
+        };
+
+        Ok((column_type, Vec::new()))
+    }
+
+    /// The `<` or `>` around the parameters of `LIST` and `STRUCT`.
+    fn expect_angle(&mut self, op: CompareOp, expected: &str) -> Result<()> {
+        if *self.peek() != Token::Compare(op) {
+            return Err(self.unexpected(expected));
+        }
+        self.advance();
+        Ok(())
+    }
+
+    fn select(&mut self) -> Result<Select> {
+        self.expect_keyword("SELECT")?;
+        let mut items = vec![self.select_item()?];
+        while self.symbol(',') {
+            items.push(self.select_item()?);
+        }
+
+        self.expect_keyword("FROM")?;
+        let from = self.table_ref()?;
+
+        let mut joins = Vec::new();
+        while let Some(join) = self.join()? {
+            joins.push(join);
+        }
+
+        let filter = match self.keyword("WHERE") {
+            true => Some(self.expr()?),
+            false => None,
+        };
+
+        let mut group_by = Vec::new();
+        if self.keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            group_by.push(self.name()?);
+            while self.symbol(',') {
+                group_by.push(self.name()?);
+            }
+        }
+
+        let mut order_by = Vec::new();
+        if self.keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            order_by.push(self.order_by()?);
+            while self.symbol(',') {
+                order_by.push(self.order_by()?);
+            }
+        }
+
+        let limit = match self.keyword("LIMIT") {
+            true => Some(self.count()?),
+            false => None,
+        };
+
+        Ok(Select { items, from, joins, filter, group_by, order_by, limit })
+    }
+
+    fn select_item(&mut self) -> Result<SelectItem> {
+        if self.symbol('*') {
+            return Ok(SelectItem::Wildcard);
+        }
+
+        if let Some(mut aggregate) = self.aggregate()? {
+            aggregate.alias = self.alias()?;
+            return Ok(SelectItem::Aggregate(aggregate));
+        }
+
+        let name = self.name()?;
+        let alias = self.alias()?;
+        Ok(SelectItem::Column { name, alias })
+    }
+
+    /// `COUNT(*)` or `FUNCTION(column)`, if next.
+    fn aggregate(&mut self) -> Result<Option<Aggregate>> {
+        let Token::Word(word) = self.peek() else {
+            return Ok(None);
+        };
+        let function = match word.to_ascii_uppercase().as_str() {
+            "COUNT" => AggregateFunction::Count,
+            "SUM" => AggregateFunction::Sum,
+            "AVG" => AggregateFunction::Avg,
+            "MIN" => AggregateFunction::Min,
+            "MAX" => AggregateFunction::Max,
+            _ => return Ok(None),
+        };
+        if *self.peek_at(1) != Token::Symbol('(') {
+            return Ok(None);
+        }
+        self.advance();
+        self.advance();
+
+        if self.peek().is_keyword("DISTINCT") {
+            return Err(syntax_error(self.offset(), "DISTINCT aggregates are not supported"));
+        }
+        let aggregate = match function == AggregateFunction::Count && self.symbol('*') {
+            true => Aggregate::count_star(),
+            false => Aggregate { function, column: Some(self.name()?), alias: None },
+        };
+
+        self.expect_symbol(')')?;
+        Ok(Some(aggregate))
+    }
+
+    fn table_ref(&mut self) -> Result<TableRef> {
+        let name = self.identifier()?;
+        let alias = self.alias()?;
+        Ok(TableRef { name, alias })
+    }
+
+    fn join(&mut self) -> Result<Option<Join>> {
+        let join_type = if self.keyword("JOIN") {
+            JoinType::Inner
+        } else if self.keyword("INNER") {
+            self.expect_keyword("JOIN")?;
+            JoinType::Inner
+        } else if self.keyword("LEFT") {
+            self.keyword("OUTER");
+            self.expect_keyword("JOIN")?;
+            JoinType::Left
+        } else {
+            return Ok(None);
+        };
+
+        let table = self.table_ref()?;
+        self.expect_keyword("ON")?;
+
+        let mut on = Vec::new();
+        loop {
+            let left = self.name()?;
+            if *self.peek() != Token::Compare(CompareOp::Eq) {
+                return Err(self.unexpected("'=' (join conditions are equalities between columns)"));
+            }
+            self.advance();
+            on.push((left, self.name()?));
+
+            if !self.keyword("AND") {
+                break;
+            }
+        }
+
+        Ok(Some(Join { join_type, table, on }))
+    }
+
+    fn order_by(&mut self) -> Result<OrderBy> {
+        let target = if let Token::Number(_) = self.peek() {
+            OrderTarget::Position(self.count()? as usize)
+        } else if let Some(aggregate) = self.aggregate()? {
+            OrderTarget::Aggregate(aggregate)
+        } else {
+            OrderTarget::Column(self.name()?)
+        };
+
+        let descending = match self.keyword("DESC") {
+            true => true,
+            false => {
+                self.keyword("ASC");
+                false
+            }
+        };
+
+        let mut nulls_first = None;
+        if self.keyword("NULLS") {
+            if self.keyword("FIRST") {
+                nulls_first = Some(true);
+            } else {
+                self.expect_keyword("LAST")?;
+                nulls_first = Some(false);
+            }
+        }
+
+        Ok(OrderBy { target, descending, nulls_first })
+    }
+
+    /// A non-negative integer literal.
+    fn count(&mut self) -> Result<u64> {
+        let offset = self.offset();
+        match self.advance() {
+            Token::Number(text) => text
+                .parse()
+                .map_err(|_| syntax_error(offset, &format!("expected a non-negative integer, found {text}"))),
+            token => Err(syntax_error(offset, &format!("expected a non-negative integer, found {token}"))),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut expr = self.and_expr()?;
+        while self.keyword("OR") {
+            expr = expr.or(self.and_expr()?);
+        }
+        Ok(expr)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr> {
+        let mut expr = self.not_expr()?;
+        while self.keyword("AND") {
+            expr = expr.and(self.not_expr()?);
+        }
+        Ok(expr)
+    }
+
+    fn not_expr(&mut self) -> Result<Expr> {
+        if self.keyword("NOT") {
+            return Ok(!self.not_expr()?);
+        }
+        self.predicate()
+    }
+
+    fn predicate(&mut self) -> Result<Expr> {
+        // Operands are never parenthesized, so `(` opens a nested condition
+        if self.symbol('(') {
+            let expr = self.expr()?;
+            self.expect_symbol(')')?;
+            return Ok(expr);
+        }
+
+        let left = self.operand()?;
+
+        if let Token::Compare(op) = *self.peek() {
+            self.advance();
+            let right = self.operand()?;
+            return Ok(Expr::Compare { op, left: Box::new(left), right: Box::new(right) });
+        }
+
+        if self.keyword("IS") {
+            let negated = self.keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Expr::IsNull { expr: Box::new(left), negated });
+        }
+
+        let negated = self.keyword("NOT");
+        if self.keyword("IN") {
+            self.expect_symbol('(')?;
+            let mut list = vec![self.operand()?];
+            while self.symbol(',') {
+                list.push(self.operand()?);
+            }
+            self.expect_symbol(')')?;
+            return Ok(Expr::In { expr: Box::new(left), list, negated });
+        }
+
+        if self.keyword("BETWEEN") {
+            let low = self.operand()?;
+            self.expect_keyword("AND")?;
+            let high = self.operand()?;
+            return Ok(Expr::Between { expr: Box::new(left), low: Box::new(low), high: Box::new(high), negated });
+        }
+
+        if self.keyword("LIKE") {
+            let Token::Text(pattern) = self.peek().clone() else {
+                return Err(self.unexpected("a string pattern"));
+            };
+            self.advance();
+            return Ok(Expr::Like { expr: Box::new(left), pattern, negated });
+        }
+
+        if negated {
+            return Err(self.unexpected("IN, BETWEEN or LIKE"));
+        }
+        Ok(left)
+    }
+
+    /// A column or a literal.
+    fn operand(&mut self) -> Result<Expr> {
+        let offset = self.offset();
+
+        if self.keyword("NULL") {
+            return Ok(Expr::Literal(Value::Null));
+        }
+        if self.keyword("TRUE") {
+            return Ok(Expr::Literal(Value::Bool(true)));
+        }
+        if self.keyword("FALSE") {
+            return Ok(Expr::Literal(Value::Bool(false)));
+        }
+        if self.peek().is_keyword("TIMESTAMP") {
+            if let Token::Text(text) = self.peek_at(1).clone() {
+                self.advance();
+                self.advance();
+                let value = Value::parse(&text, ColumnType::Timestamp).map_err(|e| syntax_error(offset, &e))?;
+                return Ok(Expr::Literal(value));
+            }
+        }
+
+        let negative = self.symbol('-');
+        if (negative || self.symbol('+')) && !matches!(self.peek(), Token::Number(_)) {
+            return Err(self.unexpected("a number"));
+        }
+        if let Token::Number(text) = self.peek().clone() {
+            self.advance();
+            let text = if negative { format!("-{text}") } else { text };
+            return number(&text).map(Expr::Literal).ok_or_else(|| {
+                syntax_error(offset, &format!("number {text} is out of range"))
+            });
+        }
+        if let Token::Text(text) = self.peek().clone() {
+            self.advance();
+            return Ok(Expr::Literal(Value::String(text)));
+        }
+
+        if self.is_identifier() {
+            return self.name().map(Expr::Column);
+        }
+        Err(self.unexpected("a column or literal"))
+    }
+}
+
+/// An integer literal as `Int64`, anything with a fraction or exponent as `Float64`.
+fn number(text: &str) -> Option<Value> {
+    if text.contains(['.', 'e', 'E']) {
+        return text.parse().ok().filter(|v: &f64| v.is_finite()).map(Value::Float64);
+    }
+    text.parse().ok().map(Value::Int64)
+}
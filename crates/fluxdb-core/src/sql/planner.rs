@@ -0,0 +1,270 @@
+use std::io::{Error, ErrorKind, Result};
+use crate::engine::catalog::Catalog;
+use crate::query::aggregate::Aggregate;
+use crate::query::plan::{AccessPath, Plan};
+use crate::query::sort_key::SortKey;
+use crate::sql::ast::{OrderTarget, Select, SelectItem, TableRef};
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+/// A table of the `FROM` clause and the columns the query reads from it.
+struct Source {
+    table: String,
+    label: String,
+    table_id: u32,
+    columns: Vec<String>,
+}
+
+/// The tables a query reads, for resolving its column names.
+struct Scope<'c> {
+    catalog: &'c Catalog,
+    sources: Vec<Source>,
+}
+
+impl<'c> Scope<'c> {
+    fn new(catalog: &'c Catalog, tables: &[&TableRef]) -> Result<Self> {
+        let mut sources: Vec<Source> = Vec::with_capacity(tables.len());
+        for table in tables {
+            let table_id = *catalog.tables_by_name.get(&table.name).ok_or_else(|| {
+                Error::new(ErrorKind::NotFound, format!("table '{}' not found", table.name))
+            })?;
+            if sources.iter().any(|s| s.label == table.label()) {
+                return Err(invalid(format!("table name '{}' used more than once, give it an alias", table.label())));
+            }
+            sources.push(Source {
+                table: table.name.clone(),
+                label: table.label().to_string(),
+                table_id,
+                columns: Vec::new(),
+            });
+        }
+        Ok(Self { catalog, sources })
+    }
+
+    fn has_column(&self, source: &Source, path: &str) -> bool {
+        self.catalog.resolve_column_path(source.table_id, path).is_some()
+    }
+
+    /// Finds the table a column name refers to: `label.path`, or a bare
+    /// path found in exactly one table. Returns the table's index and the
+    /// column path within it.
+    fn lookup(&self, name: &str) -> Result<(usize, String)> {
+        if let Some((label, path)) = name.split_once('.') {
+            if let Some(index) = self.sources.iter().position(|s| s.label == label) {
+                if self.has_column(&self.sources[index], path) {
+                    return Ok((index, path.to_string()));
+                }
+                return Err(Error::new(ErrorKind::NotFound, format!("column '{name}' not found")));
+            }
+        }
+
+        let mut found = self.sources.iter().enumerate().filter(|(_, s)| self.has_column(s, name));
+        match (found.next(), found.next()) {
+            (Some((index, _)), None) => Ok((index, name.to_string())),
+            (Some(_), Some(_)) => Err(invalid(format!("column '{name}' is ambiguous, qualify it with a table name"))),
+            (None, _) => Err(Error::new(ErrorKind::NotFound, format!("column '{name}' not found"))),
+        }
+    }
+
+    /// Resolves a column name to the qualified name its scan outputs, and
+    /// records that the scan reads it.
+    fn resolve(&mut self, name: &str) -> Result<String> {
+        let (index, path) = self.lookup(name)?;
+        Ok(self.read(index, path))
+    }
+
+    fn read(&mut self, index: usize, path: String) -> String {
+        let source = &mut self.sources[index];
+        let qualified = format!("{}.{path}", source.label);
+        if !source.columns.contains(&path) {
+            source.columns.push(path);
+        }
+        qualified
+    }
+
+    /// The aggregate reading the resolved column, without its alias.
+    fn resolve_aggregate(&mut self, aggregate: &Aggregate) -> Result<Aggregate> {
+        let column = aggregate.column.as_deref().map(|c| self.resolve(c)).transpose()?;
+        Ok(Aggregate { function: aggregate.function, column, alias: None })
+    }
+
+    /// Scan of the `index`-th table. A table the query reads no column of
+    /// still needs one to count its rows.
+    fn scan(&mut self, index: usize) -> Plan {
+        let source = &mut self.sources[index];
+        if source.columns.is_empty() {
+            if let Some(first) = self.catalog.columns_by_table.get(&source.table_id).and_then(|c| c.first()) {
+                source.columns.push(first.name.clone());
+            }
+        }
+        Plan::Scan {
+            table: source.table.clone(),
+            label: source.label.clone(),
+            columns: source.columns.clone(),
+            filter: None,
+            access: AccessPath::default(),
+        }
+    }
+}
+
+/// Turns a `SELECT` into a plan over the tables of `catalog`, resolving
+/// and checking its column names.
+///
+/// The plan scans the `FROM` table, joins the others in the order written,
+/// filters by `WHERE`, then aggregates, sorts, limits and projects the
+/// select list. [`optimize`](crate::query::optimizer::optimize) turns it into
+/// the plan to run.
+pub fn plan_select(catalog: &Catalog, select: &Select) -> Result<Plan> {
+    let tables: Vec<&TableRef> = std::iter::once(&select.from).chain(select.joins.iter().map(|j| &j.table)).collect();
+    let mut scope = Scope::new(catalog, &tables)?;
+
+    // Each join condition pairs a column of the joined table with one of
+    // the tables before it
+    let mut join_keys = Vec::with_capacity(select.joins.len());
+    for (i, join) in select.joins.iter().enumerate() {
+        let joined = i + 1;
+        let mut on = Vec::with_capacity(join.on.len());
+        for (a, b) in &join.on {
+            let (a_index, a_path) = scope.lookup(a)?;
+            let (b_index, b_path) = scope.lookup(b)?;
+            let (earlier, joined_key) = if a_index < joined && b_index == joined {
+                ((a_index, a_path), b_path)
+            } else if b_index < joined && a_index == joined {
+                ((b_index, b_path), a_path)
+            } else {
+                return Err(invalid(format!(
+                    "join condition {a} = {b} must compare a column of {} with one of an earlier table",
+                    join.table.label(),
+                )));
+            };
+            on.push((scope.read(earlier.0, earlier.1), scope.read(joined, joined_key)));
+        }
+        join_keys.push(on);
+    }
+
+    let filter = select.filter.as_ref().map(|f| f.rename_columns(&mut |name| scope.resolve(name))).transpose()?;
+
+    let aggregating = !select.group_by.is_empty()
+        || select.items.iter().any(|item| matches!(item, SelectItem::Aggregate(_)))
+        || select.order_by.iter().any(|o| matches!(o.target, OrderTarget::Aggregate(_)));
+
+    let mut group_by: Vec<String> = Vec::new();
+    for name in &select.group_by {
+        let column = scope.resolve(name)?;
+        if !group_by.contains(&column) {
+            group_by.push(column);
+        }
+    }
+
+    // Equal aggregates are computed once; returns the output column name
+    let mut aggregates: Vec<Aggregate> = Vec::new();
+    let mut add_aggregate = |scope: &mut Scope, aggregate: &Aggregate| -> Result<String> {
+        let aggregate = scope.resolve_aggregate(aggregate)?;
+        let name = aggregate.output_name();
+        if !aggregates.contains(&aggregate) {
+            aggregates.push(aggregate);
+        }
+        Ok(name)
+    };
+    let check_grouped = |column: &str, name: &str| match !aggregating || group_by.iter().any(|g| g == column) {
+        true => Ok(()),
+        false => Err(invalid(format!("column '{name}' must appear in GROUP BY or be used in an aggregate"))),
+    };
+
+    let mut projection: Vec<(String, String)> = Vec::new();
+    // Select item aliases and their columns, which ORDER BY may refer to
+    let mut aliases: Vec<(&str, String)> = Vec::new();
+    for item in &select.items {
+        match item {
+            SelectItem::Wildcard => {
+                if aggregating {
+                    return Err(invalid("SELECT * cannot be combined with GROUP BY or aggregates".to_string()));
+                }
+                for index in 0..scope.sources.len() {
+                    let names: Vec<String> = catalog.columns_by_table
+                        .get(&scope.sources[index].table_id)
+                        .map(|columns| columns.iter().map(|c| c.name.clone()).collect())
+                        .unwrap_or_default();
+                    for name in names {
+                        projection.push((scope.read(index, name.clone()), name));
+                    }
+                }
+            }
+            SelectItem::Column { name, alias } => {
+                let (index, path) = scope.lookup(name)?;
+                let column = scope.read(index, path.clone());
+                check_grouped(&column, name)?;
+                if let Some(alias) = alias {
+                    aliases.push((alias, column.clone()));
+                }
+                projection.push((column, alias.clone().unwrap_or(path)));
+            }
+            SelectItem::Aggregate(aggregate) => {
+                let column = add_aggregate(&mut scope, aggregate)?;
+                if let Some(alias) = &aggregate.alias {
+                    aliases.push((alias, column.clone()));
+                }
+                projection.push((column, aggregate.output_name()));
+            }
+        }
+    }
+
+    let mut sort_keys = Vec::with_capacity(select.order_by.len());
+    for order in &select.order_by {
+        let column = match &order.target {
+            OrderTarget::Position(position) => position
+                .checked_sub(1)
+                .and_then(|i| projection.get(i))
+                .map(|(column, _)| column.clone())
+                .ok_or_else(|| invalid(format!("ORDER BY position {position} is not in the select list")))?,
+            OrderTarget::Column(name) => match aliases.iter().find(|(alias, _)| alias == name) {
+                Some((_, column)) => column.clone(),
+                None => {
+                    let column = scope.resolve(name)?;
+                    check_grouped(&column, name)?;
+                    column
+                }
+            },
+            OrderTarget::Aggregate(aggregate) => add_aggregate(&mut scope, aggregate)?,
+        };
+
+        let key = match order.descending {
+            true => SortKey::desc(&column),
+            false => SortKey::asc(&column),
+        };
+        sort_keys.push(match order.nulls_first {
+            Some(true) => key.nulls_first(),
+            Some(false) => key.nulls_last(),
+            None => key,
+        });
+    }
+
+    let mut plan = scope.scan(0);
+    for (i, (join, on)) in select.joins.iter().zip(join_keys).enumerate() {
+        plan = Plan::Join {
+            left: Box::new(plan),
+            right: Box::new(scope.scan(i + 1)),
+            on,
+            join_type: join.join_type,
+        };
+    }
+
+    if let Some(filter) = filter {
+        plan = Plan::Filter { input: Box::new(plan), predicate: filter };
+    }
+
+    if aggregating {
+        plan = Plan::Aggregate { input: Box::new(plan), group_by, aggregates };
+    }
+
+    let limit = select.limit.map(|limit| usize::try_from(limit).unwrap_or(usize::MAX));
+    if !sort_keys.is_empty() {
+        plan = Plan::Sort { input: Box::new(plan), keys: sort_keys, limit };
+    } else if let Some(limit) = limit {
+        plan = Plan::Limit { input: Box::new(plan), limit };
+    }
+
+    Ok(Plan::Project { input: Box::new(plan), columns: projection })
+}
@@ -0,0 +1,50 @@
+use std::io::{Error, ErrorKind, Result};
+use crate::engine::database::Database;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::Value;
+use crate::query::batch::Batch;
+use crate::query::execute::{execute, QueryOptions};
+use crate::query::explain::{explain, explain_analyze, ExplainFormat};
+use crate::query::optimizer::optimize;
+use crate::query::stream::BatchStream;
+use crate::sql::ast::Statement;
+use crate::sql::parser::parse;
+use crate::sql::planner::plan_select;
+
+/// Parses, plans, optimizes and runs a SQL `SELECT` over the tables of `db`.
+///
+/// `EXPLAIN` returns the plan instead, as a single `plan` column: one row
+/// per operator in text form, one row holding the whole tree in JSON form.
+/// `EXPLAIN ANALYZE` runs the query first to report what each operator did.
+pub fn query<'a>(db: &'a Database, sql: &str, options: &QueryOptions) -> Result<BatchStream<'a>> {
+    match parse(sql)? {
+        Statement::Select(select) => {
+            let plan = optimize(db, plan_select(&db.catalog, &select)?)?;
+            execute(db, &plan, options)
+        }
+        Statement::Explain { analyze, format, select } => {
+            let plan = optimize(db, plan_select(&db.catalog, &select)?)?;
+            let explained = match analyze {
+                true => explain_analyze(db, &plan, options)?,
+                false => explain(db, &plan)?,
+            };
+
+            let text = explained.render(format);
+            let lines: Vec<Value> = match format {
+                ExplainFormat::Text => text.lines().map(|line| Value::String(line.to_string())).collect(),
+                ExplainFormat::Json => vec![Value::String(text)],
+            };
+            let batch = Batch::new(vec![lines]);
+            Ok(BatchStream::new(
+                vec!["plan".to_string()],
+                vec![ColumnType::Utf8],
+                batch.row_count as u64,
+                std::iter::once(Ok(batch)),
+            ))
+        }
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "only SELECT and EXPLAIN return rows, run schema statements with Database::execute",
+        )),
+    }
+}
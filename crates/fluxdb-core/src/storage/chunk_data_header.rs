@@ -1,3 +1,4 @@
+use crate::storage::page_header::PageHeader;
 
 #[repr(C)]
 pub struct ChunkDataHeader {
@@ -7,10 +8,11 @@ pub struct ChunkDataHeader {
     pub encoding: u8,
     pub flags: u8,
     pub next_page_id: u32,
+    pub free_start: u16,
 }
 
 impl ChunkDataHeader{
-    pub const SIZE: usize = 4 + 2 + 2 + 1 + 1 + 4;
+    pub const SIZE: usize = 4 + 2 + 2 + 1 + 1 + 4 + 2;
 
     pub fn new(table_id: u32, ordinal: u16) -> Self {
         Self {
@@ -20,6 +22,7 @@ impl ChunkDataHeader{
             encoding: 0,
             flags: 0,
             next_page_id: 0,
+            free_start: (PageHeader::SIZE + Self::SIZE) as u16,
         }
     }
 
@@ -30,6 +33,7 @@ impl ChunkDataHeader{
         buf[8..9].copy_from_slice(&[self.encoding]);
         buf[9..10].copy_from_slice(&[self.flags]);
         buf[10..14].copy_from_slice(&self.next_page_id.to_le_bytes());
+        buf[14..16].copy_from_slice(&self.free_start.to_le_bytes());
     }
 
     pub fn read_from(buf: &[u8]) -> Self {
@@ -40,6 +44,7 @@ impl ChunkDataHeader{
             encoding: buf[8],
             flags: buf[9],
             next_page_id: u32::from_le_bytes(buf[10..14].try_into().unwrap()),
+            free_start: u16::from_le_bytes(buf[14..16].try_into().unwrap()),
         }
     }
 
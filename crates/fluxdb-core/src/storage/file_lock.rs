@@ -0,0 +1,70 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+/// How a database file is locked against other processes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// Held by a single writer, keeping every other process out.
+    Exclusive,
+    /// Held by any number of read-only opens, keeping writers out.
+    Shared,
+}
+
+/// An advisory lock on a database file between processes. It is taken on
+/// a `.lock` file next to the database, so the database file's own handles
+/// read and write as before. The lock file is left in place; the lock is
+/// released when this is dropped or the process exits.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    pub mode: LockMode,
+}
+
+impl FileLock {
+    /// Locks the database at `db_path` without waiting, failing with
+    /// [`ErrorKind::ResourceBusy`] if another process holds a lock that
+    /// conflicts with `mode`.
+    pub fn acquire(db_path: &Path, mode: LockMode) -> Result<FileLock> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(Self::lock_path(db_path))?;
+
+        let locked = match mode {
+            LockMode::Exclusive => file.try_lock(),
+            LockMode::Shared => file.try_lock_shared(),
+        };
+
+        match locked {
+            Ok(()) => Ok(FileLock { file, mode }),
+            Err(TryLockError::WouldBlock) => {
+                let held = match mode {
+                    LockMode::Exclusive => "already open",
+                    LockMode::Shared => "already open for writing",
+                };
+                Err(Error::new(
+                    ErrorKind::ResourceBusy,
+                    format!("database '{}' is in use: it is {held}", db_path.display()),
+                ))
+            }
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+
+    /// The lock file of the database at `db_path`: its path with `.lock`
+    /// appended.
+    pub fn lock_path(db_path: &Path) -> PathBuf {
+        let mut path = db_path.as_os_str().to_owned();
+        path.push(".lock");
+        PathBuf::from(path)
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
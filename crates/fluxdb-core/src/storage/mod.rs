@@ -4,4 +4,5 @@ mod slot;
 pub mod page_header;
 pub mod page_type;
 pub mod heap_page_header;
-pub mod chunk_data_header;
\ No newline at end of file
+pub mod chunk_data_header;
+pub mod file_lock;
\ No newline at end of file
@@ -1,19 +1,22 @@
+use std::fmt::Write as _;
 use std::io::Error;
 use crate::metadata::db_record::DbRecord;
 use crate::metadata::record::Record;
+use crate::metadata::schema::column_type::ColumnType;
+use crate::metadata::value::{EncodedValue, Value};
 use crate::storage::chunk_data_header::ChunkDataHeader;
 use crate::storage::heap_page_header::HeapPageHeader;
 use crate::storage::page_header::PageHeader;
 use crate::storage::page_type::PageType;
 use crate::storage::slot::Slot;
 
+#[derive(Clone)]
 pub struct Page{
     pub header: PageHeader,
     pub buf: Vec<u8>
 }
 
 impl Page{
-    const HEADER_SIZE: usize = PageHeader::SIZE;
     pub fn new(page_size: usize, page_type: PageType, page_id: u32) -> Self {
         let header = PageHeader::new(
             page_type,
@@ -25,7 +28,7 @@ impl Page{
 
         match page_type {
             PageType::HeapPage | PageType::CatalogPage => {
-                let mut layout = HeapPageHeader::new(page_size);
+                let layout = HeapPageHeader::new(page_size);
                 layout.write_to(&mut buf[PageHeader::SIZE..]);
             },
             _ => panic!("Unknown page type")
@@ -34,6 +37,13 @@ impl Page{
         Self { header, buf }
     }
 
+    /// A freed page, linking to the next free page (0 when it is the last).
+    pub fn new_free(page_size: usize, page_id: u32, next_page_id: u32) -> Self {
+        let mut page = Self { header: PageHeader::new(PageType::FreePage, page_id), buf: vec![0u8; page_size] };
+        page.set_next_page(next_page_id);
+        page
+    }
+
     pub fn new_chunk_data(page_size: usize, page_id: u32, table_id: u32, ordinal: u16) -> Self {
         let header = PageHeader::new(
             PageType::DataPage,
@@ -43,7 +53,7 @@ impl Page{
         let mut buf = vec![0u8; page_size];
         header.write_to(&mut buf[..PageHeader::SIZE]);
 
-        let mut layout = ChunkDataHeader::new(table_id, ordinal);
+        let layout = ChunkDataHeader::new(table_id, ordinal);
         layout.write_to(&mut buf[PageHeader::SIZE..]);
 
         Self { header, buf }
@@ -112,6 +122,188 @@ impl Page{
             .map(|id| (id, self.read_slot(id).unwrap()))
     }
 
+    pub fn chunk_header(&self) -> ChunkDataHeader {
+        ChunkDataHeader::read_from(&self.buf[PageHeader::SIZE..PageHeader::SIZE + ChunkDataHeader::SIZE])
+    }
+
+    /// Bytes still available on a data page for values plus their validity bits.
+    ///
+    /// Data page layout:
+    /// ```text
+    /// [ PageHeader | ChunkDataHeader | values → ... free ... ← validity bitmap ]
+    /// ```
+    /// Values grow forward from `free_start`; the validity bitmap (1 bit per
+    /// value, set = non-null) grows backward from the end of the page.
+    pub fn data_free_space(&self) -> usize {
+        let layout = self.chunk_header();
+        let bitmap_start = self.buf.len() - (layout.value_count as usize).div_ceil(8);
+        bitmap_start.saturating_sub(layout.free_start as usize)
+    }
+
+    /// Longest value an empty data page of `page_size` bytes holds.
+    pub fn max_value_len(page_size: usize) -> usize {
+        page_size - PageHeader::SIZE - ChunkDataHeader::SIZE - 1
+    }
+
+    pub fn append_value(&mut self, value: &EncodedValue) -> Result<(), Error> {
+        let (bytes, valid): (&[u8], bool) = match value {
+            EncodedValue::Bytes(bytes) => (bytes, true),
+            EncodedValue::Null => (&[], false),
+        };
+
+        let slot = self.reserve_value(bytes.len(), valid).ok_or_else(|| {
+            Error::other("Not enough space on page")
+        })?;
+        slot.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Appends a value of `len` bytes and returns the space to encode it into,
+    /// or `None` when the page is full. Lets bulk writers encode straight into
+    /// the page without an intermediate buffer.
+    pub fn reserve_value(&mut self, len: usize, valid: bool) -> Option<&mut [u8]> {
+        let mut layout = self.chunk_header();
+        let index = layout.value_count as usize;
+
+        // A new bitmap byte is needed every 8 values
+        let bitmap_growth = if index.is_multiple_of(8) { 1 } else { 0 };
+        if index == u16::MAX as usize || len + bitmap_growth > self.data_free_space() {
+            return None;
+        }
+
+        let bitmap_byte = self.buf.len() - 1 - index / 8;
+        if valid {
+            self.buf[bitmap_byte] |= 1 << (index % 8);
+        } else {
+            self.buf[bitmap_byte] &= !(1 << (index % 8));
+        }
+
+        let offset = layout.free_start as usize;
+        layout.value_count += 1;
+        layout.free_start += len as u16;
+        layout.write_to(&mut self.buf[PageHeader::SIZE..]);
+
+        Some(&mut self.buf[offset..offset + len])
+    }
+
+    /// Links the next page of a heap chain, in the header and in the
+    /// page's bytes so writing the page persists the link.
+    pub fn set_next_page(&mut self, next_page_id: u32) {
+        self.header.next_page_id = next_page_id;
+        self.header.write_to(&mut self.buf[..PageHeader::SIZE]);
+    }
+
+    /// Renumbers the page, for writing it elsewhere in a file.
+    pub fn set_page_id(&mut self, page_id: u32) {
+        self.header.page_id = page_id;
+        self.header.write_to(&mut self.buf[..PageHeader::SIZE]);
+    }
+
+    pub fn set_next_data_page(&mut self, next_page_id: u32) {
+        let mut layout = self.chunk_header();
+        layout.next_page_id = next_page_id;
+        layout.write_to(&mut self.buf[PageHeader::SIZE..]);
+    }
+
+    /// Cuts a data page back to its first `value_count` values, ending at
+    /// `free_start`, and unlinks the pages after it. Later appends write
+    /// over what was cut.
+    pub fn truncate_values(&mut self, value_count: u16, free_start: u16) {
+        let mut layout = self.chunk_header();
+        layout.value_count = value_count;
+        layout.free_start = free_start;
+        layout.next_page_id = 0;
+        layout.write_to(&mut self.buf[PageHeader::SIZE..]);
+    }
+
+    pub fn is_value_valid(&self, index: u16) -> bool {
+        let bitmap_byte = self.buf.len() - 1 - index as usize / 8;
+        self.buf[bitmap_byte] & (1 << (index % 8)) != 0
+    }
+
+    /// Bytes of encoded values on a data page.
+    pub fn value_bytes(&self) -> usize {
+        self.chunk_header().free_start as usize - PageHeader::SIZE - ChunkDataHeader::SIZE
+    }
+
+    /// The encoded values of a data page, back to back. Nulls take no bytes.
+    pub fn value_data(&self) -> &[u8] {
+        &self.buf[PageHeader::SIZE + ChunkDataHeader::SIZE..self.chunk_header().free_start as usize]
+    }
+
+    /// Decodes every value stored on a data page, nulls included.
+    pub fn read_values(&self, column_type: ColumnType) -> Result<Vec<Value>, Error> {
+        let layout = self.chunk_header();
+        let mut offset = PageHeader::SIZE + ChunkDataHeader::SIZE;
+        let mut values = Vec::with_capacity(layout.value_count as usize);
+
+        for index in 0..layout.value_count {
+            if !self.is_value_valid(index) {
+                values.push(Value::Null);
+                continue;
+            }
+
+            let (value, len) = Value::decode(column_type, &self.buf[offset..layout.free_start as usize])
+                .map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?;
+            values.push(value);
+            offset += len;
+        }
+
+        Ok(values)
+    }
+
+    /// Sixteen bytes per line, each line led by the offset of its first byte.
+    pub fn hex_dump(bytes: &[u8]) -> String {
+        let mut out = String::new();
+        for (row, chunk) in bytes.chunks(16).enumerate() {
+            write!(out, "{:04x}: ", row * 16).unwrap();
+            for b in chunk {
+                write!(out, "{:02x} ", b).unwrap();
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Summary of a data page for inspection, naming its column as
+    /// `column_label`.
+    pub fn describe_data_page(&self, column_label: &str) -> String {
+        let layout = self.chunk_header();
+        let used = self.value_bytes();
+        format!(
+            "Data Page\n\
+             ─────────\n\
+             Page id     : {}\n\
+             Page size   : {} bytes\n\
+             Table id    : {}\n\
+             Column      : {}\n\
+             Values      : {}\n\
+             Used bytes  : {}\n\
+             Free bytes  : {}\n\
+             Utilization : {:.2} %",
+            self.header.page_id,
+            self.buf.len(),
+            layout.table_id,
+            column_label,
+            layout.value_count,
+            used,
+            self.data_free_space(),
+            (used as f64 / self.buf.len() as f64) * 100.0
+        )
+    }
+
+    /// The values of a data page one per line, numbered from 0. Binary and
+    /// Uuid values render as hex through `Value`'s Display.
+    pub fn format_values(&self, column_type: ColumnType) -> Result<String, Error> {
+        Ok(self
+            .read_values(column_type)?
+            .iter()
+            .enumerate()
+            .map(|(i, v)| format!("{:04} │ {}", i, v))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
     fn insert_heap_record(&mut self, record: &[u8]) -> Result<(), Error>{
         let record_len = record.len() as u16;
         let required_space = record_len + Slot::SIZE as u16;
@@ -119,8 +311,7 @@ impl Page{
 
         let free_space = layout.free_end - layout.free_start;
         if required_space > free_space {
-            return Err(Error::new(
-                std::io::ErrorKind::Other,
+            return Err(Error::other(
                 "Not enough space on page",
             ));
         }
@@ -1,5 +1,6 @@
 use crate::storage::page_type::PageType;
 
+#[derive(Clone)]
 #[repr(C)]
 pub struct PageHeader{
     pub page_type: PageType,
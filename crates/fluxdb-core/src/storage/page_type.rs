@@ -5,6 +5,8 @@ pub enum PageType {
     HeapPage = 2,
     IndexPage   = 3,
     CatalogPage = 4,
+    /// A freed page on the free list, linked by `next_page_id`.
+    FreePage = 5,
 }
 
 impl PageType {
@@ -14,6 +16,7 @@ impl PageType {
             2 => PageType::HeapPage,
             3 => PageType::IndexPage,
             4 => PageType::CatalogPage,
+            5 => PageType::FreePage,
             _ => PageType::DataPage, // or panic, your call
         }
     }
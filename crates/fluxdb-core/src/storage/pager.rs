@@ -1,13 +1,19 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{Error, Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
+use std::io::{Cursor, Error};
 use crate::engine::catalog::Catalog;
 use crate::general::header::Header;
+use crate::metadata::chunks::active_chunk_meta::ActiveChunkMeta;
 use crate::metadata::chunks::chunk_meta::ChunkMeta;
+use crate::metadata::chunks::chunk_rewrite::ChunkRewrite;
+use crate::metadata::chunks::commit_marker::CommitMarker;
+use crate::metadata::chunks::delete_vector::DeleteVector;
+use crate::metadata::chunks::table_truncate::TableTruncate;
 use crate::metadata::db_record::DbRecord;
 use crate::metadata::record::Record;
 use crate::metadata::record_type::RecordType;
+use crate::metadata::schema::catalog_change::CatalogChange;
 use crate::metadata::schema::catalog_root::CatalogRoot;
 use crate::metadata::schema::column_type::ColumnType;
 use crate::metadata::schema::table_column::TableColumn;
@@ -17,22 +23,104 @@ use crate::storage::page::Page;
 use crate::storage::page_header::PageHeader;
 use crate::storage::page_type::PageType;
 
+/// Page I/O over the database file. Reads and writes are positional, so
+/// any number of threads can read pages through a shared `&Pager`.
 pub struct Pager {
     pub header: Header,
-    file: RefCell<File>,
+    file: Arc<File>,
+    /// Whether every write is refused, for databases opened read-only.
+    read_only: bool,
+    /// Shared with the snapshots taken since pages were last freed.
+    readers: Arc<()>,
+    /// Freed pages not on the free list yet, each batch with the snapshots
+    /// that may still read them. They are listed once those are dropped.
+    freed: Vec<(Arc<()>, Vec<u32>)>,
+    /// The last page of the chunk catalog heap, where records are appended,
+    /// or 0 until the heap is first walked.
+    chunk_catalog_tail: u32,
 }
 
 impl Pager {
     pub fn new(file: File, header: Header) -> Self {
-        Self { file: RefCell::new(file), header }
+        Self { file: Arc::new(file), header, read_only: false, readers: Arc::new(()), freed: Vec::new(), chunk_catalog_tail: 0 }
+    }
+
+    /// A pager that only reads, over a file that may be opened read-only.
+    pub fn new_read_only(file: File, header: Header) -> Self {
+        Self { read_only: true, ..Self::new(file, header) }
+    }
+
+    /// A pager over the same file for reading the pages written so far.
+    /// Pages it allocates or writes would clash with this pager's.
+    pub(crate) fn snapshot(&self) -> Pager {
+        Pager {
+            header: self.header.clone(),
+            file: Arc::clone(&self.file),
+            read_only: self.read_only,
+            readers: Arc::clone(&self.readers),
+            freed: Vec::new(),
+            chunk_catalog_tail: self.chunk_catalog_tail,
+        }
+    }
+
+    /// Frees pages no longer used, for later allocations to reuse. Pages
+    /// that snapshots taken before may still read are reused only once
+    /// those snapshots are dropped.
+    pub fn free_pages(&mut self, pages: Vec<u32>) -> Result<(), Error> {
+        let readers = std::mem::replace(&mut self.readers, Arc::new(()));
+        self.freed.push((readers, pages));
+        self.release_freed()
+    }
+
+    /// Puts the freed pages no snapshot can read anymore on the free list.
+    fn release_freed(&mut self) -> Result<(), Error> {
+        let (released, held): (Vec<_>, Vec<_>) = std::mem::take(&mut self.freed)
+            .into_iter()
+            .partition(|(readers, _)| Arc::strong_count(readers) == 1);
+        self.freed = held;
+        if released.is_empty() {
+            return Ok(());
+        }
+
+        let page_size = self.header.page_size as usize;
+        for page_id in released.into_iter().flat_map(|(_, pages)| pages) {
+            let page = Page::new_free(page_size, page_id, self.header.free_page_head);
+            self.write_page(page_id as u64, &page)?;
+            self.header.free_page_head = page_id;
+            self.header.free_page_count += 1;
+        }
+        self.flush_header()
+    }
+
+    /// Takes the first page off the free list. The header is written
+    /// before the page is reused, so a crash in between leaks the page
+    /// rather than leaving a used page on the list.
+    fn pop_free_page(&mut self) -> Result<Option<u64>, Error> {
+        let page_id = self.header.free_page_head;
+        if page_id == 0 {
+            return Ok(None);
+        }
+
+        let page = self.read_page(page_id as u64)?;
+        if page.header.page_type != PageType::FreePage {
+            return Err(Error::new(std::io::ErrorKind::InvalidData, format!("page {page_id} on the free list is in use")));
+        }
+        self.header.free_page_head = page.header.next_page_id;
+        self.header.free_page_count -= 1;
+        self.flush_header()?;
+        Ok(Some(page_id as u64))
     }
 
     pub fn page_offset(&self, page_id: u64) -> u64 {
         Header::SIZE as u64 + page_id * self.header.page_size as u64
     }
 
+    /// Allocates a page, reusing a free one if there is any, else growing
+    /// the file.
     pub fn allocate_page(&mut self, page_type: PageInit) -> Result<Page, Error> {
-        let page_id = self.header.page_count; // 0-based page ids
+        self.release_freed()?;
+        let reused = self.pop_free_page()?;
+        let page_id = reused.unwrap_or(self.header.page_count); // 0-based page ids
         let offset = self.page_offset(page_id);
         let page_size = self.header.page_size as usize;
 
@@ -54,53 +142,61 @@ impl Pager {
                     column_ordinal,
                 )
             }
-            _ => panic!("Invalid page type"),
         };
 
-        // ✅ scope the file borrow so it DROPS before flush_header()
-        {
-            let mut file = self.file.borrow_mut();
-            file.seek(SeekFrom::Start(offset))?;
-            file.write_all(&page.buf)?;
-            file.flush()?;
-        } // 👈 borrow released here
+        self.write_at(&page.buf, offset)?;
 
-        self.header.page_count += 1;
-        self.flush_header()?; // ✅ now safe
+        if reused.is_none() {
+            self.header.page_count += 1;
+            self.flush_header()?;
+        }
 
         Ok(page)
     }
 
 
 
+    /// Writes `page` at the end of the file under the next page id, which
+    /// it returns. Unlike [`Pager::allocate_page`], free pages are not reused.
+    pub fn append_page(&mut self, mut page: Page) -> Result<u32, Error> {
+        let page_id = self.header.page_count as u32;
+        page.set_page_id(page_id);
+        self.write_page(page_id as u64, &page)?;
+        self.header.page_count += 1;
+        self.flush_header()?;
+        Ok(page_id)
+    }
+
     pub fn read_page(&self, page_id: u64) -> Result<Page, Error> {
         let offset = self.page_offset(page_id);
         let page_size = self.header.page_size as usize;
 
-        let mut file = self.file.borrow_mut();
-        file.seek(SeekFrom::Start(offset))?;
-
         let mut buf = vec![0u8; page_size];
-        file.read_exact(&mut buf)?;
+        read_exact_at(&self.file, &mut buf, offset)?;
 
         Ok(Page::from_buffer(buf))
     }
 
     pub fn write_page(&mut self, page_id: u64, page: &Page) -> Result<(), Error> {
-        let offset = self.page_offset(page_id);
-        let mut file = self.file.borrow_mut();
-        file.seek(SeekFrom::Start(offset))?;
-        file.write_all(&page.buf)?;
-        file.flush()?;
-        Ok(())
+        self.write_at(&page.buf, self.page_offset(page_id))
     }
 
     pub fn flush_header(&self) -> Result<(), Error> {
-        let mut file = self.file.borrow_mut();
-        file.seek(SeekFrom::Start(0))?;
-        self.header.write_to(&mut *file)?;
-        file.flush()?;
-        Ok(())
+        let mut buf = Cursor::new(Vec::with_capacity(Header::SIZE));
+        self.header.write_to(&mut buf)?;
+        self.write_at(buf.get_ref(), 0)
+    }
+
+    /// Flushes everything written so far to disk.
+    pub fn sync(&self) -> Result<(), Error> {
+        self.file.sync_all()
+    }
+
+    fn write_at(&self, buf: &[u8], offset: u64) -> Result<(), Error> {
+        if self.read_only {
+            return Err(Error::new(std::io::ErrorKind::PermissionDenied, "database is open read-only"));
+        }
+        write_all_at(&self.file, buf, offset)
     }
 
     pub fn insert_record(&mut self, page_id: u64, record: &[u8]) -> Result<(), Error> {
@@ -219,8 +315,6 @@ impl Pager {
                             std::io::ErrorKind::InvalidData,
                             "found CatalogRoot record inside catalog heap (unexpected).",
                         ));
-                        print!("Found Catalog Column");
-
                     }
                     RecordType::CatalogColumn => {
                         let column = TableColumn::deserialize(payload)
@@ -268,6 +362,7 @@ impl Pager {
     pub fn load_catalog(&mut self) -> Result<Catalog, Error> {
         let mut tables: Vec<TableMeta> = Vec::new();
         let mut cols: Vec<TableColumn> = Vec::new();
+        let mut changes: Vec<CatalogChange> = Vec::new();
 
         let root = self.load_catalog_root()?;
 
@@ -299,6 +394,11 @@ impl Pager {
                             .map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?;
                         cols.push(column);
                     }
+                    RecordType::CatalogChange => {
+                        let change = CatalogChange::deserialize(payload)
+                            .map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?;
+                        changes.push(change);
+                    }
                     RecordType::CatalogRoot => {
                         return Err(Error::new(
                             std::io::ErrorKind::InvalidData,
@@ -322,29 +422,71 @@ impl Pager {
             tables_by_id.insert(table.table_id, table);
         }
 
-        for col in cols {
+        // Dropped columns still have their records, so the highest ordinal
+        // each table used counts them too
+        let mut next_ordinals: HashMap<u32, u16> = HashMap::new();
+        for col in &cols {
+            let next = next_ordinals.entry(col.table_id).or_default();
+            *next = (*next).max(col.ordinal + 1);
+        }
+
+        // Nested columns hang off their parent; only top-level columns are indexed per table
+        let (top_level, mut nested): (Vec<_>, Vec<_>) =
+            cols.into_iter().partition(|c| c.parent_column_id == 0);
+
+        for mut col in top_level {
+            Self::attach_children(&mut col, &mut nested);
             columns_by_table
                 .entry(col.table_id)
                 .or_default()
                 .push(col);
         }
 
-        Ok(Catalog {
+        for cols in columns_by_table.values_mut() {
+            cols.sort_by_key(|c| c.ordinal);
+        }
+
+        let mut catalog = Catalog {
             tables_by_id,
             tables_by_name,
             columns_by_table,
-        })
+            next_ordinals,
+        };
+
+        // Changes are replayed in the order they were made. A name may have
+        // belonged to several tables over time, so names are indexed after
+        for change in &changes {
+            catalog.apply(change);
+        }
+        catalog.tables_by_name = catalog.tables_by_id
+            .values()
+            .map(|table| (table.name.clone(), table.table_id))
+            .collect();
+
+        Ok(catalog)
+    }
+
+    fn attach_children(column: &mut TableColumn, nested: &mut Vec<TableColumn>) {
+        let (mut children, rest): (Vec<_>, Vec<_>) = std::mem::take(nested)
+            .into_iter()
+            .partition(|c| c.parent_column_id == column.column_id);
+        *nested = rest;
+
+        children.sort_by_key(|c| c.ordinal);
+        for child in &mut children {
+            Self::attach_children(child, nested);
+        }
+        column.children = children;
     }
 
     //TODO: LOOPING OVER ZOMBIE CHUNKS, NEEDS REWORD LATER
-    pub fn load_chunk_metadata(
-        &mut self,
-    ) -> Result<HashMap<(u32, u32), Vec<ChunkMeta>>, Error> {
-        let mut index: HashMap<(u32, u32), Vec<ChunkMeta>> = HashMap::new();
+    /// Loads every chunk record, with the versions of the commit markers.
+    pub fn load_chunk_metadata(&mut self) -> Result<ChunkCatalog, Error> {
+        let mut catalog = ChunkCatalog::default();
         let mut page_id = self.header.chunk_catalog_root_page_id;
 
         if page_id == 0 {
-            return Ok(index);
+            return Ok(catalog);
         }
 
         while page_id != 0 {
@@ -359,22 +501,47 @@ impl Pager {
                     let chunk = ChunkMeta::deserialize(payload)
                         .map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?;
 
-                    index
+                    catalog.chunks
                         .entry((chunk.table_id, chunk.column_id))
                         .or_insert_with(Vec::new)
                         .push(chunk);
                 }
+                if record_type == RecordType::CommitMarker {
+                    let marker = CommitMarker::deserialize(payload)
+                        .map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?;
+                    catalog.committed.insert(marker.version);
+                }
+                if record_type == RecordType::DeleteVector {
+                    let deletes = DeleteVector::deserialize(payload)
+                        .map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?;
+                    catalog.deletes.push(deletes);
+                }
+                if record_type == RecordType::TableTruncate {
+                    let truncate = TableTruncate::deserialize(payload)
+                        .map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?;
+                    catalog.truncates.push(truncate);
+                }
+                if record_type == RecordType::ChunkRewrite {
+                    let rewrite = ChunkRewrite::deserialize(payload)
+                        .map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?;
+                    catalog.rewrites.push(rewrite);
+                }
+                if record_type == RecordType::ActiveChunkMeta {
+                    let active = ActiveChunkMeta::deserialize(payload)
+                        .map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?;
+                    catalog.active.push(active);
+                }
             }
 
             page_id = page.header.next_page_id;
         }
 
         // Optional but strongly recommended
-        for chunks in index.values_mut() {
+        for chunks in catalog.chunks.values_mut() {
             chunks.sort_by_key(|c| c.row_start);
         }
 
-        Ok(index)
+        Ok(catalog)
     }
 
     pub fn create_table(&mut self, table_name: &str) -> Result<TableMeta, Error> {
@@ -411,7 +578,7 @@ impl Pager {
                         let new_page_id = new_page.header.page_id;
 
                         // Link pages
-                        page.header.next_page_id = new_page_id;
+                        page.set_next_page(new_page_id);
                         self.write_page(page_id, &page)?;
 
                         page_id = new_page_id as u64;
@@ -465,7 +632,9 @@ impl Pager {
         table_id: u32,
         column_name: &str,
         column_type: ColumnType,
-        ordinal: u16
+        ordinal: u16,
+        parent_column_id: u32,
+        not_null: bool,
     ) -> Result<TableColumn, Error> {
 
         // 2) Load & increment CatalogRoot
@@ -476,8 +645,11 @@ impl Pager {
             column_id,
             table_id,
             ordinal,
+            parent_column_id,
             name: column_name.into(),
-            column_type
+            column_type,
+            not_null,
+            children: Vec::new(),
         };
 
         // 3) Insert ColumnMeta into catalog heap
@@ -496,7 +668,7 @@ impl Pager {
                         page_id = page.header.next_page_id as u64;
                     } else {
                         let new_page = self.allocate_page(PageInit::Catalog)?;
-                        page.header.next_page_id = new_page.header.page_id;
+                        page.set_next_page(new_page.header.page_id);
                         self.write_page(page_id, &page)?;
                         page_id = new_page.header.page_id as u64;
                     }
@@ -511,12 +683,140 @@ impl Pager {
         Ok(column)
     }
 
+    /// Appends a schema change to the last page of the catalog heap, so
+    /// changes load in the order they were made.
+    pub fn append_catalog_change(&mut self, change: &CatalogChange) -> Result<(), Error> {
+        self.append_catalog_record(change)
+    }
+
+    /// Lays out the catalog of a fresh file as `catalog` is now: its tables
+    /// and columns keep their ids and current names, and new ids carry on
+    /// from `root`. The only changes left to replay reserve the ordinals of
+    /// dropped columns.
+    pub fn write_catalog(&mut self, catalog: &Catalog, root: &CatalogRoot) -> Result<(), Error> {
+        self.init_catalog_root()?;
+        let heap_root = self.load_catalog_root()?.catalog_root_page_id;
+        self.persist_catalog_root(&CatalogRoot { catalog_root_page_id: heap_root, ..*root })?;
+
+        let mut tables: Vec<&TableMeta> = catalog.tables_by_id.values().collect();
+        tables.sort_by_key(|table| table.table_id);
+        for table in tables {
+            self.append_catalog_record(table)?;
+            let mut next_ordinal = 0;
+            for column in catalog.columns_by_table.get(&table.table_id).into_iter().flatten() {
+                for column in column.flatten() {
+                    self.append_catalog_record(column)?;
+                    next_ordinal = next_ordinal.max(column.ordinal + 1);
+                }
+            }
+
+            if catalog.next_ordinal(table.table_id) > next_ordinal {
+                self.append_catalog_record(&CatalogChange::ReserveOrdinals {
+                    table_id: table.table_id,
+                    next_ordinal: catalog.next_ordinal(table.table_id),
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends a record to the last page of the catalog heap.
+    fn append_catalog_record<T: DbRecord>(&mut self, record: &T) -> Result<(), Error> {
+        let root = self.load_catalog_root()?;
+        let mut page_id = root.catalog_root_page_id as u64;
+        let mut page = self.read_page(page_id)?;
+        while page.header.next_page_id != 0 {
+            page_id = page.header.next_page_id as u64;
+            page = self.read_page(page_id)?;
+        }
+
+        if page.insert_typed_record(record).is_ok() {
+            return self.write_page(page_id, &page);
+        }
+
+        let mut new_page = self.allocate_page(PageInit::Catalog)?;
+        page.set_next_page(new_page.header.page_id);
+        self.write_page(page_id, &page)?;
+
+        new_page.insert_typed_record(record)?;
+        self.write_page(new_page.header.page_id as u64, &new_page)
+    }
+
+    /// Appends a sealed chunk's metadata to the chunk catalog heap.
+    pub fn insert_chunk_meta(&mut self, chunk: &ChunkMeta) -> Result<(), Error> {
+        self.insert_chunk_record(chunk)
+    }
+
+    /// Appends a chunk's delete vector to the chunk catalog heap.
+    pub fn insert_delete_vector(&mut self, deletes: &DeleteVector) -> Result<(), Error> {
+        self.insert_chunk_record(deletes)
+    }
+
+    /// Appends a table truncation to the chunk catalog heap.
+    pub fn insert_table_truncate(&mut self, truncate: &TableTruncate) -> Result<(), Error> {
+        self.insert_chunk_record(truncate)
+    }
+
+    /// Appends a compaction's chunk rewrite to the chunk catalog heap.
+    pub fn insert_chunk_rewrite(&mut self, rewrite: &ChunkRewrite) -> Result<(), Error> {
+        self.insert_chunk_record(rewrite)
+    }
+
+    /// Appends how far an active chunk was committed to the chunk catalog heap.
+    pub fn insert_active_chunk_meta(&mut self, active: &ActiveChunkMeta) -> Result<(), Error> {
+        self.insert_chunk_record(active)
+    }
+
+    /// Appends a commit marker to the chunk catalog heap.
+    pub fn insert_commit_marker(&mut self, marker: &CommitMarker) -> Result<(), Error> {
+        self.insert_chunk_record(marker)
+    }
+
+    /// Appends a record to the chunk catalog heap, creating the heap (and
+    /// recording its root in the file header) on first use.
+    fn insert_chunk_record<T: DbRecord>(&mut self, record: &T) -> Result<(), Error> {
+        if self.header.chunk_catalog_root_page_id == 0 {
+            let root = self.allocate_page(PageInit::Heap)?;
+            self.header.chunk_catalog_root_page_id = root.header.page_id;
+            self.flush_header()?;
+        }
+
+        // Records go on the last page once it is known, the earlier ones
+        // having filled up before
+        let mut page_id = match self.chunk_catalog_tail {
+            0 => self.header.chunk_catalog_root_page_id as u64,
+            tail => tail as u64,
+        };
+
+        loop {
+            let mut page = self.read_page(page_id)?;
+
+            match page.insert_typed_record(record) {
+                Ok(_) => {
+                    self.write_page(page_id, &page)?;
+                    self.chunk_catalog_tail = page_id as u32;
+                    return Ok(());
+                }
+                Err(_) => {
+                    if page.header.next_page_id != 0 {
+                        page_id = page.header.next_page_id as u64;
+                    } else {
+                        let new_page = self.allocate_page(PageInit::Heap)?;
+                        page.set_next_page(new_page.header.page_id);
+                        self.write_page(page_id, &page)?;
+                        page_id = new_page.header.page_id as u64;
+                    }
+                }
+            }
+        }
+    }
+
     fn persist_catalog_root(&mut self, root: &CatalogRoot) -> Result<(), Error> {
         let page_size = self.header.page_size as usize;
 
         let mut page0 = Page::new(page_size, PageType::CatalogPage, 0);
         page0.insert_typed_record(root)
-            .map_err(|e| Error::new(std::io::ErrorKind::Other, e))?;
+            .map_err(Error::other)?;
 
         self.write_page(0, &page0)?;
         Ok(())
@@ -524,6 +824,21 @@ impl Pager {
 }
 
 
+/// The records of the chunk catalog heap, as loaded on open.
+#[derive(Default)]
+pub struct ChunkCatalog {
+    /// Chunks per `(table_id, column_id)`, sorted by `row_start`.
+    pub chunks: HashMap<(u32, u32), Vec<ChunkMeta>>,
+    /// Versions a commit marker was written for.
+    pub committed: HashSet<u64>,
+    /// Delete vectors in the order they were written.
+    pub deletes: Vec<DeleteVector>,
+    pub truncates: Vec<TableTruncate>,
+    pub rewrites: Vec<ChunkRewrite>,
+    /// Active chunk records in the order they were written.
+    pub active: Vec<ActiveChunkMeta>,
+}
+
 pub enum PageInit {
     Heap,
     Catalog,
@@ -531,4 +846,43 @@ pub enum PageInit {
         table_id: u32,
         column_ordinal: u16,
     },
-}
\ No newline at end of file
+}
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> Result<(), Error> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+#[cfg(unix)]
+fn write_all_at(file: &File, buf: &[u8], offset: u64) -> Result<(), Error> {
+    std::os::unix::fs::FileExt::write_all_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> Result<(), Error> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset)? {
+            0 => return Err(Error::new(std::io::ErrorKind::UnexpectedEof, "failed to fill whole buffer")),
+            n => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn write_all_at(file: &File, mut buf: &[u8], mut offset: u64) -> Result<(), Error> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match file.seek_write(buf, offset)? {
+            0 => return Err(Error::new(std::io::ErrorKind::WriteZero, "failed to write whole buffer")),
+            n => {
+                buf = &buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,194 @@
+mod common;
+
+use std::io::ErrorKind;
+use common::{column_types, scan_all, TempDb, TempFile};
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::formats::arrow::arrow_export::export_arrow;
+use fluxdb_core::formats::arrow::arrow_field::{ArrowField, ArrowType, TimeUnit};
+use fluxdb_core::formats::arrow::arrow_import::import_arrow;
+use fluxdb_core::formats::arrow::arrow_reader::ArrowReader;
+use fluxdb_core::formats::arrow::arrow_writer::{ArrowFormat, ArrowWriter};
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::vector::VECTOR_SIZE;
+
+const ROWS: i64 = ROWS_PER_CHUNK as i64 + 6;
+
+/// One column of every type over two sealed chunks, a full one and a
+/// flushed one, and a row left in the active chunk. Every seventh row holds
+/// nulls only.
+fn create(db: &mut Database) {
+    db.create_table("t").unwrap();
+    for (name, column_type) in [
+        ("i", ColumnType::Integer32),
+        ("l", ColumnType::Integer64),
+        ("r", ColumnType::Float32),
+        ("d", ColumnType::Float64),
+        ("b", ColumnType::Boolean),
+        ("s", ColumnType::Utf8),
+        ("ts", ColumnType::Timestamp),
+        ("bin", ColumnType::Binary),
+        ("fixed", ColumnType::FixedSizeBinary(2)),
+        ("id", ColumnType::Uuid),
+        ("tags", ColumnType::List),
+        ("point", ColumnType::Struct),
+    ] {
+        db.add_column("t", name, column_type).unwrap();
+    }
+    db.add_child_column("t", "tags", "tag", ColumnType::Utf8).unwrap();
+    db.add_child_column("t", "point", "x", ColumnType::Float64).unwrap();
+    db.add_child_column("t", "point", "y", ColumnType::Float64).unwrap();
+
+    for n in 0..ROWS - 1 {
+        if n % 7 == 3 {
+            db.append_row("t", vec![]).unwrap();
+            continue;
+        }
+        db.append_row("t", vec![
+            ("i", Value::Int32(n as i32)),
+            ("l", Value::Int64(n)),
+            ("r", Value::Float32(n as f32 / 2.0)),
+            ("d", Value::Float64(-(n as f64))),
+            ("b", Value::Bool(n % 2 == 0)),
+            ("s", Value::String(format!("row {n}"))),
+            ("ts", Value::Timestamp(n * 1_000_000 - 1)),
+            ("bin", Value::Binary(vec![n as u8; n as usize % 4])),
+            ("fixed", Value::Binary((n as u16).to_be_bytes().to_vec())),
+            ("id", Value::Uuid([n as u8; 16])),
+            ("tags", Value::List((0..n % 3).map(|t| if t == 1 { Value::Null } else { Value::String(format!("t{t}")) }).collect())),
+            ("point", Value::Struct(vec![Value::Float64(n as f64), Value::Null])),
+        ]).unwrap();
+    }
+    db.flush().unwrap();
+    db.append_row("t", vec![("l", Value::Int64(ROWS - 1))]).unwrap();
+}
+
+fn round_trip(name: &str, format: ArrowFormat) {
+    let source = TempDb::new(&format!("{name}-source"));
+    let target = TempDb::new(&format!("{name}-target"));
+    let ipc = TempFile::new(&format!("{name}.arrow"));
+
+    let mut db = source.create();
+    create(&mut db);
+    let out = std::fs::File::create(ipc.path()).unwrap();
+    assert_eq!(export_arrow(&db, "t", &[], out, format).unwrap(), ROWS as usize);
+
+    // One record batch per scan window, windows never spanning chunks
+    let mut reader = ArrowReader::open(ipc.path()).unwrap();
+    let mut batches = Vec::new();
+    while let Some(batch) = reader.read_next().unwrap() {
+        batches.push(batch.row_count);
+    }
+    let mut expected = vec![VECTOR_SIZE; ROWS_PER_CHUNK as usize / VECTOR_SIZE];
+    expected.extend([5, 1]);
+    assert_eq!(batches, expected);
+
+    {
+        let mut copy = target.create();
+        assert_eq!(import_arrow(&mut copy, "t", ipc.path()).unwrap(), ROWS as usize);
+    }
+    let copy = target.open();
+    assert_eq!(column_types(&copy, "t"), column_types(&db, "t"));
+    assert_eq!(scan_all(&copy, "t"), scan_all(&db, "t"));
+}
+
+#[test]
+fn ipc_stream_round_trips_every_type() {
+    round_trip("arrow-stream", ArrowFormat::Stream);
+}
+
+#[test]
+fn ipc_file_round_trips_every_type() {
+    round_trip("arrow-file", ArrowFormat::File);
+}
+
+#[test]
+fn projected_paths_import_as_flat_columns() {
+    let file = TempDb::new("arrow-project");
+    let ipc = TempFile::new("arrow-project.arrow");
+
+    let mut db = file.create();
+    create(&mut db);
+    let out = std::fs::File::create(ipc.path()).unwrap();
+    export_arrow(&db, "t", &["s", "point.x", "tags.tag"], out, ArrowFormat::File).unwrap();
+
+    let reader = ArrowReader::open(ipc.path()).unwrap();
+    let names: Vec<&str> = reader.fields.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["s", "point.x", "tags.tag"]);
+
+    // Dots cannot appear in column names
+    import_arrow(&mut db, "flat", ipc.path()).unwrap();
+    assert_eq!(column_types(&db, "flat"), vec![
+        ("s".to_string(), ColumnType::Utf8),
+        ("point_x".to_string(), ColumnType::Float64),
+        ("tags_tag".to_string(), ColumnType::List),
+    ]);
+    let rows = scan_all(&db, "flat");
+    assert_eq!(rows[2], vec![Value::String("row 2".into()), Value::Float64(2.0), Value::List(vec![Value::String("t0".into()), Value::Null])]);
+    assert_eq!(rows[3], vec![Value::Null, Value::Null, Value::Null]);
+}
+
+#[test]
+fn narrower_arrow_types_widen_on_import() {
+    let file = TempDb::new("arrow-widen");
+    let ipc = TempFile::new("arrow-widen.arrow");
+    let field = |name: &str, data_type: ArrowType| ArrowField { name: name.into(), nullable: true, data_type, children: vec![], extension: None };
+
+    let fields = vec![
+        field("small", ArrowType::Int { bit_width: 16, signed: true }),
+        field("unsigned", ArrowType::Int { bit_width: 32, signed: false }),
+        field("seconds", ArrowType::Timestamp { unit: TimeUnit::Second, timezone: None }),
+        field("text", ArrowType::LargeUtf8),
+    ];
+    let mut writer = ArrowWriter::new(std::fs::File::create(ipc.path()).unwrap(), fields, ArrowFormat::Stream).unwrap();
+    writer.write_batch(2, &[
+        vec![Value::Int32(-300), Value::Null],
+        vec![Value::Int64(u32::MAX as i64), Value::Int64(0)],
+        vec![Value::Timestamp(-2_000_000), Value::Timestamp(86_400_000_000)],
+        vec![Value::String("é".into()), Value::String(String::new())],
+    ]).unwrap();
+    writer.finish().unwrap();
+
+    let mut db = file.create();
+    assert_eq!(import_arrow(&mut db, "t", ipc.path()).unwrap(), 2);
+    assert_eq!(column_types(&db, "t"), vec![
+        ("small".to_string(), ColumnType::Integer32),
+        ("unsigned".to_string(), ColumnType::Integer64),
+        ("seconds".to_string(), ColumnType::Timestamp),
+        ("text".to_string(), ColumnType::Utf8),
+    ]);
+    assert_eq!(scan_all(&db, "t"), vec![
+        vec![Value::Int32(-300), Value::Int64(u32::MAX as i64), Value::Timestamp(-2_000_000), Value::String("é".into())],
+        vec![Value::Null, Value::Int64(0), Value::Timestamp(86_400_000_000), Value::String(String::new())],
+    ]);
+}
+
+#[test]
+fn imports_reject_mismatched_and_damaged_input() {
+    let file = TempDb::new("arrow-reject");
+    let ipc = TempFile::new("arrow-reject.arrow");
+
+    let mut db = file.create();
+    db.create_table("src").unwrap();
+    db.add_column("src", "x", ColumnType::Integer64).unwrap();
+    db.append_row("src", vec![("x", Value::Int64(1))]).unwrap();
+    let out = std::fs::File::create(ipc.path()).unwrap();
+    export_arrow(&db, "src", &[], out, ArrowFormat::File).unwrap();
+
+    db.create_table("other").unwrap();
+    db.add_column("other", "x", ColumnType::Utf8).unwrap();
+    assert_eq!(import_arrow(&mut db, "other", ipc.path()).err().unwrap().kind(), ErrorKind::InvalidInput);
+    db.create_table("renamed").unwrap();
+    db.add_column("renamed", "y", ColumnType::Integer64).unwrap();
+    assert_eq!(import_arrow(&mut db, "renamed", ipc.path()).err().unwrap().kind(), ErrorKind::NotFound);
+
+    let bytes = std::fs::read(ipc.path()).unwrap();
+    std::fs::write(ipc.path(), &bytes[..bytes.len() - 4]).unwrap();
+    assert_eq!(import_arrow(&mut db, "copy", ipc.path()).err().unwrap().kind(), ErrorKind::InvalidData);
+    std::fs::write(ipc.path(), b"not arrow at all").unwrap();
+    assert!(import_arrow(&mut db, "copy", ipc.path()).is_err());
+
+    assert!(scan_all(&db, "other").is_empty());
+    assert!(scan_all(&db, "renamed").is_empty());
+}
@@ -0,0 +1,111 @@
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::{EncodedValue, Value};
+use fluxdb_core::storage::page::Page;
+
+const ID: &str = "6ba7b810-9dad-11d1-80b4-00c04fd430c8";
+
+fn data_page() -> Page {
+    Page::new_chunk_data(4096, 7, 1, 0)
+}
+
+#[test]
+fn column_type_encoding_round_trips() {
+    for column_type in [ColumnType::Integer32, ColumnType::Binary, ColumnType::FixedSizeBinary(0), ColumnType::FixedSizeBinary(u16::MAX), ColumnType::Uuid] {
+        let mut buf = Vec::new();
+        column_type.write_to(&mut buf);
+        assert_eq!(buf.len(), column_type.encoded_len());
+        buf.push(0xff);
+        assert_eq!(ColumnType::read_from(&buf), Ok((column_type, column_type.encoded_len())));
+    }
+
+    assert!(ColumnType::read_from(&[8, 1]).is_err(), "width cut short");
+    assert!(ColumnType::read_from(&[42]).is_err());
+    assert!(ColumnType::read_from(&[]).is_err());
+}
+
+#[test]
+fn binary_values_round_trip_through_a_data_page() {
+    let cases = [
+        (ColumnType::Binary, vec![Value::Binary(vec![]), Value::Null, Value::Binary(vec![0, 255, 7])]),
+        (ColumnType::FixedSizeBinary(4), vec![Value::Binary(vec![1, 2, 3, 4]), Value::Null, Value::Binary(vec![0; 4])]),
+        (ColumnType::Uuid, vec![Value::Uuid(Value::parse_uuid(ID).unwrap()), Value::Null, Value::Uuid([0; 16])]),
+    ];
+
+    for (column_type, values) in cases {
+        let mut page = data_page();
+        // Nine rows spill the validity bitmap into a second byte
+        let values: Vec<Value> = values.iter().cycle().take(9).cloned().collect();
+        for value in &values {
+            page.append_value(&value.encode(column_type).unwrap()).unwrap();
+        }
+        assert_eq!(page.chunk_header().value_count, 9);
+        assert!(!page.is_value_valid(7) && page.is_value_valid(8));
+        assert_eq!(page.read_values(column_type).unwrap(), values, "{column_type:?}");
+    }
+}
+
+#[test]
+fn values_of_the_wrong_shape_are_not_encoded() {
+    assert!(Value::Binary(vec![1, 2, 3]).encode(ColumnType::FixedSizeBinary(4)).is_err());
+    assert!(Value::String("x".into()).encode(ColumnType::Binary).is_err());
+    assert!(Value::Binary(vec![0; 16]).encode(ColumnType::Uuid).is_err());
+    assert!(matches!(Value::Null.encode(ColumnType::Uuid), Ok(EncodedValue::Null)));
+}
+
+#[test]
+fn full_pages_refuse_more_values() {
+    let mut page = data_page();
+    let value = Value::Binary(vec![9; 1000]).encode(ColumnType::Binary).unwrap();
+    let mut stored = 0;
+    while page.append_value(&value).is_ok() {
+        stored += 1;
+    }
+    assert_eq!(stored, 4);
+    assert!(page.data_free_space() < 1004);
+    assert_eq!(page.read_values(ColumnType::Binary).unwrap(), vec![Value::Binary(vec![9; 1000]); 4]);
+}
+
+#[test]
+fn uuids_and_binary_render_as_hex() {
+    assert_eq!(Value::Uuid(Value::parse_uuid(ID).unwrap()).to_string(), ID);
+    assert_eq!(Value::parse_uuid(&ID.replace('-', "")), Value::parse_uuid(ID));
+    assert_eq!(Value::parse_uuid(&ID.to_uppercase()), Value::parse_uuid(ID));
+    assert_eq!(Value::parse_uuid("6ba7b810"), None);
+    assert_eq!(Value::parse_uuid(&ID.replace('b', "g")), None);
+    assert_eq!(Value::Binary(vec![0xde, 0xad, 0x01]).to_string(), "0xdead01");
+    assert_eq!(Value::Binary(vec![]).to_string(), "0x");
+}
+
+#[test]
+fn hex_dumps_break_every_sixteen_bytes() {
+    assert_eq!(Page::hex_dump(&[]), "");
+    let bytes: Vec<u8> = (0..17).collect();
+    assert_eq!(
+        Page::hex_dump(&bytes),
+        "0000: 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f \n0010: 10 \n"
+    );
+}
+
+#[test]
+fn data_pages_format_for_inspection() {
+    let mut page = data_page();
+    for value in [Value::Binary(vec![0xab, 0x01]), Value::Null, Value::Binary(vec![])] {
+        page.append_value(&value.encode(ColumnType::Binary).unwrap()).unwrap();
+    }
+
+    assert_eq!(page.format_values(ColumnType::Binary).unwrap(), "0000 │ 0xab01\n0001 │ NULL\n0002 │ 0x");
+    // The hex tab shows the value bytes alone; nulls take none
+    let dump = Page::hex_dump(page.value_data());
+    assert!(dump.starts_with("0000: ") && dump.contains("ab 01 "), "{dump}");
+    assert_eq!(dump.lines().count(), 1);
+
+    let summary = page.describe_data_page("payload (Binary)");
+    for line in ["Page id     : 7", "Page size   : 4096 bytes", "Table id    : 1", "Column      : payload (Binary)", "Values      : 3"] {
+        assert!(summary.contains(line), "{line} missing from\n{summary}");
+    }
+    assert!(summary.contains(&format!("Used bytes  : {}", page.value_bytes())));
+    assert!(summary.contains(&format!("Free bytes  : {}", page.data_free_space())));
+
+    // An empty page formats to no lines rather than failing
+    assert_eq!(data_page().format_values(ColumnType::Uuid).unwrap(), "");
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use std::io::ErrorKind;
+use common::{scan_all, TempDb};
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+
+fn create_readings(db: &mut Database) {
+    db.create_table("readings").unwrap();
+    db.add_column("readings", "id", ColumnType::Integer64).unwrap();
+    db.add_column("readings", "temp", ColumnType::Float32).unwrap();
+    db.add_column("readings", "site", ColumnType::Utf8).unwrap();
+}
+
+/// `(row_start, row_end, null_count, min, max)` of a sealed chunk.
+type ChunkStats = (u64, u64, u32, Option<Value>, Option<Value>);
+
+/// Stats of the sealed chunks of a column.
+fn chunks(db: &Database, column: &str) -> Vec<ChunkStats> {
+    let table_id = db.catalog.tables_by_name["readings"];
+    let column = db.catalog.columns_by_table[&table_id].iter().find(|c| c.name == column).unwrap();
+    db.chunk_manager
+        .chunks_for(table_id, column.column_id)
+        .iter()
+        .map(|chunk| (chunk.row_start, chunk.row_end, chunk.null_count, chunk.min.clone(), chunk.max.clone()))
+        .collect()
+}
+
+#[test]
+fn bulk_appends_continue_the_active_chunk() {
+    let file = TempDb::new("bulk-chunks");
+    let count = ROWS_PER_CHUNK as usize + 10;
+    let ids: Vec<i64> = (1..=count as i64).collect();
+    let temps: Vec<f32> = (0..count).map(|i| i as f32 / 2.0).collect();
+    // Only the first and last bulk rows of the first chunk have a value
+    let valid: Vec<bool> = (0..count).map(|i| i == 0 || i >= ROWS_PER_CHUNK as usize - 2).collect();
+    {
+        let mut db = file.create();
+        create_readings(&mut db);
+        db.append_row("readings", vec![("id", Value::Int64(0)), ("site", Value::String("first".into()))]).unwrap();
+        let appended = db.append_columns("readings", &[
+            ColumnInput::new("id", ColumnSlice::Int64(&ids)),
+            ColumnInput::new("temp", ColumnSlice::Float32(&temps)).with_validity(&valid),
+        ]).unwrap();
+        assert_eq!(appended, count);
+
+        // The row appended on its own fills the first chunk up with the bulk rows
+        let chunk = ROWS_PER_CHUNK;
+        assert_eq!(chunks(&db, "id"), vec![(0, chunk, 0, Some(Value::Int64(0)), Some(Value::Int64(chunk as i64 - 1)))]);
+        assert_eq!(chunks(&db, "temp"), vec![(0, chunk, chunk as u32 - 2, Some(Value::Float32(0.0)), Some(Value::Float32((chunk - 2) as f32 / 2.0)))]);
+        assert_eq!(chunks(&db, "site")[0].2, chunk as u32 - 1);
+        db.flush().unwrap();
+    }
+
+    let rows = scan_all(&file.open(), "readings");
+    assert_eq!(rows.len(), count + 1);
+    assert_eq!(rows[0], vec![Value::Int64(0), Value::Null, Value::String("first".into())]);
+    assert_eq!(rows[1], vec![Value::Int64(1), Value::Float32(0.0), Value::Null]);
+    assert_eq!(rows[2][1], Value::Null);
+    assert_eq!(rows[ROWS_PER_CHUNK as usize][1], Value::Float32(ROWS_PER_CHUNK as f32 / 2.0 - 0.5));
+    assert_eq!(rows[count], vec![Value::Int64(count as i64), Value::Float32((count - 1) as f32 / 2.0), Value::Null]);
+}
+
+#[test]
+fn value_inputs_mix_with_typed_slices() {
+    let file = TempDb::new("bulk-values");
+    let mut db = file.create();
+    create_readings(&mut db);
+    db.add_column("readings", "samples", ColumnType::List).unwrap();
+    db.add_child_column("readings", "samples", "sample", ColumnType::Float64).unwrap();
+
+    let samples = [Value::List(vec![Value::Float64(1.5)]), Value::Null, Value::List(vec![])];
+    db.append_columns("readings", &[
+        ColumnInput::new("samples", ColumnSlice::Values(&samples)),
+        ColumnInput::new("site", ColumnSlice::Utf8(&["a", "b", "c"])).with_validity(&[true, false, true]),
+    ]).unwrap();
+
+    assert_eq!(scan_all(&db, "readings"), vec![
+        vec![Value::Null, Value::Null, Value::String("a".into()), samples[0].clone()],
+        vec![Value::Null, Value::Null, Value::Null, Value::Null],
+        vec![Value::Null, Value::Null, Value::String("c".into()), samples[2].clone()],
+    ]);
+    assert_eq!(db.append_columns("readings", &[ColumnInput::new("id", ColumnSlice::Int64(&[]))]).unwrap(), 0);
+}
+
+#[test]
+fn malformed_inputs_write_nothing() {
+    let file = TempDb::new("bulk-malformed");
+    let mut db = file.create();
+    create_readings(&mut db);
+    db.add_column("readings", "tags", ColumnType::List).unwrap();
+    db.add_child_column("readings", "tags", "tag", ColumnType::Utf8).unwrap();
+
+    let ids = [1, 2, 3];
+    let attempts: Vec<(Vec<ColumnInput>, ErrorKind)> = vec![
+        (vec![ColumnInput::new("id", ColumnSlice::Int64(&ids)), ColumnInput::new("site", ColumnSlice::Utf8(&["a"]))], ErrorKind::InvalidInput),
+        (vec![ColumnInput::new("id", ColumnSlice::Int64(&ids)).with_validity(&[true])], ErrorKind::InvalidInput),
+        (vec![ColumnInput::new("id", ColumnSlice::Int32(&[1, 2, 3]))], ErrorKind::InvalidInput),
+        (vec![ColumnInput::new("id", ColumnSlice::Int64(&ids)), ColumnInput::new("id", ColumnSlice::Int64(&ids))], ErrorKind::InvalidInput),
+        (vec![ColumnInput::new("tags", ColumnSlice::Utf8(&["a", "b", "c"]))], ErrorKind::InvalidInput),
+        (vec![ColumnInput::new("missing", ColumnSlice::Int64(&ids))], ErrorKind::NotFound),
+    ];
+    for (inputs, kind) in attempts {
+        assert_eq!(db.append_columns("readings", &inputs).unwrap_err().kind(), kind, "{inputs:?}");
+    }
+    assert!(scan_all(&db, "readings").is_empty());
+}
+
+#[test]
+fn oversized_values_are_rejected_before_any_column_is_written() {
+    let file = TempDb::new("bulk-oversized");
+    let mut db = file.create();
+    create_readings(&mut db);
+    db.add_column("readings", "samples", ColumnType::List).unwrap();
+    db.add_child_column("readings", "samples", "sample", ColumnType::Utf8).unwrap();
+    let huge = "x".repeat(10_000);
+
+    // The oversized value comes after valid ones, in a column listed after
+    // `id`, in a typed slice and inside a list of a `Values` input
+    let samples = [Value::List(vec![]), Value::List(vec![Value::String("a".into()), Value::String(huge.clone())])];
+    for input in [
+        ColumnInput::new("site", ColumnSlice::Utf8(&["a", &huge])),
+        ColumnInput::new("samples", ColumnSlice::Values(&samples)),
+    ] {
+        let err = db.append_columns("readings", &[ColumnInput::new("id", ColumnSlice::Int64(&[1, 2])), input]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+    assert!(scan_all(&db, "readings").is_empty());
+
+    // Slots the validity marks null are never written, whatever they hold
+    db.append_columns("readings", &[
+        ColumnInput::new("id", ColumnSlice::Int64(&[3, 4])),
+        ColumnInput::new("site", ColumnSlice::Utf8(&["c", &huge])).with_validity(&[true, false]),
+    ]).unwrap();
+    let sites: Vec<Value> = scan_all(&db, "readings").into_iter().map(|row| row[2].clone()).collect();
+    assert_eq!(sites, [Value::String("c".into()), Value::Null]);
+}
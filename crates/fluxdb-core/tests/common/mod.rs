@@ -0,0 +1,160 @@
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::engine::table_scan::TableScan;
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::expr::Expr;
+
+/// A database file in the temp directory, removed along with its lock file
+/// and any vacuum copy when dropped.
+pub struct TempDb {
+    path: PathBuf,
+}
+
+impl TempDb {
+    pub fn new(name: &str) -> TempDb {
+        let path = std::env::temp_dir().join(format!("fluxdb-test-{}-{name}.flxdb", std::process::id()));
+        let db = TempDb { path };
+        db.remove();
+        db
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Creates the database file afresh.
+    pub fn create(&self) -> Database {
+        Database::open(&self.path, true).unwrap()
+    }
+
+    /// Opens the existing database file.
+    pub fn open(&self) -> Database {
+        Database::open(&self.path, false).unwrap()
+    }
+
+    fn remove(&self) {
+        for suffix in ["", ".lock", ".vacuum"] {
+            let mut path = self.path.as_os_str().to_owned();
+            path.push(suffix);
+            let _ = std::fs::remove_file(PathBuf::from(path));
+        }
+    }
+}
+
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
+
+/// A file in the temp directory, such as an import or export, removed when
+/// dropped.
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    pub fn new(name: &str) -> TempFile {
+        let path = std::env::temp_dir().join(format!("fluxdb-test-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        TempFile { path }
+    }
+
+    /// Creates the file holding `contents`.
+    pub fn with_contents(name: &str, contents: &str) -> TempFile {
+        let file = TempFile::new(name);
+        std::fs::write(&file.path, contents).unwrap();
+        file
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn read(&self) -> String {
+        std::fs::read_to_string(&self.path).unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// A directory in the temp directory, such as for spill files, removed with
+/// its contents when dropped.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("fluxdb-test-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of files left in the directory.
+    pub fn file_count(&self) -> usize {
+        std::fs::read_dir(&self.path).unwrap().count()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// The type of every top-level column of a table, by name.
+pub fn column_types(db: &Database, table: &str) -> Vec<(String, ColumnType)> {
+    let table_id = db.catalog.tables_by_name[table];
+    db.catalog.columns_by_table[&table_id]
+        .iter()
+        .map(|column| (column.name.clone(), column.column_type))
+        .collect()
+}
+
+/// Runs a SQL query and returns its rows.
+pub fn rows(db: &Database, sql: &str) -> Vec<Vec<Value>> {
+    let mut rows = Vec::new();
+    for batch in db.query(sql).unwrap() {
+        let batch = batch.unwrap();
+        rows.extend((0..batch.row_count).map(|i| batch.row(i)));
+    }
+    rows
+}
+
+/// Runs a SQL query returning a single value.
+pub fn value(db: &Database, sql: &str) -> Value {
+    rows(db, sql).remove(0).remove(0)
+}
+
+/// Every row of a table, scanning all its top-level columns.
+pub fn scan_all(db: &Database, table: &str) -> Vec<Vec<Value>> {
+    collect(db.scan(table, &[]).unwrap())
+}
+
+/// The rows of a table that pass `filter`, scanning all its top-level
+/// columns.
+pub fn scan_where(db: &Database, table: &str, filter: Expr) -> Vec<Vec<Value>> {
+    collect(db.scan_where(table, &[], filter).unwrap())
+}
+
+fn collect(scan: TableScan<'_>) -> Vec<Vec<Value>> {
+    let mut rows = Vec::new();
+    for batch in scan {
+        let batch = batch.unwrap();
+        rows.extend((0..batch.row_count).map(|i| batch.columns.iter().map(|column| column[i].clone()).collect::<Vec<_>>()));
+    }
+    rows
+}
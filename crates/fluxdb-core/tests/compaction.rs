@@ -0,0 +1,235 @@
+mod common;
+
+use std::io::ErrorKind;
+use std::thread;
+use std::time::{Duration, Instant};
+use common::{scan_all, value, TempDb};
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::compaction::CompactionOptions;
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::engine::shared_database::SharedDatabase;
+use fluxdb_core::metadata::chunks::chunk_meta::ChunkMeta;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::expr::Expr;
+
+const BATCH: i64 = 1_000;
+const BATCHES: i64 = 12;
+
+/// `BATCHES` small sealed chunks of `BATCH` rows each.
+fn create(file: &TempDb) -> Database {
+    let mut db = file.create();
+    db.execute("CREATE TABLE t (x BIGINT, name VARCHAR)").unwrap();
+    for batch in 0..BATCHES {
+        let xs: Vec<i64> = (batch * BATCH..(batch + 1) * BATCH).collect();
+        let names: Vec<String> = xs.iter().map(|x| format!("row {x}")).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let valid: Vec<bool> = xs.iter().map(|x| x % 10 != 0).collect();
+        db.append_columns("t", &[
+            ColumnInput::new("x", ColumnSlice::Int64(&xs)),
+            ColumnInput::new("name", ColumnSlice::Utf8(&names)).with_validity(&valid),
+        ]).unwrap();
+        db.flush().unwrap();
+    }
+    db
+}
+
+/// The sealed chunks of the table's first column.
+fn x_chunks(db: &Database) -> Vec<ChunkMeta> {
+    let table_id = db.catalog.tables_by_name["t"];
+    let x = &db.catalog.columns_by_table[&table_id][0];
+    db.chunk_manager.chunks_for(table_id, x.column_id).to_vec()
+}
+
+/// Row ranges of the table's sealed chunks, the same for every column.
+fn chunks(db: &Database) -> Vec<(u64, u64)> {
+    let table_id = db.catalog.tables_by_name["t"];
+    let ranges = |column_id: u32| -> Vec<(u64, u64)> {
+        db.chunk_manager.chunks_for(table_id, column_id).iter().map(|chunk| (chunk.row_start, chunk.row_end)).collect()
+    };
+    let columns = &db.catalog.columns_by_table[&table_id];
+    for column in &columns[1..] {
+        assert_eq!(ranges(column.column_id), ranges(columns[0].column_id), "columns are compacted together");
+    }
+    ranges(columns[0].column_id)
+}
+
+fn below(x: i64) -> Expr {
+    Expr::column("x").lt(Expr::literal(Value::Int64(x)))
+}
+
+#[test]
+fn small_chunks_are_merged_and_survive_reopen() {
+    let file = TempDb::new("compact-merge");
+    let copy = TempDb::new("compact-merge-copy");
+    let rows;
+    {
+        let mut db = create(&file);
+        rows = scan_all(&db, "t");
+        assert_eq!(chunks(&db).len(), BATCHES as usize);
+
+        let stats = db.compact_table("t", &CompactionOptions::default()).unwrap();
+        assert_eq!(stats.chunks_replaced, BATCHES as usize);
+        assert_eq!(stats.chunks_written, 1);
+        assert_eq!(stats.rows_written, (BATCHES * BATCH) as u64);
+        assert_eq!(stats.rows_purged, 0);
+        assert_eq!(chunks(&db), vec![(0, (BATCHES * BATCH) as u64)]);
+        assert_eq!(scan_all(&db, "t"), rows);
+
+        // Nothing is left to merge
+        let again = db.compact_table("t", &CompactionOptions::default()).unwrap();
+        assert_eq!((again.chunks_replaced, again.chunks_written), (0, 0));
+
+        // The copy stands in for the file as a crash would leave it
+        std::fs::copy(file.path(), copy.path()).unwrap();
+    }
+
+    for file in [&file, &copy] {
+        let db = file.open();
+        assert_eq!(chunks(&db), vec![(0, (BATCHES * BATCH) as u64)]);
+        assert_eq!(scan_all(&db, "t"), rows);
+        assert_eq!(value(&db, "SELECT MAX(x) FROM t WHERE x < 5000"), Value::Int64(4_999));
+    }
+}
+
+#[test]
+fn each_merged_range_commits_on_its_own() {
+    let file = TempDb::new("compact-target");
+    let mut db = create(&file);
+    db.delete_where("t", below(10)).unwrap();
+    db.delete_where("t", Expr::column("x").gt_eq(Expr::literal(Value::Int64(11_995)))).unwrap();
+    let rows = scan_all(&db, "t");
+    let version = db.version();
+
+    let options = CompactionOptions { target_chunk_rows: BATCH as u64 * 5, ..CompactionOptions::default() };
+    let stats = db.compact_table("t", &options).unwrap();
+    assert_eq!(stats.chunks_written, 3);
+    assert_eq!(stats.rows_purged, 15);
+    assert_eq!(db.version(), version + 3, "one commit per range");
+    let batch = BATCH as u64;
+    // Ranges keep their first row id, the purged rows left unused
+    assert_eq!(chunks(&db), vec![(0, batch * 5 - 10), (batch * 5, batch * 10), (batch * 10, batch * 12 - 5)]);
+    // Each range is read after the ones before it were swapped
+    assert_eq!(scan_all(&db, "t"), rows);
+    drop(db);
+    assert_eq!(scan_all(&file.open(), "t"), rows);
+}
+
+#[test]
+fn deleted_rows_are_purged_across_reopen() {
+    let file = TempDb::new("compact-purge");
+    {
+        let mut db = file.create();
+        db.execute("CREATE TABLE t (x BIGINT, name VARCHAR)").unwrap();
+        let xs: Vec<i64> = (0..ROWS_PER_CHUNK as i64 * 2).collect();
+        let names: Vec<String> = xs.iter().map(|x| format!("row {x}")).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        db.append_columns("t", &[
+            ColumnInput::new("x", ColumnSlice::Int64(&xs)),
+            ColumnInput::new("name", ColumnSlice::Utf8(&names)),
+        ]).unwrap();
+        db.flush().unwrap();
+        // Half of the first chunk, none of the second
+        db.delete_where("t", below(ROWS_PER_CHUNK as i64 / 2)).unwrap();
+
+        let stats = db.compact_table("t", &CompactionOptions::default()).unwrap();
+        assert_eq!(stats.chunks_replaced, 1);
+        assert_eq!(stats.rows_purged, ROWS_PER_CHUNK / 2);
+        assert_eq!(stats.rows_written, ROWS_PER_CHUNK / 2);
+        assert!(stats.pages_freed > 0);
+    }
+
+    let mut db = file.open();
+    let half = ROWS_PER_CHUNK / 2;
+    // The merged range keeps its first row id, and the fresh zone map
+    let first = x_chunks(&db)[0].clone();
+    assert_eq!((first.row_start, first.row_end - first.row_start), (0, half));
+    assert_eq!(first.min, Some(Value::Int64(half as i64)));
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM t"), Value::Int64(half as i64 * 3));
+    assert_eq!(value(&db, "SELECT MIN(x) FROM t"), Value::Int64(half as i64));
+
+    // Deletes after compaction land on the rewritten rows
+    db.delete_where("t", below(half as i64 + 10)).unwrap();
+    drop(db);
+    assert_eq!(value(&file.open(), "SELECT MIN(x) FROM t"), Value::Int64(half as i64 + 10));
+}
+
+#[test]
+fn large_chunks_and_active_rows_are_left_alone() {
+    let file = TempDb::new("compact-untouched");
+    let mut db = file.create();
+    db.execute("CREATE TABLE t (x BIGINT, name VARCHAR)").unwrap();
+    let xs: Vec<i64> = (0..ROWS_PER_CHUNK as i64 + 10).collect();
+    db.append_columns("t", &[ColumnInput::new("x", ColumnSlice::Int64(&xs))]).unwrap();
+    let rows = scan_all(&db, "t");
+
+    // A full chunk with a few deletes, below the threshold, and ten active
+    // rows that are not sealed yet
+    db.delete_where("t", below(100)).unwrap();
+    let stats = db.compact_table("t", &CompactionOptions::default()).unwrap();
+    assert_eq!((stats.chunks_replaced, stats.chunks_written), (0, 0));
+    assert_eq!(chunks(&db), vec![(0, ROWS_PER_CHUNK)]);
+
+    // Over the threshold, the chunk is rewritten, but not merged with the
+    // active rows
+    let options = CompactionOptions { deleted_fraction: 0.001, ..CompactionOptions::default() };
+    let stats = db.compact_table("t", &options).unwrap();
+    assert_eq!((stats.chunks_replaced, stats.rows_purged), (1, 100));
+    assert_eq!(chunks(&db), vec![(0, ROWS_PER_CHUNK - 100)]);
+    assert_eq!(scan_all(&db, "t"), rows[100..]);
+}
+
+#[test]
+fn snapshots_keep_reading_replaced_chunks() {
+    let file = TempDb::new("compact-snapshot");
+    let db = SharedDatabase::new(create(&file));
+    let before = db.snapshot();
+    let rows = scan_all(&before, "t");
+
+    {
+        let mut writer = db.write().unwrap();
+        writer.compact_table("t", &CompactionOptions::default()).unwrap();
+        writer.delete_where("t", below(100)).unwrap();
+        writer.compact_table("t", &CompactionOptions { deleted_fraction: 0.0, ..CompactionOptions::default() }).unwrap();
+    }
+
+    assert_eq!(scan_all(&before, "t"), rows);
+    assert_eq!(scan_all(&db.snapshot(), "t"), rows[100..]);
+}
+
+#[test]
+fn background_compaction_merges_chunks() {
+    let file = TempDb::new("compact-background");
+    let db = SharedDatabase::new(create(&file));
+    let rows = scan_all(&db.snapshot(), "t");
+
+    let compaction = db.compact_in_background(Duration::from_millis(10), CompactionOptions::default());
+    let started = Instant::now();
+    while compaction.stats().chunks_replaced == 0 {
+        assert!(started.elapsed() < Duration::from_secs(30), "no compaction ran");
+        thread::sleep(Duration::from_millis(10));
+    }
+    drop(compaction);
+
+    assert_eq!(chunks(&db.snapshot()).len(), 1);
+    assert_eq!(scan_all(&db.snapshot(), "t"), rows);
+    drop(db);
+    assert_eq!(scan_all(&file.open(), "t"), rows);
+}
+
+#[test]
+fn failed_compactions_change_nothing() {
+    let file = TempDb::new("compact-failures");
+    let rows = scan_all(&create(&file), "t");
+
+    let mut db = file.open();
+    let err = db.compact_table("missing", &CompactionOptions::default()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    drop(db);
+
+    let mut db = Database::open_read_only(file.path()).unwrap();
+    let err = db.compact_table("t", &CompactionOptions::default()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    assert_eq!(chunks(&db).len(), BATCHES as usize);
+    assert_eq!(scan_all(&db, "t"), rows);
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use std::io::ErrorKind;
+use common::{column_types, scan_all, TempDb, TempFile};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::formats::csv::csv_import::{import_csv, CsvImportOptions};
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+
+const DAY: i64 = 86_400 * 1_000_000;
+
+fn text(s: &str) -> Value {
+    Value::String(s.into())
+}
+
+fn create_table(db: &mut Database, table: &str, columns: &[(&str, ColumnType)]) {
+    db.create_table(table).unwrap();
+    for (name, column_type) in columns {
+        db.add_column(table, name, *column_type).unwrap();
+    }
+}
+
+#[test]
+fn infers_a_schema_and_survives_reopen() {
+    let file = TempDb::new("csv-infer");
+    // CRLF line breaks, a quoted delimiter, an escaped quote and a line
+    // break inside quotes
+    let csv = TempFile::with_contents("csv-infer.csv", "\
+id,price,active,created,note\r
+1,9.5,true,2024-01-02,\"one, two\"\r
+2,10,no,2024-01-03,\r
+3,,yes,2024-01-04 12:00:00,\"say \"\"hi\"\"\r
+again\"\r
+");
+    {
+        let mut db = file.create();
+        let report = import_csv(&mut db, "items", csv.path(), &CsvImportOptions::default()).unwrap();
+        assert_eq!((report.rows_imported, report.rows_rejected), (3, 0));
+    }
+
+    let db = file.open();
+    assert_eq!(column_types(&db, "items"), vec![
+        ("id".to_string(), ColumnType::Integer64),
+        ("price".to_string(), ColumnType::Float64),
+        ("active".to_string(), ColumnType::Boolean),
+        ("created".to_string(), ColumnType::Timestamp),
+        ("note".to_string(), ColumnType::Utf8),
+    ]);
+
+    let jan_2 = 19_724 * DAY;
+    assert_eq!(scan_all(&db, "items"), vec![
+        vec![Value::Int64(1), Value::Float64(9.5), Value::Bool(true), Value::Timestamp(jan_2), text("one, two")],
+        vec![Value::Int64(2), Value::Float64(10.0), Value::Bool(false), Value::Timestamp(jan_2 + DAY), Value::Null],
+        vec![Value::Int64(3), Value::Null, Value::Bool(true), Value::Timestamp(jan_2 + 2 * DAY + DAY / 2), text("say \"hi\"\r\nagain")],
+    ]);
+}
+
+#[test]
+fn inference_only_sees_the_sampled_rows() {
+    let file = TempDb::new("csv-sample");
+    // `1`/`0` stay integers, a column that is null throughout is text, and
+    // the blank line does not count as a record
+    let csv = TempFile::with_contents("csv-sample.csv", "n,flag,empty\n1,1,\n\n2,0,\nthree,1,\n4,0,x\n");
+    let rejects = TempFile::new("csv-sample.rejects.csv");
+
+    let mut db = file.create();
+    let options = CsvImportOptions { infer_rows: 2, reject_file: Some(rejects.path().to_path_buf()), ..CsvImportOptions::default() };
+    let report = import_csv(&mut db, "t", csv.path(), &options).unwrap();
+
+    assert_eq!(column_types(&db, "t"), vec![
+        ("n".to_string(), ColumnType::Integer64),
+        ("flag".to_string(), ColumnType::Integer64),
+        ("empty".to_string(), ColumnType::Utf8),
+    ]);
+    assert_eq!((report.rows_imported, report.rows_rejected), (3, 1));
+    assert_eq!(report.errors[0].line, 5);
+    assert!(report.errors[0].message.contains("column 'n'"), "{}", report.errors[0].message);
+    assert_eq!(rejects.read(), "n,flag,empty\nthree,1,\n");
+    assert_eq!(scan_all(&db, "t")[2], vec![Value::Int64(4), Value::Int64(0), text("x")]);
+}
+
+#[test]
+fn bad_rows_are_written_aside_as_they_appeared() {
+    let file = TempDb::new("csv-reject");
+    let csv = TempFile::with_contents("csv-reject.csv", "\
+x,s
+1,a
+two,b
+3
+\"4\",\"multi
+line\",extra
+5,e
+");
+    let rejects = TempFile::new("csv-reject.rejects.csv");
+
+    let mut db = file.create();
+    create_table(&mut db, "t", &[("x", ColumnType::Integer64), ("s", ColumnType::Utf8)]);
+    let options = CsvImportOptions { reject_file: Some(rejects.path().to_path_buf()), ..CsvImportOptions::default() };
+    let report = import_csv(&mut db, "t", csv.path(), &options).unwrap();
+
+    assert_eq!((report.rows_imported, report.rows_rejected), (2, 3));
+    let lines: Vec<u64> = report.errors.iter().map(|e| e.line).collect();
+    assert_eq!(lines, vec![3, 4, 5]);
+    assert_eq!(report.errors[1].message, "expected 2 fields, found 1");
+    assert_eq!(rejects.read(), "x,s\ntwo,b\n3\n\"4\",\"multi\nline\",extra\n");
+
+    assert_eq!(scan_all(&db, "t"), vec![
+        vec![Value::Int64(1), text("a")],
+        vec![Value::Int64(5), text("e")],
+    ]);
+}
+
+#[test]
+fn values_an_append_would_refuse_reject_only_their_row() {
+    let file = TempDb::new("csv-checked");
+    let long = "y".repeat(5_000);
+    let csv = TempFile::with_contents("csv-checked.csv", &format!("x,s\n1,a\n,b\n3,{long}\n4,d\n5,\n"));
+    let rejects = TempFile::new("csv-checked.rejects.csv");
+
+    let mut db = file.create();
+    db.execute("CREATE TABLE t (x BIGINT NOT NULL, s VARCHAR)").unwrap();
+    let options = CsvImportOptions { batch_size: 2, reject_file: Some(rejects.path().to_path_buf()), ..CsvImportOptions::default() };
+    let report = import_csv(&mut db, "t", csv.path(), &options).unwrap();
+
+    assert_eq!((report.rows_imported, report.rows_rejected), (3, 2));
+    assert_eq!(report.errors[0].message, "column 'x' is NOT NULL");
+    assert!(report.errors[1].message.contains("more than a data page holds"), "{}", report.errors[1].message);
+    assert_eq!(rejects.read(), format!("x,s\n,b\n3,{long}\n"));
+    assert_eq!(scan_all(&db, "t"), vec![
+        vec![Value::Int64(1), text("a")],
+        vec![Value::Int64(4), text("d")],
+        vec![Value::Int64(5), Value::Null],
+    ]);
+
+    // A NOT NULL column the file has no field for fails up front
+    let csv = TempFile::with_contents("csv-checked-unmapped.csv", "s\ne\n");
+    let err = import_csv(&mut db, "t", csv.path(), &CsvImportOptions::default()).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(scan_all(&db, "t").len(), 3);
+}
+
+#[test]
+fn headerless_files_append_by_position_in_batches() {
+    let file = TempDb::new("csv-position");
+    let csv = TempFile::with_contents("csv-position.csv", "5;x\n6;-\n7;\"-\"\n8;\n");
+
+    let mut db = file.create();
+    create_table(&mut db, "t", &[("x", ColumnType::Integer64), ("s", ColumnType::Utf8)]);
+    db.append_row("t", vec![("x", Value::Int64(4))]).unwrap();
+    let options = CsvImportOptions { delimiter: ';', has_header: false, batch_size: 3, null_values: vec!["-".into()], ..CsvImportOptions::default() };
+    let report = import_csv(&mut db, "t", csv.path(), &options).unwrap();
+    assert_eq!(report.rows_imported, 4);
+
+    // A quoted null marker is a string, and an empty field is no longer null
+    assert_eq!(scan_all(&db, "t"), vec![
+        vec![Value::Int64(4), Value::Null],
+        vec![Value::Int64(5), text("x")],
+        vec![Value::Int64(6), Value::Null],
+        vec![Value::Int64(7), text("-")],
+        vec![Value::Int64(8), text("")],
+    ]);
+}
+
+#[test]
+fn unusable_files_fail_the_import() {
+    let file = TempDb::new("csv-failures");
+    let mut db = file.create();
+    create_table(&mut db, "t", &[("x", ColumnType::Integer64), ("tags", ColumnType::List)]);
+    db.add_child_column("t", "tags", "tag", ColumnType::Utf8).unwrap();
+
+    let attempts = [
+        ("t", "x,nope\n1,2\n", ErrorKind::NotFound),
+        ("t", "x,x\n1,2\n", ErrorKind::InvalidData),
+        ("t", "tags\na\n", ErrorKind::InvalidInput),
+        ("fresh", "", ErrorKind::InvalidData),
+        ("fresh", "a,,b\n1,2,3\n", ErrorKind::InvalidData),
+        ("fresh", "a,b,a\n1,2,3\n", ErrorKind::InvalidData),
+    ];
+    for (i, (table, contents, kind)) in attempts.into_iter().enumerate() {
+        let csv = TempFile::with_contents(&format!("csv-failures-{i}.csv"), contents);
+        let err = import_csv(&mut db, table, csv.path(), &CsvImportOptions::default()).err().unwrap();
+        assert_eq!(err.kind(), kind, "{contents:?}");
+    }
+
+    let csv = TempFile::with_contents("csv-failures-batch.csv", "x\n1\n");
+    let options = CsvImportOptions { batch_size: 0, ..CsvImportOptions::default() };
+    assert_eq!(import_csv(&mut db, "t", csv.path(), &options).err().unwrap().kind(), ErrorKind::InvalidInput);
+
+    assert!(scan_all(&db, "t").is_empty());
+    assert!(!db.catalog.tables_by_name.contains_key("fresh"));
+}
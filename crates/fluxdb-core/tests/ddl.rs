@@ -0,0 +1,162 @@
+mod common;
+
+use std::io::ErrorKind;
+use common::{column_types, rows, scan_all, TempDb};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+
+fn names(db: &Database, table: &str) -> Vec<String> {
+    column_types(db, table).into_iter().map(|(name, _)| name).collect()
+}
+
+#[test]
+fn create_table_script_survives_reopen() {
+    let file = TempDb::new("ddl-create");
+    {
+        let mut db = file.create();
+        let ran = db.execute("
+            -- accounts and their events
+            CREATE TABLE accounts (
+                id BIGINT NOT NULL,
+                name VARCHAR(64),
+                balance DOUBLE PRECISION NULL,
+                key BINARY(8)
+            );
+            /* nested columns */
+            CREATE TABLE IF NOT EXISTS events (at TIMESTAMP, tags LIST<TEXT>, origin STRUCT<lat FLOAT64, lon FLOAT64>);
+            CREATE TABLE IF NOT EXISTS accounts (other INT);
+        ").unwrap();
+        assert_eq!(ran, 3);
+    }
+
+    let mut db = file.open();
+    assert_eq!(column_types(&db, "accounts"), vec![
+        ("id".to_string(), ColumnType::Integer64),
+        ("name".to_string(), ColumnType::Utf8),
+        ("balance".to_string(), ColumnType::Float64),
+        ("key".to_string(), ColumnType::FixedSizeBinary(8)),
+    ]);
+    assert_eq!(names(&db, "events"), ["at", "tags", "origin"]);
+
+    let table_id = db.catalog.tables_by_name["events"];
+    let origin = &db.catalog.columns_by_table[&table_id][2];
+    let fields: Vec<(&str, ColumnType)> = origin.children.iter().map(|c| (c.name.as_str(), c.column_type)).collect();
+    assert_eq!(fields, [("lat", ColumnType::Float64), ("lon", ColumnType::Float64)]);
+
+    // NOT NULL is kept too
+    let err = db.append_row("accounts", vec![("name", Value::String("x".into()))]).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    db.append_row("accounts", vec![("id", Value::Int64(1))]).unwrap();
+}
+
+#[test]
+fn alter_table_changes_survive_reopen() {
+    let file = TempDb::new("ddl-alter");
+    {
+        let mut db = file.create();
+        db.execute("CREATE TABLE t (a BIGINT, b VARCHAR, c BOOLEAN)").unwrap();
+        db.append_row("t", vec![("a", Value::Int64(1)), ("b", Value::String("one".into())), ("c", Value::Bool(true))]).unwrap();
+        db.execute("
+            ALTER TABLE t ADD COLUMN d DOUBLE;
+            ALTER TABLE t DROP COLUMN c;
+            ALTER TABLE t RENAME COLUMN b TO label;
+            ALTER TABLE t RENAME TO items;
+            ALTER TABLE items ADD COLUMN c VARCHAR;
+        ").unwrap();
+        db.append_row("items", vec![("a", Value::Int64(2)), ("d", Value::Float64(2.5))]).unwrap();
+        db.flush().unwrap();
+    }
+
+    let db = file.open();
+    assert!(!db.catalog.tables_by_name.contains_key("t"));
+    assert_eq!(names(&db, "items"), ["a", "label", "d", "c"]);
+    // The new `c` does not see the dropped column's values
+    assert_eq!(rows(&db, "SELECT a, label, d, c FROM items ORDER BY a"), vec![
+        vec![Value::Int64(1), Value::String("one".into()), Value::Null, Value::Null],
+        vec![Value::Int64(2), Value::Null, Value::Float64(2.5), Value::Null],
+    ]);
+}
+
+fn ordinal(db: &Database, table: &str, column: &str) -> u16 {
+    let table_id = db.catalog.tables_by_name[table];
+    db.catalog.columns_by_table[&table_id].iter().find(|c| c.name == column).unwrap().ordinal
+}
+
+#[test]
+fn dropped_ordinals_are_not_reused_across_reopen_and_vacuum() {
+    let file = TempDb::new("ddl-ordinals");
+    {
+        let mut db = file.create();
+        db.execute("CREATE TABLE t (a BIGINT, b VARCHAR)").unwrap();
+        db.append_row("t", vec![("a", Value::Int64(1)), ("b", Value::String("old".into()))]).unwrap();
+        db.commit().unwrap();
+
+        // Dropping the highest ordinal leaves nothing in use above it
+        db.execute("ALTER TABLE t DROP COLUMN b; ALTER TABLE t ADD COLUMN c VARCHAR").unwrap();
+        assert_eq!(ordinal(&db, "t", "c"), 2);
+        db.execute("ALTER TABLE t DROP COLUMN c").unwrap();
+        db.commit().unwrap();
+    }
+
+    {
+        let mut db = file.open();
+        db.execute("ALTER TABLE t ADD COLUMN d VARCHAR; ALTER TABLE t DROP COLUMN d").unwrap();
+        assert_eq!(db.catalog.next_ordinal(db.catalog.tables_by_name["t"]), 4);
+        // Vacuum drops the column records, not the reservation
+        db.vacuum().unwrap();
+    }
+
+    let mut db = file.open();
+    db.execute("ALTER TABLE t ADD COLUMN e VARCHAR").unwrap();
+    assert_eq!(ordinal(&db, "t", "e"), 4);
+    assert_eq!(rows(&db, "SELECT a, e FROM t"), vec![vec![Value::Int64(1), Value::Null]]);
+
+    // A table with no drops needs no reservation, and new tables start at 0
+    db.execute("CREATE TABLE u (x BIGINT)").unwrap();
+    db.vacuum().unwrap();
+    assert_eq!(db.catalog.next_ordinal(db.catalog.tables_by_name["u"]), 1);
+}
+
+#[test]
+fn drop_table_survives_reopen() {
+    let file = TempDb::new("ddl-drop");
+    {
+        let mut db = file.create();
+        db.execute("CREATE TABLE t (a BIGINT)").unwrap();
+        db.append_row("t", vec![("a", Value::Int64(1))]).unwrap();
+        db.flush().unwrap();
+        db.execute("DROP TABLE t; DROP TABLE IF EXISTS t").unwrap();
+        assert_eq!(db.execute("DROP TABLE t").err().unwrap().kind(), ErrorKind::NotFound);
+    }
+
+    let mut db = file.open();
+    assert!(!db.catalog.tables_by_name.contains_key("t"));
+    db.execute("CREATE TABLE t (a VARCHAR)").unwrap();
+    assert!(scan_all(&db, "t").is_empty());
+}
+
+#[test]
+fn bad_statements_change_nothing_after_the_last_good_one() {
+    let file = TempDb::new("ddl-errors");
+    let mut db = file.create();
+    db.execute("CREATE TABLE t (a BIGINT)").unwrap();
+
+    let kind = |db: &mut Database, sql: &str| db.execute(sql).err().unwrap().kind();
+    assert_eq!(kind(&mut db, "CREATE TABLE t (b INT)"), ErrorKind::AlreadyExists);
+    assert_eq!(kind(&mut db, "CREATE TABLE u (a INT, a INT)"), ErrorKind::InvalidInput);
+    assert_eq!(kind(&mut db, "CREATE TABLE u (s STRUCT<x INT, x INT>)"), ErrorKind::InvalidInput);
+    assert_eq!(kind(&mut db, "CREATE TABLE u (a NUMBER)"), ErrorKind::InvalidInput);
+    assert_eq!(kind(&mut db, "CREATE TABLE u (a BINARY(0))"), ErrorKind::InvalidInput);
+    assert_eq!(kind(&mut db, "ALTER TABLE t ADD COLUMN a INT"), ErrorKind::AlreadyExists);
+    assert_eq!(kind(&mut db, "ALTER TABLE nope ADD COLUMN a INT"), ErrorKind::NotFound);
+    assert!(!db.catalog.tables_by_name.contains_key("u"));
+
+    // A script parses in full before anything runs, then stops at the
+    // first statement that fails
+    assert_eq!(kind(&mut db, "CREATE TABLE u (a INT); CREATE TABL v (a INT)"), ErrorKind::InvalidInput);
+    assert!(!db.catalog.tables_by_name.contains_key("u"));
+    assert_eq!(kind(&mut db, "CREATE TABLE u (a INT); CREATE TABLE t (a INT); CREATE TABLE v (a INT)"), ErrorKind::AlreadyExists);
+    assert!(db.catalog.tables_by_name.contains_key("u"));
+    assert!(!db.catalog.tables_by_name.contains_key("v"));
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use std::io::ErrorKind;
+use common::{scan_all, value, TempDb};
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::expr::Expr;
+
+/// Two sealed chunks and part of an active one.
+const ROWS: i64 = ROWS_PER_CHUNK as i64 * 2 + 500;
+
+fn create(file: &TempDb) -> Database {
+    let xs: Vec<i64> = (0..ROWS).collect();
+    let mut db = file.create();
+    db.execute("CREATE TABLE t (x BIGINT)").unwrap();
+    db.append_columns("t", &[ColumnInput::new("x", ColumnSlice::Int64(&xs))]).unwrap();
+    db.flush().unwrap();
+    db
+}
+
+fn multiple_of(n: i64) -> Expr {
+    Expr::column("x").in_list((0..ROWS).step_by(n as usize).map(|x| Expr::literal(Value::Int64(x))).collect())
+}
+
+fn xs(db: &Database) -> Vec<i64> {
+    scan_all(db, "t")
+        .into_iter()
+        .map(|row| match row[0] {
+            Value::Int64(x) => x,
+            ref other => panic!("unexpected value {other:?}"),
+        })
+        .collect()
+}
+
+fn count(db: &Database) -> Value {
+    value(db, "SELECT COUNT(*) FROM t")
+}
+
+#[test]
+fn deletes_hide_rows_and_survive_reopen() {
+    let file = TempDb::new("delete-reopen");
+    {
+        let mut db = create(&file);
+        // Spans both sealed chunks and the active one
+        let middle = Expr::column("x").between(Expr::literal(Value::Int64(10_000)), Expr::literal(Value::Int64(ROWS - 100)));
+        assert_eq!(db.delete_where("t", middle).unwrap(), ROWS as u64 - 100 - 10_000 + 1);
+        let multiples = (0..ROWS).filter(|x| x % 97 == 0 && !(10_000..=ROWS - 100).contains(x)).count();
+        assert_eq!(db.delete_where("t", multiple_of(97)).unwrap(), multiples as u64);
+        // Rows deleted already are not selected again
+        assert_eq!(db.delete_where("t", Expr::column("x").lt(Expr::literal(Value::Int64(10)))).unwrap(), 9);
+        db.flush().unwrap();
+    }
+
+    let expected: Vec<i64> = (0..ROWS).filter(|x| (*x >= 10 && *x < 10_000 || *x > ROWS - 100) && x % 97 != 0).collect();
+    let db = file.open();
+    assert_eq!(xs(&db), expected);
+    assert_eq!(count(&db), Value::Int64(expected.len() as i64));
+    assert_eq!(value(&db, "SELECT SUM(x) FROM t"), Value::Int64(expected.iter().sum()));
+}
+
+#[test]
+fn deletes_in_a_transaction_wait_for_commit() {
+    let file = TempDb::new("delete-transaction");
+    let mut db = create(&file);
+    let version = db.version();
+
+    db.begin();
+    assert_eq!(db.delete_where("t", Expr::column("x").gt_eq(Expr::literal(Value::Int64(100)))).unwrap(), ROWS as u64 - 100);
+    assert_eq!(count(&db), Value::Int64(ROWS));
+    assert_eq!(db.version(), version);
+    assert_eq!(db.commit().unwrap(), version + 1);
+    assert_eq!(count(&db), Value::Int64(100));
+
+    // A delete that selects nothing commits nothing
+    assert_eq!(db.delete_where("t", Expr::column("x").gt_eq(Expr::literal(Value::Int64(100)))).unwrap(), 0);
+    assert_eq!(db.version(), version + 1);
+
+    db.flush().unwrap();
+    drop(db);
+    assert_eq!(count(&file.open()), Value::Int64(100));
+}
+
+#[test]
+fn deletes_in_a_transaction_see_its_own_writes() {
+    let file = TempDb::new("delete-own-writes");
+    let mut db = create(&file);
+
+    db.begin();
+    for x in ROWS..ROWS + 10 {
+        db.append_row("t", vec![("x", Value::Int64(x))]).unwrap();
+    }
+    // Rows appended in the transaction are deleted with the others
+    let from_last_chunk = Expr::column("x").gt_eq(Expr::literal(Value::Int64(ROWS - 5)));
+    assert_eq!(db.delete_where("t", from_last_chunk.clone()).unwrap(), 15);
+    // Rows already deleted in the transaction are not deleted again
+    assert_eq!(db.delete_where("t", from_last_chunk).unwrap(), 0);
+    assert_eq!(db.delete_where("t", Expr::column("x").gt_eq(Expr::literal(Value::Int64(ROWS - 6)))).unwrap(), 1);
+    assert_eq!(count(&db), Value::Int64(ROWS));
+
+    db.commit().unwrap();
+    assert_eq!(xs(&db), (0..ROWS - 6).collect::<Vec<_>>());
+    drop(db);
+    assert_eq!(xs(&file.open()), (0..ROWS - 6).collect::<Vec<_>>());
+}
+
+#[test]
+fn uncommitted_deletes_are_rolled_back_on_reopen() {
+    let file = TempDb::new("delete-rollback");
+    {
+        let mut db = create(&file);
+        db.delete_where("t", Expr::column("x").lt(Expr::literal(Value::Int64(50)))).unwrap();
+        db.begin();
+        db.delete_where("t", multiple_of(97)).unwrap();
+        db.flush().unwrap();
+    }
+
+    let mut db = file.open();
+    assert_eq!(xs(&db), (50..ROWS).collect::<Vec<_>>());
+    // Later deletes commit on their own
+    db.delete_where("t", Expr::column("x").lt(Expr::literal(Value::Int64(60)))).unwrap();
+    db.flush().unwrap();
+    drop(db);
+    assert_eq!(count(&file.open()), Value::Int64(ROWS - 60));
+}
+
+#[test]
+fn failed_deletes_change_nothing() {
+    let file = TempDb::new("delete-failures");
+    drop(create(&file));
+
+    let mut db = file.open();
+    let err = db.delete_where("missing", Expr::column("x").lt(Expr::literal(Value::Int64(5)))).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(db.delete_where("t", Expr::column("nope").lt(Expr::literal(Value::Int64(5)))).is_err());
+    assert_eq!(count(&db), Value::Int64(ROWS));
+    drop(db);
+
+    let mut db = Database::open_read_only(file.path()).unwrap();
+    let err = db.delete_where("t", Expr::column("x").lt(Expr::literal(Value::Int64(5)))).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    assert_eq!(count(&db), Value::Int64(ROWS));
+}
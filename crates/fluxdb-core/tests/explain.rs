@@ -0,0 +1,136 @@
+mod common;
+
+use std::io::ErrorKind;
+use common::{rows, TempDb};
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::execute::QueryOptions;
+use fluxdb_core::query::explain::{explain, explain_analyze, ExplainNode};
+use fluxdb_core::query::optimizer::optimize;
+use fluxdb_core::sql::ast::Statement;
+use fluxdb_core::sql::parser::parse;
+use fluxdb_core::sql::planner::plan_select;
+
+const ROWS: i64 = ROWS_PER_CHUNK as i64 * 3;
+
+/// `t` holds ascending `x` over three sealed chunks, `d` its first ten.
+fn create(file: &TempDb) -> Database {
+    let xs: Vec<i64> = (0..ROWS).collect();
+    let names: Vec<&str> = (0..10).map(|i| ["even", "odd"][i % 2]).collect();
+
+    let mut db = file.create();
+    db.execute("CREATE TABLE t (x BIGINT); CREATE TABLE d (x BIGINT, name VARCHAR)").unwrap();
+    db.append_columns("t", &[ColumnInput::new("x", ColumnSlice::Int64(&xs))]).unwrap();
+    db.append_columns("d", &[
+        ColumnInput::new("x", ColumnSlice::Int64(&xs[..10])),
+        ColumnInput::new("name", ColumnSlice::Utf8(&names)),
+    ]).unwrap();
+    db.flush().unwrap();
+    db
+}
+
+fn lines(db: &Database, sql: &str) -> Vec<String> {
+    rows(db, sql).into_iter().map(|row| row[0].to_string()).collect()
+}
+
+fn node(db: &Database, sql: &str, analyze: bool) -> ExplainNode {
+    let Statement::Select(select) = parse(sql).unwrap() else { panic!("not a SELECT") };
+    let plan = optimize(db, plan_select(&db.catalog, &select).unwrap()).unwrap();
+    match analyze {
+        true => explain_analyze(db, &plan, &QueryOptions::default()).unwrap(),
+        false => explain(db, &plan).unwrap(),
+    }
+}
+
+/// The operators of a tree, depth first.
+fn operators(node: &ExplainNode) -> Vec<&'static str> {
+    let mut out = vec![node.operator];
+    for child in &node.children {
+        out.extend(operators(child));
+    }
+    out
+}
+
+const JOIN: &str = "SELECT d.name, COUNT(*) FROM t JOIN d ON t.x = d.x WHERE t.x >= 5 GROUP BY d.name ORDER BY 2 DESC LIMIT 3";
+
+#[test]
+fn explain_shows_the_operator_tree_with_estimates() {
+    let file = TempDb::new("explain-text");
+    let db = create(&file);
+
+    let text = lines(&db, &format!("EXPLAIN {JOIN}"));
+    assert_eq!(text.len(), 6, "{text:#?}");
+    assert!(text[0].starts_with("Project "), "{}", text[0]);
+    assert!(text[1].starts_with("  -> TopK "), "{}", text[1]);
+    assert!(text[3].starts_with("          -> HashJoin inner"), "{}", text[3]);
+    assert!(text.iter().all(|line| line.contains("estimated rows=") && !line.contains("actual")));
+
+    let tree = node(&db, JOIN, false);
+    assert_eq!(operators(&tree), ["Project", "TopK", "HashAggregate", "HashJoin", "FullScan", "FullScan"]);
+    assert!(tree.actual.is_none());
+
+    // Zone maps leave one of the three chunks to read
+    let lookup = node(&db, &format!("SELECT x FROM t WHERE x >= {}", ROWS - 10), false);
+    let scan = &lookup.children[0];
+    assert!(scan.estimated_rows < ROWS_PER_CHUNK, "{}", scan.estimated_rows);
+    let (scanned, _) = scan.estimated_chunks.unwrap();
+    assert_eq!(scanned, 1);
+}
+
+#[test]
+fn explain_analyze_reports_what_each_operator_did() {
+    let file = TempDb::new("explain-analyze");
+    let db = create(&file);
+
+    let tree = node(&db, JOIN, true);
+    let actual = tree.actual.unwrap();
+    assert_eq!(actual.rows, rows(&db, JOIN).len() as u64);
+
+    let join = &tree.children[0].children[0].children[0];
+    assert_eq!(join.operator, "HashJoin");
+    assert_eq!(join.actual.unwrap().rows, 5);
+    let scans: Vec<_> = join.children.iter().map(|scan| scan.actual.unwrap().scan.unwrap()).collect();
+    assert_eq!(scans.iter().map(|s| s.rows_scanned).sum::<u64>(), ROWS as u64 + 10);
+    assert!(scans.iter().all(|s| s.pages_read > 0 && s.bytes_decoded > 0));
+
+    let pruned = node(&db, "SELECT x FROM t WHERE x < 100", true);
+    let scan = pruned.children[0].actual.unwrap().scan.unwrap();
+    assert_eq!(pruned.actual.unwrap().rows, 100);
+    assert_eq!((scan.ranges_scanned, scan.ranges_pruned), (1, 2));
+
+    let text = lines(&db, "EXPLAIN ANALYZE SELECT x FROM t WHERE x < 100");
+    assert!(text[1].contains("(actual rows=100, batches=1"), "{}", text[1]);
+    assert!(text[1].contains("chunks=1, pruned=2"), "{}", text[1]);
+}
+
+#[test]
+fn explain_renders_json() {
+    let file = TempDb::new("explain-json");
+    let db = create(&file);
+
+    let json = lines(&db, &format!("EXPLAIN FORMAT JSON {JOIN}"));
+    assert_eq!(json.len(), 1);
+    let json = &json[0];
+    assert!(json.starts_with(r#"{"operator":"Project","detail":"d.name AS name, COUNT(*)","estimated_rows":"#), "{json}");
+    assert_eq!(json.matches("\"operator\":").count(), 6);
+    assert!(!json.contains("\"actual\""));
+    assert_eq!(json.matches('{').count(), json.matches('}').count());
+
+    let json = &lines(&db, "EXPLAIN ANALYZE FORMAT JSON SELECT x FROM t WHERE x < 100")[0];
+    assert!(json.contains(r#""actual":{"rows":100,"batches":1,"time_ms":"#), "{json}");
+    assert!(json.contains(r#""chunks_scanned":1,"chunks_pruned":2,"rows_scanned":16384,"#), "{json}");
+}
+
+#[test]
+fn explain_checks_the_query() {
+    let file = TempDb::new("explain-errors");
+    let mut db = file.create();
+    db.execute("CREATE TABLE t (x BIGINT)").unwrap();
+
+    assert_eq!(db.query("EXPLAIN SELECT nope FROM t").err().unwrap().kind(), ErrorKind::NotFound);
+    assert_eq!(db.execute("EXPLAIN SELECT x FROM t").err().unwrap().kind(), ErrorKind::InvalidInput);
+    assert_eq!(rows(&db, "EXPLAIN SELECT x FROM t").len(), 2);
+    assert_eq!(rows(&db, "SELECT COUNT(*) FROM t"), vec![vec![Value::Int64(0)]]);
+}
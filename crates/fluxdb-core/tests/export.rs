@@ -0,0 +1,150 @@
+mod common;
+
+use common::{scan_all, TempDb, TempFile};
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::formats::csv::csv_export::{export_csv, CsvExportOptions};
+use fluxdb_core::formats::csv::csv_import::{import_csv, CsvImportOptions};
+use fluxdb_core::formats::json::json_export::export_json_lines;
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+
+fn create_table(db: &mut Database, table: &str, columns: &[(&str, ColumnType)]) {
+    db.create_table(table).unwrap();
+    for (name, column_type) in columns {
+        db.add_column(table, name, *column_type).unwrap();
+    }
+}
+
+/// `orders (id BIGINT, lines LIST<STRUCT<sku VARCHAR, qty INTEGER>>)`
+fn create_orders(db: &mut Database) {
+    create_table(db, "orders", &[("id", ColumnType::Integer64), ("lines", ColumnType::List)]);
+    db.add_child_column("orders", "lines", "line", ColumnType::Struct).unwrap();
+    db.add_child_column("orders", "lines.line", "sku", ColumnType::Utf8).unwrap();
+    db.add_child_column("orders", "lines.line", "qty", ColumnType::Integer32).unwrap();
+}
+
+fn line(sku: &str, qty: i32) -> Value {
+    Value::Struct(vec![Value::String(sku.into()), Value::Int32(qty)])
+}
+
+#[test]
+fn csv_export_imports_back_unchanged() {
+    let file = TempDb::new("export-csv");
+    let csv = TempFile::new("export-csv.csv");
+    let columns = [("x", ColumnType::Integer64), ("s", ColumnType::Utf8), ("f", ColumnType::Float64)];
+
+    let mut db = file.create();
+    create_table(&mut db, "t", &columns);
+    // Strings that need quoting, including one equal to the null marker
+    let strings = [Some("plain"), Some("a, b"), Some("say \"hi\""), Some("two\nlines"), Some("cr\r"), Some(""), None];
+    for (i, s) in strings.iter().enumerate() {
+        db.append_row("t", vec![
+            ("x", Value::Int64(i as i64)),
+            ("s", s.map_or(Value::Null, |s| Value::String(s.into()))),
+            ("f", Value::Float64(i as f64 / 4.0)),
+        ]).unwrap();
+    }
+
+    let out = std::fs::File::create(csv.path()).unwrap();
+    let written = export_csv(&db, "t", &[], None, out, &CsvExportOptions::default()).unwrap();
+    assert_eq!(written, strings.len());
+    assert!(csv.read().contains("\n5,\"\",1.25\n6,,1.5\n"), "{}", csv.read());
+
+    create_table(&mut db, "copy", &columns);
+    import_csv(&mut db, "copy", csv.path(), &CsvImportOptions::default()).unwrap();
+    assert_eq!(scan_all(&db, "copy"), scan_all(&db, "t"));
+}
+
+#[test]
+fn csv_export_projects_and_filters() {
+    let file = TempDb::new("export-csv-filter");
+    let mut db = file.create();
+    create_orders(&mut db);
+    for id in 0..5 {
+        db.append_row("orders", vec![("id", Value::Int64(id)), ("lines", Value::List(vec![line("a|b", id as i32)]))]).unwrap();
+    }
+
+    // The filter sees the projected columns, in projection order
+    let keep_odd = |row: &[&Value]| matches!(row[1], Value::Int64(x) if x % 2 == 1);
+    let options = CsvExportOptions { delimiter: '|', write_header: false, ..CsvExportOptions::default() };
+    let mut out = Vec::new();
+    let written = export_csv(&db, "orders", &["lines.line.qty", "id"], Some(&keep_odd), &mut out, &options).unwrap();
+    assert_eq!(written, 2);
+    assert_eq!(String::from_utf8(out).unwrap(), "[1]|1\n[3]|3\n");
+
+    // Nested values holding the delimiter are quoted
+    let mut out = Vec::new();
+    export_csv(&db, "orders", &["lines"], Some(&|row: &[&Value]| row[0] == &Value::List(vec![line("a|b", 0)])), &mut out, &options).unwrap();
+    assert!(String::from_utf8(out).unwrap().starts_with('"'));
+}
+
+#[test]
+fn json_lines_export_writes_one_object_per_row() {
+    let file = TempDb::new("export-json");
+    let mut db = file.create();
+    create_table(&mut db, "t", &[
+        ("x", ColumnType::Integer64),
+        ("s", ColumnType::Utf8),
+        ("ts", ColumnType::Timestamp),
+        ("b", ColumnType::Binary),
+        ("f", ColumnType::Float64),
+        ("id", ColumnType::Uuid),
+    ]);
+    db.append_row("t", vec![
+        ("x", Value::Int64(1)),
+        ("s", Value::String("quote \" \\ tab\t bell\u{7}".into())),
+        ("ts", Value::Timestamp(1_500_000)),
+        ("b", Value::Binary(vec![0xca, 0xfe])),
+        ("f", Value::Float64(f64::INFINITY)),
+        ("id", Value::Uuid([0xab; 16])),
+    ]).unwrap();
+    db.append_row("t", vec![("x", Value::Int64(-2)), ("ts", Value::Timestamp(-1))]).unwrap();
+
+    let mut out = Vec::new();
+    assert_eq!(export_json_lines(&db, "t", &[], None, &mut out).unwrap(), 2);
+    assert_eq!(String::from_utf8(out).unwrap(), concat!(
+        r#"{"x":1,"s":"quote \" \\ tab\t bell\u0007","ts":"1970-01-01T00:00:01.500000Z","b":"0xcafe","f":null,"id":"abababab-abab-abab-abab-abababababab"}"#, "\n",
+        r#"{"x":-2,"s":null,"ts":"1969-12-31T23:59:59.999999Z","b":null,"f":null,"id":null}"#, "\n",
+    ));
+}
+
+#[test]
+fn json_lines_name_struct_fields_along_projected_paths() {
+    let file = TempDb::new("export-json-nested");
+    let mut db = file.create();
+    create_orders(&mut db);
+    db.append_row("orders", vec![("id", Value::Int64(1)), ("lines", Value::List(vec![line("a", 2), Value::Null]))]).unwrap();
+    db.append_row("orders", vec![("id", Value::Int64(2))]).unwrap();
+
+    let mut out = Vec::new();
+    export_json_lines(&db, "orders", &["lines", "lines.line.sku"], None, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), concat!(
+        r#"{"lines":[{"sku":"a","qty":2},null],"lines.line.sku":["a",null]}"#, "\n",
+        r#"{"lines":null,"lines.line.sku":null}"#, "\n",
+    ));
+}
+
+#[test]
+fn exports_stream_sealed_and_active_chunks() {
+    let file = TempDb::new("export-chunks");
+    let total = ROWS_PER_CHUNK as i64 * 2 + 10;
+    {
+        let mut db = file.create();
+        create_table(&mut db, "t", &[("x", ColumnType::Integer64)]);
+        let xs: Vec<i64> = (0..total).collect();
+        db.append_columns("t", &[ColumnInput::new("x", ColumnSlice::Int64(&xs))]).unwrap();
+        db.flush().unwrap();
+    }
+
+    let mut db = file.open();
+    db.append_row("t", vec![("x", Value::Int64(total))]).unwrap();
+    let mut out = Vec::new();
+    let from = ROWS_PER_CHUNK as i64 - 1;
+    let filter = |row: &[&Value]| matches!(row[0], Value::Int64(x) if *x >= from);
+    assert_eq!(export_json_lines(&db, "t", &[], Some(&filter), &mut out).unwrap(), (total + 1 - from) as usize);
+    let text = String::from_utf8(out).unwrap();
+    assert_eq!(text.lines().next(), Some(format!(r#"{{"x":{from}}}"#).as_str()));
+    assert_eq!(text.lines().last(), Some(format!(r#"{{"x":{total}}}"#).as_str()));
+}
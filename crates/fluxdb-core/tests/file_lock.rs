@@ -0,0 +1,97 @@
+mod common;
+
+use std::io::ErrorKind;
+use common::{value, TempDb};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::engine::shared_database::SharedDatabase;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::storage::file_lock::{FileLock, LockMode};
+
+#[test]
+fn opening_a_missing_database_leaves_no_files_behind() {
+    let file = TempDb::new("lock-missing");
+
+    let err = Database::open_read_only(file.path()).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    let err = Database::open(file.path(), false).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(!FileLock::lock_path(file.path()).exists());
+    assert!(!file.path().exists());
+
+    // Initializing still creates the database, lock file and all
+    drop(file.create());
+    assert!(file.path().exists());
+    assert!(FileLock::lock_path(file.path()).exists());
+}
+
+#[test]
+fn a_writer_keeps_other_opens_out() {
+    let file = TempDb::new("lock-writer");
+    let db = file.create();
+
+    let err = Database::open(file.path(), false).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::ResourceBusy);
+    let err = Database::open_read_only(file.path()).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::ResourceBusy);
+
+    drop(db);
+    file.open();
+}
+
+#[test]
+fn readers_share_the_database_but_cannot_write() {
+    let file = TempDb::new("lock-readers");
+    {
+        let mut db = file.create();
+        db.execute("CREATE TABLE t (x BIGINT)").unwrap();
+        db.append_row("t", vec![("x", Value::Int64(1))]).unwrap();
+        db.flush().unwrap();
+    }
+
+    let mut first = Database::open_read_only(file.path()).unwrap();
+    let second = Database::open_read_only(file.path()).unwrap();
+    assert_eq!(value(&second, "SELECT COUNT(*) FROM t"), Value::Int64(1));
+
+    let err = first.append_row("t", vec![("x", Value::Int64(2))]).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    let err = first.execute("DROP TABLE t").err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    let err = Database::open(file.path(), false).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::ResourceBusy);
+
+    drop((first, second));
+    assert_eq!(value(&file.open(), "SELECT COUNT(*) FROM t"), Value::Int64(1));
+}
+
+#[test]
+fn snapshots_hold_the_lock_after_the_writer_is_gone() {
+    let file = TempDb::new("lock-snapshot");
+    let shared = SharedDatabase::new(file.create());
+    let snapshot = shared.snapshot();
+
+    drop(shared);
+    let err = Database::open(file.path(), false).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::ResourceBusy);
+
+    drop(snapshot);
+    file.open();
+}
+
+#[test]
+fn locks_are_taken_on_a_file_beside_the_database() {
+    let file = TempDb::new("lock-file");
+    let lock_path = FileLock::lock_path(file.path());
+    assert_eq!(lock_path.parent(), file.path().parent());
+    assert!(lock_path.to_str().unwrap().ends_with("-lock-file.flxdb.lock"));
+
+    let shared = FileLock::acquire(file.path(), LockMode::Shared).unwrap();
+    assert_eq!(shared.mode, LockMode::Shared);
+    let other = FileLock::acquire(file.path(), LockMode::Shared).unwrap();
+    let err = FileLock::acquire(file.path(), LockMode::Exclusive).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::ResourceBusy);
+
+    // The lock file stays; only the lock is released
+    drop((shared, other));
+    assert!(lock_path.exists());
+    FileLock::acquire(file.path(), LockMode::Exclusive).unwrap();
+}
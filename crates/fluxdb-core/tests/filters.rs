@@ -0,0 +1,180 @@
+mod common;
+
+use std::io::ErrorKind;
+use common::{scan_all, scan_where, TempDb};
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::expr::Expr;
+use fluxdb_core::query::vector::VECTOR_SIZE;
+
+/// Three sealed chunks; the database is reopened before any scan.
+const ROWS: usize = ROWS_PER_CHUNK as usize * 3;
+
+fn col(name: &str) -> Expr {
+    Expr::column(name)
+}
+
+fn int(v: i64) -> Expr {
+    Expr::literal(Value::Int64(v))
+}
+
+fn text(s: &str) -> Expr {
+    Expr::literal(Value::String(s.into()))
+}
+
+/// `x` counts up from 0; `s` cycles through a few strings, two of them
+/// holding LIKE wildcards, and is null on every seventh row.
+fn create(file: &TempDb) -> Database {
+    let xs: Vec<i64> = (0..ROWS as i64).collect();
+    let words = ["apple", "banana", "cherry", "a_b", "a%b"];
+    let strings: Vec<&str> = (0..ROWS).map(|i| words[i % words.len()]).collect();
+    let validity: Vec<bool> = (0..ROWS).map(|i| i % 7 != 0).collect();
+
+    let mut db = file.create();
+    db.create_table("t").unwrap();
+    db.add_column("t", "x", ColumnType::Integer64).unwrap();
+    db.add_column("t", "s", ColumnType::Utf8).unwrap();
+    db.append_columns("t", &[
+        ColumnInput::new("x", ColumnSlice::Int64(&xs)),
+        ColumnInput::new("s", ColumnSlice::Utf8(&strings)).with_validity(&validity),
+    ]).unwrap();
+    db.flush().unwrap();
+    drop(db);
+    file.open()
+}
+
+/// The rows `filter` selects must be exactly those `expected` accepts.
+fn check(db: &Database, filter: Expr, expected: impl Fn(i64, Option<&str>) -> bool) {
+    let want: Vec<Vec<Value>> = scan_all(db, "t")
+        .into_iter()
+        .filter(|row| {
+            let Value::Int64(x) = row[0] else { unreachable!() };
+            let s = match &row[1] {
+                Value::String(s) => Some(s.as_str()),
+                _ => None,
+            };
+            expected(x, s)
+        })
+        .collect();
+    assert_eq!(scan_where(db, "t", filter.clone()), want, "{filter:?}");
+}
+
+#[test]
+fn filters_follow_three_valued_logic() {
+    let file = TempDb::new("filter-logic");
+    let db = create(&file);
+    let last = ROWS as i64 - 1;
+
+    check(&db, col("x").lt(int(10)).or(col("x").gt_eq(int(last - 2))), |x, _| x < 10 || x > last - 3);
+    check(&db, col("x").between(int(100), int(120)).and(col("s").eq(text("apple"))), |x, s| {
+        (100..=120).contains(&x) && s == Some("apple")
+    });
+    // Bounds given the wrong way round select nothing
+    check(&db, col("x").between(int(120), int(100)), |_, _| false);
+    check(&db, col("x").not_between(int(10), int(last)), |x, _| x < 10);
+    check(&db, col("x").in_list(vec![int(1), int(14), int(99_999_999)]), |x, _| x == 1 || x == 14);
+    check(&db, col("x").lt(int(50)).and(col("s").not_in_list(vec![text("apple"), text("banana")])), |x, s| {
+        x < 50 && s.is_some_and(|s| s != "apple" && s != "banana")
+    });
+    // NOT IN with a null in the list is never true
+    check(&db, col("x").lt(int(50)).and(col("s").not_in_list(vec![text("apple"), Expr::literal(Value::Null)])), |_, _| false);
+    check(&db, col("x").lt(int(30)).and(col("s").is_null()), |x, s| x < 30 && s.is_none());
+
+    // NOT of a comparison with a null is still null, so the row is dropped,
+    // but `null OR true` is true
+    check(&db, (!col("s").eq(text("apple"))).and(col("x").lt(int(40))), |x, s| {
+        x < 40 && s.is_some_and(|s| s != "apple")
+    });
+    check(&db, col("s").eq(text("apple")).or(col("x").lt(int(8))), |x, s| x < 8 || s == Some("apple"));
+    check(&db, col("s").eq(Expr::literal(Value::Null)), |_, _| false);
+}
+
+#[test]
+fn like_treats_escaped_wildcards_literally() {
+    let file = TempDb::new("filter-like");
+    let db = create(&file);
+    let first = |x: i64| x < 20;
+
+    check(&db, col("s").like("%an%").and(col("x").lt(int(20))), |x, s| first(x) && s == Some("banana"));
+    check(&db, col("s").like("a_b").and(col("x").lt(int(20))), |x, s| {
+        first(x) && matches!(s, Some("a_b" | "a%b"))
+    });
+    check(&db, col("s").like(r"a\%b").and(col("x").lt(int(20))), |x, s| first(x) && s == Some("a%b"));
+    check(&db, col("s").like(r"a\_%").and(col("x").lt(int(20))), |x, s| first(x) && s == Some("a_b"));
+    check(&db, col("s").like("ch%y").and(col("x").lt(int(20))), |x, s| first(x) && s == Some("cherry"));
+    check(&db, col("s").not_like("%e%").and(col("x").lt(int(20))), |x, s| {
+        first(x) && s.is_some_and(|s| !s.contains('e'))
+    });
+}
+
+#[test]
+fn zone_maps_prune_sealed_chunks_only() {
+    let file = TempDb::new("filter-prune");
+    let mut db = create(&file);
+
+    let mut scan = db.scan_where("t", &["s"], col("x").gt_eq(int(ROWS as i64 - 5))).unwrap();
+    let batches: Vec<_> = scan.by_ref().map(|batch| batch.unwrap()).collect();
+    assert_eq!(batches.len(), 1);
+    // The filter's column is read but not returned
+    assert_eq!(batches[0].columns.len(), 1);
+    // Selections are offsets into the last window of the chunk
+    let offsets: Vec<u32> = batches[0].selection.as_ref().unwrap().to_vec();
+    assert_eq!(offsets, (VECTOR_SIZE as u32 - 5..VECTOR_SIZE as u32).collect::<Vec<_>>());
+    assert_eq!((scan.stats.ranges_scanned, scan.stats.ranges_pruned, scan.stats.rows_selected), (1, 2, 5));
+
+    // A range that may hold a matching row is read, even if none matches
+    let mut scan = db.scan_where("t", &[], col("x").eq(int(-1)).or(col("s").eq(text("blueberry")))).unwrap();
+    assert_eq!(scan.by_ref().count(), 0);
+    assert_eq!(scan.stats.ranges_scanned, 3);
+
+    // Active rows have no zone map yet
+    db.append_row("t", vec![("x", Value::Int64(-1))]).unwrap();
+    let mut scan = db.scan_where("t", &[], col("x").lt(int(0))).unwrap().with_filter(col("s").is_null()).unwrap();
+    let rows: usize = scan.by_ref().map(|batch| batch.unwrap().row_count).sum();
+    assert_eq!(rows, 1);
+    assert_eq!((scan.stats.ranges_scanned, scan.stats.ranges_pruned), (1, 3));
+}
+
+#[test]
+fn filters_reach_into_struct_fields() {
+    let file = TempDb::new("filter-nested");
+    let mut db = file.create();
+    db.create_table("events").unwrap();
+    db.add_column("events", "id", ColumnType::Integer64).unwrap();
+    db.add_column("events", "user", ColumnType::Struct).unwrap();
+    db.add_child_column("events", "user", "name", ColumnType::Utf8).unwrap();
+    for (id, name) in [(1, Some("ann")), (2, None), (3, Some("bob")), (4, Some("anna"))] {
+        let user = match name {
+            Some(name) => Value::Struct(vec![Value::String(name.into())]),
+            None => Value::Null,
+        };
+        db.append_row("events", vec![("id", Value::Int64(id)), ("user", user)]).unwrap();
+    }
+
+    let ids = |filter: Expr| -> Vec<Value> { scan_where(&db, "events", filter).into_iter().map(|row| row[0].clone()).collect() };
+    assert_eq!(ids(col("user.name").like("ann%")), vec![Value::Int64(1), Value::Int64(4)]);
+    assert_eq!(ids(col("user.name").is_null()), vec![Value::Int64(2)]);
+}
+
+#[test]
+fn filters_are_type_checked() {
+    let file = TempDb::new("filter-types");
+    let db = create(&file);
+
+    // A text literal parses as the column's type
+    check(&db, col("x").eq(text("12")), |x, _| x == 12);
+
+    for filter in [
+        col("x").eq(col("s")),
+        col("x").eq(text("twelve")),
+        col("x").like("1%"),
+        col("s").gt(int(3)),
+    ] {
+        assert_eq!(db.scan_where("t", &[], filter).err().unwrap().kind(), ErrorKind::InvalidInput);
+    }
+    let err = db.scan_where("t", &[], col("s").like("a%").and(col("missing").eq(int(1)))).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use common::{TempDb, TempDir};
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::aggregate::Aggregate;
+use fluxdb_core::query::expr::Expr;
+use fluxdb_core::query::hash_aggregate::{aggregate_table, AggregateOptions, AggregateResults};
+
+const ROWS: usize = 20_000;
+
+/// `g` takes `groups` values, `k` is null on every eleventh row, `n` is a
+/// 32-bit count and `v` is null on every thirteenth row.
+fn create(file: &TempDb, groups: i64) -> Database {
+    let gs: Vec<i64> = (0..ROWS as i64).map(|i| i % groups).collect();
+    let ks: Vec<&str> = (0..ROWS).map(|i| ["red", "green", "blue"][i % 3]).collect();
+    let k_valid: Vec<bool> = (0..ROWS).map(|i| i % 11 != 0).collect();
+    let ns: Vec<i32> = (0..ROWS as i32).map(|i| i32::MAX - i).collect();
+    let vs: Vec<f64> = (0..ROWS).map(|i| i as f64 / 8.0).collect();
+    let v_valid: Vec<bool> = (0..ROWS).map(|i| i % 13 != 0).collect();
+
+    let mut db = file.create();
+    db.create_table("t").unwrap();
+    for (name, column_type) in [("g", ColumnType::Integer64), ("k", ColumnType::Utf8), ("n", ColumnType::Integer32), ("v", ColumnType::Float64)] {
+        db.add_column("t", name, column_type).unwrap();
+    }
+    db.append_columns("t", &[
+        ColumnInput::new("g", ColumnSlice::Int64(&gs)),
+        ColumnInput::new("k", ColumnSlice::Utf8(&ks)).with_validity(&k_valid),
+        ColumnInput::new("n", ColumnSlice::Int32(&ns)),
+        ColumnInput::new("v", ColumnSlice::Float64(&vs)).with_validity(&v_valid),
+    ]).unwrap();
+    db
+}
+
+fn aggregates() -> Vec<Aggregate> {
+    vec![Aggregate::count_star(), Aggregate::count("v"), Aggregate::sum("v"), Aggregate::avg("v"), Aggregate::min("v"), Aggregate::max("v")]
+}
+
+fn sorted(results: AggregateResults) -> Vec<Vec<Value>> {
+    let mut rows: Vec<Vec<Value>> = Vec::new();
+    for batch in results {
+        let batch = batch.unwrap();
+        rows.extend((0..batch.row_count).map(|i| batch.row(i)));
+    }
+    rows.sort_by(|a, b| format!("{a:?}").cmp(&format!("{b:?}")));
+    rows
+}
+
+/// What the aggregates of [`aggregates`] give over the rows of each key.
+fn expected(keys: impl Fn(usize) -> Vec<Value>) -> Vec<Vec<Value>> {
+    let mut groups: BTreeMap<String, (Vec<Value>, i64, Vec<f64>)> = BTreeMap::new();
+    for i in 0..ROWS {
+        let key = keys(i);
+        let group = groups.entry(format!("{key:?}")).or_insert((key, 0, Vec::new()));
+        group.1 += 1;
+        if i % 13 != 0 {
+            group.2.push(i as f64 / 8.0);
+        }
+    }
+
+    let mut rows: Vec<Vec<Value>> = groups
+        .into_values()
+        .map(|(mut row, count, vs)| {
+            let sum: f64 = vs.iter().sum();
+            row.push(Value::Int64(count));
+            row.push(Value::Int64(vs.len() as i64));
+            if vs.is_empty() {
+                row.extend([Value::Null, Value::Null, Value::Null, Value::Null]);
+            } else {
+                row.push(Value::Float64(sum));
+                row.push(Value::Float64(sum / vs.len() as f64));
+                row.push(Value::Float64(vs.iter().copied().fold(f64::INFINITY, f64::min)));
+                row.push(Value::Float64(vs.iter().copied().fold(f64::NEG_INFINITY, f64::max)));
+            }
+            row
+        })
+        .collect();
+    rows.sort_by(|a, b| format!("{a:?}").cmp(&format!("{b:?}")));
+    rows
+}
+
+#[test]
+fn groups_by_one_and_several_columns() {
+    let file = TempDb::new("group-by");
+    let db = create(&file, 7);
+
+    let results = aggregate_table(&db, "t", &["g"], &aggregates(), None, AggregateOptions::default()).unwrap();
+    assert_eq!(results.column_names, ["g", "COUNT(*)", "COUNT(v)", "SUM(v)", "AVG(v)", "MIN(v)", "MAX(v)"]);
+    assert_eq!(sorted(results), expected(|i| vec![Value::Int64(i as i64 % 7)]));
+
+    // Nulls form a group of their own
+    let k = |i: usize| if i.is_multiple_of(11) { Value::Null } else { Value::String(["red", "green", "blue"][i % 3].into()) };
+    let results = aggregate_table(&db, "t", &["k", "g"], &aggregates(), None, AggregateOptions::default()).unwrap();
+    assert_eq!(sorted(results), expected(|i| vec![k(i), Value::Int64(i as i64 % 7)]));
+}
+
+#[test]
+fn output_types_follow_the_input() {
+    let file = TempDb::new("group-by-types");
+    let db = create(&file, 7);
+
+    let aggregates = [Aggregate::sum("n").with_alias("total"), Aggregate::min("k"), Aggregate::max("n"), Aggregate::avg("n")];
+    let results = aggregate_table(&db, "t", &[], &aggregates, None, AggregateOptions::default()).unwrap();
+    assert_eq!(results.column_names, ["total", "MIN(k)", "MAX(n)", "AVG(n)"]);
+    assert_eq!(results.column_types, [ColumnType::Integer64, ColumnType::Utf8, ColumnType::Integer32, ColumnType::Float64]);
+
+    // The sum of 32-bit values runs past i32 without wrapping
+    let sum: i64 = (0..ROWS as i64).map(|i| i32::MAX as i64 - i).sum();
+    assert_eq!(sorted(results), vec![vec![
+        Value::Int64(sum),
+        Value::String("blue".into()),
+        Value::Int32(i32::MAX),
+        Value::Float64(sum as f64 / ROWS as f64),
+    ]]);
+}
+
+#[test]
+fn spilled_groups_merge_back_exactly() {
+    let file = TempDb::new("group-by-spill");
+    let spill_dir = TempDir::new("group-by-spill");
+    let db = create(&file, 5_000);
+
+    let options = AggregateOptions { memory_budget: 16 * 1024, spill_dir: spill_dir.path().to_path_buf(), batch_size: 100, threads: 1 };
+    let mut results = aggregate_table(&db, "t", &["g"], &aggregates(), None, options).unwrap();
+    assert!(results.stats.spills > 1);
+    assert!(results.stats.spilled_groups >= 5_000);
+    assert!(spill_dir.file_count() > 0);
+
+    // Output comes in batches of at most `batch_size` rows
+    let first = results.next().unwrap().unwrap();
+    assert_eq!(first.row_count, 100);
+    let mut rows: Vec<Vec<Value>> = (0..first.row_count).map(|i| first.row(i)).collect();
+    rows.extend(sorted(results));
+    rows.sort_by(|a, b| format!("{a:?}").cmp(&format!("{b:?}")));
+
+    assert_eq!(rows, expected(|i| vec![Value::Int64(i as i64 % 5_000)]));
+    assert_eq!(spill_dir.file_count(), 0);
+}
+
+#[test]
+fn no_input_rows_give_one_global_row_and_no_groups() {
+    let file = TempDb::new("group-by-empty");
+    let db = create(&file, 7);
+    let nothing = || Some(Expr::column("g").lt(Expr::literal(Value::Int64(0))));
+
+    let results = aggregate_table(&db, "t", &[], &aggregates(), nothing(), AggregateOptions::default()).unwrap();
+    assert_eq!(sorted(results), vec![vec![
+        Value::Int64(0), Value::Int64(0), Value::Null, Value::Null, Value::Null, Value::Null,
+    ]]);
+    let results = aggregate_table(&db, "t", &["g"], &aggregates(), nothing(), AggregateOptions::default()).unwrap();
+    assert!(sorted(results).is_empty());
+}
+
+#[test]
+fn aggregates_must_fit_their_column() {
+    let file = TempDb::new("group-by-errors");
+    let db = create(&file, 7);
+
+    for aggregate in [Aggregate::sum("k"), Aggregate::avg("k")] {
+        let err = aggregate_table(&db, "t", &[], &[aggregate], None, AggregateOptions::default()).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+    let err = aggregate_table(&db, "t", &["nope"], &aggregates(), None, AggregateOptions::default()).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    let err = aggregate_table(&db, "missing", &[], &[Aggregate::count_star()], None, AggregateOptions::default()).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
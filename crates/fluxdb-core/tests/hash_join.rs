@@ -0,0 +1,196 @@
+mod common;
+
+use std::io::ErrorKind;
+use common::{scan_all, TempDb, TempDir};
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::expr::Expr;
+use fluxdb_core::query::hash_join::{join_tables, JoinOptions, JoinResults, JoinTable, JoinType};
+
+const PEOPLE: i32 = 300;
+const EVENTS: usize = 10_000;
+
+/// People with ids 0..300, and events pointing at ids 0..400, so some
+/// events have no person; every seventeenth event points at nobody.
+fn create(file: &TempDb) -> Database {
+    let ids: Vec<i32> = (0..PEOPLE).collect();
+    let names: Vec<String> = (0..PEOPLE).map(|i| format!("p{i}")).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+    let user_ids: Vec<i64> = (0..EVENTS as i64).map(|i| i * 37 % 400).collect();
+    let valid: Vec<bool> = (0..EVENTS).map(|i| i % 17 != 0).collect();
+    let kinds: Vec<&str> = (0..EVENTS).map(|i| ["view", "click"][i % 2]).collect();
+
+    let mut db = file.create();
+    for (table, columns) in [
+        ("people", [("id", ColumnType::Integer32), ("name", ColumnType::Utf8)]),
+        ("events", [("user_id", ColumnType::Integer64), ("kind", ColumnType::Utf8)]),
+    ] {
+        db.create_table(table).unwrap();
+        for (name, column_type) in columns {
+            db.add_column(table, name, column_type).unwrap();
+        }
+    }
+    db.append_columns("people", &[
+        ColumnInput::new("id", ColumnSlice::Int32(&ids)),
+        ColumnInput::new("name", ColumnSlice::Utf8(&names)),
+    ]).unwrap();
+    db.append_columns("events", &[
+        ColumnInput::new("user_id", ColumnSlice::Int64(&user_ids)).with_validity(&valid),
+        ColumnInput::new("kind", ColumnSlice::Utf8(&kinds)),
+    ]).unwrap();
+    db
+}
+
+fn sorted(results: JoinResults) -> Vec<Vec<Value>> {
+    let mut rows = Vec::new();
+    for batch in results {
+        let batch = batch.unwrap();
+        rows.extend((0..batch.row_count).map(|i| batch.row(i)));
+    }
+    rows.sort_by(|a, b| format!("{a:?}").cmp(&format!("{b:?}")));
+    rows
+}
+
+/// Events joined to people by nested loops: event columns, then people
+/// columns; unmatched events kept with nulls when `left`.
+fn expected(db: &Database, left: bool) -> Vec<Vec<Value>> {
+    let people = scan_all(db, "people");
+    let mut rows = Vec::new();
+    for event in scan_all(db, "events") {
+        let matches: Vec<&Vec<Value>> = people
+            .iter()
+            .filter(|person| event[0].as_i64().is_some() && event[0].as_i64() == person[0].as_i64())
+            .collect();
+        for person in &matches {
+            rows.push(event.iter().chain(person.iter()).cloned().collect());
+        }
+        if matches.is_empty() && left {
+            rows.push(vec![event[0].clone(), event[1].clone(), Value::Null, Value::Null]);
+        }
+    }
+    rows.sort_by(|a: &Vec<Value>, b| format!("{a:?}").cmp(&format!("{b:?}")));
+    rows
+}
+
+fn join(db: &Database, join_type: JoinType, options: JoinOptions) -> JoinResults<'_> {
+    join_tables(db, JoinTable::new("events", &[]), JoinTable::new("people", &[]), &[("user_id", "id")], join_type, options)
+        .unwrap()
+}
+
+#[test]
+fn inner_and_left_joins_match_nested_loops() {
+    let file = TempDb::new("join");
+    let db = create(&file);
+
+    let results = join(&db, JoinType::Inner, JoinOptions::default());
+    assert_eq!(results.column_names, ["events.user_id", "events.kind", "people.id", "people.name"]);
+    // The smaller side is built, even on the right of a left join
+    assert!(!results.stats.build_left);
+    assert_eq!(sorted(results), expected(&db, false));
+
+    let results = join(&db, JoinType::Left, JoinOptions::default());
+    assert!(!results.stats.build_left);
+    assert_eq!(sorted(results), expected(&db, true));
+}
+
+#[test]
+fn left_join_can_build_the_left_side() {
+    let file = TempDb::new("join-build-left");
+    let db = create(&file);
+
+    let few = Expr::column("user_id").lt(Expr::literal(Value::Int64(5)));
+    let results = join_tables(
+        &db,
+        JoinTable::new("people", &["name"]).with_filter(Expr::column("id").lt(Expr::literal(Value::Int32(10)))),
+        JoinTable::new("events", &["kind"]).with_filter(few),
+        &[("id", "user_id")],
+        JoinType::Left,
+        JoinOptions::default(),
+    ).unwrap();
+    assert!(results.stats.build_left);
+
+    let rows = sorted(results);
+    let unmatched: Vec<&Vec<Value>> = rows.iter().filter(|row| row[2] == Value::Null).collect();
+    assert_eq!(unmatched.len(), 5);
+    assert!(unmatched.iter().all(|row| row[3] == Value::Null));
+    assert_eq!(rows.len() - 5, scan_all(&db, "events").iter().filter(|e| e[0].as_i64().is_some_and(|id| id < 5)).count());
+}
+
+#[test]
+fn grace_hash_fallback_partitions_both_sides() {
+    let file = TempDb::new("join-grace");
+    let spill_dir = TempDir::new("join-grace");
+    let db = create(&file);
+
+    for join_type in [JoinType::Inner, JoinType::Left] {
+        let options = JoinOptions { memory_budget: 1024, spill_dir: spill_dir.path().to_path_buf(), batch_size: 100 };
+        let results = join(&db, join_type, options);
+        let rows = sorted(results);
+        assert_eq!(rows, expected(&db, join_type == JoinType::Left));
+        assert_eq!(spill_dir.file_count(), 0);
+    }
+
+    let options = JoinOptions { memory_budget: 1024, spill_dir: spill_dir.path().to_path_buf(), batch_size: 100 };
+    let mut results = join(&db, JoinType::Inner, options);
+    results.next().unwrap().unwrap();
+    assert!(results.stats.partitions > 0);
+}
+
+#[test]
+fn composite_and_string_keys_repeat_matching_rows() {
+    let file = TempDb::new("join-composite");
+    let mut db = file.create();
+    for table in ["a", "b"] {
+        db.create_table(table).unwrap();
+        db.add_column(table, "k1", ColumnType::Utf8).unwrap();
+        db.add_column(table, "k2", ColumnType::Integer32).unwrap();
+        db.add_column(table, "v", ColumnType::Integer32).unwrap();
+    }
+    // Two build rows share ("x", 1); ("x", 2) matches only on the first key;
+    // a null key matches nothing, not even another null
+    db.append_columns("a", &[
+        ColumnInput::new("k1", ColumnSlice::Utf8(&["x", "x", "y", ""])).with_validity(&[true, true, true, false]),
+        ColumnInput::new("k2", ColumnSlice::Int32(&[1, 2, 1, 1])),
+        ColumnInput::new("v", ColumnSlice::Int32(&[10, 20, 30, 40])),
+    ]).unwrap();
+    db.append_columns("b", &[
+        ColumnInput::new("k1", ColumnSlice::Utf8(&["x", "x", "y", ""])).with_validity(&[true, true, true, false]),
+        ColumnInput::new("k2", ColumnSlice::Int32(&[1, 1, 2, 1])),
+        ColumnInput::new("v", ColumnSlice::Int32(&[1, 2, 3, 4])),
+    ]).unwrap();
+
+    let on = [("k1", "k1"), ("k2", "k2")];
+    let results = join_tables(&db, JoinTable::new("a", &["v"]), JoinTable::new("b", &["v"]), &on, JoinType::Inner, JoinOptions::default()).unwrap();
+    // Key columns missing from the projection are added after it
+    assert_eq!(results.column_names, ["a.v", "a.k1", "a.k2", "b.v", "b.k1", "b.k2"]);
+    let values: Vec<(Value, Value)> = sorted(results).into_iter().map(|row| (row[0].clone(), row[3].clone())).collect();
+    assert_eq!(values, vec![(Value::Int32(10), Value::Int32(1)), (Value::Int32(10), Value::Int32(2))]);
+
+    let results = join_tables(&db, JoinTable::new("a", &["v"]), JoinTable::new("b", &["v"]), &on, JoinType::Left, JoinOptions::default()).unwrap();
+    let rows = sorted(results);
+    assert_eq!(rows.len(), 5);
+    let unmatched: Vec<&Value> = rows.iter().filter(|row| row[3] == Value::Null).map(|row| &row[0]).collect();
+    assert_eq!(unmatched.len(), 3);
+    for v in [20, 30, 40] {
+        assert!(unmatched.contains(&&Value::Int32(v)));
+    }
+}
+
+#[test]
+fn rejects_joins_without_keys_or_with_unknown_columns() {
+    let file = TempDb::new("join-errors");
+    let db = create(&file);
+
+    let join = |on: &[(&str, &str)]| {
+        join_tables(&db, JoinTable::new("events", &[]), JoinTable::new("people", &[]), on, JoinType::Inner, JoinOptions::default())
+            .err()
+            .unwrap()
+            .kind()
+    };
+    assert_eq!(join(&[]), ErrorKind::InvalidInput);
+    assert_eq!(join(&[("user_id", "nope")]), ErrorKind::NotFound);
+    assert_eq!(join(&[("kind", "id")]), ErrorKind::InvalidInput);
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use std::io::ErrorKind;
+use common::{column_types, scan_all, TempDb};
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::storage::page::Page;
+
+/// `events (id BIGINT, payload STRUCT<user VARCHAR, tags LIST<LIST<VARCHAR>>>)`
+fn create_events(db: &mut Database) {
+    db.create_table("events").unwrap();
+    db.add_column("events", "id", ColumnType::Integer64).unwrap();
+    db.add_column("events", "payload", ColumnType::Struct).unwrap();
+    db.add_child_column("events", "payload", "user", ColumnType::Utf8).unwrap();
+    db.add_child_column("events", "payload", "tags", ColumnType::List).unwrap();
+    db.add_child_column("events", "payload.tags", "group", ColumnType::List).unwrap();
+    db.add_child_column("events", "payload.tags.group", "tag", ColumnType::Utf8).unwrap();
+}
+
+fn text(s: &str) -> Value {
+    Value::String(s.into())
+}
+
+fn tags(groups: &[&[&str]]) -> Value {
+    Value::List(groups.iter().map(|group| Value::List(group.iter().map(|tag| text(tag)).collect())).collect())
+}
+
+fn append(db: &mut Database, id: i64, payload: Value) {
+    db.append_row("events", vec![("id", Value::Int64(id)), ("payload", payload)]).unwrap();
+}
+
+#[test]
+fn nulls_and_empty_lists_stay_apart_across_reopen() {
+    let file = TempDb::new("nested-nulls");
+    let payloads = vec![
+        Value::Null,
+        Value::Struct(vec![Value::Null, Value::Null]),
+        Value::Struct(vec![text("ann"), Value::List(vec![])]),
+        Value::Struct(vec![text("bob"), tags(&[&[]])]),
+        Value::Struct(vec![text("cy"), Value::List(vec![Value::Null, Value::List(vec![Value::Null, text("x")])])]),
+        Value::Struct(vec![text("di"), tags(&[&["a", "b"], &[], &["c"]])]),
+    ];
+    let expected: Vec<Vec<Value>> = payloads.iter().enumerate().map(|(id, payload)| vec![Value::Int64(id as i64), payload.clone()]).collect();
+    {
+        let mut db = file.create();
+        create_events(&mut db);
+        for (id, payload) in payloads.into_iter().enumerate() {
+            append(&mut db, id as i64, payload);
+        }
+        assert_eq!(scan_all(&db, "events"), expected);
+        db.flush().unwrap();
+    }
+
+    let db = file.open();
+    assert_eq!(column_types(&db, "events"), vec![("id".into(), ColumnType::Integer64), ("payload".into(), ColumnType::Struct)]);
+    assert_eq!(scan_all(&db, "events"), expected);
+}
+
+#[test]
+fn nested_paths_project_a_single_field() {
+    let file = TempDb::new("nested-projection");
+    let mut db = file.create();
+    create_events(&mut db);
+    append(&mut db, 1, Value::Struct(vec![text("ann"), tags(&[&["a"]])]));
+    append(&mut db, 2, Value::Null);
+    db.flush().unwrap();
+    append(&mut db, 3, Value::Struct(vec![text("cy"), tags(&[&["b", "c"], &["d"]])]));
+
+    let mut users = Vec::new();
+    let mut groups = Vec::new();
+    for batch in db.scan("events", &["payload.user", "payload.tags"]).unwrap() {
+        let batch = batch.unwrap();
+        users.extend(batch.columns[0].iter().cloned());
+        groups.extend(batch.columns[1].iter().cloned());
+    }
+    assert_eq!(users, vec![text("ann"), Value::Null, text("cy")]);
+    assert_eq!(groups, vec![tags(&[&["a"]]), Value::Null, tags(&[&["b", "c"], &["d"]])]);
+
+    assert_eq!(db.scan("events", &["payload.missing"]).err().unwrap().kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn element_columns_fill_many_pages_per_chunk() {
+    let file = TempDb::new("nested-long-lists");
+    // Each row holds far more elements than rows, so element streams run
+    // well past the row count of a chunk
+    let rows = ROWS_PER_CHUNK as i64 / 64 + 3;
+    let row = |id: i64| Value::Struct(vec![text(&format!("user {id}")), Value::List(vec![Value::List((0..70).map(|i| text(&format!("{id}/{i}"))).collect())])]);
+    {
+        let mut db = file.create();
+        create_events(&mut db);
+        for id in 0..rows {
+            append(&mut db, id, row(id));
+        }
+        db.flush().unwrap();
+    }
+
+    let all = scan_all(&file.open(), "events");
+    assert_eq!(all.len() as i64, rows);
+    for (id, values) in all.into_iter().enumerate() {
+        assert_eq!(values, vec![Value::Int64(id as i64), row(id as i64)]);
+    }
+}
+
+#[test]
+fn child_columns_need_a_nested_parent() {
+    let file = TempDb::new("nested-schema");
+    let mut db = file.create();
+    create_events(&mut db);
+
+    let err = db.add_child_column("events", "id", "x", ColumnType::Utf8).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = db.add_child_column("events", "payload.tags", "other", ColumnType::Utf8).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = db.add_child_column("events", "payload.nope", "x", ColumnType::Utf8).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    // Values that do not fit the tree are refused
+    let err = db.append_row("events", vec![("payload", Value::List(vec![]))]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(scan_all(&db, "events").is_empty());
+}
+
+#[test]
+fn oversized_values_are_rejected_before_anything_is_written() {
+    let file = TempDb::new("nested-oversized");
+    let mut db = file.create();
+    create_events(&mut db);
+    let max = Page::max_value_len(db.chunk_manager.pager.header.page_size as usize);
+
+    // One byte too long once encoded, at the top level of the struct and
+    // deep inside its lists, after the row's `id` would have been written
+    let long = "x".repeat(max - 3);
+    for payload in [
+        Value::Struct(vec![text(&long), Value::Null]),
+        Value::Struct(vec![text("ann"), tags(&[&["a"], &["b", &long]])]),
+    ] {
+        let err = db.append_row("events", vec![("id", Value::Int64(1)), ("payload", payload)]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    // A string whose encoding, a four-byte length then the bytes, just
+    // fits a page is fine
+    let longest = "y".repeat(max - 4);
+    append(&mut db, 2, Value::Struct(vec![text(&longest), tags(&[&["c"]])]));
+    assert_eq!(scan_all(&db, "events"), vec![vec![
+        Value::Int64(2),
+        Value::Struct(vec![text(&longest), tags(&[&["c"]])]),
+    ]]);
+}
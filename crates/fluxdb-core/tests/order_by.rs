@@ -0,0 +1,183 @@
+mod common;
+
+use std::cmp::Ordering;
+use std::io::ErrorKind;
+use common::{scan_all, TempDb, TempDir};
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::expr::Expr;
+use fluxdb_core::query::sort::{sort_table, Sort, SortOptions, SortResults};
+use fluxdb_core::query::sort_key::SortKey;
+
+const ROWS: usize = 20_000;
+
+/// `id` counts up, `g` repeats a scrambled sequence and is null on every
+/// ninth row, `s` holds a few strings.
+fn create(file: &TempDb) -> Database {
+    let ids: Vec<i64> = (0..ROWS as i64).collect();
+    let gs: Vec<i64> = (0..ROWS as i64).map(|i| i * 7_919 % 1_000).collect();
+    let g_valid: Vec<bool> = (0..ROWS).map(|i| i % 9 != 0).collect();
+    let ss: Vec<&str> = (0..ROWS).map(|i| ["x", "y", "z"][i * 31 % 3]).collect();
+
+    let mut db = file.create();
+    db.create_table("t").unwrap();
+    for (name, column_type) in [("id", ColumnType::Integer64), ("g", ColumnType::Integer64), ("s", ColumnType::Utf8), ("tags", ColumnType::List)] {
+        db.add_column("t", name, column_type).unwrap();
+    }
+    db.add_child_column("t", "tags", "tag", ColumnType::Integer64).unwrap();
+    db.append_columns("t", &[
+        ColumnInput::new("id", ColumnSlice::Int64(&ids)),
+        ColumnInput::new("g", ColumnSlice::Int64(&gs)).with_validity(&g_valid),
+        ColumnInput::new("s", ColumnSlice::Utf8(&ss)),
+    ]).unwrap();
+    db
+}
+
+fn collect(results: SortResults) -> Vec<Vec<Value>> {
+    let mut rows = Vec::new();
+    for batch in results {
+        let batch = batch.unwrap();
+        rows.extend((0..batch.row_count).map(|i| batch.row(i)));
+    }
+    rows
+}
+
+/// Orders by column `index`, with nulls first or last.
+fn by(index: usize, descending: bool, nulls_first: bool) -> impl Fn(&Vec<Value>, &Vec<Value>) -> Ordering {
+    move |a, b| match (&a[index], &b[index]) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => if nulls_first { Ordering::Less } else { Ordering::Greater },
+        (_, Value::Null) => if nulls_first { Ordering::Greater } else { Ordering::Less },
+        (a, b) if descending => b.compare(a).unwrap(),
+        (a, b) => a.compare(b).unwrap(),
+    }
+}
+
+/// The table's `id`, `g` and `s` rows, stably sorted by `s` ascending then
+/// `g` descending with nulls last.
+fn expected(db: &Database) -> Vec<Vec<Value>> {
+    let mut rows: Vec<Vec<Value>> = scan_all(db, "t").into_iter().map(|mut row| {
+        row.truncate(3);
+        row
+    }).collect();
+    let (s, g) = (by(2, false, false), by(1, true, false));
+    rows.sort_by(|a, b| s(a, b).then_with(|| g(a, b)));
+    rows
+}
+
+fn keys() -> Vec<SortKey> {
+    vec![SortKey::asc("s"), SortKey::desc("g").nulls_last()]
+}
+
+#[test]
+fn sorts_by_several_keys_with_null_ordering() {
+    let file = TempDb::new("order-by");
+    let db = create(&file);
+
+    let results = sort_table(&db, "t", &["id", "g", "s"], &keys(), None, None, SortOptions::default()).unwrap();
+    assert_eq!(collect(results), expected(&db));
+
+    // Descending keys put nulls first by default
+    let results = sort_table(&db, "t", &["g"], &[SortKey::desc("g")], None, None, SortOptions::default()).unwrap();
+    let rows = collect(results);
+    assert_eq!(rows[0], vec![Value::Null]);
+    assert_eq!(rows[ROWS.div_ceil(9)], vec![Value::Int64(999)]);
+    assert_eq!(rows.last(), Some(&vec![Value::Int64(0)]));
+}
+
+#[test]
+fn top_k_matches_the_full_sort() {
+    let file = TempDb::new("order-by-top-k");
+    let db = create(&file);
+    let all = expected(&db);
+
+    for limit in [0, 1, 10, 1_000] {
+        let results = sort_table(&db, "t", &["id", "g", "s"], &keys(), None, Some(limit), SortOptions::default()).unwrap();
+        assert_eq!(collect(results), all[..limit], "LIMIT {limit}");
+    }
+
+    // Sort keys need not be returned
+    let results = sort_table(&db, "t", &["id"], &[SortKey::asc("g").nulls_first()], None, Some(3), SortOptions::default()).unwrap();
+    assert_eq!(collect(results), vec![vec![Value::Int64(0)], vec![Value::Int64(9)], vec![Value::Int64(18)]]);
+
+    // The filter runs before the limit is taken
+    let filter = Expr::column("g").is_null();
+    let results = sort_table(&db, "t", &["id"], &[SortKey::desc("id")], Some(filter), Some(2), SortOptions::default()).unwrap();
+    let last = (ROWS as i64 - 1) / 9 * 9;
+    assert_eq!(collect(results), vec![vec![Value::Int64(last)], vec![Value::Int64(last - 9)]]);
+}
+
+#[test]
+fn external_sort_spills_runs() {
+    let file = TempDb::new("order-by-spill");
+    let spill_dir = TempDir::new("order-by-spill");
+    let db = create(&file);
+
+    let options = SortOptions { memory_budget: 8 * 1024, spill_dir: spill_dir.path().to_path_buf(), batch_size: 500 };
+    let results = sort_table(&db, "t", &["id", "g", "s"], &keys(), None, None, options).unwrap();
+    assert!(results.stats.runs_spilled > 1, "{:?}", results.stats);
+    assert!(spill_dir.file_count() > 0);
+
+    assert_eq!(collect(results), expected(&db));
+    assert_eq!(spill_dir.file_count(), 0);
+}
+
+#[test]
+fn external_sort_merges_runs_in_passes() {
+    let file = TempDb::new("order-by-merge");
+    let spill_dir = TempDir::new("order-by-merge");
+    let db = create(&file);
+    let rows = expected(&db);
+
+    // A run per pushed batch, more than one merge takes at once
+    let names = ["id", "g", "s"].map(String::from);
+    let types = [ColumnType::Integer64, ColumnType::Integer64, ColumnType::Utf8];
+    let options = SortOptions { memory_budget: 1, spill_dir: spill_dir.path().to_path_buf(), batch_size: 500 };
+    let mut sort = Sort::new(&names, &types, &keys(), None, options).unwrap();
+    for part in scan_all(&db, "t").chunks(100) {
+        let columns: Vec<Vec<Value>> = (0..3).map(|c| part.iter().map(|row| row[c].clone()).collect()).collect();
+        sort.push(&columns, part.len()).unwrap();
+    }
+
+    let results = sort.finish().unwrap();
+    assert_eq!(results.stats.merge_passes, (ROWS / 100 - 64).div_ceil(63));
+    assert_eq!(collect(results), rows);
+    assert_eq!(spill_dir.file_count(), 0);
+}
+
+#[test]
+fn keys_order_signs_and_prefixes() {
+    let file = TempDb::new("order-by-encoding");
+    let mut db = file.create();
+    db.create_table("k").unwrap();
+    db.add_column("k", "f", ColumnType::Float64).unwrap();
+    db.add_column("k", "s", ColumnType::Utf8).unwrap();
+    let fs = [0.5, -0.5, f64::MAX, -1e300, 0.0, f64::MIN_POSITIVE, -3.0];
+    let ss = ["ab", "a", "", "b", "a\u{0}", "\u{e9}", "B"];
+    db.append_columns("k", &[ColumnInput::new("f", ColumnSlice::Float64(&fs)), ColumnInput::new("s", ColumnSlice::Utf8(&ss))]).unwrap();
+
+    let column = |keys: &[SortKey], index: usize| -> Vec<Value> {
+        collect(sort_table(&db, "k", &["f", "s"], keys, None, None, SortOptions::default()).unwrap()).into_iter().map(|row| row[index].clone()).collect()
+    };
+
+    let mut floats = fs.to_vec();
+    floats.sort_by(f64::total_cmp);
+    assert_eq!(column(&[SortKey::asc("f")], 0), floats.iter().map(|f| Value::Float64(*f)).collect::<Vec<_>>());
+    // A string sorts after its prefixes, and before them when descending
+    let strings = |order: &[&str]| order.iter().map(|s| Value::String(s.to_string())).collect::<Vec<_>>();
+    assert_eq!(column(&[SortKey::asc("s")], 1), strings(&["", "B", "a", "a\u{0}", "ab", "b", "\u{e9}"]));
+    assert_eq!(column(&[SortKey::desc("s")], 1), strings(&["\u{e9}", "b", "ab", "a\u{0}", "a", "B", ""]));
+}
+
+#[test]
+fn rejects_unorderable_and_unknown_keys() {
+    let file = TempDb::new("order-by-errors");
+    let db = create(&file);
+
+    let err = sort_table(&db, "t", &[], &[SortKey::asc("tags")], None, None, SortOptions::default()).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = sort_table(&db, "t", &[], &[SortKey::asc("nope")], None, None, SortOptions::default()).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
@@ -0,0 +1,179 @@
+mod common;
+
+use std::thread;
+use common::{scan_all, TempDb, TempDir};
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::aggregate::Aggregate;
+use fluxdb_core::query::execute::{execute, QueryOptions};
+use fluxdb_core::query::expr::Expr;
+use fluxdb_core::query::hash_aggregate::{aggregate_table, AggregateOptions, AggregateResults};
+use fluxdb_core::query::optimizer::optimize;
+use fluxdb_core::sql::ast::Statement;
+use fluxdb_core::sql::parser::parse;
+use fluxdb_core::sql::planner::plan_select;
+
+/// Six sealed chunks and a partly filled active one.
+const ROWS: usize = ROWS_PER_CHUNK as usize * 6 + 1_000;
+
+/// `g` takes 50 values and is null on every seventeenth row, `v` is null
+/// on every thirteenth. Reopened, so every scan reads from the file.
+fn create(file: &TempDb) -> Database {
+    let xs: Vec<i64> = (0..ROWS as i64).collect();
+    let gs: Vec<i64> = xs.iter().map(|x| x % 50).collect();
+    let g_valid: Vec<bool> = (0..ROWS).map(|i| i % 17 != 0).collect();
+    let vs: Vec<f64> = (0..ROWS).map(|i| i as f64 / 8.0).collect();
+    let v_valid: Vec<bool> = (0..ROWS).map(|i| i % 13 != 0).collect();
+
+    let mut db = file.create();
+    db.execute("CREATE TABLE t (x BIGINT, g BIGINT, v DOUBLE)").unwrap();
+    db.append_columns("t", &[
+        ColumnInput::new("x", ColumnSlice::Int64(&xs)),
+        ColumnInput::new("g", ColumnSlice::Int64(&gs)).with_validity(&g_valid),
+        ColumnInput::new("v", ColumnSlice::Float64(&vs)).with_validity(&v_valid),
+    ]).unwrap();
+    db.flush().unwrap();
+    drop(db);
+    file.open()
+}
+
+fn sorted(mut rows: Vec<Vec<Value>>) -> Vec<Vec<Value>> {
+    rows.sort_by(|a, b| format!("{a:?}").cmp(&format!("{b:?}")));
+    rows
+}
+
+fn collect(results: AggregateResults) -> Vec<Vec<Value>> {
+    let mut rows = Vec::new();
+    for batch in results {
+        let batch = batch.unwrap();
+        rows.extend((0..batch.row_count).map(|i| batch.row(i)));
+    }
+    sorted(rows)
+}
+
+fn aggregates() -> Vec<Aggregate> {
+    vec![Aggregate::count_star(), Aggregate::count("v"), Aggregate::sum("v"), Aggregate::avg("v"), Aggregate::min("v"), Aggregate::max("v")]
+}
+
+#[test]
+fn parallel_aggregates_match_a_single_thread() {
+    let file = TempDb::new("parallel-aggregate");
+    let spill = TempDir::new("parallel-aggregate-spill");
+    let db = create(&file);
+    let filter = Expr::column("x").gt_eq(Expr::literal(Value::Int64(5_000)));
+
+    for (group_by, filter) in [(vec![], None), (vec!["g"], None), (vec!["g"], Some(filter))] {
+        let run = |threads: usize, memory_budget: usize| {
+            let options = AggregateOptions { threads, memory_budget, spill_dir: spill.path().to_path_buf(), ..AggregateOptions::default() };
+            collect(aggregate_table(&db, "t", &group_by, &aggregates(), filter.clone(), options).unwrap())
+        };
+
+        let expected = run(1, AggregateOptions::default().memory_budget);
+        assert_eq!(expected.len(), if group_by.is_empty() { 1 } else { 51 });
+        for threads in [2, 4, 16] {
+            assert_eq!(run(threads, AggregateOptions::default().memory_budget), expected, "{threads} threads");
+        }
+        // Partials that spilled are merged through their partitions
+        assert_eq!(run(4, 1024), expected, "spilling");
+    }
+    assert_eq!(spill.file_count(), 0);
+}
+
+#[test]
+fn parallel_sql_aggregates_match_a_single_thread() {
+    let file = TempDb::new("parallel-sql");
+    let db = create(&file);
+
+    let Statement::Select(select) = parse("SELECT g, COUNT(*), SUM(v) FROM t WHERE x < 90000 GROUP BY g").unwrap() else {
+        panic!("not a SELECT");
+    };
+    let plan = optimize(&db, plan_select(&db.catalog, &select).unwrap()).unwrap();
+    let run = |threads: usize| {
+        let mut rows = Vec::new();
+        for batch in execute(&db, &plan, &QueryOptions { threads, ..QueryOptions::default() }).unwrap() {
+            let batch = batch.unwrap();
+            rows.extend((0..batch.row_count).map(|i| batch.row(i)));
+        }
+        sorted(rows)
+    };
+
+    let expected = run(1);
+    assert_eq!(expected.len(), 51);
+    assert_eq!(run(4), expected);
+}
+
+#[test]
+fn split_scans_cover_every_row_once() {
+    let file = TempDb::new("parallel-split");
+    let db = create(&file);
+    let expected = scan_all(&db, "t");
+
+    for parts in [1, 3, 7, 20] {
+        let scan = db.scan("t", &[]).unwrap();
+        let (ranges, _) = scan.estimated_ranges();
+        let scans = scan.split(parts);
+        assert_eq!(scans.len(), parts);
+        assert_eq!(scans.iter().map(|part| part.estimated_ranges().0).sum::<usize>(), ranges);
+
+        let mut rows = Vec::new();
+        let mut rows_scanned = 0;
+        for mut part in scans {
+            for batch in part.by_ref() {
+                let batch = batch.unwrap();
+                let count = batch.selection.as_ref().map_or(batch.row_count, |selection| selection.len());
+                rows.extend((0..count).map(|i| batch.columns.iter().map(|column| column[i].clone()).collect::<Vec<_>>()));
+            }
+            rows_scanned += part.stats.rows_scanned;
+        }
+        assert_eq!(rows_scanned, ROWS as u64, "{parts} parts");
+        rows.sort_by_key(|row| match row[0] {
+            Value::Int64(x) => x,
+            _ => unreachable!(),
+        });
+        assert_eq!(rows, expected, "{parts} parts");
+    }
+}
+
+#[test]
+fn split_after_reading_keeps_what_was_read() {
+    let file = TempDb::new("parallel-split-late");
+    let db = create(&file);
+    let filter = Expr::column("x").lt(Expr::literal(Value::Int64(ROWS_PER_CHUNK as i64 * 3)));
+
+    let mut scan = db.scan_where("t", &["x"], filter).unwrap();
+    let (ranges, _) = scan.estimated_ranges();
+    let first = scan.next().unwrap().unwrap();
+    let mut xs: Vec<Value> = first.columns[0].clone();
+
+    // The parts share what is left; the first carries on from the stats of
+    // the scan it was split from, which read one of the three chunks the
+    // filter keeps
+    let scans = scan.split(4);
+    assert_eq!(scans.iter().map(|part| part.estimated_ranges().0).sum::<usize>(), ranges - 1);
+    let mut ranges_scanned = 0;
+    for mut part in scans {
+        for batch in part.by_ref() {
+            xs.extend(batch.unwrap().columns[0].iter().cloned());
+        }
+        ranges_scanned += part.stats.ranges_scanned;
+    }
+    assert_eq!(ranges_scanned, 3);
+    xs.sort_by_key(|x| x.as_i64());
+    assert_eq!(xs, (0..ROWS_PER_CHUNK as i64 * 3).map(Value::Int64).collect::<Vec<_>>());
+}
+
+#[test]
+fn threads_scan_one_database_at_once() {
+    let file = TempDb::new("parallel-readers");
+    let db = create(&file);
+    let expected = scan_all(&db, "t");
+
+    thread::scope(|scope| {
+        let readers: Vec<_> = (0..4).map(|_| scope.spawn(|| scan_all(&db, "t"))).collect();
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), expected);
+        }
+    });
+}
@@ -0,0 +1,170 @@
+#![cfg(feature = "parquet")]
+
+mod common;
+
+use std::io::ErrorKind;
+use std::sync::Arc;
+use arrow_array::builder::{Int64Builder, ListBuilder, MapBuilder, StringBuilder};
+use arrow_array::{ArrayRef, Date32Array, Decimal128Array, Float64Array, Int16Array, Int32Array, RecordBatch, StringArray, StructArray, TimestampMillisecondArray};
+use arrow_schema::{DataType, Field, Fields};
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use common::{column_types, scan_all, TempDb, TempFile};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::formats::parquet::parquet_import::import_parquet;
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+fn write(file: &TempFile, columns: Vec<(&str, ArrayRef)>, row_group_size: usize) {
+    let batch = RecordBatch::try_from_iter(columns).unwrap();
+    let properties = WriterProperties::builder().set_max_row_group_size(row_group_size).build();
+    let mut writer = ArrowWriter::try_new(std::fs::File::create(file.path()).unwrap(), batch.schema(), Some(properties)).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// Logical types that map onto wider columns, plus a list and a struct.
+fn facts(rows: usize) -> Vec<(&'static str, ArrayRef)> {
+    let mut tags = ListBuilder::new(Int64Builder::new());
+    for i in 0..rows {
+        if i % 4 == 3 {
+            tags.append_null();
+        } else {
+            tags.values().append_slice(&vec![i as i64; i % 4]);
+            tags.append(true);
+        }
+    }
+
+    let point_fields = Fields::from(vec![Field::new("x", DataType::Float64, true)]);
+    let point = StructArray::new(
+        point_fields,
+        vec![Arc::new(Float64Array::from_iter_values((0..rows).map(|i| i as f64 * 0.5))) as ArrayRef],
+        None,
+    );
+
+    vec![
+        ("id", Arc::new(Int32Array::from_iter_values(0..rows as i32))),
+        ("small", Arc::new(Int16Array::from_iter((0..rows).map(|i| (i % 3 != 0).then_some(-(i as i16)))))),
+        ("name", Arc::new(StringArray::from_iter((0..rows).map(|i| (i % 5 != 0).then(|| format!("n{i}")))))),
+        ("seen", Arc::new(TimestampMillisecondArray::from_iter_values((0..rows as i64).map(|i| i * 1_500 - 1)))),
+        ("day", Arc::new(Date32Array::from_iter_values((0..rows as i32).map(|i| i - 1)))),
+        ("price", Arc::new(Decimal128Array::from_iter_values((0..rows as i128).map(|i| i * 25)).with_precision_and_scale(10, 2).unwrap())),
+        ("tags", Arc::new(tags.finish())),
+        ("point", Arc::new(point)),
+    ]
+}
+
+/// Row ranges of the sealed chunks of a table.
+fn chunks(db: &Database, table: &str) -> Vec<(u64, u64)> {
+    let table_id = db.catalog.tables_by_name[table];
+    let column = &db.catalog.columns_by_table[&table_id][0];
+    db.chunk_manager.chunks_for(table_id, column.column_id).iter().map(|chunk| (chunk.row_start, chunk.row_end)).collect()
+}
+
+#[test]
+fn row_groups_become_chunks_and_survive_reopen() {
+    let file = TempDb::new("parquet-import");
+    let parquet = TempFile::new("parquet-import.parquet");
+    write(&parquet, facts(250), 100);
+
+    {
+        let mut db = file.create();
+        let report = import_parquet(&mut db, "facts", parquet.path()).unwrap();
+        assert_eq!((report.rows_imported, report.row_groups), (250, 3));
+    }
+
+    let db = file.open();
+    assert_eq!(chunks(&db, "facts"), vec![(0, 100), (100, 200), (200, 250)]);
+    assert_eq!(column_types(&db, "facts"), vec![
+        ("id".to_string(), ColumnType::Integer32),
+        ("small".to_string(), ColumnType::Integer32),
+        ("name".to_string(), ColumnType::Utf8),
+        ("seen".to_string(), ColumnType::Timestamp),
+        ("day".to_string(), ColumnType::Timestamp),
+        ("price".to_string(), ColumnType::Float64),
+        ("tags".to_string(), ColumnType::List),
+        ("point".to_string(), ColumnType::Struct),
+    ]);
+
+    let rows = scan_all(&db, "facts");
+    assert_eq!(rows.len(), 250);
+    assert_eq!(rows[0][3..5], [Value::Timestamp(-1_000), Value::Timestamp(-MICROS_PER_DAY)]);
+    assert_eq!(rows[123], vec![
+        Value::Int32(123),
+        Value::Null,
+        Value::String("n123".into()),
+        Value::Timestamp(123 * 1_500_000 - 1_000),
+        Value::Timestamp(122 * MICROS_PER_DAY),
+        Value::Float64(30.75),
+        Value::Null,
+        Value::Struct(vec![Value::Float64(61.5)]),
+    ]);
+    assert_eq!(rows[125][1..3], [Value::Int32(-125), Value::Null]);
+    assert_eq!(rows[102][6], Value::List(vec![Value::Int64(102), Value::Int64(102)]));
+    assert_eq!(rows[100][6], Value::List(vec![]));
+}
+
+#[test]
+fn large_row_groups_split_over_chunks() {
+    let file = TempDb::new("parquet-large");
+    let parquet = TempFile::new("parquet-large.parquet");
+    let rows = ROWS_PER_CHUNK as i32 + 10;
+    write(&parquet, vec![("id", Arc::new(Int32Array::from_iter_values(0..rows)) as ArrayRef)], rows as usize);
+
+    let mut db = file.create();
+    assert_eq!(import_parquet(&mut db, "t", parquet.path()).unwrap().row_groups, 1);
+    assert_eq!(chunks(&db, "t"), vec![(0, ROWS_PER_CHUNK), (ROWS_PER_CHUNK, rows as u64)]);
+    assert_eq!(scan_all(&db, "t").last(), Some(&vec![Value::Int32(rows - 1)]));
+}
+
+#[test]
+fn maps_become_lists_of_entries() {
+    let file = TempDb::new("parquet-map");
+    let parquet = TempFile::new("parquet-map.parquet");
+    let mut attrs = MapBuilder::new(None, StringBuilder::new(), Int64Builder::new());
+    attrs.keys().append_value("a");
+    attrs.values().append_value(1);
+    attrs.keys().append_value("b");
+    attrs.values().append_null();
+    attrs.append(true).unwrap();
+    attrs.append(false).unwrap();
+    write(&parquet, vec![("user.attrs", Arc::new(attrs.finish()) as ArrayRef)], 10);
+
+    let mut db = file.create();
+    import_parquet(&mut db, "t", parquet.path()).unwrap();
+    // Dots separate column paths, so they cannot stay in the name
+    assert_eq!(column_types(&db, "t"), vec![("user_attrs".to_string(), ColumnType::List)]);
+    let entry = |key: &str, value: Value| Value::Struct(vec![Value::String(key.into()), value]);
+    assert_eq!(scan_all(&db, "t"), vec![
+        vec![Value::List(vec![entry("a", Value::Int64(1)), entry("b", Value::Null)])],
+        vec![Value::Null],
+    ]);
+}
+
+#[test]
+fn imports_into_an_existing_table_start_a_new_chunk() {
+    let file = TempDb::new("parquet-existing");
+    let parquet = TempFile::new("parquet-existing.parquet");
+    write(&parquet, facts(10), 10);
+
+    let mut db = file.create();
+    import_parquet(&mut db, "facts", parquet.path()).unwrap();
+    db.append_row("facts", vec![("id", Value::Int32(-1))]).unwrap();
+    import_parquet(&mut db, "facts", parquet.path()).unwrap();
+    assert_eq!(chunks(&db, "facts"), vec![(0, 10), (10, 11), (11, 21)]);
+
+    db.create_table("other").unwrap();
+    db.add_column("other", "id", ColumnType::Integer64).unwrap();
+    assert_eq!(import_parquet(&mut db, "other", parquet.path()).err().unwrap().kind(), ErrorKind::InvalidInput);
+    db.create_table("narrow").unwrap();
+    db.add_column("narrow", "id", ColumnType::Integer32).unwrap();
+    assert_eq!(import_parquet(&mut db, "narrow", parquet.path()).err().unwrap().kind(), ErrorKind::NotFound);
+
+    std::fs::write(parquet.path(), b"not parquet").unwrap();
+    assert_eq!(import_parquet(&mut db, "facts", parquet.path()).err().unwrap().kind(), ErrorKind::InvalidData);
+    assert_eq!(scan_all(&db, "facts").len(), 21);
+    assert!(scan_all(&db, "other").is_empty());
+}
@@ -0,0 +1,184 @@
+mod common;
+
+use common::{rows, TempDb};
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::execute::{execute, QueryOptions};
+use fluxdb_core::query::optimizer::optimize;
+use fluxdb_core::query::plan::{AccessPath, Plan};
+use fluxdb_core::query::statistics::TableStats;
+use fluxdb_core::sql::ast::Statement;
+use fluxdb_core::sql::parser::parse;
+use fluxdb_core::sql::planner::plan_select;
+
+const FACTS: i64 = ROWS_PER_CHUNK as i64 * 4;
+
+/// `facts` has an ascending `id`, a descending `rev`, a scrambled `k` and
+/// a `grp` into `dims`; `tiny` has three rows.
+fn create(file: &TempDb) -> Database {
+    let ids: Vec<i64> = (0..FACTS).collect();
+    let revs: Vec<i64> = (0..FACTS).rev().collect();
+    let ks: Vec<i64> = (0..FACTS).map(|i| i * 7_919 % FACTS).collect();
+    let grps: Vec<i64> = (0..FACTS).map(|i| i % 100).collect();
+    let valid: Vec<bool> = (0..FACTS).map(|i| i % 4 != 0).collect();
+    let dim_ids: Vec<i64> = (0..100).collect();
+    let labels: Vec<String> = (0..100).map(|i| format!("g{i}")).collect();
+    let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+
+    let mut db = file.create();
+    db.execute("
+        CREATE TABLE facts (id BIGINT, rev BIGINT, k BIGINT, grp BIGINT);
+        CREATE TABLE dims (id BIGINT, label VARCHAR);
+        CREATE TABLE tiny (grp BIGINT);
+    ").unwrap();
+    db.append_columns("facts", &[
+        ColumnInput::new("id", ColumnSlice::Int64(&ids)),
+        ColumnInput::new("rev", ColumnSlice::Int64(&revs)),
+        ColumnInput::new("k", ColumnSlice::Int64(&ks)),
+        ColumnInput::new("grp", ColumnSlice::Int64(&grps)).with_validity(&valid),
+    ]).unwrap();
+    db.append_columns("dims", &[
+        ColumnInput::new("id", ColumnSlice::Int64(&dim_ids)),
+        ColumnInput::new("label", ColumnSlice::Utf8(&labels)),
+    ]).unwrap();
+    db.append_columns("tiny", &[ColumnInput::new("grp", ColumnSlice::Int64(&[1, 2, 3]))]).unwrap();
+    db.execute("ALTER TABLE facts ADD COLUMN late BIGINT").unwrap();
+    db.flush().unwrap();
+    db
+}
+
+/// Whether a distinct estimate is near `actual`, given the sketch's error.
+fn roughly(estimate: u64, actual: u64) -> bool {
+    estimate > actual / 2 && estimate < actual * 2
+}
+
+fn plans(db: &Database, sql: &str) -> (Plan, Plan) {
+    let Statement::Select(select) = parse(sql).unwrap() else { panic!("not a SELECT") };
+    let plan = plan_select(&db.catalog, &select).unwrap();
+    (plan.clone(), optimize(db, plan).unwrap())
+}
+
+fn run(db: &Database, plan: &Plan) -> Vec<Vec<Value>> {
+    let mut out = Vec::new();
+    for batch in execute(db, plan, &QueryOptions::default()).unwrap() {
+        let batch = batch.unwrap();
+        out.extend((0..batch.row_count).map(|i| batch.row(i)));
+    }
+    out.sort_by(|a, b| format!("{a:?}").cmp(&format!("{b:?}")));
+    out
+}
+
+/// The scans of a plan, left to right.
+fn scans(plan: &Plan) -> Vec<&Plan> {
+    match plan {
+        Plan::Scan { .. } => vec![plan],
+        _ => plan.inputs().into_iter().flat_map(scans).collect(),
+    }
+}
+
+fn access(plan: &Plan) -> &AccessPath {
+    let [Plan::Scan { access, .. }] = scans(plan)[..] else { panic!("expected one scan") };
+    access
+}
+
+#[test]
+fn statistics_come_from_chunk_metadata() {
+    let file = TempDb::new("planner-stats");
+    // Reopening reads back chunk records over several catalog heap pages
+    drop(create(&file));
+    let db = file.open();
+
+    let stats = TableStats::collect(&db, "facts").unwrap();
+    assert_eq!(stats.row_count, FACTS as u64);
+
+    let id = stats.column("id").unwrap();
+    assert_eq!((id.min.clone(), id.max.clone()), (Some(Value::Int64(0)), Some(Value::Int64(FACTS - 1))));
+    assert!(id.ascending);
+    assert!(roughly(id.distinct, FACTS as u64), "{}", id.distinct);
+    assert!(!stats.column("rev").unwrap().ascending);
+    assert!(!stats.column("k").unwrap().ascending);
+
+    let grp = stats.column("grp").unwrap();
+    assert_eq!(grp.null_count, FACTS as u64 / 4);
+    assert!(roughly(grp.distinct, 100), "{}", grp.distinct);
+
+    // Rows from before a column was added are null
+    let late = stats.column("late").unwrap();
+    assert_eq!(late.null_count, FACTS as u64);
+    assert_eq!(late.null_fraction(stats.row_count), 1.0);
+}
+
+#[test]
+fn access_paths_follow_selectivity() {
+    let file = TempDb::new("planner-access");
+    let db = create(&file);
+
+    let (_, plan) = plans(&db, "SELECT k FROM facts WHERE id BETWEEN 10 AND 20");
+    assert!(matches!(access(&plan), AccessPath::Lookup { column, .. } if column == "id"), "{plan:?}");
+    // Zone maps only help where values cluster by chunk
+    let (_, plan) = plans(&db, "SELECT k FROM facts WHERE rev < 100");
+    assert_eq!(access(&plan), &AccessPath::Pruned);
+    let (_, plan) = plans(&db, "SELECT k FROM facts WHERE k = 5");
+    assert_eq!(access(&plan), &AccessPath::Full);
+    let (_, plan) = plans(&db, "SELECT k FROM facts");
+    assert_eq!(access(&plan), &AccessPath::Full);
+
+    for sql in [
+        "SELECT k FROM facts WHERE id BETWEEN 10 AND 20",
+        "SELECT id FROM facts WHERE rev < 100",
+        "SELECT id FROM facts WHERE k = 5",
+    ] {
+        let (naive, optimized) = plans(&db, sql);
+        assert_eq!(run(&db, &optimized), run(&db, &naive), "{sql}");
+    }
+}
+
+#[test]
+fn filters_and_projections_are_pushed_into_scans() {
+    let file = TempDb::new("planner-push-down");
+    let db = create(&file);
+
+    let sql = "SELECT d.label, f.k FROM facts f JOIN dims d ON f.grp = d.id WHERE f.id < 50 AND d.label = 'g7'";
+    let (naive, plan) = plans(&db, sql);
+    for scan in scans(&plan) {
+        let Plan::Scan { table, columns, filter, .. } = scan else { unreachable!() };
+        assert!(filter.is_some(), "{table} has no filter");
+        let mut columns = columns.clone();
+        columns.sort();
+        match table.as_str() {
+            // `id` is read by the scan's filter, but not output
+            "facts" => assert_eq!(columns, ["grp", "k"]),
+            _ => assert_eq!(columns, ["id", "label"]),
+        }
+    }
+    assert_eq!(run(&db, &plan), run(&db, &naive));
+    assert_eq!(run(&db, &plan), vec![vec![Value::String("g7".into()), Value::Int64(7 * 7_919 % FACTS)]]);
+
+    // A filter on the right of a left join must see its nulls
+    let sql = "SELECT f.id FROM facts f LEFT JOIN tiny t ON f.grp = t.grp WHERE f.id < 10 AND t.grp IS NULL";
+    let (naive, plan) = plans(&db, sql);
+    let Plan::Project { input, .. } = &plan else { panic!("{plan:?}") };
+    assert!(matches!(**input, Plan::Filter { .. }), "{plan:?}");
+    assert_eq!(run(&db, &plan), run(&db, &naive));
+    assert_eq!(run(&db, &plan).len(), 7);
+}
+
+#[test]
+fn joins_start_from_the_smallest_input() {
+    let file = TempDb::new("planner-join-order");
+    let db = create(&file);
+
+    let sql = "SELECT COUNT(*) FROM facts f JOIN dims d ON f.grp = d.id JOIN tiny t ON d.id = t.grp";
+    let (naive, plan) = plans(&db, sql);
+    let tables: Vec<&str> = scans(&plan).into_iter().map(|scan| match scan {
+        Plan::Scan { table, .. } => table.as_str(),
+        _ => unreachable!(),
+    }).collect();
+    assert_eq!(tables[0], "tiny", "{tables:?}");
+
+    assert_eq!(run(&db, &plan), run(&db, &naive));
+    let matching = (0..FACTS).filter(|i| i % 4 != 0 && (1..=3).contains(&(i % 100))).count();
+    assert_eq!(rows(&db, sql), vec![vec![Value::Int64(matching as i64)]]);
+}
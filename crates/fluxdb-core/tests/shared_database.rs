@@ -0,0 +1,132 @@
+mod common;
+
+use std::thread;
+use common::{rows, value, TempDb};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::engine::shared_database::SharedDatabase;
+use fluxdb_core::metadata::value::Value;
+
+fn count(db: &Database) -> i64 {
+    match value(db, "SELECT COUNT(*) FROM t") {
+        Value::Int64(count) => count,
+        other => panic!("unexpected count {other:?}"),
+    }
+}
+
+fn shared(file: &TempDb) -> SharedDatabase {
+    let mut db = file.create();
+    db.execute("CREATE TABLE t (x BIGINT)").unwrap();
+    SharedDatabase::new(db)
+}
+
+#[test]
+fn snapshots_see_writes_once_the_guard_is_done() {
+    let file = TempDb::new("shared-publish");
+    let db = shared(&file);
+    let before = db.snapshot();
+    {
+        let mut writer = db.write().unwrap();
+        writer.append_row("t", vec![("x", Value::Int64(1))]).unwrap();
+        assert_eq!(count(&db.snapshot()), 0);
+    }
+    assert_eq!(count(&before), 0);
+    assert_eq!(count(&db.snapshot()), 1);
+}
+
+#[test]
+fn snapshots_keep_the_schema_they_were_taken_with() {
+    let file = TempDb::new("shared-schema");
+    let db = shared(&file);
+    db.write().unwrap().append_row("t", vec![("x", Value::Int64(1))]).unwrap();
+    let before = db.snapshot();
+
+    db.write().unwrap().execute("ALTER TABLE t ADD COLUMN y VARCHAR; CREATE TABLE u (z BIGINT)").unwrap();
+    assert!(before.query("SELECT y FROM t").is_err());
+    assert!(!before.catalog.tables_by_name.contains_key("u"));
+    assert_eq!(rows(&db.snapshot(), "SELECT x, y FROM t"), vec![vec![Value::Int64(1), Value::Null]]);
+}
+
+#[test]
+fn a_panicking_writer_publishes_nothing() {
+    let file = TempDb::new("shared-panic");
+    let db = shared(&file);
+
+    let writer = {
+        let db = db.clone();
+        thread::spawn(move || {
+            let mut writer = db.write().unwrap();
+            writer.append_row("t", vec![("x", Value::Int64(1))]).unwrap();
+            panic!("writer gave up part way");
+        })
+    };
+    assert!(writer.join().is_err());
+    assert_eq!(count(&db.snapshot()), 0);
+
+    // The next writer takes over, without the panicked writer's row
+    {
+        let mut writer = db.write().unwrap();
+        assert_eq!(count(&writer), 0);
+        writer.append_row("t", vec![("x", Value::Int64(2))]).unwrap();
+    }
+    assert_eq!(rows(&db.snapshot(), "SELECT x FROM t"), vec![vec![Value::Int64(2)]]);
+    drop(db);
+    assert_eq!(rows(&file.open(), "SELECT x FROM t"), vec![vec![Value::Int64(2)]]);
+}
+
+#[test]
+fn a_writer_panicking_after_a_commit_keeps_what_it_committed() {
+    let file = TempDb::new("shared-panic-commit");
+    let db = shared(&file);
+
+    let writer = {
+        let db = db.clone();
+        thread::spawn(move || {
+            let mut writer = db.write().unwrap();
+            writer.append_row("t", vec![("x", Value::Int64(1))]).unwrap();
+            writer.commit().unwrap();
+            writer.append_row("t", vec![("x", Value::Int64(2))]).unwrap();
+            panic!("writer gave up after its first commit");
+        })
+    };
+    assert!(writer.join().is_err());
+    assert_eq!(rows(&db.snapshot(), "SELECT x FROM t"), vec![vec![Value::Int64(1)]]);
+    assert_eq!(rows(&db.write().unwrap(), "SELECT x FROM t"), vec![vec![Value::Int64(1)]]);
+}
+
+#[test]
+fn readers_see_whole_writes_while_a_writer_appends() {
+    let file = TempDb::new("shared-concurrent");
+    let db = shared(&file);
+
+    let writer = {
+        let db = db.clone();
+        thread::spawn(move || {
+            for batch in 0..20 {
+                let mut writer = db.write().unwrap();
+                for x in 0..10 {
+                    writer.append_row("t", vec![("x", Value::Int64(batch * 10 + x))]).unwrap();
+                }
+            }
+        })
+    };
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let db = db.clone();
+            thread::spawn(move || {
+                let mut last = 0;
+                for _ in 0..50 {
+                    let seen = count(&db.snapshot());
+                    assert_eq!(seen % 10, 0, "a snapshot saw part of a write");
+                    assert!(seen >= last);
+                    last = seen;
+                }
+            })
+        })
+        .collect();
+
+    writer.join().unwrap();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    assert_eq!(count(&db.snapshot()), 200);
+}
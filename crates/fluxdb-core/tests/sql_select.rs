@@ -0,0 +1,155 @@
+mod common;
+
+use std::io::ErrorKind;
+use common::{rows, value, TempDb};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+
+fn create(file: &TempDb) -> Database {
+    let mut db = file.create();
+    for (table, columns) in [
+        ("people", [("id", ColumnType::Integer64), ("name", ColumnType::Utf8), ("city", ColumnType::Utf8)]),
+        ("orders", [("person_id", ColumnType::Integer64), ("amount", ColumnType::Float64), ("placed", ColumnType::Timestamp)]),
+    ] {
+        db.create_table(table).unwrap();
+        for (name, column_type) in columns {
+            db.add_column(table, name, column_type).unwrap();
+        }
+    }
+
+    let people = [(1, "ann", Some("oslo")), (2, "bob", Some("rome")), (3, "cy", None), (4, "dee", Some("oslo"))];
+    for (id, name, city) in people {
+        db.append_row("people", vec![
+            ("id", Value::Int64(id)),
+            ("name", Value::String(name.into())),
+            ("city", city.map_or(Value::Null, |c| Value::String(c.into()))),
+        ]).unwrap();
+    }
+    let orders = [(1, 10.0), (1, 5.5), (2, 7.0), (4, 1.0), (4, 2.0), (4, 3.0), (9, 100.0)];
+    for (day, (person_id, amount)) in orders.into_iter().enumerate() {
+        db.append_row("orders", vec![
+            ("person_id", Value::Int64(person_id)),
+            ("amount", Value::Float64(amount)),
+            ("placed", Value::Timestamp(day as i64 * 86_400_000_000)),
+        ]).unwrap();
+    }
+    db
+}
+
+fn strings(values: &[&str]) -> Vec<Vec<Value>> {
+    values.iter().map(|v| vec![Value::String(v.to_string())]).collect()
+}
+
+#[test]
+fn projects_filters_sorts_and_limits() {
+    let file = TempDb::new("sql-select");
+    let db = create(&file);
+
+    let stream = db.query("SELECT name AS who, id FROM people WHERE id > 1 ORDER BY id DESC LIMIT 2").unwrap();
+    assert_eq!(stream.column_names, ["who", "id"]);
+    assert_eq!(rows(&db, "SELECT name AS who, id FROM people WHERE id > 1 ORDER BY id DESC LIMIT 2"), vec![
+        vec![Value::String("dee".into()), Value::Int64(4)],
+        vec![Value::String("cy".into()), Value::Int64(3)],
+    ]);
+
+    assert_eq!(rows(&db, "SELECT name FROM people WHERE city = 'oslo' OR city IS NULL ORDER BY 1"), strings(&["ann", "cy", "dee"]));
+    assert_eq!(rows(&db, "SELECT name FROM people WHERE id IN (2, 3) AND name LIKE 'b%'"), strings(&["bob"]));
+    assert_eq!(rows(&db, "SELECT name FROM people WHERE NOT id BETWEEN 2 AND 3 ORDER BY name DESC"), strings(&["dee", "ann"]));
+    assert_eq!(rows(&db, "SELECT city FROM people ORDER BY city NULLS FIRST LIMIT 2"), vec![
+        vec![Value::Null],
+        vec![Value::String("oslo".into())],
+    ]);
+
+    // Text literals parse as timestamps when compared with one
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM orders WHERE placed >= '1970-01-05'"), Value::Int64(3));
+}
+
+#[test]
+fn aggregates_with_and_without_group_by() {
+    let file = TempDb::new("sql-aggregate");
+    let db = create(&file);
+
+    assert_eq!(rows(&db, "SELECT COUNT(*), COUNT(city), MIN(name), MAX(id) FROM people"), vec![vec![
+        Value::Int64(4), Value::Int64(3), Value::String("ann".into()), Value::Int64(4),
+    ]]);
+
+    let stream = db.query("SELECT person_id, SUM(amount) AS total, COUNT(*) FROM orders GROUP BY person_id ORDER BY total DESC").unwrap();
+    assert_eq!(stream.column_names, ["person_id", "total", "COUNT(*)"]);
+    assert_eq!(rows(&db, "SELECT person_id, SUM(amount) AS total, COUNT(*) FROM orders GROUP BY person_id ORDER BY total DESC"), vec![
+        vec![Value::Int64(9), Value::Float64(100.0), Value::Int64(1)],
+        vec![Value::Int64(1), Value::Float64(15.5), Value::Int64(2)],
+        vec![Value::Int64(2), Value::Float64(7.0), Value::Int64(1)],
+        vec![Value::Int64(4), Value::Float64(6.0), Value::Int64(3)],
+    ]);
+
+    assert_eq!(
+        rows(&db, "SELECT city, AVG(id) FROM people WHERE city IS NOT NULL GROUP BY city ORDER BY AVG(id) LIMIT 1"),
+        vec![vec![Value::String("rome".into()), Value::Float64(2.0)]]
+    );
+}
+
+#[test]
+fn joins_tables_by_alias() {
+    let file = TempDb::new("sql-join");
+    let db = create(&file);
+
+    assert_eq!(
+        rows(&db, "SELECT p.name, SUM(o.amount) FROM orders o JOIN people p ON o.person_id = p.id GROUP BY p.name ORDER BY 2 DESC"),
+        vec![
+            vec![Value::String("ann".into()), Value::Float64(15.5)],
+            vec![Value::String("bob".into()), Value::Float64(7.0)],
+            vec![Value::String("dee".into()), Value::Float64(6.0)],
+        ]
+    );
+
+    assert_eq!(
+        rows(&db, "SELECT name, amount FROM people LEFT JOIN orders ON id = person_id WHERE amount IS NULL"),
+        vec![vec![Value::String("cy".into()), Value::Null]]
+    );
+
+    let stream = db.query("SELECT * FROM people JOIN orders ON people.id = orders.person_id").unwrap();
+    assert_eq!(stream.column_names.len(), 6);
+    assert_eq!(rows(&db, "SELECT * FROM people JOIN orders ON people.id = orders.person_id").len(), 6);
+}
+
+#[test]
+fn lexes_comments_quotes_and_operators() {
+    let file = TempDb::new("sql-lexer");
+    let mut db = create(&file);
+    db.create_table("odd").unwrap();
+    db.add_column("odd", "select", ColumnType::Utf8).unwrap();
+    db.append_row("odd", vec![("select", Value::String("it's".into()))]).unwrap();
+    db.append_row("odd", vec![("select", Value::String("--".into()))]).unwrap();
+
+    // Keywords can be column names when quoted, and a doubled quote escapes
+    // itself inside a literal
+    assert_eq!(rows(&db, "SELECT \"select\" FROM odd WHERE \"select\" = 'it''s'"), strings(&["it's"]));
+    // Comment markers inside a literal are text
+    assert_eq!(rows(&db, "SELECT \"select\" FROM odd WHERE \"select\" = '--' -- trailing"), strings(&["--"]));
+    assert_eq!(value(&db, "/* leading */ SELECT COUNT(*) FROM people WHERE id <> 2 AND id != 3"), Value::Int64(2));
+    // Keywords are case-insensitive
+    assert_eq!(value(&db, "select count(*) from people where id >= -1e0 and id <= 1.5e0;"), Value::Int64(1));
+
+    let kind = |sql: &str| db.query(sql).err().unwrap_or_else(|| panic!("{sql} should fail")).kind();
+    assert_eq!(kind("SELECT name FROM people WHERE name = 'open"), ErrorKind::InvalidInput);
+    assert_eq!(kind("SELECT name FROM people /* open"), ErrorKind::InvalidInput);
+    assert_eq!(kind("SELECT name FROM people WHERE id ! 2"), ErrorKind::InvalidInput);
+    assert_eq!(kind("SELECT name FROM people; SELECT id FROM people"), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn reports_bad_queries() {
+    let file = TempDb::new("sql-errors");
+    let db = create(&file);
+
+    let kind = |sql: &str| db.query(sql).err().unwrap_or_else(|| panic!("{sql} should fail")).kind();
+    assert_eq!(kind("SELECT * FROM nope"), ErrorKind::NotFound);
+    assert_eq!(kind("SELECT nope FROM people"), ErrorKind::NotFound);
+    assert_eq!(kind("SELECT name, COUNT(*) FROM people"), ErrorKind::InvalidInput);
+    assert_eq!(kind("SELECT name FROM people WHERE name > 3"), ErrorKind::InvalidInput);
+    assert_eq!(kind("SELECT FROM people"), ErrorKind::InvalidInput);
+    // `id` is in both tables
+    assert_eq!(kind("SELECT id FROM people a JOIN people b ON a.id = b.id"), ErrorKind::InvalidInput);
+    assert_eq!(kind("DELETE FROM people"), ErrorKind::InvalidInput);
+}
@@ -0,0 +1,238 @@
+mod common;
+
+use common::{value, TempDb};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::engine::shared_database::SharedDatabase;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::expr::Expr;
+
+fn append(db: &mut Database, from: i64, count: i64) {
+    for x in from..from + count {
+        db.append_row("t", vec![("x", Value::Int64(x))]).unwrap();
+    }
+}
+
+fn count(db: &Database) -> Value {
+    value(db, "SELECT COUNT(*) FROM t")
+}
+
+fn create(file: &TempDb) -> Database {
+    let mut db = file.create();
+    db.execute("CREATE TABLE t (x BIGINT)").unwrap();
+    db
+}
+
+#[test]
+fn transaction_rows_are_hidden_until_commit() {
+    let file = TempDb::new("txn-visibility");
+    let mut db = create(&file);
+    append(&mut db, 0, 10);
+    let version = db.version();
+    assert_eq!(version, 10, "every append outside a transaction commits");
+
+    db.begin();
+    append(&mut db, 10, 100);
+    assert_eq!(count(&db), Value::Int64(10));
+    assert_eq!(db.version(), version);
+
+    assert_eq!(db.commit().unwrap(), version + 1);
+    assert_eq!(count(&db), Value::Int64(110));
+    // Nothing new to commit keeps the version
+    assert_eq!(db.commit().unwrap(), version + 1);
+}
+
+#[test]
+fn committed_rows_survive_reopen() {
+    let file = TempDb::new("txn-reopen");
+    {
+        let mut db = create(&file);
+        db.begin();
+        append(&mut db, 0, 100);
+        db.commit().unwrap();
+    }
+
+    let db = file.open();
+    assert_eq!(count(&db), Value::Int64(100));
+    assert_eq!(value(&db, "SELECT SUM(x) FROM t"), Value::Int64(4_950));
+}
+
+#[test]
+fn chunks_sealed_mid_transaction_count_once_it_commits() {
+    let file = TempDb::new("txn-sealed");
+    {
+        let mut db = create(&file);
+        db.begin();
+        append(&mut db, 0, 30);
+        db.flush().unwrap();
+        append(&mut db, 30, 20);
+        db.commit().unwrap();
+        db.flush().unwrap();
+    }
+
+    let db = file.open();
+    assert_eq!(count(&db), Value::Int64(50));
+}
+
+#[test]
+fn open_transaction_is_rolled_back_on_reopen() {
+    let file = TempDb::new("txn-rollback");
+    {
+        let mut db = create(&file);
+        append(&mut db, 0, 5);
+        db.begin();
+        append(&mut db, 5, 50);
+        // The sealed chunk holds the five committed rows and the fifty
+        // uncommitted ones
+        db.flush().unwrap();
+    }
+
+    let mut db = file.open();
+    assert_eq!(count(&db), Value::Int64(5));
+    assert_eq!(value(&db, "SELECT MAX(x) FROM t"), Value::Int64(4));
+
+    // The abandoned version is never reused, so a later commit does not
+    // revive the rolled back rows
+    append(&mut db, 5, 1);
+    db.flush().unwrap();
+    drop(db);
+    let db = file.open();
+    assert_eq!(count(&db), Value::Int64(6));
+    assert_eq!(value(&db, "SELECT MAX(x) FROM t"), Value::Int64(5));
+}
+
+#[test]
+fn guard_commits_publish_before_the_guard_is_dropped() {
+    let file = TempDb::new("txn-guard");
+    let db = SharedDatabase::new(create(&file));
+
+    let mut writer = db.write().unwrap();
+    append(&mut writer, 0, 3);
+    assert_eq!(count(&db.snapshot()), Value::Int64(0));
+    let version = writer.commit().unwrap();
+    assert_eq!(db.snapshot().version(), version);
+    assert_eq!(count(&db.snapshot()), Value::Int64(3));
+
+    // Appends after a commit are the guard's next transaction
+    append(&mut writer, 3, 2);
+    assert_eq!(count(&writer), Value::Int64(3));
+    drop(writer);
+    assert_eq!(count(&db.snapshot()), Value::Int64(5));
+}
+
+fn x_chunk_count(db: &Database) -> usize {
+    let table_id = db.catalog.tables_by_name["t"];
+    let x = &db.catalog.columns_by_table[&table_id][0];
+    db.chunk_manager.chunks_for(table_id, x.column_id).len()
+}
+
+fn file_len(file: &TempDb) -> u64 {
+    std::fs::metadata(file.path()).unwrap().len()
+}
+
+fn append_text(db: &mut Database, count: usize) {
+    for _ in 0..count {
+        db.append_row("notes", vec![("s", Value::String("n".repeat(1_000)))]).unwrap();
+    }
+}
+
+#[test]
+fn commits_leave_rows_in_the_active_chunk_and_replay_them_on_open() {
+    let file = TempDb::new("txn-replay");
+    {
+        let mut db = create(&file);
+        append(&mut db, 0, 200);
+        assert_eq!(db.version(), 200);
+        assert_eq!(x_chunk_count(&db), 0, "a commit seals nothing");
+    }
+
+    let mut db = file.open();
+    assert_eq!(db.version(), 200);
+    assert_eq!(count(&db), Value::Int64(200));
+    assert_eq!(value(&db, "SELECT MIN(x) FROM t WHERE x > 150"), Value::Int64(151));
+
+    // Later commits go on filling the same active chunk
+    append(&mut db, 200, 50);
+    drop(db);
+    let mut db = file.open();
+    assert_eq!(count(&db), Value::Int64(250));
+    assert_eq!(value(&db, "SELECT SUM(x) FROM t"), Value::Int64(31_125));
+
+    db.flush().unwrap();
+    assert_eq!(x_chunk_count(&db), 1);
+    drop(db);
+    assert_eq!(count(&file.open()), Value::Int64(250));
+}
+
+#[test]
+fn a_copy_taken_mid_transaction_reads_back_the_last_commit() {
+    let file = TempDb::new("txn-crash");
+    let copy = TempDb::new("txn-crash-copy");
+    let mut db = create(&file);
+    db.execute("CREATE TABLE notes (s VARCHAR)").unwrap();
+    append(&mut db, 0, 5);
+    append_text(&mut db, 3);
+
+    // The open transaction fills and chains pages past the committed tail
+    db.begin();
+    append(&mut db, 5, 1_000);
+    append_text(&mut db, 40);
+    std::fs::copy(file.path(), copy.path()).unwrap();
+    db.commit().unwrap();
+
+    let mut crashed = copy.open();
+    assert_eq!(count(&crashed), Value::Int64(5));
+    assert_eq!(value(&crashed, "SELECT COUNT(*) FROM notes"), Value::Int64(3));
+    // Appends after the replay write over the uncommitted values
+    append(&mut crashed, 5, 2);
+    append_text(&mut crashed, 1);
+    drop(crashed);
+    let crashed = copy.open();
+    assert_eq!(count(&crashed), Value::Int64(7));
+    assert_eq!(value(&crashed, "SELECT MAX(x) FROM t"), Value::Int64(6));
+    assert_eq!(value(&crashed, "SELECT COUNT(*) FROM notes"), Value::Int64(4));
+}
+
+#[test]
+fn dropping_an_open_transaction_frees_its_pages() {
+    let file = TempDb::new("txn-drop");
+    {
+        let mut db = create(&file);
+        db.execute("CREATE TABLE notes (s VARCHAR)").unwrap();
+        append_text(&mut db, 2);
+        db.begin();
+        append_text(&mut db, 40);
+    }
+    let len = file_len(&file);
+
+    let mut db = file.open();
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM notes"), Value::Int64(2));
+    // The rolled back rows' pages are reused rather than leaked
+    append_text(&mut db, 30);
+    drop(db);
+    assert_eq!(file_len(&file), len);
+    assert_eq!(value(&file.open(), "SELECT COUNT(*) FROM notes"), Value::Int64(32));
+}
+
+#[test]
+fn rollback_discards_appends_and_deletes_since_the_last_commit() {
+    let file = TempDb::new("txn-rollback-call");
+    let mut db = create(&file);
+    append(&mut db, 0, 10);
+    let version = db.version();
+
+    db.begin();
+    append(&mut db, 10, 20);
+    assert_eq!(db.delete_where("t", Expr::column("x").lt(Expr::literal(Value::Int64(5)))).unwrap(), 5);
+    db.rollback().unwrap();
+    assert_eq!(count(&db), Value::Int64(10));
+    assert_eq!(value(&db, "SELECT MIN(x) FROM t"), Value::Int64(0));
+
+    // The rolled back deletes were written for a version no commit reuses
+    append(&mut db, 10, 1);
+    assert!(db.version() > version + 1);
+    assert_eq!(count(&db), Value::Int64(11));
+    drop(db);
+    let db = file.open();
+    assert_eq!(count(&db), Value::Int64(11));
+    assert_eq!(value(&db, "SELECT MAX(x) FROM t"), Value::Int64(10));
+}
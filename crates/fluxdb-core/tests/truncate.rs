@@ -0,0 +1,142 @@
+mod common;
+
+use std::io::ErrorKind;
+use common::{column_types, scan_all, value, TempDb};
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::engine::shared_database::SharedDatabase;
+use fluxdb_core::metadata::value::Value;
+
+const ROWS: i64 = ROWS_PER_CHUNK as i64 * 3 + 700;
+
+fn append(db: &mut Database, table: &str, from: i64) {
+    let xs: Vec<i64> = (from..from + ROWS).collect();
+    let names: Vec<String> = xs.iter().map(|x| format!("row {x}")).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    db.append_columns(table, &[
+        ColumnInput::new("x", ColumnSlice::Int64(&xs)),
+        ColumnInput::new("name", ColumnSlice::Utf8(&names)),
+    ]).unwrap();
+    db.flush().unwrap();
+}
+
+fn create(file: &TempDb) -> Database {
+    let mut db = file.create();
+    db.execute("CREATE TABLE t (x BIGINT, name VARCHAR); CREATE TABLE other (x BIGINT, name VARCHAR)").unwrap();
+    append(&mut db, "t", 0);
+    append(&mut db, "other", 0);
+    db
+}
+
+fn count(db: &Database, table: &str) -> Value {
+    value(db, &format!("SELECT COUNT(*) FROM {table}"))
+}
+
+fn file_len(file: &TempDb) -> u64 {
+    std::fs::metadata(file.path()).unwrap().len()
+}
+
+#[test]
+fn truncation_survives_reopen() {
+    let file = TempDb::new("truncate-reopen");
+    let schema;
+    {
+        let mut db = create(&file);
+        schema = column_types(&db, "t");
+        db.truncate_table("t").unwrap();
+        assert_eq!(count(&db, "t"), Value::Int64(0));
+    }
+
+    let mut db = file.open();
+    assert_eq!(count(&db, "t"), Value::Int64(0));
+    assert_eq!(column_types(&db, "t"), schema);
+    assert_eq!(count(&db, "other"), Value::Int64(ROWS));
+
+    // The table takes rows again, and keeps them across reopen
+    append(&mut db, "t", 1_000_000);
+    drop(db);
+    let db = file.open();
+    let rows = scan_all(&db, "t");
+    assert_eq!(rows.len() as i64, ROWS);
+    assert_eq!(rows[0], vec![Value::Int64(1_000_000), Value::String("row 1000000".into())]);
+    assert_eq!(value(&db, "SELECT MIN(x) FROM t"), Value::Int64(1_000_000));
+}
+
+#[test]
+fn truncation_commits_the_open_transaction() {
+    let file = TempDb::new("truncate-transaction");
+    {
+        let mut db = create(&file);
+        db.begin();
+        db.append_row("other", vec![("x", Value::Int64(-1))]).unwrap();
+        let version = db.version();
+        db.truncate_table("t").unwrap();
+        // One commit for the transaction, one for the truncation
+        assert_eq!(db.version(), version + 2);
+        assert_eq!(count(&db, "other"), Value::Int64(ROWS + 1));
+        db.flush().unwrap();
+    }
+
+    let db = file.open();
+    assert_eq!(count(&db, "t"), Value::Int64(0));
+    assert_eq!(count(&db, "other"), Value::Int64(ROWS + 1));
+}
+
+#[test]
+fn freed_pages_are_reused_across_reopen() {
+    let file = TempDb::new("truncate-free-list");
+    drop(create(&file));
+    let full = file_len(&file);
+
+    {
+        let mut db = file.open();
+        db.truncate_table("t").unwrap();
+    }
+    // Truncation frees pages without shrinking the file
+    assert_eq!(file_len(&file), full);
+
+    {
+        let mut db = file.open();
+        append(&mut db, "t", 0);
+        db.flush().unwrap();
+        assert_eq!(count(&db, "t"), Value::Int64(ROWS));
+    }
+    assert!(file_len(&file) <= full + full / 20, "{} grew past {full}", file_len(&file));
+    let db = file.open();
+    assert_eq!(scan_all(&db, "t"), scan_all(&db, "other"));
+}
+
+#[test]
+fn snapshots_keep_reading_truncated_rows() {
+    let file = TempDb::new("truncate-snapshot");
+    let db = SharedDatabase::new(create(&file));
+    let before = db.snapshot();
+    let rows = scan_all(&before, "t");
+
+    {
+        let mut writer = db.write().unwrap();
+        writer.truncate_table("t").unwrap();
+        // Pages the snapshot reads are not handed out again
+        append(&mut writer, "other", ROWS);
+    }
+
+    assert_eq!(count(&db.snapshot(), "t"), Value::Int64(0));
+    assert_eq!(scan_all(&before, "t"), rows);
+}
+
+#[test]
+fn failed_truncations_change_nothing() {
+    let file = TempDb::new("truncate-failures");
+    drop(create(&file));
+
+    let mut db = file.open();
+    assert_eq!(db.truncate_table("missing").unwrap_err().kind(), ErrorKind::NotFound);
+    drop(db);
+
+    let mut db = Database::open_read_only(file.path()).unwrap();
+    assert_eq!(db.truncate_table("t").unwrap_err().kind(), ErrorKind::PermissionDenied);
+    assert_eq!(count(&db, "t"), Value::Int64(ROWS));
+    drop(db);
+    assert_eq!(count(&file.open(), "t"), Value::Int64(ROWS));
+}
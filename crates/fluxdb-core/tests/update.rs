@@ -0,0 +1,146 @@
+mod common;
+
+use std::io::ErrorKind;
+use common::{rows, value, TempDb};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::expr::Expr;
+use fluxdb_core::storage::page::Page;
+
+fn create_people(db: &mut Database, count: i64) {
+    db.execute("CREATE TABLE people (id BIGINT NOT NULL, name VARCHAR)").unwrap();
+    for id in 0..count {
+        db.append_row("people", vec![("id", Value::Int64(id)), ("name", Value::String(format!("n{id}")))]).unwrap();
+    }
+}
+
+fn below(id: i64) -> Expr {
+    Expr::column("id").lt(Expr::literal(Value::Int64(id)))
+}
+
+#[test]
+fn update_rewrites_matching_rows_and_survives_reopen() {
+    let file = TempDb::new("update-round-trip");
+    {
+        let mut db = file.create();
+        create_people(&mut db, 10);
+        let updated = db.update_where("people", below(5), &[("name", Value::String("five".into()))]).unwrap();
+        assert_eq!(updated, 5);
+        db.flush().unwrap();
+    }
+
+    let db = file.open();
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM people"), Value::Int64(10));
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM people WHERE name = 'five'"), Value::Int64(5));
+    assert_eq!(
+        rows(&db, "SELECT name FROM people WHERE id = 7"),
+        vec![vec![Value::String("n7".into())]]
+    );
+}
+
+#[test]
+fn updated_rows_move_to_the_end_and_are_not_updated_twice() {
+    let file = TempDb::new("update-reappend");
+    let mut db = file.create();
+    create_people(&mut db, 6);
+
+    // The new ids still match the predicate, but only the rows scanned
+    // before the update are rewritten
+    assert_eq!(db.update_where("people", below(3), &[("id", Value::Int64(1))]).unwrap(), 3);
+    let ids: Vec<Value> = rows(&db, "SELECT id FROM people").into_iter().map(|mut row| row.remove(0)).collect();
+    assert_eq!(ids, [3, 4, 5, 1, 1, 1].map(Value::Int64));
+    assert_eq!(
+        rows(&db, "SELECT name FROM people WHERE id = 1"),
+        [0, 1, 2].map(|i| vec![Value::String(format!("n{i}"))]).to_vec()
+    );
+
+    assert_eq!(db.update_where("people", below(0), &[("name", Value::Null)]).unwrap(), 0);
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM people"), Value::Int64(6));
+}
+
+#[test]
+fn an_update_is_one_commit() {
+    let file = TempDb::new("update-commit");
+    let mut db = file.create();
+    create_people(&mut db, 10);
+    let version = db.version();
+
+    db.update_where("people", below(4), &[("name", Value::Null)]).unwrap();
+    assert_eq!(db.version(), version + 1);
+
+    // Inside a transaction, neither the deletes nor the new rows show
+    // until it commits
+    db.begin();
+    db.update_where("people", below(10), &[("name", Value::String("x".into()))]).unwrap();
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM people"), Value::Int64(10));
+    assert_eq!(value(&db, "SELECT COUNT(name) FROM people"), Value::Int64(6));
+    db.commit().unwrap();
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM people WHERE name = 'x'"), Value::Int64(10));
+}
+
+#[test]
+fn updates_in_a_transaction_see_its_own_writes() {
+    let file = TempDb::new("update-own-writes");
+    let mut db = file.create();
+    create_people(&mut db, 4);
+
+    db.begin();
+    db.append_row("people", vec![("id", Value::Int64(4)), ("name", Value::String("n4".into()))]).unwrap();
+    // The row appended in the transaction is updated with the others
+    assert_eq!(db.update_where("people", below(10), &[("name", Value::String("a".into()))]).unwrap(), 5);
+    // An update after an update rewrites the new rows, not the old ones
+    assert_eq!(db.update_where("people", below(2), &[("name", Value::String("b".into()))]).unwrap(), 2);
+    db.delete_where("people", Expr::column("id").eq(Expr::literal(Value::Int64(3)))).unwrap();
+    assert_eq!(db.update_where("people", below(10), &[("id", Value::Int64(9))]).unwrap(), 4);
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM people"), Value::Int64(4));
+
+    db.commit().unwrap();
+    assert_eq!(
+        rows(&db, "SELECT id, name FROM people ORDER BY name"),
+        ["a", "a", "b", "b"].map(|name| vec![Value::Int64(9), Value::String(name.into())]).to_vec()
+    );
+    drop(db);
+    assert_eq!(value(&file.open(), "SELECT COUNT(*) FROM people WHERE id = 9"), Value::Int64(4));
+}
+
+#[test]
+fn bad_assignments_are_rejected_before_any_row_changes() {
+    let file = TempDb::new("update-errors");
+    let mut db = file.create();
+    create_people(&mut db, 10);
+    let version = db.version();
+
+    let kind = |db: &mut Database, assignments: &[(&str, Value)]| db.update_where("people", below(5), assignments).unwrap_err().kind();
+    assert_eq!(kind(&mut db, &[("nope", Value::Null)]), ErrorKind::NotFound);
+    assert_eq!(kind(&mut db, &[("id", Value::Null)]), ErrorKind::InvalidInput);
+    assert_eq!(kind(&mut db, &[("id", Value::String("x".into()))]), ErrorKind::InvalidInput);
+    // The first assignment is fine, the second is not
+    assert_eq!(kind(&mut db, &[("name", Value::Null), ("id", Value::Bool(true))]), ErrorKind::InvalidInput);
+    let err = db.update_where("missing", below(5), &[("name", Value::Null)]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    assert_eq!(db.version(), version);
+    assert_eq!(value(&db, "SELECT COUNT(name) FROM people WHERE id < 5"), Value::Int64(5));
+}
+
+#[test]
+fn oversized_values_fail_the_update_without_touching_the_table() {
+    let file = TempDb::new("update-oversized");
+    let mut db = file.create();
+    create_people(&mut db, 10);
+    let before = rows(&db, "SELECT id, name FROM people ORDER BY id");
+    let version = db.version();
+
+    let max = Page::max_value_len(db.chunk_manager.pager.header.page_size as usize);
+    let huge = Value::String("x".repeat(max - 3));
+    let err = db.update_where("people", below(5), &[("name", huge)]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(db.version(), version);
+    assert_eq!(rows(&db, "SELECT id, name FROM people ORDER BY id"), before);
+
+    // The largest value that fits a page is still accepted
+    let largest = Value::String("y".repeat(max - 4));
+    assert_eq!(db.update_where("people", below(1), &[("name", largest.clone())]).unwrap(), 1);
+    assert_eq!(rows(&db, "SELECT name FROM people WHERE id = 0"), vec![vec![largest]]);
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM people"), Value::Int64(10));
+}
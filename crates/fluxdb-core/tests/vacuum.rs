@@ -0,0 +1,139 @@
+mod common;
+
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use common::{column_types, scan_all, value, TempDb};
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::database::Database;
+use fluxdb_core::engine::shared_database::SharedDatabase;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::expr::Expr;
+
+const ROWS: i64 = ROWS_PER_CHUNK as i64 * 2 + 300;
+
+fn append(db: &mut Database, table: &str) {
+    let xs: Vec<i64> = (0..ROWS).collect();
+    let names: Vec<String> = xs.iter().map(|x| format!("row {x}")).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let valid: Vec<bool> = xs.iter().map(|x| x % 9 != 0).collect();
+    db.append_columns(table, &[
+        ColumnInput::new("x", ColumnSlice::Int64(&xs)),
+        ColumnInput::new("name", ColumnSlice::Utf8(&names)).with_validity(&valid),
+    ]).unwrap();
+    db.flush().unwrap();
+}
+
+/// `t` keeps its rows but loses some to a delete, `gone` is dropped and
+/// `empty` truncated, leaving plenty for a vacuum to reclaim.
+fn create(file: &TempDb) -> Database {
+    let mut db = file.create();
+    db.execute("CREATE TABLE t (x BIGINT, name VARCHAR); CREATE TABLE gone (x BIGINT, name VARCHAR); CREATE TABLE empty (x BIGINT, name VARCHAR)").unwrap();
+    for table in ["t", "gone", "empty"] {
+        append(&mut db, table);
+    }
+    db.delete_where("t", Expr::column("x").lt(Expr::literal(Value::Int64(1_000)))).unwrap();
+    db.drop_table("gone").unwrap();
+    db.truncate_table("empty").unwrap();
+    db
+}
+
+fn vacuum_path(file: &TempDb) -> PathBuf {
+    let mut path = file.path().as_os_str().to_owned();
+    path.push(".vacuum");
+    PathBuf::from(path)
+}
+
+fn file_len(file: &TempDb) -> u64 {
+    std::fs::metadata(file.path()).unwrap().len()
+}
+
+#[test]
+fn vacuum_shrinks_the_file_and_keeps_the_data() {
+    let file = TempDb::new("vacuum-shrink");
+    let (rows, schema);
+    {
+        let mut db = create(&file);
+        rows = scan_all(&db, "t");
+        schema = column_types(&db, "t");
+        let before = file_len(&file);
+
+        let stats = db.vacuum().unwrap();
+        assert!(stats.pages_after < stats.pages_before / 2, "{stats:?}");
+        assert!(file_len(&file) < before / 2);
+        assert!(!vacuum_path(&file).exists());
+        assert_eq!(scan_all(&db, "t"), rows);
+        assert_eq!(db.vacuum().unwrap().pages_after, stats.pages_after);
+    }
+
+    let mut db = file.open();
+    assert_eq!(scan_all(&db, "t"), rows);
+    assert_eq!(column_types(&db, "t"), schema);
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM t"), Value::Int64(ROWS - 1_000));
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM empty"), Value::Int64(0));
+    assert!(!db.catalog.tables_by_name.contains_key("gone"));
+
+    // The vacuumed file takes writes like any other
+    append(&mut db, "empty");
+    db.delete_where("t", Expr::column("x").lt(Expr::literal(Value::Int64(2_000)))).unwrap();
+    db.flush().unwrap();
+    drop(db);
+    let db = file.open();
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM empty"), Value::Int64(ROWS));
+    assert_eq!(value(&db, "SELECT MIN(x) FROM t"), Value::Int64(2_000));
+}
+
+#[test]
+fn vacuum_commits_and_seals_pending_rows() {
+    let file = TempDb::new("vacuum-pending");
+    {
+        let mut db = create(&file);
+        db.begin();
+        db.append_row("t", vec![("x", Value::Int64(-1)), ("name", Value::String("late".into()))]).unwrap();
+        db.vacuum().unwrap();
+    }
+
+    let db = file.open();
+    assert_eq!(value(&db, "SELECT COUNT(*) FROM t"), Value::Int64(ROWS - 1_000 + 1));
+    assert_eq!(value(&db, "SELECT MIN(x) FROM t"), Value::Int64(-1));
+}
+
+#[test]
+fn snapshots_keep_reading_the_old_file() {
+    let file = TempDb::new("vacuum-snapshot");
+    let db = SharedDatabase::new(create(&file));
+    let before = db.snapshot();
+    let rows = scan_all(&before, "t");
+
+    db.write().unwrap().vacuum().unwrap();
+    assert_eq!(scan_all(&before, "t"), rows);
+    assert_eq!(scan_all(&db.snapshot(), "t"), rows);
+}
+
+#[test]
+fn failed_vacuums_leave_the_database_as_it_was() {
+    let file = TempDb::new("vacuum-failures");
+    let rows = scan_all(&create(&file), "t");
+    let len = file_len(&file);
+
+    let mut db = Database::open_read_only(file.path()).unwrap();
+    assert_eq!(db.vacuum().unwrap_err().kind(), ErrorKind::PermissionDenied);
+    drop(db);
+    assert_eq!(file_len(&file), len);
+
+    // The copy cannot be written where a directory is in the way
+    std::fs::create_dir(vacuum_path(&file)).unwrap();
+    let mut db = file.open();
+    let failed = db.vacuum();
+    std::fs::remove_dir(vacuum_path(&file)).unwrap();
+    assert!(failed.is_err());
+    assert_eq!(file_len(&file), len);
+    assert_eq!(scan_all(&db, "t"), rows);
+
+    // A copy left behind by a crash is overwritten
+    std::fs::write(vacuum_path(&file), b"stale").unwrap();
+    db.vacuum().unwrap();
+    assert!(!vacuum_path(&file).exists());
+    drop(db);
+    assert_eq!(scan_all(&file.open(), "t"), rows);
+}
@@ -0,0 +1,176 @@
+mod common;
+
+use common::{scan_all, scan_where, TempDb};
+use fluxdb_core::engine::chunk_manager::ROWS_PER_CHUNK;
+use fluxdb_core::engine::column_slice::{ColumnInput, ColumnSlice};
+use fluxdb_core::engine::table_scan::TableScan;
+use fluxdb_core::metadata::schema::column_type::ColumnType;
+use fluxdb_core::metadata::value::Value;
+use fluxdb_core::query::aggregate::Aggregate;
+use fluxdb_core::query::expr::Expr;
+use fluxdb_core::query::predicate;
+use fluxdb_core::query::vector::{Vector, VECTOR_SIZE};
+use fluxdb_core::query::vector_predicate::select_vectors;
+
+const ROWS: usize = 5_000;
+
+fn col(name: &str) -> Expr {
+    Expr::column(name)
+}
+
+fn lit(value: Value) -> Expr {
+    Expr::literal(value)
+}
+
+/// `i` is null on every seventh row, `s` on every fifth; `f` and `b` have
+/// no nulls.
+fn values() -> Vec<(&'static str, ColumnType, Vec<Value>)> {
+    let i = (0..ROWS).map(|r| if r % 7 == 0 { Value::Null } else { Value::Int64(r as i64 % 100) }).collect();
+    let f = (0..ROWS).map(|r| Value::Float64(r as f64 / 4.0)).collect();
+    let s = (0..ROWS)
+        .map(|r| if r % 5 == 0 { Value::Null } else { Value::String(["ab", "abc", "b_c", "xyz"][r % 4].into()) })
+        .collect();
+    let b = (0..ROWS).map(|r| Value::Bool(r % 3 == 0)).collect();
+    vec![
+        ("i", ColumnType::Integer64, i),
+        ("f", ColumnType::Float64, f),
+        ("s", ColumnType::Utf8, s),
+        ("b", ColumnType::Boolean, b),
+    ]
+}
+
+#[test]
+fn select_vectors_matches_row_predicates() {
+    let columns = values();
+    let vectors: Vec<Vec<Vector>> = columns
+        .iter()
+        .map(|(_, column_type, values)| Vector::from_values(*column_type, values.clone()).split())
+        .collect();
+    assert_eq!(vectors[0].len(), ROWS.div_ceil(VECTOR_SIZE));
+
+    let filters = [
+        col("i").gt(lit(Value::Int64(50))),
+        col("i").lt_eq(lit(Value::Float64(10.5))),
+        col("i").eq(lit(Value::Null)),
+        col("f").gt_eq(lit(Value::Int64(600))).and(col("i").not_eq(lit(Value::Int64(3)))),
+        col("i").between(lit(Value::Int64(10)), lit(Value::Int64(20))).or(col("f").lt(lit(Value::Float64(5.0)))),
+        col("i").not_between(lit(Value::Int64(10)), lit(Value::Int64(90))),
+        col("i").in_list(vec![lit(Value::Int64(1)), lit(Value::Int64(42)), lit(Value::Null)]),
+        col("i").not_in_list(vec![lit(Value::Int64(1)), lit(Value::Int64(42))]),
+        col("s").is_null().or(col("b").eq(lit(Value::Bool(true)))),
+        col("i").is_not_null().and(col("s").eq(lit(Value::String("abc".into())))),
+        col("s").like("a%").and(col("i").gt(lit(Value::Int64(5)))),
+        col("s").not_like("b\\_c"),
+        Expr::Not(Box::new(col("i").lt(lit(Value::Int64(30))))),
+        col("i").lt(col("f")),
+    ];
+
+    for filter in &filters {
+        for (window, start) in (0..ROWS).step_by(VECTOR_SIZE).enumerate() {
+            let end = (start + VECTOR_SIZE).min(ROWS);
+            let rows: Vec<(&str, &[Value])> = columns.iter().map(|(name, _, values)| (*name, &values[start..end])).collect();
+            let typed: Vec<(&str, &Vector)> = columns.iter().zip(&vectors).map(|((name, _, _), v)| (*name, &v[window])).collect();
+
+            let expected = predicate::select(filter, &rows, end - start).unwrap();
+            assert_eq!(select_vectors(filter, &typed, end - start).unwrap(), expected, "{filter:?}");
+        }
+    }
+}
+
+#[test]
+fn vectors_round_trip_values() {
+    for (name, column_type, values) in values() {
+        let vector = Vector::from_values(column_type, values.clone());
+        assert_eq!(vector.len(), ROWS, "{name}");
+        for (row, value) in values.iter().enumerate() {
+            assert_eq!(&vector.value(row), value, "{name} row {row}");
+            assert_eq!(vector.is_valid(row), *value != Value::Null, "{name} row {row}");
+        }
+
+        let selection: Vec<u32> = (0..ROWS as u32).filter(|r| r % 3 == 1).collect();
+        let expected: Vec<Value> = selection.iter().map(|&r| values[r as usize].clone()).collect();
+        assert_eq!(vector.clone().into_values(Some(&selection)), expected, "{name}");
+
+        let pieces = vector.split();
+        assert!(pieces.iter().all(|piece| piece.len() <= VECTOR_SIZE));
+        let joined: Vec<Value> = pieces.into_iter().flat_map(|piece| piece.into_values(None)).collect();
+        assert_eq!(joined, values, "{name}");
+    }
+}
+
+#[test]
+fn vector_scans_return_the_row_scan_batches() {
+    let file = TempDb::new("vectorized-scan");
+    let xs: Vec<i64> = (0..ROWS as i64 * 4).collect();
+    let ys: Vec<f64> = xs.iter().map(|&x| x as f64 * 0.5).collect();
+    let valid: Vec<bool> = xs.iter().map(|x| x % 9 != 0).collect();
+
+    let mut db = file.create();
+    db.execute("CREATE TABLE t (x BIGINT, y DOUBLE)").unwrap();
+    db.append_columns("t", &[
+        ColumnInput::new("x", ColumnSlice::Int64(&xs)),
+        ColumnInput::new("y", ColumnSlice::Float64(&ys)).with_validity(&valid),
+    ]).unwrap();
+    db.flush().unwrap();
+    drop(db);
+    let db = file.open();
+
+    let filter = col("x").gt_eq(lit(Value::Int64(1_000))).and(col("y").lt(lit(Value::Float64(6_000.0))));
+    for (scan, expected) in [
+        (db.scan("t", &[]).unwrap(), scan_all(&db, "t")),
+        (db.scan_where("t", &[], filter.clone()).unwrap(), scan_where(&db, "t", filter)),
+    ] {
+        let mut scan = scan;
+        let mut rows: Vec<Vec<Value>> = Vec::new();
+        while let Some(vectors) = scan.next_vectors() {
+            let vectors = vectors.unwrap();
+            assert!(vectors.row_count <= VECTOR_SIZE);
+            assert!(vectors.columns.iter().all(|vector| vector.len() == vectors.row_count));
+            let selected = vectors.selected();
+            let batch = vectors.into_batch();
+            assert_eq!(batch.row_count, selected);
+            rows.extend((0..batch.row_count).map(|r| batch.columns.iter().map(|c| c[r].clone()).collect::<Vec<_>>()));
+        }
+        assert!(!expected.is_empty());
+        assert_eq!(rows, expected);
+    }
+
+    // Windows stop at the sealed chunk's end, and skip rows the filter
+    // rejects everywhere
+    let windows = |mut scan: TableScan| {
+        let mut sizes = Vec::new();
+        while let Some(vectors) = scan.next_vectors() {
+            sizes.push(vectors.unwrap().row_count);
+        }
+        sizes
+    };
+    let mut expected = vec![VECTOR_SIZE; ROWS_PER_CHUNK as usize / VECTOR_SIZE];
+    expected.extend([VECTOR_SIZE, ROWS * 4 - ROWS_PER_CHUNK as usize - VECTOR_SIZE]);
+    assert_eq!(windows(db.scan("t", &["x"]).unwrap()), expected);
+    let last = col("x").gt_eq(lit(Value::Int64(ROWS as i64 * 4 - 10)));
+    assert_eq!(windows(db.scan_where("t", &["x"], last).unwrap()), expected[expected.len() - 1..]);
+}
+
+#[test]
+fn vector_accumulators_match_row_accumulators() {
+    let aggregates = [Aggregate::count_star(), Aggregate::count("x"), Aggregate::sum("x"), Aggregate::avg("x"), Aggregate::min("x"), Aggregate::max("x")];
+    for (_, column_type, values) in values().into_iter().filter(|(name, _, _)| *name == "i" || *name == "f") {
+        let vectors = Vector::from_values(column_type, values.clone()).split();
+        for aggregate in &aggregates {
+            let input = aggregate.column.as_ref().map(|_| column_type);
+            let mut rows = aggregate.accumulator(input).unwrap();
+            let mut vectorized = aggregate.accumulator(input).unwrap();
+
+            for (window, vector) in vectors.iter().enumerate() {
+                let selection: Vec<u32> = (0..vector.len() as u32).filter(|r| r % 4 != 3).collect();
+                for &row in &selection {
+                    let value = if aggregate.column.is_some() { values[window * VECTOR_SIZE + row as usize].clone() } else { Value::Int64(1) };
+                    rows.update(&value).unwrap();
+                }
+                let vector = aggregate.column.as_ref().map(|_| vector);
+                vectorized.update_vector(vector, &selection).unwrap();
+            }
+            assert_eq!(vectorized.finish(), rows.finish(), "{} over {column_type:?}", aggregate.output_name());
+        }
+    }
+}
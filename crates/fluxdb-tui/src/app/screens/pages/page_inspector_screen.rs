@@ -133,11 +133,13 @@ impl PageInspectorScreen {
                              ─────────────\n\
                              table_id : {} ({})\n\
                              name     : {}\n\
-                             type     : {:?}",
+                             type     : {:?}\n\
+                             parent   : {}",
                             c.table_id,
                             table_name,
                             c.name,
-                            c.column_type
+                            c.column_type,
+                            c.parent_column_id
                         )
                     }
                     Err(e) => format!("❌ Decode failed:\n{e}"),
@@ -148,20 +150,6 @@ impl PageInspectorScreen {
         }
     }
 
-    fn hex_dump(bytes: &[u8]) -> String {
-        let mut out = String::new();
-
-        for (row, chunk) in bytes.chunks(16).enumerate() {
-            out.push_str(&format!("{:04x}: ", row * 16));
-            for b in chunk {
-                out.push_str(&format!("{:02x} ", b));
-            }
-            out.push('\n');
-        }
-
-        out
-    }
-
     // ───────────────────────── heap page view ─────────────────────────
 
     fn render_heap_page(&self, f: &mut Frame, area: Rect, page: &Page, ctx: &AppContext) {
@@ -231,7 +219,7 @@ impl PageInspectorScreen {
         let body = match self.record_tab {
             RecordTab::Decoded => Self::decode_payload(rt, payload, ctx),
             RecordTab::Payload => String::from_utf8_lossy(payload).to_string(),
-            RecordTab::Hex => Self::hex_dump(raw),
+            RecordTab::Hex => Page::hex_dump(raw),
         };
 
         let title = match self.record_tab {
@@ -263,36 +251,45 @@ impl PageInspectorScreen {
 
     fn render_data_page(&self, f: &mut Frame, area: Rect, page: &Page, ctx: &AppContext) {
         let db = ctx.db.unwrap();
-        let page_size = db.pager.header.page_size;
-
-        //TODO:
-        // let used = page.header.free_start;
-        // let free = page.header.free_end.saturating_sub(page.header.free_start);
-
-
-        let used = 0;
-        let free = 0;
-
-
-        let text = format!(
-            "Data Page\n\
-             ─────────\n\
-             Page id     : {}\n\
-             Page size   : {} bytes\n\
-             Used bytes  : {}\n\
-             Free bytes  : {}\n\
-             Utilization : {:.2} %",
-            page.header.page_id,
-            page_size,
-            used,
-            free,
-            (used as f64 / page_size as f64) * 100.0
-        );
+
+        let layout = page.chunk_header();
+        let column = db
+            .catalog
+            .find_column_by_ordinal(layout.table_id, layout.column_ordinal);
+
+        let column_label = column
+            .map(|c| format!("{} ({:?})", c.name, c.column_type))
+            .unwrap_or_else(|| "<unknown>".to_string());
+
+        let text = page.describe_data_page(&column_label);
+
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(40), // summary
+                Constraint::Min(1),     // values
+            ])
+            .split(area);
 
         f.render_widget(
             Paragraph::new(text)
                 .block(Block::default().title(" Data ").borders(Borders::ALL)),
-            area,
+            chunks[0],
+        );
+
+        let values = match (self.record_tab, column) {
+            (RecordTab::Hex, _) => Page::hex_dump(page.value_data()),
+            (_, Some(column)) => page
+                .format_values(column.column_type)
+                .unwrap_or_else(|e| format!("❌ Decode failed:\n{e}")),
+            (_, None) => "Column not found in catalog".to_string(),
+        };
+
+        f.render_widget(
+            Paragraph::new(values)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().title(" Values ").borders(Borders::ALL)),
+            chunks[1],
         );
     }
 }